@@ -0,0 +1,83 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use integer_encoding::VarInt;
+use kittymc_lib::packets::client::play::chunk_data_20::Chunk;
+use kittymc_lib::packets::client::play::{ChunkDataPacket, ServerKeepAlivePacket};
+use kittymc_lib::packets::packet_buffer::PacketBuffer;
+use kittymc_lib::packets::packet_serialization::{compress_packet, SerializablePacket};
+
+const COMPRESSION_THRESHOLD: u32 = 256;
+
+/// How packets were serialized before `PacketBuffer`: into a new `Vec`, with the packet id and the
+/// length spliced in front afterwards
+fn splice_serialize<T: SerializablePacket>(packet: &T) -> Vec<u8> {
+    let mut data = vec![];
+    packet.serialize_fields(&mut data);
+    data.splice(0..0, T::id().encode_var_vec());
+    let len = data.len();
+    data.splice(0..0, len.encode_var_vec());
+    data
+}
+
+/// A hilly chunk with a few different blocks, so the sections need real palettes
+fn test_chunk() -> Chunk {
+    let mut chunk = Chunk::default();
+    for x in 0..16 {
+        for z in 0..16 {
+            let height = 60 + (x * 3 + z * 5) % 12;
+            for y in 0..height {
+                let state = match y {
+                    0 => 7 << 4,
+                    y if y + 4 < height => 1 << 4,
+                    y if y + 1 < height => 3 << 4,
+                    _ => 2 << 4,
+                };
+                chunk.set_block(x, y, z, state).unwrap();
+            }
+        }
+    }
+    chunk
+}
+
+fn bench_serialization(c: &mut Criterion) {
+    let chunk = test_chunk();
+    let chunk_packet = ChunkDataPacket::new(&chunk, 0, 0);
+    let keep_alive = ServerKeepAlivePacket::new(0x1234_5678_9ABC);
+
+    let mut group = c.benchmark_group("chunk_data");
+    group.bench_function("splice", |b| {
+        b.iter(|| splice_serialize(black_box(&chunk_packet)))
+    });
+    group.bench_function("packet_buffer", |b| {
+        let mut buffer = PacketBuffer::new();
+        b.iter(|| buffer.write_packet(black_box(&chunk_packet)).len())
+    });
+    group.bench_function("splice_compressed", |b| {
+        b.iter(|| {
+            compress_packet(&splice_serialize(black_box(&chunk_packet)), COMPRESSION_THRESHOLD)
+                .unwrap()
+        })
+    });
+    group.bench_function("packet_buffer_compressed", |b| {
+        let mut buffer = PacketBuffer::new();
+        b.iter(|| {
+            buffer
+                .write_packet_compressed(black_box(&chunk_packet), COMPRESSION_THRESHOLD)
+                .unwrap()
+                .len()
+        })
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("keep_alive");
+    group.bench_function("splice", |b| {
+        b.iter(|| splice_serialize(black_box(&keep_alive)))
+    });
+    group.bench_function("packet_buffer", |b| {
+        let mut buffer = PacketBuffer::new();
+        b.iter(|| buffer.write_packet(black_box(&keep_alive)).len())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_serialization);
+criterion_main!(benches);
@@ -0,0 +1,112 @@
+//! Protocol encryption.
+//!
+//! The server sends its RSA public key in the Encryption Request, the client answers with a
+//! shared secret encrypted with that key and from then on both directions are encrypted with
+//! AES-128 in CFB8 mode, using the shared secret as both key and IV.
+
+use crate::error::KittyMCError;
+use aes::cipher::inout::InOutBuf;
+use aes::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use aes::Aes128;
+use num_bigint::BigInt;
+use rsa::pkcs8::{DecodePublicKey, EncodePublicKey};
+use rsa::rand_core::OsRng;
+use rsa::{Pkcs1v15Encrypt, RsaPrivateKey, RsaPublicKey};
+use sha1::{Digest, Sha1};
+use std::fmt::{Debug, Formatter};
+
+/// The vanilla server uses 1024 bit keys and clients don't expect anything else
+pub const SERVER_KEY_BITS: usize = 1024;
+pub const SHARED_SECRET_LEN: usize = 16;
+
+/// The key pair a server uses for the login of all its clients.
+pub struct ServerKeyPair {
+    private_key: RsaPrivateKey,
+    public_key_der: Vec<u8>,
+}
+
+impl ServerKeyPair {
+    pub fn generate() -> Result<Self, KittyMCError> {
+        let private_key = RsaPrivateKey::new(&mut OsRng, SERVER_KEY_BITS)?;
+        let public_key_der = private_key
+            .to_public_key()
+            .to_public_key_der()
+            .map_err(|_| KittyMCError::EncryptionError("couldn't encode the public key"))?
+            .into_vec();
+
+        Ok(ServerKeyPair {
+            private_key,
+            public_key_der,
+        })
+    }
+
+    /// The public key as an X.509 SubjectPublicKeyInfo, the format the Encryption Request uses
+    pub fn public_key_der(&self) -> &[u8] {
+        &self.public_key_der
+    }
+
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, KittyMCError> {
+        Ok(self.private_key.decrypt(Pkcs1v15Encrypt, data)?)
+    }
+}
+
+impl Debug for ServerKeyPair {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ServerKeyPair").finish_non_exhaustive()
+    }
+}
+
+/// What a client does with the key from the Encryption Request
+pub fn encrypt_with_public_key(public_key_der: &[u8], data: &[u8]) -> Result<Vec<u8>, KittyMCError> {
+    let public_key = RsaPublicKey::from_public_key_der(public_key_der)
+        .map_err(|_| KittyMCError::EncryptionError("couldn't decode the public key"))?;
+
+    Ok(public_key.encrypt(&mut OsRng, Pkcs1v15Encrypt, data)?)
+}
+
+/// The hash both sides send to the session server. It's a SHA-1 digest printed as a signed
+/// big-endian number in hex, so it can be negative and has no leading zeros.
+pub fn server_id_hash(server_id: &str, shared_secret: &[u8], public_key_der: &[u8]) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(server_id.as_bytes());
+    hasher.update(shared_secret);
+    hasher.update(public_key_der);
+
+    BigInt::from_signed_bytes_be(&hasher.finalize()).to_str_radix(16)
+}
+
+/// The AES-128-CFB8 stream cipher of one connection, one half per direction.
+pub struct PacketCipher {
+    encryptor: cfb8::Encryptor<Aes128>,
+    decryptor: cfb8::Decryptor<Aes128>,
+}
+
+impl PacketCipher {
+    pub fn new(shared_secret: &[u8]) -> Result<Self, KittyMCError> {
+        if shared_secret.len() != SHARED_SECRET_LEN {
+            return Err(KittyMCError::InvalidSharedSecret(shared_secret.len()));
+        }
+
+        Ok(PacketCipher {
+            encryptor: cfb8::Encryptor::new(shared_secret.into(), shared_secret.into()),
+            decryptor: cfb8::Decryptor::new(shared_secret.into(), shared_secret.into()),
+        })
+    }
+
+    pub fn encrypt(&mut self, data: &mut [u8]) {
+        // CFB8 works on one byte blocks, so there's never a tail left over
+        let (blocks, _) = InOutBuf::from(data).into_chunks();
+        self.encryptor.encrypt_blocks_inout_mut(blocks);
+    }
+
+    pub fn decrypt(&mut self, data: &mut [u8]) {
+        let (blocks, _) = InOutBuf::from(data).into_chunks();
+        self.decryptor.decrypt_blocks_inout_mut(blocks);
+    }
+}
+
+impl Debug for PacketCipher {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PacketCipher").finish_non_exhaustive()
+    }
+}
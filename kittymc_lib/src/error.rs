@@ -48,6 +48,8 @@ pub enum KittyMCError {
     InvalidPacketLength,
     #[error("Zlib Decompression failed with error: {0}")]
     ZlibDecompressionError(miniz_oxide::inflate::DecompressError),
+    #[error("Zlib Compression failed with status: {0:?}")]
+    ZlibCompressionError(miniz_oxide::deflate::core::TDEFLStatus),
     #[error("The decompressed packet size was different than previously announced. Assuming corruption. {0} != {1}"
     )]
     InvalidDecompressedPacketLength(usize, usize), // Announced, Actual
@@ -63,10 +65,36 @@ pub enum KittyMCError {
     InvalidBlock(Location),
     #[error("Couldn't parse NBT data")]
     NBTError(#[from] fastnbt::error::Error),
+    #[error("Couldn't parse SNBT at byte {0}: {1}")]
+    SnbtError(usize, &'static str),
     #[error("The requested player could not be found")]
     PlayerNotFound,
     #[error("The requested inventory slot {0} was empty")]
     InventorySlotEmpty(i16),
     #[error("{0}")]
     SaveFileError(#[from] SavefileError),
+    #[error("Value {1} is not a valid \"{0}\"")]
+    InvalidEnumValue(&'static str, i64),
+    #[error("{0}")]
+    RsaError(#[from] rsa::Error),
+    #[error("Encryption failed: {0}")]
+    EncryptionError(&'static str),
+    #[error("The shared secret has to be 16 bytes long, got {0}")]
+    InvalidSharedSecret(usize),
+    #[error("The client sent back a different verify token")]
+    VerifyTokenMismatch,
+    #[error("{0} couldn't be authenticated with the session server")]
+    NotAuthenticated(String),
+    #[error("The session server couldn't be reached: {0}")]
+    SessionServerError(String),
+    #[error("Didn't expect a {0} at this point")]
+    UnexpectedPacket(&'static str),
+    #[error("Version {1} of the {0} format isn't supported")]
+    UnsupportedVersion(&'static str, i32),
+    #[error("The region file is corrupted: {0}")]
+    CorruptRegion(&'static str),
+    #[error("There's no world generator called {0:?}")]
+    UnknownGenerator(String),
+    #[error("Invalid superflat preset: {0}")]
+    InvalidFlatPreset(String),
 }
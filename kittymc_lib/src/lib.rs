@@ -1,4 +1,6 @@
+pub mod crypto;
 pub mod error;
 pub mod packets;
+pub mod session;
 pub mod subtypes;
 pub mod utils;
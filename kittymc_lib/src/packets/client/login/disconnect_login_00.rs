@@ -1,12 +1,11 @@
-use crate::packets::packet_serialization::SerializablePacket;
-use crate::packets::wrap_packet;
 use crate::subtypes::components::{Component, TextComponent};
 use crate::utils::KITTYMC_TAG;
 use kittymc_macros::Packet;
 
-#[derive(Debug, Packet)]
+#[derive(PartialEq, Debug, Clone, Packet)]
+#[packet(id = 0x00, state = Login, direction = Clientbound)]
 pub struct DisconnectLoginPacket {
-    reason: Component,
+    pub reason: Component,
 }
 
 impl Default for DisconnectLoginPacket {
@@ -29,26 +28,22 @@ impl DisconnectLoginPacket {
             reason: Component::Text(
                 TextComponent::builder()
                     .text(format!(
-                        "{KITTYMC_TAG} BUUUH, WRONG VERSION. §b:<§r!\n§dHop on 1.12.2 :3"
+                        "{KITTYMC_TAG} BUUUH, WRONG VERSION. §b:<§r!\n§dHop on 1.12 - 1.12.2 :3"
                     ))
                     .build(),
             ),
         }
     }
-}
-
-impl SerializablePacket for DisconnectLoginPacket {
-    fn serialize(&self) -> Vec<u8> {
-        let mut packet = vec![];
-
-        self.reason.write(&mut packet);
-
-        wrap_packet(&mut packet, Self::id());
 
-        packet
-    }
-
-    fn id() -> u32 {
-        0
+    pub fn not_authenticated() -> Self {
+        DisconnectLoginPacket {
+            reason: Component::Text(
+                TextComponent::builder()
+                    .text(format!(
+                        "{KITTYMC_TAG} WHO ARE YOU?? §b:<§r!\n§dCouldn't verify your account with the session server"
+                    ))
+                    .build(),
+            ),
+        }
     }
 }
@@ -0,0 +1,20 @@
+use kittymc_macros::Packet;
+
+#[derive(PartialEq, Debug, Clone, Packet)]
+#[packet(id = 0x01, state = Login, direction = Clientbound)]
+pub struct EncryptionRequestPacket {
+    /// Always empty since 1.7, but still part of the server id hash
+    pub server_id: String,
+    pub public_key: Vec<u8>,
+    pub verify_token: Vec<u8>,
+}
+
+impl EncryptionRequestPacket {
+    pub fn new(public_key: &[u8], verify_token: &[u8]) -> Self {
+        EncryptionRequestPacket {
+            server_id: String::new(),
+            public_key: public_key.to_vec(),
+            verify_token: verify_token.to_vec(),
+        }
+    }
+}
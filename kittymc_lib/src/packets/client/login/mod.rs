@@ -1,7 +1,9 @@
 pub mod disconnect_login_00;
+pub mod encryption_request_01;
 pub mod set_compression_03;
 pub mod success_02;
 
 pub use disconnect_login_00::DisconnectLoginPacket;
+pub use encryption_request_01::EncryptionRequestPacket;
 pub use set_compression_03::SetCompressionPacket;
 pub use success_02::LoginSuccessPacket;
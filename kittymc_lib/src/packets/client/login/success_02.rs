@@ -2,13 +2,14 @@ use crate::error::KittyMCError;
 use crate::packets::packet_serialization::{
     read_length_prefixed_string, write_length_prefixed_string, SerializablePacket,
 };
-use crate::packets::{wrap_packet, Packet};
+use crate::packets::{ClientboundPacket, Packet};
 use crate::utils::generate_cracked_uuid;
 use kittymc_macros::Packet;
 use std::str::FromStr;
 use uuid::Uuid;
 
 #[derive(PartialEq, Debug, Clone, Packet)]
+#[packet(state = Login, direction = Clientbound)]
 pub struct LoginSuccessPacket {
     pub uuid: Uuid,
     pub username: String,
@@ -23,32 +24,35 @@ impl LoginSuccessPacket {
             username: name.to_string(),
         })
     }
-}
 
-impl SerializablePacket for LoginSuccessPacket {
-    fn serialize(&self) -> Vec<u8> {
-        let mut packet = vec![];
+    fn read(mut data: &[u8]) -> Result<(usize, Self), KittyMCError> {
+        let mut size = 0;
 
-        write_length_prefixed_string(&mut packet, &self.uuid.hyphenated().to_string());
-        write_length_prefixed_string(&mut packet, &self.username);
+        let uuid = read_length_prefixed_string(&mut data, &mut size)?;
+        let uuid = Uuid::from_str(&uuid)?;
 
-        wrap_packet(&mut packet, Self::id());
+        let username = read_length_prefixed_string(&mut data, &mut size)?;
 
-        packet
+        Ok((size, LoginSuccessPacket { uuid, username }))
     }
+}
 
-    fn deserialize(mut data: &[u8]) -> Result<(usize, Packet), KittyMCError> {
-        let mut size = 0;
+impl SerializablePacket for LoginSuccessPacket {
+    fn serialize_fields(&self, packet: &mut Vec<u8>) {
+        write_length_prefixed_string(packet, &self.uuid.hyphenated().to_string());
+        write_length_prefixed_string(packet, &self.username);
+    }
 
-        let uuid = read_length_prefixed_string(&mut data, &mut size)?;
-        let uuid = Uuid::from_str(&uuid)?;
+    fn deserialize(data: &[u8]) -> Result<(usize, Packet), KittyMCError> {
+        let (size, packet) = Self::read(data)?;
 
-        let username = read_length_prefixed_string(&mut data, &mut size)?;
+        Ok((size, Packet::LoginSuccess(packet)))
+    }
+
+    fn deserialize_clientbound(data: &[u8]) -> Result<(usize, ClientboundPacket), KittyMCError> {
+        let (size, packet) = Self::read(data)?;
 
-        Ok((
-            size,
-            Packet::LoginSuccess(LoginSuccessPacket { uuid, username }),
-        ))
+        Ok((size, ClientboundPacket::LoginSuccess(packet)))
     }
 
     fn id() -> u32 {
@@ -1,5 +1,6 @@
-use crate::packets::packet_serialization::{write_u8, write_varint_i32, SerializablePacket};
-use crate::packets::wrap_packet;
+use crate::error::KittyMCError;
+use crate::packets::packet_fields::PacketField;
+use crate::packets::packet_serialization::{read_u8, write_u8};
 use kittymc_macros::Packet;
 
 #[derive(PartialEq, Debug, Clone)]
@@ -12,24 +13,36 @@ pub enum AnimationType {
     MagicCriticalEffect = 5,
 }
 
-#[derive(PartialEq, Debug, Clone, Packet)]
-pub struct ServerAnimationPacket {
-    pub entity_id: i32,
-    pub animation: AnimationType,
-}
-
-impl SerializablePacket for ServerAnimationPacket {
-    fn serialize(&self) -> Vec<u8> {
-        let mut packet = vec![];
+impl TryFrom<u8> for AnimationType {
+    type Error = KittyMCError;
 
-        write_varint_i32(&mut packet, self.entity_id);
-        write_u8(&mut packet, self.animation.clone() as u8);
-        wrap_packet(&mut packet, Self::id());
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => AnimationType::SwingMainArm,
+            1 => AnimationType::TakeDamage,
+            2 => AnimationType::LeaveBed,
+            3 => AnimationType::SwingOffHand,
+            4 => AnimationType::CriticalEffect,
+            5 => AnimationType::MagicCriticalEffect,
+            _ => return Err(KittyMCError::InvalidEnumValue("AnimationType", value as i64)),
+        })
+    }
+}
 
-        packet
+impl PacketField for AnimationType {
+    fn write_field(&self, buffer: &mut Vec<u8>) {
+        write_u8(buffer, self.clone() as u8);
     }
 
-    fn id() -> u32 {
-        6
+    fn read_field(data: &mut &[u8], total_size: &mut usize) -> Result<Self, KittyMCError> {
+        AnimationType::try_from(read_u8(data, total_size)?)
     }
 }
+
+#[derive(PartialEq, Debug, Clone, Packet)]
+#[packet(id = 0x06, state = Play, direction = Clientbound)]
+pub struct ServerAnimationPacket {
+    #[packet(varint)]
+    pub entity_id: i32,
+    pub animation: AnimationType,
+}
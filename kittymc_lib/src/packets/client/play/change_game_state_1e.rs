@@ -0,0 +1,21 @@
+use crate::packets::client::play::GameMode;
+use kittymc_macros::Packet;
+
+const CHANGE_GAME_MODE: u8 = 3;
+
+#[derive(PartialEq, Debug, Clone, Packet)]
+#[packet(id = 0x1E, state = Play, direction = Clientbound)]
+pub struct ChangeGameStatePacket {
+    pub reason: u8,
+    pub value: f32,
+}
+
+impl ChangeGameStatePacket {
+    /// Switches the game mode of the player receiving it. The hardcore flag can't be changed.
+    pub fn change_game_mode(game_mode: GameMode) -> Self {
+        ChangeGameStatePacket {
+            reason: CHANGE_GAME_MODE,
+            value: (game_mode as u8 & 0b111) as f32,
+        }
+    }
+}
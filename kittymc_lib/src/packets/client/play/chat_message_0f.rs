@@ -1,5 +1,6 @@
-use crate::packets::packet_serialization::{write_i8, SerializablePacket};
-use crate::packets::wrap_packet;
+use crate::error::KittyMCError;
+use crate::packets::packet_fields::PacketField;
+use crate::packets::packet_serialization::{read_i8, write_i8};
 use crate::subtypes::components::Component;
 use kittymc_macros::Packet;
 
@@ -10,10 +11,34 @@ pub enum ChatPosition {
     Hotbar = 2,
 }
 
-#[derive(Debug, Packet)]
+impl TryFrom<i8> for ChatPosition {
+    type Error = KittyMCError;
+
+    fn try_from(value: i8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => ChatPosition::Chat,
+            1 => ChatPosition::System,
+            2 => ChatPosition::Hotbar,
+            _ => return Err(KittyMCError::InvalidEnumValue("ChatPosition", value as i64)),
+        })
+    }
+}
+
+impl PacketField for ChatPosition {
+    fn write_field(&self, buffer: &mut Vec<u8>) {
+        write_i8(buffer, *self as i8);
+    }
+
+    fn read_field(data: &mut &[u8], total_size: &mut usize) -> Result<Self, KittyMCError> {
+        ChatPosition::try_from(read_i8(data, total_size)?)
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Packet)]
+#[packet(id = 0x0F, state = Play, direction = Clientbound)]
 pub struct ClientChatMessagePacket {
-    text: Component,
-    position: ChatPosition,
+    pub text: Component,
+    pub position: ChatPosition,
 }
 
 impl ClientChatMessagePacket {
@@ -38,28 +63,3 @@ impl ClientChatMessagePacket {
         }
     }
 }
-
-impl SerializablePacket for ClientChatMessagePacket {
-    fn serialize(&self) -> Vec<u8> {
-        let mut packet = vec![];
-
-        // write_length_prefixed_string(
-        //     &mut packet,
-        //     &serde_json::to_string(&json!({
-        //         "text": self.text
-        //     }))
-        //     .unwrap(),
-        // );
-
-        self.text.write(&mut packet);
-        write_i8(&mut packet, self.position as i8);
-
-        wrap_packet(&mut packet, Self::id());
-
-        packet
-    }
-
-    fn id() -> u32 {
-        0x0F
-    }
-}
@@ -1,16 +1,23 @@
 use crate::subtypes::Location;
 use crate::packets::packet_serialization::{
-    write_bool, write_i32, write_u64, write_u8, write_varint_u32, write_varint_u32_splice,
-    SerializablePacket,
+    read_bool, read_i32, read_nbt, read_u64, read_u8, read_varint_u32, write_bool, write_i32,
+    write_nbt, write_u64, write_u8, write_varint_u32, SerializablePacket,
 };
-use crate::packets::wrap_packet;
+use crate::packets::packet_buffer::PacketBuffer;
+use crate::packets::{ClientboundPacket, CompressionInfo};
+use integer_encoding::VarInt;
 use kittymc_macros::Packet;
 use lazy_static::lazy_static;
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::fs;
 use std::path::Path;
-use savefile::{load_file, save_file_compressed};
+use savefile::{load_from_mem, save_compressed};
 use savefile_derive::Savefile;
 use crate::error::KittyMCError;
+use crate::utils::write_file_atomically;
 
 const NUM_SECTIONS_PER_CHUNK_COLUMN: usize = 16;
 
@@ -22,6 +29,8 @@ const MAX_PALETTE_BITS: u8 = 8;
 
 const GLOBAL_BITS_PER_BLOCK: u8 = 13;
 
+pub const MAX_LIGHT_LEVEL: u8 = 15;
+
 pub type BlockStateId = u32;
 
 #[derive(PartialEq, Debug, Clone)]
@@ -46,6 +55,31 @@ impl ChunkSection {
         }
     }
 
+    /// How many bytes [`Self::write`] writes
+    pub fn serialized_len(&self, has_sky_light: bool) -> usize {
+        let palette_len = if self.bits_per_block <= MAX_PALETTE_BITS {
+            (self.palette.len() as u32).required_space()
+                + self
+                    .palette
+                    .iter()
+                    .map(|pal_entry| pal_entry.required_space())
+                    .sum::<usize>()
+        } else {
+            1
+        };
+
+        let mut len = 1
+            + palette_len
+            + (self.data.len() as u32).required_space()
+            + self.data.len() * 8
+            + self.block_light.len();
+        if has_sky_light {
+            len += self.sky_light.len();
+        }
+
+        len
+    }
+
     pub fn write(&self, out: &mut Vec<u8>, has_sky_light: bool) {
         write_u8(out, self.bits_per_block);
 
@@ -78,15 +112,245 @@ impl ChunkSection {
         }
     }
 
+    pub fn read(
+        data: &mut &[u8],
+        size: &mut usize,
+        section_y: u32,
+        has_sky_light: bool,
+    ) -> Result<Self, KittyMCError> {
+        let bits_per_block = read_u8(data, size)?;
+        if bits_per_block == 0 || bits_per_block > 64 {
+            return Err(KittyMCError::DeserializationError);
+        }
+
+        // Every entry takes at least a byte, so a length longer than what's left can't be right
+        let palette_len = read_varint_u32(data, size)? as usize;
+        if palette_len > data.len() {
+            return Err(KittyMCError::NotEnoughBytesToDeserialize(
+                "Palette",
+                palette_len,
+                data.len(),
+            ));
+        }
+        let mut palette = Vec::with_capacity(palette_len);
+        for _ in 0..palette_len {
+            palette.push(read_varint_u32(data, size)?);
+        }
+
+        let data_len = read_varint_u32(data, size)? as usize;
+        if data_len * 64 < SECTION_SIZE * bits_per_block as usize {
+            return Err(KittyMCError::DeserializationError);
+        }
+        if data_len.saturating_mul(8) > data.len() {
+            return Err(KittyMCError::NotEnoughBytesToDeserialize(
+                "Block Data",
+                data_len.saturating_mul(8),
+                data.len(),
+            ));
+        }
+        let mut longs = Vec::with_capacity(data_len);
+        for _ in 0..data_len {
+            longs.push(read_u64(data, size)?);
+        }
+
+        let block_light = read_light(data, size)?;
+        let sky_light = match has_sky_light {
+            true => read_light(data, size)?,
+            false => vec![0; SECTION_SIZE / 2],
+        };
+
+        Ok(ChunkSection {
+            bits_per_block,
+            palette,
+            data: longs,
+            block_light,
+            sky_light,
+            section_y,
+        })
+    }
+
+    /// Unpacks all block states of this section in y, z, x order
+    pub fn block_states(&self) -> Result<Vec<BlockStateId>, KittyMCError> {
+        let bits = self.bits_per_block as usize;
+        let mask = u64::MAX >> (64 - bits);
+        let uses_palette = self.bits_per_block <= MAX_PALETTE_BITS;
+
+        let mut states = Vec::with_capacity(SECTION_SIZE);
+        for i in 0..SECTION_SIZE {
+            let bit_index = i * bits;
+            let word = bit_index / 64;
+            let offset = bit_index % 64;
+
+            let mut value = self.data[word] >> offset;
+            if offset + bits > 64 {
+                value |= self.data[word + 1] << (64 - offset);
+            }
+            let value = value & mask;
+
+            let state = match uses_palette {
+                true => *self
+                    .palette
+                    .get(value as usize)
+                    .ok_or(KittyMCError::DeserializationError)?,
+                false => value as BlockStateId,
+            };
+            states.push(state);
+        }
+
+        Ok(states)
+    }
+
     pub fn section_y(&self) -> u32 {
         self.section_y
     }
 }
 
-#[derive(PartialEq, Debug, Clone, Savefile)]
+fn read_light(data: &mut &[u8], size: &mut usize) -> Result<Vec<u8>, KittyMCError> {
+    let len = SECTION_SIZE / 2;
+    let Some(light) = data.get(..len) else {
+        return Err(KittyMCError::NotEnoughBytesToDeserialize(
+            "Light",
+            len,
+            data.len(),
+        ));
+    };
+    let light = light.to_vec();
+    *data = &data[len..];
+    *size += len;
+
+    Ok(light)
+}
+
+/// A chunk data packet that was already framed for a connection
+struct EncodedFrame {
+    x: i32,
+    z: i32,
+    /// The compression threshold, if the connection had compression enabled
+    threshold: Option<u32>,
+    frame: Arc<[u8]>,
+}
+
+/// The encoded sections of a chunk, each filled in the first time it's needed and cleared when a
+/// block in it changes. Two chunks with the same blocks are equal no matter what they have cached.
+#[derive(Default)]
+pub struct ChunkEncodingCache {
+    /// The sections as written with sky light, `None` for empty ones
+    sections: [OnceLock<Option<Box<[u8]>>>; NUM_SECTIONS_PER_CHUNK_COLUMN],
+    /// The last whole packet that was built, which is only valid while no section changed
+    frame: Mutex<Option<EncodedFrame>>,
+}
+
+impl ChunkEncodingCache {
+    fn invalidate_section(&mut self, section_y: usize) {
+        if let Some(section) = self.sections.get_mut(section_y) {
+            section.take();
+        }
+        self.invalidate_frame();
+    }
+
+    fn invalidate_frame(&mut self) {
+        if let Ok(frame) = self.frame.get_mut() {
+            frame.take();
+        }
+    }
+
+    /// How many sections are cached right now, empty ones included
+    pub fn cached_sections(&self) -> usize {
+        self.sections
+            .iter()
+            .filter(|section| section.get().is_some())
+            .count()
+    }
+}
+
+impl Clone for ChunkEncodingCache {
+    /// Keeps the sections, the frame belongs to a position
+    fn clone(&self) -> Self {
+        ChunkEncodingCache {
+            sections: self.sections.clone(),
+            frame: Mutex::new(None),
+        }
+    }
+}
+
+// Never saved, but Savefile wants every field to say whether it can be written as raw memory
+impl savefile::Packed for ChunkEncodingCache {}
+
+impl PartialEq for ChunkEncodingCache {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Debug for ChunkEncodingCache {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChunkEncodingCache")
+            .field("cached_sections", &self.cached_sections())
+            .finish_non_exhaustive()
+    }
+}
+
+/// The block and sky light of a chunk column, packed two blocks per byte the same way sections
+/// are sent. Without a light engine filling it in, everything is dark but in full daylight.
+#[derive(PartialEq, Debug, Clone)]
+struct ChunkLight {
+    block: Vec<u8>,
+    sky: Vec<u8>,
+}
+
+impl ChunkLight {
+    fn get(levels: &[u8], index: usize) -> Option<u8> {
+        let byte = levels.get(index / 2)?;
+        Some(match index % 2 {
+            0 => byte & 0x0F,
+            _ => byte >> 4,
+        })
+    }
+
+    /// Returns whether the level changed
+    fn set(levels: &mut [u8], index: usize, level: u8) -> Option<bool> {
+        let byte = levels.get_mut(index / 2)?;
+        let level = level.min(MAX_LIGHT_LEVEL);
+        let new = match index % 2 {
+            0 => (*byte & 0xF0) | level,
+            _ => (*byte & 0x0F) | level << 4,
+        };
+        let changed = *byte != new;
+        *byte = new;
+
+        Some(changed)
+    }
+}
+
+impl Default for ChunkLight {
+    fn default() -> Self {
+        let len = SECTION_SIZE * NUM_SECTIONS_PER_CHUNK_COLUMN / 2;
+        ChunkLight {
+            block: vec![0; len],
+            sky: vec![0xFF; len],
+        }
+    }
+}
+
+// Light is worked out again whenever a chunk is loaded, so it's never saved
+impl savefile::Packed for ChunkLight {}
+
+/// A chunk column. Blocks and light should be changed through [`Chunk::set_block`] and friends,
+/// which keep the cached encoding up to date and remember that the chunk has to be saved.
+#[derive(Debug, Clone, Savefile)]
 pub struct Chunk {
     pub blocks: Vec<BlockStateId>,
     pub biomes: Vec<u8>,
+    #[savefile_ignore]
+    #[savefile_introspect_ignore]
+    light: ChunkLight,
+    #[savefile_ignore]
+    #[savefile_introspect_ignore]
+    encoding: Box<ChunkEncodingCache>,
+    /// Whether a block changed since the chunk was last saved or loaded
+    #[savefile_ignore]
+    #[savefile_introspect_ignore]
+    dirty: bool,
 }
 
 impl Default for Chunk {
@@ -94,24 +358,90 @@ impl Default for Chunk {
         Chunk {
             blocks: vec![0; SECTION_SIZE * NUM_SECTIONS_PER_CHUNK_COLUMN],
             biomes: vec![1; 16 * 16],
+            light: ChunkLight::default(),
+            encoding: Box::default(),
+            dirty: false,
         }
     }
 }
 
+// Two chunks with the same blocks are equal no matter if they were saved
+impl PartialEq for Chunk {
+    fn eq(&self, other: &Self) -> bool {
+        self.blocks == other.blocks && self.biomes == other.biomes && self.light == other.light
+    }
+}
+
+fn block_index(x: usize, y: usize, z: usize) -> Option<usize> {
+    (x < SECTION_WIDTH && z < SECTION_WIDTH && y < SECTION_HEIGHT * NUM_SECTIONS_PER_CHUNK_COLUMN)
+        .then_some(y * 16 * 16 + z * 16 + x)
+}
+
 impl Chunk {
     pub fn get_block(&self, x: usize, y: usize, z: usize) -> Option<BlockStateId> {
-        self.blocks.get(y * 16 * 16 + z * 16 + x).cloned()
+        self.blocks.get(block_index(x, y, z)?).cloned()
     }
 
     pub fn set_block(&mut self, x: usize, y: usize, z: usize, state: BlockStateId) -> Result<(), KittyMCError> {
-        let Some(block) = self.blocks.get_mut(y * 16 * 16 + z * 16 + x) else {
+        let Some(block) = block_index(x, y, z).and_then(|index| self.blocks.get_mut(index)) else {
             return Err(KittyMCError::InvalidBlock(Location::new(x as f32, y as f32, z as f32)));
         };
 
-        *block = state;
+        if *block != state {
+            *block = state;
+            self.encoding.invalidate_section(y / SECTION_HEIGHT);
+            self.dirty = true;
+        }
+        Ok(())
+    }
+
+    /// Whether the chunk changed since it was last saved. Light isn't saved, so changing it
+    /// doesn't count.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Makes the chunk get saved even though none of its blocks changed
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    pub fn block_light(&self, x: usize, y: usize, z: usize) -> Option<u8> {
+        ChunkLight::get(&self.light.block, block_index(x, y, z)?)
+    }
+
+    /// Sets the light emitted by or spread to a block, from 0 to [`MAX_LIGHT_LEVEL`]
+    pub fn set_block_light(&mut self, x: usize, y: usize, z: usize, level: u8) -> Result<(), KittyMCError> {
+        let changed = block_index(x, y, z)
+            .and_then(|index| ChunkLight::set(&mut self.light.block, index, level))
+            .ok_or(KittyMCError::InvalidBlock(Location::new(x as f32, y as f32, z as f32)))?;
+
+        if changed {
+            self.encoding.invalidate_section(y / SECTION_HEIGHT);
+        }
+        Ok(())
+    }
+
+    pub fn sky_light(&self, x: usize, y: usize, z: usize) -> Option<u8> {
+        ChunkLight::get(&self.light.sky, block_index(x, y, z)?)
+    }
+
+    /// Sets how much sky light reaches a block, from 0 to [`MAX_LIGHT_LEVEL`]
+    pub fn set_sky_light(&mut self, x: usize, y: usize, z: usize, level: u8) -> Result<(), KittyMCError> {
+        let changed = block_index(x, y, z)
+            .and_then(|index| ChunkLight::set(&mut self.light.sky, index, level))
+            .ok_or(KittyMCError::InvalidBlock(Location::new(x as f32, y as f32, z as f32)))?;
+
+        if changed {
+            self.encoding.invalidate_section(y / SECTION_HEIGHT);
+        }
         Ok(())
     }
 
+    pub fn encoding_cache(&self) -> &ChunkEncodingCache {
+        &self.encoding
+    }
+
     fn is_section_empty(&self, section_y: usize) -> bool {
         let start_y = section_y * SECTION_HEIGHT;
         let end_y = start_y + SECTION_HEIGHT;
@@ -131,138 +461,280 @@ impl Chunk {
         let mut sections = Vec::new();
 
         for section_y in 0..NUM_SECTIONS_PER_CHUNK_COLUMN {
-            if self.is_section_empty(section_y) {
-                continue;
+            if let Some(section) = self.section(section_y) {
+                *primary_bit_mask_out |= 1 << section_y;
+                sections.push(section);
             }
+        }
+
+        sections
+    }
 
-            *primary_bit_mask_out |= 1 << section_y;
+    /// Palettizes one section, or returns `None` if it's empty
+    pub fn section(&self, section_y: usize) -> Option<ChunkSection> {
+        if self.is_section_empty(section_y) {
+            return None;
+        }
 
-            let mut block_states = Vec::with_capacity(SECTION_SIZE);
-            let base_y = section_y * SECTION_HEIGHT;
-            for y in 0..SECTION_HEIGHT {
-                for z in 0..SECTION_WIDTH {
-                    for x in 0..SECTION_WIDTH {
-                        let global_y = base_y + y;
-                        block_states.push(self.get_block(x, global_y, z).unwrap());
-                    }
+        let mut block_states = Vec::with_capacity(SECTION_SIZE);
+        let base_y = section_y * SECTION_HEIGHT;
+        for y in 0..SECTION_HEIGHT {
+            for z in 0..SECTION_WIDTH {
+                for x in 0..SECTION_WIDTH {
+                    let global_y = base_y + y;
+                    block_states.push(self.get_block(x, global_y, z).unwrap());
                 }
             }
+        }
 
-            let mut distinct_map = HashMap::new();
-            for &block_id in &block_states {
-                distinct_map.entry(block_id).or_insert(true);
-            }
-            let distinct_count = distinct_map.len();
+        let mut distinct_map = HashMap::new();
+        for &block_id in &block_states {
+            distinct_map.entry(block_id).or_insert(true);
+        }
+        let distinct_count = distinct_map.len();
 
-            let bits_per_block = if distinct_count <= 1 {
-                4
-            } else if distinct_count > 256 {
-                GLOBAL_BITS_PER_BLOCK
-            } else {
-                let b = (64 - (distinct_count - 1).leading_zeros()) as u8;
-                let b = b.max(4).min(MAX_PALETTE_BITS);
-                b
-            };
+        let bits_per_block = if distinct_count <= 1 {
+            4
+        } else if distinct_count > 256 {
+            GLOBAL_BITS_PER_BLOCK
+        } else {
+            let b = (64 - (distinct_count - 1).leading_zeros()) as u8;
+            b.clamp(4, MAX_PALETTE_BITS)
+        };
 
-            let mut section = ChunkSection::new(bits_per_block, section_y as u32);
+        let mut section = ChunkSection::new(bits_per_block, section_y as u32);
 
-            let mut palette_index_map = HashMap::new();
-            if bits_per_block <= MAX_PALETTE_BITS {
-                let mut local_palette: Vec<BlockStateId> = distinct_map.keys().copied().collect();
-                local_palette.sort_unstable();
+        let mut palette_index_map = HashMap::new();
+        if bits_per_block <= MAX_PALETTE_BITS {
+            let mut local_palette: Vec<BlockStateId> = distinct_map.keys().copied().collect();
+            local_palette.sort_unstable();
 
-                for (idx, &bs) in local_palette.iter().enumerate() {
-                    palette_index_map.insert(bs, idx as u64);
-                }
-                section.palette = local_palette;
+            for (idx, &bs) in local_palette.iter().enumerate() {
+                palette_index_map.insert(bs, idx as u64);
             }
+            section.palette = local_palette;
+        }
 
-            let mut data_words = Vec::new();
-            let mut current_word = 0u64;
-            let mut bits_filled = 0;
-
-            for &block_id in &block_states {
-                let index = if bits_per_block <= MAX_PALETTE_BITS {
-                    *palette_index_map.get(&block_id).unwrap()
-                } else {
-                    block_id as u64
-                };
-
-                // put index in current_word
-                current_word |= index << bits_filled;
-                bits_filled += bits_per_block;
-
-                if bits_filled >= 64 {
-                    data_words.push(current_word);
-                    current_word = 0;
-                    bits_filled = 0;
-                }
-            }
-            if bits_filled > 0 {
-                data_words.push(current_word);
-            }
+        let bits = bits_per_block as usize;
+        let mut data_words = vec![0u64; SECTION_SIZE * bits / 64];
 
-            section.data = data_words;
+        for (i, &block_id) in block_states.iter().enumerate() {
+            let index = if bits_per_block <= MAX_PALETTE_BITS {
+                *palette_index_map.get(&block_id).unwrap()
+            } else {
+                block_id as u64
+            };
 
-            for byte in &mut section.block_light {
-                *byte = 0x0;
-            }
-            for byte in &mut section.sky_light {
-                *byte = 0xFF;
+            // entries may span across two longs
+            let bit_index = i * bits;
+            let word = bit_index / 64;
+            let offset = bit_index % 64;
+            data_words[word] |= index << offset;
+            if offset + bits > 64 {
+                data_words[word + 1] |= index >> (64 - offset);
             }
+        }
+
+        section.data = data_words;
+
+        let light = section_y * SECTION_SIZE / 2..(section_y + 1) * SECTION_SIZE / 2;
+        section.block_light.copy_from_slice(&self.light.block[light.clone()]);
+        section.sky_light.copy_from_slice(&self.light.sky[light]);
 
-            sections.push(section);
+        Some(section)
+    }
+
+    /// The section as it's written in a chunk data packet, or `None` if it's empty. Sections with
+    /// sky light are cached until one of their blocks changes.
+    pub fn encoded_section(
+        &self,
+        section_y: usize,
+        dimension_has_sky_light: bool,
+    ) -> Option<Cow<'_, [u8]>> {
+        let encode = |has_sky_light| {
+            self.section(section_y).map(|section| {
+                let mut buf = Vec::with_capacity(section.serialized_len(has_sky_light));
+                section.write(&mut buf, has_sky_light);
+                buf
+            })
+        };
+
+        if !dimension_has_sky_light {
+            return encode(false).map(Cow::Owned);
         }
 
-        sections
+        self.encoding.sections[section_y]
+            .get_or_init(|| encode(true).map(Vec::into_boxed_slice))
+            .as_deref()
+            .map(Cow::Borrowed)
     }
 
+    /// Writes the primary bit mask followed by the size prefixed chunk column data. The size is
+    /// added up beforehand, so nothing has to be moved to put it in front.
     pub fn write(
         &self,
         buf: &mut Vec<u8>,
         ground_up_continuous: bool,
         dimension_has_sky_light: bool,
-        primary_bit_mask: &mut u32,
     ) {
-        let start_len = buf.len();
+        let sections: Vec<_> = (0..NUM_SECTIONS_PER_CHUNK_COLUMN)
+            .map(|section_y| self.encoded_section(section_y, dimension_has_sky_light))
+            .collect();
+
+        let primary_bit_mask = sections
+            .iter()
+            .enumerate()
+            .filter(|(_, section)| section.is_some())
+            .fold(0u32, |mask, (section_y, _)| mask | 1 << section_y);
+        write_varint_u32(buf, primary_bit_mask);
+
+        let mut full_size: usize = sections.iter().flatten().map(|section| section.len()).sum();
+        if ground_up_continuous {
+            full_size += self.biomes.len();
+        }
+        write_varint_u32(buf, full_size as u32);
+        buf.reserve(full_size);
 
-        let sections = self.to_chunk_sections(primary_bit_mask);
+        for section in sections.iter().flatten() {
+            buf.extend_from_slice(section);
+        }
 
-        for section_y in 0..NUM_SECTIONS_PER_CHUNK_COLUMN {
-            if (*primary_bit_mask & (1 << section_y)) == 0 {
-                continue;
+        if ground_up_continuous {
+            buf.extend_from_slice(self.biomes.as_slice());
+        }
+    }
+
+    /// Reads the chunk column data of a chunk data packet, not including the size prefix.
+    pub fn read(
+        data: &[u8],
+        ground_up_continuous: bool,
+        primary_bit_mask: u32,
+    ) -> Result<Self, KittyMCError> {
+        let biome_len = if ground_up_continuous { 16 * 16 } else { 0 };
+
+        // The packet doesn't tell us if the dimension has sky light, but only one of both will
+        // leave exactly the biomes behind
+        let mut parsed = None;
+        for has_sky_light in [true, false] {
+            let mut rest = data;
+            let mut size = 0;
+            let sections: Result<Vec<ChunkSection>, KittyMCError> = (0..NUM_SECTIONS_PER_CHUNK_COLUMN)
+                .filter(|section_y| primary_bit_mask & (1 << section_y) != 0)
+                .map(|section_y| {
+                    ChunkSection::read(&mut rest, &mut size, section_y as u32, has_sky_light)
+                })
+                .collect();
+
+            if let Ok(sections) = sections {
+                if rest.len() == biome_len {
+                    parsed = Some((sections, rest, has_sky_light));
+                    break;
+                }
+            }
+        }
+
+        let Some((sections, biomes, has_sky_light)) = parsed else {
+            return Err(KittyMCError::DeserializationError);
+        };
+
+        let mut chunk = Chunk::default();
+        for section in &sections {
+            let base_y = section.section_y() as usize * SECTION_HEIGHT;
+            for (i, state) in section.block_states()?.into_iter().enumerate() {
+                chunk.blocks[base_y * SECTION_WIDTH * SECTION_WIDTH + i] = state;
+            }
+
+            let light_start = section.section_y() as usize * SECTION_SIZE / 2;
+            let light = light_start..light_start + SECTION_SIZE / 2;
+            chunk.light.block[light.clone()].copy_from_slice(&section.block_light);
+            if has_sky_light {
+                chunk.light.sky[light].copy_from_slice(&section.sky_light);
             }
-            let section = &sections
-                .iter()
-                .find(|s| s.section_y() == section_y as u32)
-                .unwrap();
-            section.write(buf, dimension_has_sky_light);
         }
 
         if ground_up_continuous {
-            buf.extend_from_slice(self.biomes.as_slice());
+            chunk.biomes = biomes.to_vec();
         }
 
-        let full_size = (buf.len() - start_len) as u32;
-        write_varint_u32_splice(buf, full_size, start_len..start_len);
+        Ok(chunk)
     }
 
-    pub fn save_to(&self, path: &Path) -> Result<(), KittyMCError> {
-        Ok(save_file_compressed(path, 0, self)?)
+    /// The chunk data packet for this chunk at `x`, `z`, framed for a connection with
+    /// `compression`. The frame is kept until a block changes, so everyone loading the chunk
+    /// shares the work of compressing it.
+    pub fn encoded_frame(
+        &self,
+        x: i32,
+        z: i32,
+        compression: &CompressionInfo,
+    ) -> Result<Arc<[u8]>, KittyMCError> {
+        let threshold = compression
+            .enabled
+            .then_some(compression.compression_threshold);
+
+        let mut cached = self
+            .encoding
+            .frame
+            .lock()
+            .map_err(|_| KittyMCError::LockPoisonError)?;
+        if let Some(encoded) = cached.as_ref() {
+            if encoded.x == x && encoded.z == z && encoded.threshold == threshold {
+                return Ok(encoded.frame.clone());
+            }
+        }
+
+        let mut buffer = PacketBuffer::new();
+        let frame: Arc<[u8]> =
+            Arc::from(&*buffer.write_packet_with(&ChunkDataPacket::new(self, x, z), compression)?);
+        *cached = Some(EncodedFrame {
+            x,
+            z,
+            threshold,
+            frame: frame.clone(),
+        });
+
+        Ok(frame)
+    }
+
+    /// Remembers that the chunk was written somewhere as it is now
+    pub fn mark_saved(&mut self) {
+        self.dirty = false;
+    }
+
+    /// The blocks and biomes of the chunk in the compressed savefile format
+    pub fn to_bytes(&self) -> Result<Vec<u8>, KittyMCError> {
+        let mut data = Vec::new();
+        save_compressed(&mut data, 0, self)?;
+        Ok(data)
+    }
+
+    /// Reads what [`Chunk::to_bytes`] wrote. Light has to be worked out again afterwards.
+    pub fn from_bytes(data: &[u8]) -> Result<Box<Self>, KittyMCError> {
+        Ok(load_from_mem(data, 0)?)
+    }
+
+    /// Writes the chunk to a temporary file next to `path` and then moves it into place, so a
+    /// crash can't leave a half written chunk behind. The chunk is clean afterwards.
+    pub fn save_to(&mut self, path: &Path) -> Result<(), KittyMCError> {
+        write_file_atomically(path, &self.to_bytes()?)?;
+        self.mark_saved();
+
+        Ok(())
     }
 
     pub fn load_from(path: &Path) -> Result<Box<Self>, KittyMCError> {
-        Ok(load_file(path, 0)?)
+        Self::from_bytes(&fs::read(path)?)
     }
 }
 
 #[derive(PartialEq, Debug, Clone, Packet)]
+#[packet(state = Play, direction = Clientbound)]
 pub struct ChunkDataPacket<'a> {
-    x: i32,
-    z: i32,
-    ground_up_continuous: bool,
-    data: &'a Chunk,
-    block_entities: Vec<()>,
+    pub x: i32,
+    pub z: i32,
+    pub ground_up_continuous: bool,
+    pub data: Cow<'a, Chunk>,
+    pub block_entities: Vec<fastnbt::Value>,
 }
 
 lazy_static! {
@@ -334,48 +806,73 @@ impl ChunkDataPacket<'_> {
             x,
             z,
             ground_up_continuous: true,
-            data: &DEFAULT_FLAT_CHUNK,
+            data: Cow::Borrowed(DEFAULT_FLAT_CHUNK.as_ref()),
             block_entities: vec![],
         }
     }
 
-    pub fn new(chunk: &Chunk, x: i32, z: i32) -> ChunkDataPacket {
+    pub fn new(chunk: &Chunk, x: i32, z: i32) -> ChunkDataPacket<'_> {
         ChunkDataPacket {
             x,
             z,
             ground_up_continuous: true,
-            data: chunk,
+            data: Cow::Borrowed(chunk),
             block_entities: vec![],
         }
     }
 }
 
 impl SerializablePacket for ChunkDataPacket<'_> {
-    fn serialize(&self) -> Vec<u8> {
-        let mut packet = vec![];
-
-        write_i32(&mut packet, self.x);
-        write_i32(&mut packet, self.z);
-        write_bool(&mut packet, self.ground_up_continuous);
-
-        let mask_pos = packet.len();
+    fn serialize_fields(&self, packet: &mut Vec<u8>) {
+        write_i32(packet, self.x);
+        write_i32(packet, self.z);
+        write_bool(packet, self.ground_up_continuous);
 
-        let mut primary_bit_mask = 0u32;
-        self.data.write(
-            &mut packet,
-            self.ground_up_continuous,
-            true,
-            &mut primary_bit_mask,
-        ); // TODO: Nobody knows if this is an overworld chunk or not yet
+        // TODO: Nobody knows if this is an overworld chunk or not yet
+        self.data.write(packet, self.ground_up_continuous, true);
 
-        self.data.to_chunk_sections(&mut primary_bit_mask);
-        write_varint_u32_splice(&mut packet, primary_bit_mask, mask_pos..mask_pos);
-
-        write_varint_u32(&mut packet, 0u32); // TODO: Implement Block Entitites
+        write_varint_u32(packet, self.block_entities.len() as u32);
+        for block_entity in &self.block_entities {
+            write_nbt(packet, block_entity);
+        }
+    }
 
-        wrap_packet(&mut packet, Self::id());
+    fn deserialize_clientbound(mut data: &[u8]) -> Result<(usize, ClientboundPacket), KittyMCError> {
+        let mut size = 0;
+
+        let x = read_i32(&mut data, &mut size)?;
+        let z = read_i32(&mut data, &mut size)?;
+        let ground_up_continuous = read_bool(&mut data, &mut size)?;
+        let primary_bit_mask = read_varint_u32(&mut data, &mut size)?;
+
+        let chunk_data_len = read_varint_u32(&mut data, &mut size)? as usize;
+        let Some(chunk_data) = data.get(..chunk_data_len) else {
+            return Err(KittyMCError::NotEnoughBytesToDeserialize(
+                "Chunk Data",
+                chunk_data_len,
+                data.len(),
+            ));
+        };
+        let chunk = Chunk::read(chunk_data, ground_up_continuous, primary_bit_mask)?;
+        data = &data[chunk_data_len..];
+        size += chunk_data_len;
+
+        let block_entity_count = read_varint_u32(&mut data, &mut size)?;
+        let mut block_entities = Vec::new();
+        for _ in 0..block_entity_count {
+            block_entities.push(read_nbt(&mut data, &mut size)?);
+        }
 
-        packet
+        Ok((
+            size,
+            ClientboundPacket::ChunkData(ChunkDataPacket {
+                x,
+                z,
+                ground_up_continuous,
+                data: Cow::Owned(chunk),
+                block_entities,
+            }),
+        ))
     }
 
     fn id() -> u32 {
@@ -1,12 +1,11 @@
 use std::error::Error;
-use crate::packets::packet_serialization::SerializablePacket;
-use crate::packets::wrap_packet;
 use crate::subtypes::components::Component;
 use kittymc_macros::Packet;
 
 #[derive(PartialEq, Debug, Clone, Packet)]
+#[packet(id = 0x1A, state = Play, direction = Clientbound)]
 pub struct DisconnectPlayPacket {
-    reason: Component,
+    pub reason: Component,
 }
 
 impl DisconnectPlayPacket {
@@ -22,18 +21,3 @@ impl DisconnectPlayPacket {
         }
     }
 }
-
-impl SerializablePacket for DisconnectPlayPacket {
-    fn serialize(&self) -> Vec<u8> {
-        let mut packet = vec![];
-
-        self.reason.write(&mut packet);
-        wrap_packet(&mut packet, Self::id());
-
-        packet
-    }
-
-    fn id() -> u32 {
-        0x1A
-    }
-}
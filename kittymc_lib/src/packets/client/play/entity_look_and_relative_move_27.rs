@@ -0,0 +1,17 @@
+use crate::subtypes::Direction;
+use kittymc_macros::Packet;
+
+/// A relative move and a look in one packet. The deltas are encoded like in
+/// [`super::EntityRelativeMovePacket`].
+#[derive(PartialEq, Debug, Clone, Packet)]
+#[packet(id = 0x27, state = Play, direction = Clientbound)]
+pub struct EntityLookAndRelativeMovePacket {
+    #[packet(varint)]
+    pub entity_id: i32,
+    pub delta_x: i16,
+    pub delta_y: i16,
+    pub delta_z: i16,
+    #[packet(angle)]
+    pub direction: Direction,
+    pub on_ground: bool,
+}
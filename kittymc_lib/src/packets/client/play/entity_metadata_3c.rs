@@ -1,12 +1,14 @@
 use kittymc_macros::Packet;
-use crate::packets::packet_serialization::{write_varint_i32, SerializablePacket};
-use crate::packets::wrap_packet;
-use crate::subtypes::metadata::MetadataObject;
+use crate::error::KittyMCError;
+use crate::packets::packet_serialization::{read_varint_i32, write_varint_i32, SerializablePacket};
+use crate::packets::ClientboundPacket;
+use crate::subtypes::metadata::{read_metadata, MetadataObject};
 
 #[derive(Clone, PartialEq, Debug, Packet)]
+#[packet(state = Play, direction = Clientbound)]
 pub struct EntityMetadataPacket<M> {
-    entity_id: i32,
-    metadata: M,
+    pub entity_id: i32,
+    pub metadata: M,
 }
 
 impl<M: MetadataObject> EntityMetadataPacket<M> {
@@ -16,16 +18,23 @@ impl<M: MetadataObject> EntityMetadataPacket<M> {
 }
 
 impl<M: MetadataObject> SerializablePacket for EntityMetadataPacket<M> {
-    fn serialize(&self) -> Vec<u8> {
-        let mut packet = vec![];
+    fn serialize_fields(&self, packet: &mut Vec<u8>) {
+        write_varint_i32(packet, self.entity_id);
+        self.metadata.write_metadata(packet);
+    }
 
-        write_varint_i32(&mut packet, self.entity_id);
-        self.metadata.write_metadata(&mut packet);
+    fn deserialize_clientbound(mut data: &[u8]) -> Result<(usize, ClientboundPacket), KittyMCError> {
+        let mut size = 0;
 
-        wrap_packet(&mut packet, Self::id());
+        let entity_id = read_varint_i32(&mut data, &mut size)?;
+        let metadata = read_metadata(&mut data, &mut size)?;
 
-        packet
+        Ok((
+            size,
+            ClientboundPacket::EntityMetadata(EntityMetadataPacket::new(entity_id, metadata)),
+        ))
     }
+
     fn id() -> u32 {
         0x3C
     }
@@ -1,9 +1,13 @@
-use crate::packets::packet_serialization::{write_bool, write_i16, write_varint_i32, SerializablePacket};
-use crate::packets::wrap_packet;
+use crate::subtypes::Location2;
 use kittymc_macros::Packet;
 
+/// Relative moves count in 1/4096 of a block
+pub const RELATIVE_MOVE_SCALE: f64 = 4096.;
+
 #[derive(PartialEq, Debug, Clone, Packet)]
+#[packet(id = 0x26, state = Play, direction = Clientbound)]
 pub struct EntityRelativeMovePacket {
+    #[packet(varint)]
     pub entity_id: i32,
     pub delta_x: i16,
     pub delta_y: i16,
@@ -11,22 +15,21 @@ pub struct EntityRelativeMovePacket {
     pub on_ground: bool,
 }
 
-impl SerializablePacket for EntityRelativeMovePacket {
-    fn serialize(&self) -> Vec<u8> {
-        let mut packet = vec![];
-
-        write_varint_i32(&mut packet, self.entity_id);
-        write_i16(&mut packet, self.delta_x);
-        write_i16(&mut packet, self.delta_y);
-        write_i16(&mut packet, self.delta_z);
-        write_bool(&mut packet, self.on_ground);
-
-        wrap_packet(&mut packet, Self::id());
-
-        packet
-    }
+/// The movement from `from` to `to` in the fixed point format of relative moves, or `None` if it's
+/// too far for one and the entity has to be teleported instead.
+///
+/// The client adds the deltas to the fixed point position it knows, so both positions are rounded
+/// down to that grid before subtracting. That way rounding errors don't add up over many moves.
+pub fn relative_move_delta(from: &Location2, to: &Location2) -> Option<[i16; 3]> {
+    let axis = |from: f64, to: f64| {
+        let from = (from * RELATIVE_MOVE_SCALE).floor() as i64;
+        let to = (to * RELATIVE_MOVE_SCALE).floor() as i64;
+        i16::try_from(to - from).ok()
+    };
 
-    fn id() -> u32 {
-        0x26
-    }
+    Some([
+        axis(from.x, to.x)?,
+        axis(from.y, to.y)?,
+        axis(from.z, to.z)?,
+    ])
 }
@@ -0,0 +1,13 @@
+use crate::subtypes::{Direction, Location2};
+use kittymc_macros::Packet;
+
+#[derive(PartialEq, Debug, Clone, Packet)]
+#[packet(id = 0x4C, state = Play, direction = Clientbound)]
+pub struct EntityTeleportPacket {
+    #[packet(varint)]
+    pub entity_id: i32,
+    pub location: Location2,
+    #[packet(angle)]
+    pub direction: Direction,
+    pub on_ground: bool,
+}
@@ -1,20 +1,23 @@
 use crate::packets::client::play::{Difficulty, Dimension, GameMode, LevelType};
+use crate::error::KittyMCError;
 use crate::packets::packet_serialization::{
-    write_bool, write_i32, write_length_prefixed_string, write_u8, SerializablePacket,
+    read_bool, read_i32, read_length_prefixed_string, read_u8, write_bool, write_i32,
+    write_length_prefixed_string, write_u8, SerializablePacket,
 };
-use crate::packets::wrap_packet;
+use crate::packets::ClientboundPacket;
 use kittymc_macros::Packet;
+use std::str::FromStr;
 
-#[derive(Clone, Debug, Packet)]
-#[allow(dead_code)]
+#[derive(PartialEq, Clone, Debug, Packet)]
+#[packet(state = Play, direction = Clientbound)]
 pub struct JoinGamePacket {
-    entity_id: i32,
-    gamemode: GameMode,
-    dimension: Dimension,
-    difficulty: Difficulty,
-    max_players: u8,
-    level_type: LevelType,
-    reduced_debug_info: bool,
+    pub entity_id: i32,
+    pub gamemode: GameMode,
+    pub dimension: Dimension,
+    pub difficulty: Difficulty,
+    pub max_players: u8,
+    pub level_type: LevelType,
+    pub reduced_debug_info: bool,
 }
 
 impl JoinGamePacket {
@@ -25,27 +28,46 @@ impl JoinGamePacket {
             dimension: Dimension::Overworld,
             difficulty: Difficulty::Peaceful,
             max_players: 1,
-            level_type: LevelType::Flat,
+            level_type: LevelType::Default,
             reduced_debug_info: false,
         }
     }
 }
 
 impl SerializablePacket for JoinGamePacket {
-    fn serialize(&self) -> Vec<u8> {
-        let mut packet = vec![];
+    fn serialize_fields(&self, packet: &mut Vec<u8>) {
+        write_i32(packet, self.entity_id);
+        write_u8(packet, self.gamemode as u8);
+        write_i32(packet, self.dimension as i32);
+        write_u8(packet, self.difficulty as u8);
+        write_u8(packet, 69); // TODO: Actual max players
+        write_length_prefixed_string(packet, self.level_type.as_str());
+        write_bool(packet, self.reduced_debug_info);
+    }
 
-        write_i32(&mut packet, self.entity_id);
-        write_u8(&mut packet, self.gamemode as u8);
-        write_i32(&mut packet, self.dimension as i32);
-        write_u8(&mut packet, self.difficulty as u8);
-        write_u8(&mut packet, 69); // TODO: Actual max players
-        write_length_prefixed_string(&mut packet, self.level_type.as_str());
-        write_bool(&mut packet, self.reduced_debug_info);
+    fn deserialize_clientbound(mut data: &[u8]) -> Result<(usize, ClientboundPacket), KittyMCError> {
+        let mut size = 0;
 
-        wrap_packet(&mut packet, Self::id());
+        let entity_id = read_i32(&mut data, &mut size)?;
+        let gamemode = GameMode::try_from(read_u8(&mut data, &mut size)?)?;
+        let dimension = Dimension::try_from(read_i32(&mut data, &mut size)?)?;
+        let difficulty = Difficulty::try_from(read_u8(&mut data, &mut size)?)?;
+        let max_players = read_u8(&mut data, &mut size)?;
+        let level_type = LevelType::from_str(&read_length_prefixed_string(&mut data, &mut size)?)?;
+        let reduced_debug_info = read_bool(&mut data, &mut size)?;
 
-        packet
+        Ok((
+            size,
+            ClientboundPacket::JoinGame(JoinGamePacket {
+                entity_id,
+                gamemode,
+                dimension,
+                difficulty,
+                max_players,
+                level_type,
+                reduced_debug_info,
+            }),
+        ))
     }
 
     fn id() -> u32 {
@@ -1,5 +1,4 @@
 use crate::packets::packet_serialization::SerializablePacket;
-use crate::packets::wrap_packet;
 use kittymc_macros::Packet;
 
 #[allow(dead_code)]
@@ -9,28 +8,12 @@ pub struct ChunkMeta {
     primary_bit_mask: u16,
 }
 
-#[derive(PartialEq, Debug, Clone, Packet)]
+#[derive(PartialEq, Debug, Clone, Default, Packet)]
 pub struct MapChunkBulkPacket {
     sky_light_sent: bool,
 }
 
-impl Default for MapChunkBulkPacket {
-    fn default() -> Self {
-        MapChunkBulkPacket {
-            sky_light_sent: false,
-        }
-    }
-}
-
 impl SerializablePacket for MapChunkBulkPacket {
-    fn serialize(&self) -> Vec<u8> {
-        let mut packet = vec![];
-
-        wrap_packet(&mut packet, Self::id());
-
-        packet
-    }
-
     fn id() -> u32 {
         0x26
     }
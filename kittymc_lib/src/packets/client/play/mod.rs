@@ -1,13 +1,16 @@
 pub mod animation_06;
 pub mod block_break_animation_08;
 pub mod block_change_0b;
+pub mod change_game_state_1e;
 pub mod chat_message_0f;
 pub mod chunk_data_20;
 pub mod disconnect_1a;
 pub mod entity_head_look_36;
 pub mod entity_look_28;
+pub mod entity_look_and_relative_move_27;
 pub mod entity_relative_move_26;
 pub mod entity_status_1b;
+pub mod entity_teleport_4c;
 pub mod join_game_23;
 pub mod keep_alive_1f;
 pub mod map_chunk_bulk_26;
@@ -22,18 +25,25 @@ pub mod spawn_position_46;
 pub mod time_update_47;
 pub mod unload_chunk_1d;
 pub mod unlock_recipes_31;
+pub mod update_health_41;
 pub mod window_items_14;
 pub mod entity_metadata_3c;
 pub mod destroy_entities_32;
+pub mod tab_complete_0e;
 
+pub use animation_06::ServerAnimationPacket;
 pub use block_break_animation_08::BlockBreakAnimationPacket;
 pub use block_change_0b::BlockChangePacket;
+pub use change_game_state_1e::ChangeGameStatePacket;
 pub use chat_message_0f::ClientChatMessagePacket;
 pub use chunk_data_20::ChunkDataPacket;
+pub use disconnect_1a::DisconnectPlayPacket;
 pub use entity_head_look_36::EntityHeadLookPacket;
 pub use entity_look_28::EntityLookPacket;
+pub use entity_look_and_relative_move_27::EntityLookAndRelativeMovePacket;
 pub use entity_relative_move_26::EntityRelativeMovePacket;
 pub use entity_status_1b::EntityStatusPacket;
+pub use entity_teleport_4c::EntityTeleportPacket;
 pub use join_game_23::JoinGamePacket;
 pub use keep_alive_1f::ServerKeepAlivePacket;
 pub use map_chunk_bulk_26::MapChunkBulkPacket;
@@ -48,9 +58,16 @@ pub use spawn_position_46::SpawnPositionPacket;
 pub use time_update_47::TimeUpdatePacket;
 pub use unload_chunk_1d::UnloadChunkPacket;
 pub use unlock_recipes_31::UnlockRecipesPacket;
+pub use update_health_41::UpdateHealthPacket;
 pub use window_items_14::WindowItemsPacket;
 pub use entity_metadata_3c::EntityMetadataPacket;
 pub use destroy_entities_32::DestroyEntitiesPacket;
+pub use tab_complete_0e::ClientTabCompletePacket;
+
+use crate::error::KittyMCError;
+use crate::packets::packet_fields::PacketField;
+use crate::packets::packet_serialization::{read_u8, write_u8};
+use std::str::FromStr;
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum GameMode {
@@ -58,12 +75,30 @@ pub enum GameMode {
     Creative = 1,
     Adventure = 2,
     Spectator = 3,
-    SurvivalH = 0 | 8,
+    SurvivalH = 8,
     CreativeH = 1 | 8,
     AdventureH = 2 | 8,
     SpectatorH = 3 | 8,
 }
 
+impl TryFrom<u8> for GameMode {
+    type Error = KittyMCError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => GameMode::Survival,
+            1 => GameMode::Creative,
+            2 => GameMode::Adventure,
+            3 => GameMode::Spectator,
+            8 => GameMode::SurvivalH,
+            9 => GameMode::CreativeH,
+            10 => GameMode::AdventureH,
+            11 => GameMode::SpectatorH,
+            _ => return Err(KittyMCError::InvalidEnumValue("GameMode", value as i64)),
+        })
+    }
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum Dimension {
     Nether = -1,
@@ -71,6 +106,19 @@ pub enum Dimension {
     End = 1,
 }
 
+impl TryFrom<i32> for Dimension {
+    type Error = KittyMCError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        Ok(match value {
+            -1 => Dimension::Nether,
+            0 => Dimension::Overworld,
+            1 => Dimension::End,
+            _ => return Err(KittyMCError::InvalidEnumValue("Dimension", value as i64)),
+        })
+    }
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum Difficulty {
     Peaceful = 0,
@@ -79,12 +127,37 @@ pub enum Difficulty {
     Hard = 3,
 }
 
+impl TryFrom<u8> for Difficulty {
+    type Error = KittyMCError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => Difficulty::Peaceful,
+            1 => Difficulty::Easy,
+            2 => Difficulty::Normal,
+            3 => Difficulty::Hard,
+            _ => return Err(KittyMCError::InvalidEnumValue("Difficulty", value as i64)),
+        })
+    }
+}
+
+impl PacketField for Difficulty {
+    fn write_field(&self, buffer: &mut Vec<u8>) {
+        write_u8(buffer, *self as u8);
+    }
+
+    fn read_field(data: &mut &[u8], total_size: &mut usize) -> Result<Self, KittyMCError> {
+        Difficulty::try_from(read_u8(data, total_size)?)
+    }
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum LevelType {
     Default,
     Flat,
     LargeBiomes,
     Amplified,
+    Customized,
     Default11,
 }
 
@@ -95,7 +168,25 @@ impl LevelType {
             LevelType::Flat => "flat",
             LevelType::LargeBiomes => "largeBiomes",
             LevelType::Amplified => "amplified",
+            LevelType::Customized => "customized",
             LevelType::Default11 => "default_1_1",
         }
     }
 }
+
+impl FromStr for LevelType {
+    type Err = KittyMCError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // The client compares these case-insensitively, so we do too
+        Ok(match s.to_lowercase().as_str() {
+            "default" => LevelType::Default,
+            "flat" => LevelType::Flat,
+            "largebiomes" => LevelType::LargeBiomes,
+            "amplified" => LevelType::Amplified,
+            "customized" => LevelType::Customized,
+            "default_1_1" => LevelType::Default11,
+            _ => return Err(KittyMCError::DeserializationError),
+        })
+    }
+}
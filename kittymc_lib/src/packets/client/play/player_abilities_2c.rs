@@ -1,5 +1,6 @@
-use crate::packets::packet_serialization::{write_f32, write_u8, SerializablePacket};
-use crate::packets::wrap_packet;
+use crate::error::KittyMCError;
+use crate::packets::packet_fields::PacketField;
+use crate::packets::packet_serialization::{read_u8, write_u8};
 use bitflags::bitflags;
 use kittymc_macros::Packet;
 
@@ -15,11 +16,22 @@ bitflags! {
     }
 }
 
+impl PacketField for PlayerAbilitiesFlags {
+    fn write_field(&self, buffer: &mut Vec<u8>) {
+        write_u8(buffer, self.bits());
+    }
+
+    fn read_field(data: &mut &[u8], total_size: &mut usize) -> Result<Self, KittyMCError> {
+        Ok(PlayerAbilitiesFlags::from_bits_retain(read_u8(data, total_size)?))
+    }
+}
+
 #[derive(PartialEq, Debug, Clone, Packet)]
+#[packet(id = 0x2C, state = Play, direction = Clientbound)]
 pub struct PlayerAbilitiesPacket {
-    flags: PlayerAbilitiesFlags,
-    flying_speed: f32,
-    field_of_view_modifier: f32,
+    pub flags: PlayerAbilitiesFlags,
+    pub flying_speed: f32,
+    pub field_of_view_modifier: f32,
 }
 
 impl Default for PlayerAbilitiesPacket {
@@ -31,21 +43,3 @@ impl Default for PlayerAbilitiesPacket {
         }
     }
 }
-
-impl SerializablePacket for PlayerAbilitiesPacket {
-    fn serialize(&self) -> Vec<u8> {
-        let mut packet = vec![];
-
-        write_u8(&mut packet, self.flags.bits());
-        write_f32(&mut packet, self.flying_speed);
-        write_f32(&mut packet, self.field_of_view_modifier);
-
-        wrap_packet(&mut packet, Self::id());
-
-        packet
-    }
-
-    fn id() -> u32 {
-        0x2C
-    }
-}
@@ -1,14 +1,17 @@
+use crate::error::KittyMCError;
 use crate::packets::client::play::GameMode;
 use crate::packets::packet_serialization::{
-    write_bool, write_length_prefixed_string, write_uuid, write_varint_u32, SerializablePacket,
+    read_bool, read_length_prefixed_string, read_uuid, read_varint_u32, write_bool,
+    write_length_prefixed_string, write_uuid, write_varint_u32, SerializablePacket,
 };
-use crate::packets::wrap_packet;
+use crate::packets::ClientboundPacket;
 use crate::subtypes::components::TextComponent;
 use kittymc_macros::Packet;
 use log::warn;
+use serde::Deserialize;
 use uuid::Uuid;
 
-#[derive(PartialEq, Debug, Clone)]
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Deserialize)]
 pub struct PlayerListItemProperties {
     pub name: String,
     pub value: String,
@@ -19,13 +22,27 @@ pub struct PlayerListItemProperties {
 impl PlayerListItemProperties {
     pub fn write(&self, buffer: &mut Vec<u8>) {
         write_length_prefixed_string(buffer, &self.name);
-        write_length_prefixed_string(buffer, &self.name);
+        write_length_prefixed_string(buffer, &self.value);
         write_bool(buffer, self.signature.is_some());
         if let Some(sig) = &self.signature {
-            write_bool(buffer, true);
             write_length_prefixed_string(buffer, sig);
         }
     }
+
+    pub fn read(data: &mut &[u8], size: &mut usize) -> Result<Self, KittyMCError> {
+        let name = read_length_prefixed_string(data, size)?;
+        let value = read_length_prefixed_string(data, size)?;
+        let signature = match read_bool(data, size)? {
+            true => Some(read_length_prefixed_string(data, size)?),
+            false => None,
+        };
+
+        Ok(PlayerListItemProperties {
+            name,
+            value,
+            signature,
+        })
+    }
 }
 
 #[derive(PartialEq, Debug, Clone)]
@@ -91,9 +108,52 @@ impl PlayerListItemAction {
             PlayerListItemAction::RemovePlayer => (),
         }
     }
+
+    pub fn read(id: u32, data: &mut &[u8], size: &mut usize) -> Result<Self, KittyMCError> {
+        Ok(match id {
+            0 => {
+                let name = read_length_prefixed_string(data, size)?;
+                let property_count = read_varint_u32(data, size)?;
+                let mut properties = Vec::new();
+                for _ in 0..property_count {
+                    properties.push(PlayerListItemProperties::read(data, size)?);
+                }
+                let game_mode = GameMode::try_from(read_varint_u32(data, size)? as u8)?;
+                let ping = read_varint_u32(data, size)?;
+                let display_name = match read_bool(data, size)? {
+                    true => Some(TextComponent::read(data, size)?),
+                    false => None,
+                };
+
+                PlayerListItemAction::AddPlayer {
+                    name,
+                    properties,
+                    game_mode,
+                    ping,
+                    display_name,
+                }
+            }
+            1 => PlayerListItemAction::UpdateGameMode(GameMode::try_from(
+                read_varint_u32(data, size)? as u8,
+            )?),
+            2 => PlayerListItemAction::UpdateLatency(read_varint_u32(data, size)?),
+            3 => PlayerListItemAction::UpdateDisplayName(match read_bool(data, size)? {
+                true => Some(TextComponent::read(data, size)?),
+                false => None,
+            }),
+            4 => PlayerListItemAction::RemovePlayer,
+            _ => {
+                return Err(KittyMCError::InvalidEnumValue(
+                    "PlayerListItemAction",
+                    id as i64,
+                ))
+            }
+        })
+    }
 }
 
 #[derive(PartialEq, Debug, Clone, Packet)]
+#[packet(state = Play, direction = Clientbound)]
 pub struct PlayerListItemPacket {
     pub actions: Vec<(Uuid, PlayerListItemAction)>,
 }
@@ -116,32 +176,46 @@ impl Default for PlayerListItemPacket {
 }
 
 impl SerializablePacket for PlayerListItemPacket {
-    fn serialize(&self) -> Vec<u8> {
-        let mut packet = vec![];
-
+    fn serialize_fields(&self, packet: &mut Vec<u8>) {
         if !self.actions.is_empty() {
             let first = &self.actions[0];
             for action in &self.actions {
                 if first.1.id() != action.1.id() {
                     warn!("Server tried to serialize a packet with different action types. This is not possible. Sending default packet");
-                    return vec![3, 0x2E, 0, 0];
+                    packet.extend_from_slice(&[0, 0]);
+                    return;
                 }
             }
         } else {
             warn!("Server tried sending an empty PlayerListItem Packet for some reason");
-            return vec![3, 0x2E, 0, 0];
+            packet.extend_from_slice(&[0, 0]);
+            return;
         }
 
-        write_varint_u32(&mut packet, self.actions[0].1.id());
-        write_varint_u32(&mut packet, self.actions.len() as u32);
+        write_varint_u32(packet, self.actions[0].1.id());
+        write_varint_u32(packet, self.actions.len() as u32);
         for (uuid, action) in &self.actions {
-            write_uuid(&mut packet, uuid);
-            action.write(&mut packet);
+            write_uuid(packet, uuid);
+            action.write(packet);
         }
+    }
 
-        wrap_packet(&mut packet, Self::id());
+    fn deserialize_clientbound(mut data: &[u8]) -> Result<(usize, ClientboundPacket), KittyMCError> {
+        let mut size = 0;
+
+        let action_id = read_varint_u32(&mut data, &mut size)?;
+        let count = read_varint_u32(&mut data, &mut size)?;
+        let mut actions = Vec::new();
+        for _ in 0..count {
+            let uuid = read_uuid(&mut data, &mut size)?;
+            let action = PlayerListItemAction::read(action_id, &mut data, &mut size)?;
+            actions.push((uuid, action));
+        }
 
-        packet
+        Ok((
+            size,
+            ClientboundPacket::PlayerListItem(PlayerListItemPacket { actions }),
+        ))
     }
 
     fn id() -> u32 {
@@ -1,17 +1,15 @@
-use crate::packets::packet_serialization::{
-    write_direction, write_location2, write_u8, write_varint_u32, SerializablePacket,
-};
-use crate::packets::wrap_packet;
 use crate::subtypes::{Direction, Location2};
 use kittymc_macros::Packet;
 use rand::random;
 
 #[derive(PartialEq, Debug, Clone, Packet)]
+#[packet(id = 0x2F, state = Play, direction = Clientbound)]
 pub struct ServerPlayerPositionAndLookPacket {
-    location: Location2, // Feet
-    direction: Direction,
-    relative_flags: u8,
-    teleport_id: u32,
+    pub location: Location2, // Feet
+    pub direction: Direction,
+    pub relative_flags: u8,
+    #[packet(varint)]
+    pub teleport_id: u32,
 }
 
 impl Default for ServerPlayerPositionAndLookPacket {
@@ -24,22 +22,3 @@ impl Default for ServerPlayerPositionAndLookPacket {
         }
     }
 }
-
-impl SerializablePacket for ServerPlayerPositionAndLookPacket {
-    fn serialize(&self) -> Vec<u8> {
-        let mut packet = vec![];
-
-        write_location2(&mut packet, &self.location);
-        write_direction(&mut packet, &self.direction);
-        write_u8(&mut packet, self.relative_flags);
-        write_varint_u32(&mut packet, self.teleport_id);
-
-        wrap_packet(&mut packet, Self::id());
-
-        packet
-    }
-
-    fn id() -> u32 {
-        0x2F
-    }
-}
@@ -1,11 +1,10 @@
 use crate::packets::client::play::Difficulty;
-use crate::packets::packet_serialization::{write_u8, SerializablePacket};
-use crate::packets::wrap_packet;
 use kittymc_macros::Packet;
 
 #[derive(PartialEq, Debug, Clone, Packet)]
+#[packet(id = 0x0D, state = Play, direction = Clientbound)]
 pub struct ServerDifficultyPacket {
-    difficulty: Difficulty,
+    pub difficulty: Difficulty,
 }
 
 impl Default for ServerDifficultyPacket {
@@ -15,19 +14,3 @@ impl Default for ServerDifficultyPacket {
         }
     }
 }
-
-impl SerializablePacket for ServerDifficultyPacket {
-    fn serialize(&self) -> Vec<u8> {
-        let mut packet = vec![];
-
-        write_u8(&mut packet, self.difficulty as u8);
-
-        wrap_packet(&mut packet, Self::id());
-
-        packet
-    }
-
-    fn id() -> u32 {
-        0x0D
-    }
-}
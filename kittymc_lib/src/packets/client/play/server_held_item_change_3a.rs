@@ -1,24 +1,7 @@
-use crate::packets::packet_serialization::{write_u8, SerializablePacket};
-use crate::packets::wrap_packet;
 use kittymc_macros::Packet;
 
 #[derive(PartialEq, Debug, Clone, Default, Packet)]
+#[packet(id = 0x3A, state = Play, direction = Clientbound)]
 pub struct ServerHeldItemChangePacket {
-    slot: u8,
-}
-
-impl SerializablePacket for ServerHeldItemChangePacket {
-    fn serialize(&self) -> Vec<u8> {
-        let mut packet = vec![];
-
-        write_u8(&mut packet, self.slot);
-
-        wrap_packet(&mut packet, Self::id());
-
-        packet
-    }
-
-    fn id() -> u32 {
-        0x3A
-    }
+    pub slot: u8,
 }
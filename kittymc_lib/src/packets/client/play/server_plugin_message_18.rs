@@ -1,10 +1,7 @@
-use crate::packets::packet_serialization::{
-    write_length_prefixed_bytes, write_length_prefixed_string, SerializablePacket,
-};
-use crate::packets::wrap_packet;
 use kittymc_macros::Packet;
 
 #[derive(PartialEq, Debug, Clone, Packet)]
+#[packet(id = 0x18, state = Play, direction = Clientbound)]
 pub struct ServerPluginMessagePacket {
     pub channel: String,
     pub data: Vec<u8>,
@@ -18,20 +15,3 @@ impl ServerPluginMessagePacket {
         }
     }
 }
-
-impl SerializablePacket for ServerPluginMessagePacket {
-    fn serialize(&self) -> Vec<u8> {
-        let mut packet = vec![];
-
-        write_length_prefixed_string(&mut packet, &self.channel);
-        write_length_prefixed_bytes(&mut packet, &self.data);
-
-        wrap_packet(&mut packet, Self::id());
-
-        packet
-    }
-
-    fn id() -> u32 {
-        0x18
-    }
-}
@@ -1,32 +1,52 @@
-use crate::packets::packet_serialization::{write_direction_as_angles, write_location2, write_uuid, write_varint_i32, SerializablePacket};
-use crate::packets::wrap_packet;
-use crate::subtypes::metadata::{EntityMetadata, MetadataObject};
+use crate::error::KittyMCError;
+use crate::packets::packet_serialization::{
+    read_direction_as_angles, read_location2, read_uuid, read_varint_i32,
+    write_direction_as_angles, write_location2, write_uuid, write_varint_i32, SerializablePacket,
+};
+use crate::packets::ClientboundPacket;
+use crate::subtypes::metadata::{read_metadata, EntityMetadata, MetadataObject};
 use crate::subtypes::{Direction, Location2};
 use kittymc_macros::Packet;
 use uuid::Uuid;
 
 #[derive(PartialEq, Debug, Clone, Packet)]
-pub struct SpawnPlayerPacket {
+#[packet(state = Play, direction = Clientbound)]
+pub struct SpawnPlayerPacket<M = EntityMetadata> {
     pub entity_id: i32,
     pub player_uuid: Uuid,
     pub location: Location2,
     pub direction: Direction,
-    pub metadata: EntityMetadata,
+    pub metadata: M,
 }
 
-impl SerializablePacket for SpawnPlayerPacket {
-    fn serialize(&self) -> Vec<u8> {
-        let mut packet = vec![];
+impl<M: MetadataObject> SerializablePacket for SpawnPlayerPacket<M> {
+    fn serialize_fields(&self, packet: &mut Vec<u8>) {
+        write_varint_i32(packet, self.entity_id);
+        write_uuid(packet, &self.player_uuid);
+        write_location2(packet, &self.location);
+        write_direction_as_angles(packet, &self.direction);
+        self.metadata.write_metadata(packet);
+    }
 
-        write_varint_i32(&mut packet, self.entity_id);
-        write_uuid(&mut packet, &self.player_uuid);
-        write_location2(&mut packet, &self.location);
-        write_direction_as_angles(&mut packet, &self.direction);
-        self.metadata.write_metadata(&mut packet);
+    fn deserialize_clientbound(mut data: &[u8]) -> Result<(usize, ClientboundPacket), KittyMCError> {
+        let mut size = 0;
 
-        wrap_packet(&mut packet, Self::id());
+        let entity_id = read_varint_i32(&mut data, &mut size)?;
+        let player_uuid = read_uuid(&mut data, &mut size)?;
+        let location = read_location2(&mut data, &mut size)?;
+        let direction = read_direction_as_angles(&mut data, &mut size)?;
+        let metadata = read_metadata(&mut data, &mut size)?;
 
-        packet
+        Ok((
+            size,
+            ClientboundPacket::SpawnPlayer(SpawnPlayerPacket {
+                entity_id,
+                player_uuid,
+                location,
+                direction,
+                metadata,
+            }),
+        ))
     }
 
     fn id() -> u32 {
@@ -1,11 +1,11 @@
-use crate::packets::packet_serialization::{write_block_location, SerializablePacket};
-use crate::packets::wrap_packet;
 use crate::subtypes::Location;
 use kittymc_macros::Packet;
 
 #[derive(PartialEq, Debug, Clone, Packet)]
+#[packet(id = 0x46, state = Play, direction = Clientbound)]
 pub struct SpawnPositionPacket {
-    position: Location,
+    #[packet(position)]
+    pub position: Location,
 }
 
 impl Default for SpawnPositionPacket {
@@ -15,19 +15,3 @@ impl Default for SpawnPositionPacket {
         }
     }
 }
-
-impl SerializablePacket for SpawnPositionPacket {
-    fn serialize(&self) -> Vec<u8> {
-        let mut packet = vec![];
-
-        write_block_location(&mut packet, &self.position);
-
-        wrap_packet(&mut packet, Self::id());
-
-        packet
-    }
-
-    fn id() -> u32 {
-        0x46
-    }
-}
@@ -0,0 +1,15 @@
+use kittymc_macros::Packet;
+
+/// The answer to a [`ServerTabCompletePacket`](crate::packets::server::play::ServerTabCompletePacket).
+/// Every match replaces the last word the player typed.
+#[derive(PartialEq, Debug, Clone, Packet)]
+#[packet(id = 0x0E, state = Play, direction = Clientbound)]
+pub struct ClientTabCompletePacket {
+    pub matches: Vec<String>,
+}
+
+impl ClientTabCompletePacket {
+    pub fn new(matches: Vec<String>) -> Self {
+        ClientTabCompletePacket { matches }
+    }
+}
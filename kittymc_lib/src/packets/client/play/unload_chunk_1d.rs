@@ -1,12 +1,11 @@
-use crate::packets::packet_serialization::{write_i32, SerializablePacket};
-use crate::packets::wrap_packet;
 use crate::subtypes::ChunkPosition;
 use kittymc_macros::Packet;
 
 #[derive(PartialEq, Debug, Clone, Packet)]
+#[packet(id = 0x1D, state = Play, direction = Clientbound)]
 pub struct UnloadChunkPacket {
-    x: i32,
-    z: i32,
+    pub x: i32,
+    pub z: i32,
 }
 
 impl UnloadChunkPacket {
@@ -17,20 +16,3 @@ impl UnloadChunkPacket {
         }
     }
 }
-
-impl SerializablePacket for UnloadChunkPacket {
-    fn serialize(&self) -> Vec<u8> {
-        let mut packet = vec![];
-
-        write_i32(&mut packet, self.x);
-        write_i32(&mut packet, self.z);
-
-        wrap_packet(&mut packet, Self::id());
-
-        packet
-    }
-
-    fn id() -> u32 {
-        0x1D
-    }
-}
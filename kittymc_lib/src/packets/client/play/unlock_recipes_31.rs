@@ -1,5 +1,8 @@
-use crate::packets::packet_serialization::{write_bool, write_varint_u32, SerializablePacket};
-use crate::packets::wrap_packet;
+use crate::error::KittyMCError;
+use crate::packets::packet_serialization::{
+    read_bool, read_varint_u32, write_bool, write_varint_u32, SerializablePacket,
+};
+use crate::packets::ClientboundPacket;
 use kittymc_macros::Packet;
 
 #[repr(u32)]
@@ -10,13 +13,27 @@ pub enum UnlockAction {
     Remove = 2,
 }
 
+impl TryFrom<u32> for UnlockAction {
+    type Error = KittyMCError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => UnlockAction::Init,
+            1 => UnlockAction::Add,
+            2 => UnlockAction::Remove,
+            _ => return Err(KittyMCError::InvalidEnumValue("UnlockAction", value as i64)),
+        })
+    }
+}
+
 #[derive(PartialEq, Debug, Clone, Packet)]
+#[packet(state = Play, direction = Clientbound)]
 pub struct UnlockRecipesPacket {
-    action: UnlockAction,
-    crafting_book_open: bool,
-    filtering_craftable: bool,
-    recipe_ids: Vec<u32>,
-    recipe_ids_2: Vec<u32>,
+    pub action: UnlockAction,
+    pub crafting_book_open: bool,
+    pub filtering_craftable: bool,
+    pub recipe_ids: Vec<u32>,
+    pub recipe_ids_2: Vec<u32>,
 }
 
 impl Default for UnlockRecipesPacket {
@@ -32,26 +49,56 @@ impl Default for UnlockRecipesPacket {
 }
 
 impl SerializablePacket for UnlockRecipesPacket {
-    fn serialize(&self) -> Vec<u8> {
-        let mut packet = vec![];
-
-        write_varint_u32(&mut packet, self.action as u32);
-        write_bool(&mut packet, self.crafting_book_open);
-        write_bool(&mut packet, self.filtering_craftable);
+    fn serialize_fields(&self, packet: &mut Vec<u8>) {
+        write_varint_u32(packet, self.action as u32);
+        write_bool(packet, self.crafting_book_open);
+        write_bool(packet, self.filtering_craftable);
 
-        write_varint_u32(&mut packet, self.recipe_ids.len() as u32);
+        write_varint_u32(packet, self.recipe_ids.len() as u32);
         for id in &self.recipe_ids {
-            write_varint_u32(&mut packet, *id);
+            write_varint_u32(packet, *id);
         }
 
-        write_varint_u32(&mut packet, self.recipe_ids_2.len() as u32);
-        for id in &self.recipe_ids_2 {
-            write_varint_u32(&mut packet, *id);
+        if self.action == UnlockAction::Init {
+            write_varint_u32(packet, self.recipe_ids_2.len() as u32);
+            for id in &self.recipe_ids_2 {
+                write_varint_u32(packet, *id);
+            }
         }
+    }
 
-        wrap_packet(&mut packet, Self::id());
+    fn deserialize_clientbound(mut data: &[u8]) -> Result<(usize, ClientboundPacket), KittyMCError> {
+        let mut size = 0;
+
+        let action = UnlockAction::try_from(read_varint_u32(&mut data, &mut size)?)?;
+        let crafting_book_open = read_bool(&mut data, &mut size)?;
+        let filtering_craftable = read_bool(&mut data, &mut size)?;
+
+        let recipe_count = read_varint_u32(&mut data, &mut size)?;
+        let mut recipe_ids = Vec::new();
+        for _ in 0..recipe_count {
+            recipe_ids.push(read_varint_u32(&mut data, &mut size)?);
+        }
+
+        // The second list is only sent when initializing
+        let mut recipe_ids_2 = Vec::new();
+        if action == UnlockAction::Init {
+            let recipe_count_2 = read_varint_u32(&mut data, &mut size)?;
+            for _ in 0..recipe_count_2 {
+                recipe_ids_2.push(read_varint_u32(&mut data, &mut size)?);
+            }
+        }
 
-        packet
+        Ok((
+            size,
+            ClientboundPacket::UnlockRecipes(UnlockRecipesPacket {
+                action,
+                crafting_book_open,
+                filtering_craftable,
+                recipe_ids,
+                recipe_ids_2,
+            }),
+        ))
     }
 
     fn id() -> u32 {
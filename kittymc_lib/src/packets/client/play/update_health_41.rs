@@ -0,0 +1,25 @@
+use kittymc_macros::Packet;
+
+#[derive(PartialEq, Debug, Clone, Packet)]
+#[packet(id = 0x41, state = Play, direction = Clientbound)]
+pub struct UpdateHealthPacket {
+    pub health: f32,
+    #[packet(varint)]
+    pub food: u32,
+    pub food_saturation: f32,
+}
+
+/// What a new player starts with
+const MAX_FOOD: u32 = 20;
+const START_SATURATION: f32 = 5.;
+
+impl UpdateHealthPacket {
+    /// Leaves the food bar full, since there's no hunger yet
+    pub fn with_full_food(health: f32) -> Self {
+        UpdateHealthPacket {
+            health,
+            food: MAX_FOOD,
+            food_saturation: START_SATURATION,
+        }
+    }
+}
@@ -1,14 +1,19 @@
-use crate::packets::packet_serialization::{read_nbt, read_u16, read_u8, write_i16, write_u16, write_u8, SerializablePacket};
-use crate::packets::wrap_packet;
+use crate::packets::packet_fields::PacketField;
+use crate::packets::packet_serialization::{
+    read_i16, read_nbt, read_u16, read_u8, write_i16, write_nbt, write_u16, write_u8,
+    SerializablePacket,
+};
+use crate::packets::ClientboundPacket;
 use kittymc_macros::Packet;
 use crate::error::KittyMCError;
+use crate::subtypes::item_nbt::ItemNbt;
 
 #[derive(PartialEq, Debug, Clone)]
 pub struct SlotData {
     pub id: u16, // 0xffff is empty
     pub item_count: u8,
     pub item_damage: u16,
-    pub nbt: Option<fastnbt::Value>,
+    pub nbt: Option<ItemNbt>,
 }
 
 impl Default for SlotData {
@@ -28,6 +33,10 @@ impl SlotData {
         if self.id != u16::MAX {
             write_u8(data, self.item_count);
             write_u16(data, self.item_damage);
+            match &self.nbt {
+                Some(nbt) => write_nbt(data, &nbt.to_value()),
+                None => write_u8(data, 0), // TAG_End
+            }
         }
     }
 
@@ -35,11 +44,19 @@ impl SlotData {
         let block_id = read_u16(data, size)?;
         let mut item_count: u8 = 0;
         let mut item_damage: u16 = 0;
+        let mut nbt = None;
         if block_id != u16::MAX {
             item_count = read_u8(data, size)?;
             item_damage = read_u16(data, size)?;
+
+            // A single TAG_End means there's no NBT data
+            if data.first() == Some(&0) {
+                *data = &data[1..];
+                *size += 1;
+            } else {
+                nbt = Some(ItemNbt::from_value(read_nbt(data, size)?)?);
+            }
         }
-        let nbt = read_nbt(data, size).ok();
 
         Ok(Self {
             id: block_id,
@@ -50,10 +67,21 @@ impl SlotData {
     }
 }
 
+impl PacketField for SlotData {
+    fn write_field(&self, buffer: &mut Vec<u8>) {
+        self.write(buffer);
+    }
+
+    fn read_field(data: &mut &[u8], total_size: &mut usize) -> Result<Self, KittyMCError> {
+        SlotData::read(data, total_size)
+    }
+}
+
 #[derive(PartialEq, Debug, Clone, Packet)]
+#[packet(state = Play, direction = Clientbound)]
 pub struct WindowItemsPacket {
-    window_id: u8,
-    slot_data: Vec<SlotData>,
+    pub window_id: u8,
+    pub slot_data: Vec<SlotData>,
 }
 
 impl Default for WindowItemsPacket {
@@ -66,18 +94,31 @@ impl Default for WindowItemsPacket {
 }
 
 impl SerializablePacket for WindowItemsPacket {
-    fn serialize(&self) -> Vec<u8> {
-        let mut packet = vec![];
-
-        write_u8(&mut packet, self.window_id);
-        write_i16(&mut packet, self.slot_data.len() as i16);
+    fn serialize_fields(&self, packet: &mut Vec<u8>) {
+        write_u8(packet, self.window_id);
+        write_i16(packet, self.slot_data.len() as i16);
         for slot in &self.slot_data {
-            slot.write(&mut packet);
+            slot.write(packet);
         }
+    }
 
-        wrap_packet(&mut packet, Self::id());
+    fn deserialize_clientbound(mut data: &[u8]) -> Result<(usize, ClientboundPacket), KittyMCError> {
+        let mut size = 0;
+
+        let window_id = read_u8(&mut data, &mut size)?;
+        let count = read_i16(&mut data, &mut size)?;
+        let mut slot_data = Vec::new();
+        for _ in 0..count {
+            slot_data.push(SlotData::read(&mut data, &mut size)?);
+        }
 
-        packet
+        Ok((
+            size,
+            ClientboundPacket::WindowItems(WindowItemsPacket {
+                window_id,
+                slot_data,
+            }),
+        ))
     }
 
     fn id() -> u32 {
@@ -2,7 +2,8 @@ use crate::error::KittyMCError;
 use crate::packets::packet_serialization::{
     read_length_prefixed_string, write_length_prefixed_string, SerializablePacket,
 };
-use crate::packets::{wrap_packet, Packet};
+use crate::packets::protocol::ProtocolVersion;
+use crate::packets::{ClientboundPacket, Packet};
 use kittymc_macros::Packet;
 use serde::{Deserialize, Serialize};
 use std::fmt::{Debug, Formatter};
@@ -36,6 +37,7 @@ pub struct StatusResponseText {
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Packet)]
+#[packet(state = Status, direction = Clientbound)]
 pub struct StatusResponsePacket {
     version: StatusResponseVersion,
     players: StatusResponsePlayers,
@@ -62,7 +64,7 @@ impl Default for StatusResponsePacket {
         StatusResponsePacket {
             version: StatusResponseVersion {
                 name: "KittyMC".to_string(),
-                protocol: 340, // TODO: Replace this with global versioned consts
+                protocol: ProtocolVersion::LATEST.id(),
             },
             players: StatusResponsePlayers {
                 max: 1,
@@ -82,16 +84,20 @@ impl Default for StatusResponsePacket {
     }
 }
 
+impl StatusResponsePacket {
+    /// Tells a client of a supported version that it can join, even if it's not the latest one
+    pub fn for_protocol(protocol: ProtocolVersion) -> Self {
+        let mut response = Self::default();
+        response.version.protocol = protocol.id();
+        response
+    }
+}
+
 impl SerializablePacket for StatusResponsePacket {
-    fn serialize(&self) -> Vec<u8> {
-        let mut packet = vec![];
+    fn serialize_fields(&self, packet: &mut Vec<u8>) {
         let json_response = serde_json::to_string(self).unwrap_or_else(|_| "".to_string());
 
-        write_length_prefixed_string(&mut packet, json_response.as_str());
-
-        wrap_packet(&mut packet, Self::id());
-
-        packet
+        write_length_prefixed_string(packet, json_response.as_str());
     }
 
     fn deserialize(mut data: &[u8]) -> Result<(usize, Packet), KittyMCError> {
@@ -104,6 +110,16 @@ impl SerializablePacket for StatusResponsePacket {
         Ok((size, Packet::StatusResponse(response)))
     }
 
+    fn deserialize_clientbound(mut data: &[u8]) -> Result<(usize, ClientboundPacket), KittyMCError> {
+        let mut size = 0;
+
+        let string = read_length_prefixed_string(&mut data, &mut size)?;
+
+        let response = serde_json::from_str::<StatusResponsePacket>(string.as_str())?;
+
+        Ok((size, ClientboundPacket::StatusResponse(response)))
+    }
+
     fn id() -> u32 {
         0
     }
@@ -0,0 +1,482 @@
+//! Framing for a whole connection.
+//!
+//! [`PacketCodec`] owns the stream and the receive buffer, cuts the incoming bytes into frames,
+//! decodes them and keeps the connection [`State`], [`CompressionInfo`] and [`ProtocolVersion`] up
+//! to date by looking at the packets going through it in both directions. Frames of older protocol
+//! versions are translated to and from the 1.12.2 packets on the way. With the `tokio` feature the same codec
+//! works on `AsyncRead + AsyncWrite` streams through the `*_async` functions.
+//!
+//! Encryption is the one thing the codec can't follow by itself, since the shared secret only
+//! travels encrypted with the server's key. Whoever knows the secret turns it on with
+//! [`PacketCodec::enable_encryption`], everything below that sees plain frames.
+
+use crate::crypto::PacketCipher;
+use crate::error::KittyMCError;
+use crate::packets::packet_buffer::PacketBuffer;
+use crate::packets::packet_serialization::{
+    decompress_packet, read_varint_u32, write_varint_u32_splice, SerializablePacket,
+};
+use crate::packets::protocol::ProtocolVersion;
+use crate::packets::{ClientboundPacket, CompressionInfo, Packet, PacketDirection};
+use crate::subtypes::state::State;
+use log::warn;
+use std::borrow::Cow;
+use std::io::{ErrorKind, Read, Write};
+use std::marker::PhantomData;
+use std::mem;
+
+const READ_CHUNK_SIZE: usize = 4096;
+/// The length of a frame is at most a three byte VarInt
+const MAX_FRAME_LEN_BYTES: usize = 3;
+pub const MAX_FRAME_LEN: usize = (1 << (7 * MAX_FRAME_LEN_BYTES)) - 1;
+
+/// A packet enum that a [`PacketCodec`] can decode.
+pub trait CodecPacket: Sized {
+    /// The packets travelling the other way on the same connection
+    type Outgoing: CodecPacket;
+
+    const DIRECTION: PacketDirection;
+
+    fn decode(
+        state: State,
+        data: &[u8],
+        compression: &CompressionInfo,
+    ) -> Result<(usize, Self), KittyMCError>;
+
+    /// Applies the state, compression or protocol version change this packet causes, if any
+    fn transition(
+        &self,
+        state: &mut State,
+        compression: &mut CompressionInfo,
+        protocol: &mut ProtocolVersion,
+    );
+}
+
+impl CodecPacket for Packet {
+    type Outgoing = ClientboundPacket;
+
+    const DIRECTION: PacketDirection = PacketDirection::Serverbound;
+
+    fn decode(
+        state: State,
+        data: &[u8],
+        compression: &CompressionInfo,
+    ) -> Result<(usize, Self), KittyMCError> {
+        Packet::deserialize(state, data, compression)
+    }
+
+    fn transition(
+        &self,
+        state: &mut State,
+        _compression: &mut CompressionInfo,
+        protocol: &mut ProtocolVersion,
+    ) {
+        if let Packet::Handshake(handshake) = self {
+            *state = handshake.next_state;
+            // Unsupported versions are left for whoever reads the handshake to turn away
+            if let Some(version) = ProtocolVersion::from_id(handshake.protocol_version) {
+                *protocol = version;
+            }
+        }
+    }
+}
+
+impl CodecPacket for ClientboundPacket {
+    type Outgoing = Packet;
+
+    const DIRECTION: PacketDirection = PacketDirection::Clientbound;
+
+    fn decode(
+        state: State,
+        data: &[u8],
+        compression: &CompressionInfo,
+    ) -> Result<(usize, Self), KittyMCError> {
+        ClientboundPacket::deserialize(state, data, compression)
+    }
+
+    fn transition(
+        &self,
+        state: &mut State,
+        compression: &mut CompressionInfo,
+        _protocol: &mut ProtocolVersion,
+    ) {
+        match self {
+            ClientboundPacket::LoginSuccess(_) => *state = State::Play,
+            ClientboundPacket::SetCompression(packet) => {
+                compression.enabled = true;
+                compression.compression_threshold = packet.threshold;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// One frame exactly as it was on the wire, together with what it decoded to.
+#[derive(Debug)]
+pub struct Frame<P> {
+    pub data: Vec<u8>,
+    pub packet: Result<P, KittyMCError>,
+}
+
+/// Reads `P`s from and writes packets to a stream. `P` is what the other side sends, so a server
+/// uses `PacketCodec<_, Packet>` and a client `PacketCodec<_, ClientboundPacket>`.
+#[derive(Debug)]
+pub struct PacketCodec<S, P = Packet> {
+    stream: S,
+    state: State,
+    compression: CompressionInfo,
+    protocol: ProtocolVersion,
+    buffer: Vec<u8>,
+    /// Outgoing packets are serialized into this, so it only has to grow once
+    out: PacketBuffer,
+    cipher: Option<PacketCipher>,
+    _packet: PhantomData<P>,
+}
+
+impl<S, P: CodecPacket> PacketCodec<S, P> {
+    pub fn new(stream: S) -> Self {
+        PacketCodec {
+            stream,
+            state: State::Handshake,
+            compression: CompressionInfo::default(),
+            protocol: ProtocolVersion::LATEST,
+            buffer: Vec::with_capacity(READ_CHUNK_SIZE),
+            out: PacketBuffer::new(),
+            cipher: None,
+            _packet: PhantomData,
+        }
+    }
+
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    /// Only needed if the state changes in a way the codec can't see
+    pub fn set_state(&mut self, state: State) {
+        self.state = state;
+    }
+
+    pub fn compression(&self) -> &CompressionInfo {
+        &self.compression
+    }
+
+    pub fn set_compression(&mut self, compression: CompressionInfo) {
+        self.compression = compression;
+    }
+
+    pub fn protocol(&self) -> ProtocolVersion {
+        self.protocol
+    }
+
+    /// Only needed if the version isn't negotiated through a handshake on this codec
+    pub fn set_protocol(&mut self, protocol: ProtocolVersion) {
+        self.protocol = protocol;
+    }
+
+    /// Encrypts everything written and decrypts everything read from now on. Has to be called
+    /// right after the Encryption Response went through, before any other packet.
+    pub fn enable_encryption(&mut self, shared_secret: &[u8]) -> Result<(), KittyMCError> {
+        let mut cipher = PacketCipher::new(shared_secret)?;
+
+        // Anything that's already buffered arrived after the Encryption Response
+        cipher.decrypt(&mut self.buffer);
+        self.cipher = Some(cipher);
+
+        Ok(())
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.cipher.is_some()
+    }
+
+    pub fn get_ref(&self) -> &S {
+        &self.stream
+    }
+
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
+
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+
+    fn receive(&mut self, data: &mut [u8]) {
+        if let Some(cipher) = &mut self.cipher {
+            cipher.decrypt(data);
+        }
+        self.buffer.extend_from_slice(data);
+    }
+
+    fn encrypt_outgoing<'a>(&mut self, frame: &'a [u8]) -> Cow<'a, [u8]> {
+        match &mut self.cipher {
+            Some(cipher) => {
+                let mut encrypted = frame.to_vec();
+                cipher.encrypt(&mut encrypted);
+                Cow::Owned(encrypted)
+            }
+            None => Cow::Borrowed(frame),
+        }
+    }
+
+    /// Cuts the next complete frame off the receive buffer and decodes it
+    fn next_frame(&mut self) -> Result<Option<Frame<P>>, KittyMCError> {
+        let Some(frame_len) = complete_frame_len(&self.buffer)? else {
+            return Ok(None);
+        };
+
+        let data: Vec<u8> = self.buffer.drain(..frame_len).collect();
+        let packet = self.decode_frame::<P>(&data, &self.compression);
+        if let Ok(packet) = &packet {
+            packet.transition(&mut self.state, &mut self.compression, &mut self.protocol);
+        }
+
+        Ok(Some(Frame { data, packet }))
+    }
+
+    /// Decodes a frame in this connection's protocol version into a 1.12.2 packet
+    fn decode_frame<T: CodecPacket>(
+        &self,
+        frame: &[u8],
+        compression: &CompressionInfo,
+    ) -> Result<T, KittyMCError> {
+        if self.protocol == ProtocolVersion::LATEST {
+            return T::decode(self.state, frame, compression).map(|(_, packet)| packet);
+        }
+
+        let packet = unframe(frame, compression)?;
+        let upgraded = self.protocol.upgrade_packet(self.state, T::DIRECTION, &packet)?;
+
+        // Compression can't change what a packet decodes to, so the upgraded one isn't compressed
+        // again
+        let mut reframed = upgraded;
+        if compression.enabled {
+            write_varint_u32_splice(&mut reframed, 0, ..0);
+        }
+        let len = reframed.len() as u32;
+        write_varint_u32_splice(&mut reframed, len, ..0);
+
+        T::decode(self.state, &reframed, compression).map(|(_, packet)| packet)
+    }
+
+    /// Serializes, translates and, if enabled, compresses a packet into its wire format
+    fn frame_packet<'a, T: SerializablePacket>(
+        &self,
+        out: &'a mut PacketBuffer,
+        packet: &T,
+    ) -> Result<&'a mut [u8], KittyMCError> {
+        if self.protocol == ProtocolVersion::LATEST {
+            return out.write_packet_with(packet, &self.compression);
+        }
+
+        let mut data: &[u8] = out.write_packet(packet);
+        read_varint_u32(&mut data, &mut 0)?;
+        let downgraded = self
+            .protocol
+            .downgrade_packet(self.state, P::Outgoing::DIRECTION, data)?;
+
+        out.write_raw_packet(&downgraded, &self.compression)
+    }
+
+    /// Serializes `packet` into the reused outgoing buffer, observes it and encrypts it in place.
+    /// `send` then gets the frame as it goes on the wire.
+    fn prepare_packet<T: SerializablePacket>(
+        &mut self,
+        packet: &T,
+    ) -> Result<PacketBuffer, KittyMCError> {
+        let mut out = mem::take(&mut self.out);
+        let frame = match self.frame_packet(&mut out, packet) {
+            Ok(frame) => frame,
+            Err(e) => {
+                self.out = out;
+                return Err(e);
+            }
+        };
+
+        let compression = self.compression.clone();
+        self.observe_outgoing(frame, &compression);
+        if let Some(cipher) = &mut self.cipher {
+            cipher.encrypt(frame);
+        }
+
+        Ok(out)
+    }
+
+    /// Has to be called once a frame is built, but before the change is applied, so that e.g. Set
+    /// Compression itself still goes out uncompressed.
+    fn observe_outgoing(&mut self, frame: &[u8], compression: &CompressionInfo) {
+        // Only Handshake and Login have packets that change anything
+        if !matches!(self.state, State::Handshake | State::Login) {
+            return;
+        }
+
+        if let Ok(packet) = self.decode_frame::<P::Outgoing>(frame, compression) {
+            packet.transition(&mut self.state, &mut self.compression, &mut self.protocol);
+        }
+    }
+}
+
+impl<S: Read + Write, P: CodecPacket> PacketCodec<S, P> {
+    /// Returns the next frame or `None` if a non-blocking stream has no complete frame yet.
+    pub fn read_frame(&mut self) -> Result<Option<Frame<P>>, KittyMCError> {
+        loop {
+            if let Some(frame) = self.next_frame()? {
+                return Ok(Some(frame));
+            }
+
+            let mut chunk = [0u8; READ_CHUNK_SIZE];
+            match self.stream.read(&mut chunk) {
+                Ok(0) => return Err(KittyMCError::Disconnected),
+                Ok(n) => self.receive(&mut chunk[..n]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(None),
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// Like [`Self::read_frame`], but skips packets that aren't implemented.
+    pub fn read_packet(&mut self) -> Result<Option<P>, KittyMCError> {
+        while let Some(frame) = self.read_frame()? {
+            match frame.packet {
+                Ok(packet) => return Ok(Some(packet)),
+                Err(KittyMCError::NotImplemented(id, len)) => {
+                    warn!("Skipping unimplemented packet 0x{id:02X} (len: {len})");
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub fn write_packet<T: SerializablePacket>(&mut self, packet: &T) -> Result<(), KittyMCError> {
+        let out = self.prepare_packet(packet)?;
+        let result = self.stream.write_all(out.frame());
+        self.out = out;
+
+        Ok(result?)
+    }
+
+    /// Writes a frame that is already in the wire format of this connection, e.g. one that was
+    /// read from another codec.
+    pub fn write_frame(&mut self, frame: &[u8]) -> Result<(), KittyMCError> {
+        let wire = self.encrypt_outgoing(frame);
+        self.stream.write_all(&wire)?;
+
+        let compression = self.compression.clone();
+        self.observe_outgoing(frame, &compression);
+
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), KittyMCError> {
+        Ok(self.stream.flush()?)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<S, P> PacketCodec<S, P>
+where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+    P: CodecPacket,
+{
+    /// Waits for the next frame. This is cancel safe, so it can be used in `tokio::select!`.
+    pub async fn read_frame_async(&mut self) -> Result<Frame<P>, KittyMCError> {
+        use tokio::io::AsyncReadExt;
+
+        loop {
+            if let Some(frame) = self.next_frame()? {
+                return Ok(frame);
+            }
+
+            let mut chunk = [0u8; READ_CHUNK_SIZE];
+            match self.stream.read(&mut chunk).await? {
+                0 => return Err(KittyMCError::Disconnected),
+                n => self.receive(&mut chunk[..n]),
+            }
+        }
+    }
+
+    /// Like [`Self::read_frame_async`], but skips packets that aren't implemented.
+    pub async fn read_packet_async(&mut self) -> Result<P, KittyMCError> {
+        loop {
+            match self.read_frame_async().await?.packet {
+                Ok(packet) => return Ok(packet),
+                Err(KittyMCError::NotImplemented(id, len)) => {
+                    warn!("Skipping unimplemented packet 0x{id:02X} (len: {len})");
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    pub async fn write_packet_async<T: SerializablePacket>(
+        &mut self,
+        packet: &T,
+    ) -> Result<(), KittyMCError> {
+        use tokio::io::AsyncWriteExt;
+
+        let out = self.prepare_packet(packet)?;
+        let result = self.stream.write_all(out.frame()).await;
+        self.out = out;
+
+        Ok(result?)
+    }
+
+    pub async fn write_frame_async(&mut self, frame: &[u8]) -> Result<(), KittyMCError> {
+        use tokio::io::AsyncWriteExt;
+
+        let wire = self.encrypt_outgoing(frame);
+        self.stream.write_all(&wire).await?;
+
+        let compression = self.compression.clone();
+        self.observe_outgoing(frame, &compression);
+
+        Ok(())
+    }
+}
+
+/// Returns the packet id and fields of a frame, decompressed if needed
+fn unframe(frame: &[u8], compression: &CompressionInfo) -> Result<Vec<u8>, KittyMCError> {
+    let mut data = frame;
+    let mut size = 0;
+    let len = read_varint_u32(&mut data, &mut size)? as usize;
+    let Some(packet) = data.get(..len) else {
+        return Err(KittyMCError::NotEnoughData(data.len(), len));
+    };
+
+    if !compression.enabled {
+        return Ok(packet.to_vec());
+    }
+
+    let mut data = packet;
+    let uncompressed_len = read_varint_u32(&mut data, &mut size)?;
+    if uncompressed_len == 0 {
+        return Ok(data.to_vec());
+    }
+
+    Ok(decompress_packet(frame)?.1)
+}
+
+/// Every frame, compressed or not, starts with its length as a VarInt. Returns the full length of
+/// the first frame in `buffer` if all of it has arrived. Lengths over [`MAX_FRAME_LEN`] are
+/// refused before anything of the frame is waited for, so nobody can make us buffer without end.
+fn complete_frame_len(buffer: &[u8]) -> Result<Option<usize>, KittyMCError> {
+    let mut len: usize = 0;
+
+    for (i, byte) in buffer.iter().take(MAX_FRAME_LEN_BYTES).enumerate() {
+        len |= ((byte & 0x7F) as usize) << (7 * i);
+
+        if byte & 0x80 == 0 {
+            let frame_len = len + i + 1;
+            return Ok((buffer.len() >= frame_len).then_some(frame_len));
+        }
+    }
+
+    if buffer.len() >= MAX_FRAME_LEN_BYTES {
+        return Err(KittyMCError::InvalidPacketLength);
+    }
+
+    Ok(None)
+}
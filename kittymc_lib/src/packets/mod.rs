@@ -1,22 +1,29 @@
 use crate::error::KittyMCError;
 use crate::packets::client::login::*;
+use crate::packets::client::play::*;
 use crate::packets::client::status::*;
 use crate::packets::packet_serialization::NamedPacket;
 use crate::packets::packet_serialization::{
     decompress_packet, read_varint_u32, write_varint_u32_splice, SerializablePacket,
 };
+use crate::packets::registry::PACKET_REGISTRY;
 use crate::packets::server::handshake::*;
 use crate::packets::server::login::*;
 use crate::packets::server::play::entity_action_15::EntityActionPacket;
 use crate::packets::server::play::*;
 use crate::packets::server::status::*;
+use crate::subtypes::metadata::MetadataMap;
 use crate::subtypes::state::State;
-use integer_encoding::VarInt;
 use kittymc_macros::PacketHelperFuncs;
 use log::{trace, warn};
 
 pub mod client;
+pub mod codec;
+pub mod packet_buffer;
+pub mod packet_fields;
 pub mod packet_serialization;
+pub mod protocol;
+pub mod registry;
 pub mod server;
 
 #[derive(Debug, Default, Clone)]
@@ -25,10 +32,19 @@ pub struct CompressionInfo {
     pub compression_threshold: u32,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PacketDirection {
+    /// Client -> Server
+    Serverbound,
+    /// Server -> Client
+    Clientbound,
+}
+
 #[derive(PartialEq, Debug, Clone, PacketHelperFuncs)]
 pub enum Packet {
     Handshake(HandshakePacket),
     LoginStart(LoginStartPacket),
+    EncryptionResponse(EncryptionResponsePacket),
     LoginSuccess(LoginSuccessPacket),
     StatusRequest(StatusRequestPacket),
     StatusResponse(StatusResponsePacket),
@@ -49,6 +65,7 @@ pub enum Packet {
     EntityAction(EntityActionPacket),
     CreativeInventoryAction(CreativeInventoryActionPacket),
     PlayerBlockPlacement(PlayerBlockPlacementPacket),
+    TabComplete(ServerTabCompletePacket),
 }
 
 impl Packet {
@@ -57,107 +74,102 @@ impl Packet {
         rawr_data: &[u8],
         compression: &CompressionInfo,
     ) -> Result<(usize, Packet), KittyMCError> {
-        let mut data_part = rawr_data;
-        let mut compressed_packet_len;
-        let compressed_packet_data_len;
-        let mut compressed_packet_len_len = 0;
-        let mut uncompressed_data_len_len = 0;
-        let uncompressed_packet_data_len;
-        let uncompressed_data_len;
-
-        let is_packet_compressed = {
-            compressed_packet_data_len =
-                read_varint_u32(&mut data_part, &mut compressed_packet_len_len)? as usize;
-            compressed_packet_len = compressed_packet_data_len + compressed_packet_len_len;
-
-            uncompressed_data_len =
-                read_varint_u32(&mut data_part, &mut uncompressed_data_len_len)? as usize;
-            uncompressed_data_len != 0
-                && uncompressed_data_len >= compression.compression_threshold as usize
-        };
+        deserialize_compressed_with(state, rawr_data, compression, Self::deserialize_by_state)
+    }
 
-        uncompressed_packet_data_len = match is_packet_compressed {
-            true => uncompressed_data_len + uncompressed_data_len_len,
-            false => compressed_packet_data_len - uncompressed_data_len_len,
-        };
+    pub fn deserialize_uncompressed(
+        state: State,
+        data: &[u8],
+    ) -> Result<(usize, Packet), KittyMCError> {
+        deserialize_uncompressed_with(state, data, Self::deserialize_by_state)
+    }
 
-        let mut b_packet;
+    fn deserialize_by_state(
+        state: State,
+        data: &mut &[u8],
+        full_packet_len: usize,
+        packet_id: usize,
+    ) -> Result<(usize, Packet), KittyMCError> {
+        let decoder = PACKET_REGISTRY
+            .serverbound_decoder(state, packet_id as u32)
+            .ok_or(KittyMCError::NotImplemented(packet_id, full_packet_len))?;
 
-        if is_packet_compressed {
-            let (size, owned_data) = decompress_packet(&rawr_data)?;
-            b_packet = owned_data;
-            write_varint_u32_splice(&mut b_packet, uncompressed_data_len as u32, ..0);
-            trace!("Complete Uncompressed Packet : {:?}", b_packet);
+        decoder(data)
+    }
 
-            if compressed_packet_len != size {
-                warn!("Handled size of decompression function should be equal to deserialize_compressed functions size. Using decompression size.");
-                compressed_packet_len = size;
-            }
+    pub fn deserialize(
+        state: State,
+        raw_data: &[u8],
+        compression: &CompressionInfo,
+    ) -> Result<(usize, Packet), KittyMCError> {
+        if compression.enabled {
+            Self::deserialize_compressed(state, raw_data, compression)
         } else {
-            b_packet = match data_part.get(..uncompressed_packet_data_len) {
-                None => {
-                    return Err(KittyMCError::NotEnoughData(
-                        data_part.len(),
-                        uncompressed_packet_data_len,
-                    ))
-                }
-                Some(packet) => packet.to_vec(),
-            };
-            write_varint_u32_splice(&mut b_packet, uncompressed_packet_data_len as u32, ..0);
+            Self::deserialize_uncompressed(state, raw_data)
         }
+    }
+}
 
-        let (decompressed_size, packet) = match Self::deserialize_uncompressed(state, &b_packet) {
-            Ok(p) => Ok(p),
-            Err(KittyMCError::NotImplemented(id, _len)) => {
-                Err(KittyMCError::NotImplemented(id, compressed_packet_len))
-            } // Replace the length with the compressed length
-            Err(e) => Err(e),
-        }?;
-
-        if b_packet.len() != decompressed_size {
-            return Err(KittyMCError::InvalidDecompressedPacketLength(
-                b_packet.len(),
-                decompressed_size,
-            ));
-        }
+/// Every packet the server sends to the client, for anything that sits on the receiving end of a
+/// server connection (proxies, bots, tests).
+#[derive(PartialEq, Debug, Clone, PacketHelperFuncs)]
+pub enum ClientboundPacket {
+    // Status
+    StatusResponse(StatusResponsePacket),
+    StatusPong(StatusPingPongPacket),
+    // Login
+    LoginDisconnect(DisconnectLoginPacket),
+    EncryptionRequest(EncryptionRequestPacket),
+    LoginSuccess(LoginSuccessPacket),
+    SetCompression(SetCompressionPacket),
+    // Play
+    SpawnPlayer(SpawnPlayerPacket<MetadataMap>),
+    Animation(ServerAnimationPacket),
+    BlockBreakAnimation(BlockBreakAnimationPacket),
+    BlockChange(BlockChangePacket),
+    ServerDifficulty(ServerDifficultyPacket),
+    ChatMessage(ClientChatMessagePacket),
+    WindowItems(WindowItemsPacket),
+    PluginMessage(ServerPluginMessagePacket),
+    Disconnect(DisconnectPlayPacket),
+    EntityStatus(EntityStatusPacket),
+    UnloadChunk(UnloadChunkPacket),
+    KeepAlive(ServerKeepAlivePacket),
+    ChunkData(ChunkDataPacket<'static>),
+    JoinGame(JoinGamePacket),
+    EntityRelativeMove(EntityRelativeMovePacket),
+    EntityLook(EntityLookPacket),
+    EntityLookAndRelativeMove(EntityLookAndRelativeMovePacket),
+    EntityTeleport(EntityTeleportPacket),
+    PlayerAbilities(PlayerAbilitiesPacket),
+    PlayerListItem(PlayerListItemPacket),
+    PlayerPositionAndLook(ServerPlayerPositionAndLookPacket),
+    UnlockRecipes(UnlockRecipesPacket),
+    DestroyEntities(DestroyEntitiesPacket),
+    EntityHeadLook(EntityHeadLookPacket),
+    HeldItemChange(ServerHeldItemChangePacket),
+    EntityMetadata(EntityMetadataPacket<MetadataMap>),
+    SpawnPosition(SpawnPositionPacket),
+    TimeUpdate(TimeUpdatePacket),
+    TabComplete(ClientTabCompletePacket),
+    ChangeGameState(ChangeGameStatePacket),
+    UpdateHealth(UpdateHealthPacket),
+}
 
-        Ok((compressed_packet_len, packet))
+impl ClientboundPacket {
+    pub fn deserialize_compressed(
+        state: State,
+        rawr_data: &[u8],
+        compression: &CompressionInfo,
+    ) -> Result<(usize, ClientboundPacket), KittyMCError> {
+        deserialize_compressed_with(state, rawr_data, compression, Self::deserialize_by_state)
     }
 
     pub fn deserialize_uncompressed(
         state: State,
-        mut data: &[u8],
-    ) -> Result<(usize, Packet), KittyMCError> {
-        let mut header_size = 0;
-        let packet_data_and_id_len = read_varint_u32(&mut data, &mut header_size)? as usize;
-        let full_packet_len = packet_data_and_id_len + header_size;
-
-        if packet_data_and_id_len > data.len() {
-            trace!(
-                "Not enough data yet. Packet length: {}. Current Data length: {}",
-                packet_data_and_id_len,
-                data.len()
-            );
-            return Err(KittyMCError::NotEnoughData(
-                data.len(),
-                packet_data_and_id_len,
-            ));
-        }
-
-        let packet_id = read_varint_u32(&mut data, &mut header_size)? as usize;
-        let Some(packet_data_len) = full_packet_len.checked_sub(header_size) else {
-            return Err(KittyMCError::InvalidPacketLength);
-        };
-
-        let (packet_size, packet) = Self::deserialize_by_state(
-            state,
-            &mut &data[..packet_data_len],
-            full_packet_len,
-            packet_id,
-        )?;
-
-        let full_size = header_size + packet_size;
-        Ok((full_size, packet))
+        data: &[u8],
+    ) -> Result<(usize, ClientboundPacket), KittyMCError> {
+        deserialize_uncompressed_with(state, data, Self::deserialize_by_state)
     }
 
     fn deserialize_by_state(
@@ -165,48 +177,19 @@ impl Packet {
         data: &mut &[u8],
         full_packet_len: usize,
         packet_id: usize,
-    ) -> Result<(usize, Packet), KittyMCError> {
-        let (packet_size, packet) = match state {
-            State::Handshake => match packet_id {
-                0 => HandshakePacket::deserialize(data)?,
-                _ => return Err(KittyMCError::NotImplemented(packet_id, full_packet_len)),
-            },
-            State::Status => match packet_id {
-                0 => StatusRequestPacket::deserialize(data)?,
-                1 => StatusPingPongPacket::deserialize(data)?,
-                _ => return Err(KittyMCError::NotImplemented(packet_id, full_packet_len)),
-            },
-            State::Login => match packet_id {
-                0 => LoginStartPacket::deserialize(data)?,
-                _ => return Err(KittyMCError::NotImplemented(packet_id, full_packet_len)),
-            },
-            State::Play => match packet_id {
-                0 => TeleportConfirmPacket::deserialize(data)?,
-                2 => ServerChatMessagePacket::deserialize(data)?,
-                4 => ClientSettingsPacket::deserialize(data)?,
-                9 => ClientPluginMessagePacket::deserialize(data)?,
-                0xB => ClientKeepAlivePacket::deserialize(data)?,
-                0xD => PlayerPositionPacket::deserialize(data)?,
-                0xE => ClientPlayerPositionAndLookPacket::deserialize(data)?,
-                0xF => PlayerLookPacket::deserialize(data)?,
-                0x14 => PlayerDiggingPacket::deserialize(data)?,
-                0x15 => EntityActionPacket::deserialize(data)?,
-                0x1A => ClientHeldItemChangePacket::deserialize(data)?,
-                0x1B => CreativeInventoryActionPacket::deserialize(data)?,
-                0x1D => ClientAnimationPacket::deserialize(data)?,
-                0x1F => PlayerBlockPlacementPacket::deserialize(data)?,
-                _ => return Err(KittyMCError::NotImplemented(packet_id, full_packet_len)),
-            },
-            _ => return Err(KittyMCError::NotImplemented(packet_id, full_packet_len)),
-        };
-        Ok((packet_size, packet))
+    ) -> Result<(usize, ClientboundPacket), KittyMCError> {
+        let decoder = PACKET_REGISTRY
+            .clientbound_decoder(state, packet_id as u32)
+            .ok_or(KittyMCError::NotImplemented(packet_id, full_packet_len))?;
+
+        decoder(data)
     }
 
     pub fn deserialize(
         state: State,
         raw_data: &[u8],
         compression: &CompressionInfo,
-    ) -> Result<(usize, Packet), KittyMCError> {
+    ) -> Result<(usize, ClientboundPacket), KittyMCError> {
         if compression.enabled {
             Self::deserialize_compressed(state, raw_data, compression)
         } else {
@@ -215,11 +198,114 @@ impl Packet {
     }
 }
 
-pub fn wrap_packet(packet: &mut Vec<u8>, id: u32) {
-    // add packet id
-    packet.splice(0..0, id.encode_var_vec());
+type StateDeserializer<P> = fn(State, &mut &[u8], usize, usize) -> Result<(usize, P), KittyMCError>;
+
+fn deserialize_compressed_with<P>(
+    state: State,
+    rawr_data: &[u8],
+    compression: &CompressionInfo,
+    by_state: StateDeserializer<P>,
+) -> Result<(usize, P), KittyMCError> {
+    let mut data_part = rawr_data;
+    let mut compressed_packet_len;
+    let compressed_packet_data_len;
+    let mut compressed_packet_len_len = 0;
+    let mut uncompressed_data_len_len = 0;
+    let uncompressed_data_len;
+
+    let is_packet_compressed = {
+        compressed_packet_data_len =
+            read_varint_u32(&mut data_part, &mut compressed_packet_len_len)? as usize;
+        compressed_packet_len = compressed_packet_data_len + compressed_packet_len_len;
+
+        uncompressed_data_len =
+            read_varint_u32(&mut data_part, &mut uncompressed_data_len_len)? as usize;
+        uncompressed_data_len != 0
+            && uncompressed_data_len >= compression.compression_threshold as usize
+    };
+
+    let uncompressed_packet_data_len = match is_packet_compressed {
+        true => uncompressed_data_len + uncompressed_data_len_len,
+        false => compressed_packet_data_len - uncompressed_data_len_len,
+    };
+
+    let mut b_packet;
+
+    if is_packet_compressed {
+        let (size, owned_data) = decompress_packet(rawr_data)?;
+        b_packet = owned_data;
+        write_varint_u32_splice(&mut b_packet, uncompressed_data_len as u32, ..0);
+        trace!("Complete Uncompressed Packet : {:?}", b_packet);
+
+        if compressed_packet_len != size {
+            warn!("Handled size of decompression function should be equal to deserialize_compressed functions size. Using decompression size.");
+            compressed_packet_len = size;
+        }
+    } else {
+        b_packet = match data_part.get(..uncompressed_packet_data_len) {
+            None => {
+                return Err(KittyMCError::NotEnoughData(
+                    data_part.len(),
+                    uncompressed_packet_data_len,
+                ))
+            }
+            Some(packet) => packet.to_vec(),
+        };
+        write_varint_u32_splice(&mut b_packet, uncompressed_packet_data_len as u32, ..0);
+    }
+
+    let (decompressed_size, packet) =
+        match deserialize_uncompressed_with(state, &b_packet, by_state) {
+            Ok(p) => Ok(p),
+            Err(KittyMCError::NotImplemented(id, _len)) => {
+                Err(KittyMCError::NotImplemented(id, compressed_packet_len))
+            } // Replace the length with the compressed length
+            Err(e) => Err(e),
+        }?;
+
+    if b_packet.len() != decompressed_size {
+        return Err(KittyMCError::InvalidDecompressedPacketLength(
+            b_packet.len(),
+            decompressed_size,
+        ));
+    }
+
+    Ok((compressed_packet_len, packet))
+}
+
+fn deserialize_uncompressed_with<P>(
+    state: State,
+    mut data: &[u8],
+    by_state: StateDeserializer<P>,
+) -> Result<(usize, P), KittyMCError> {
+    let mut header_size = 0;
+    let packet_data_and_id_len = read_varint_u32(&mut data, &mut header_size)? as usize;
+    let full_packet_len = packet_data_and_id_len + header_size;
+
+    if packet_data_and_id_len > data.len() {
+        trace!(
+            "Not enough data yet. Packet length: {}. Current Data length: {}",
+            packet_data_and_id_len,
+            data.len()
+        );
+        return Err(KittyMCError::NotEnoughData(
+            data.len(),
+            packet_data_and_id_len,
+        ));
+    }
+
+    let packet_id = read_varint_u32(&mut data, &mut header_size)? as usize;
+    let Some(packet_data_len) = full_packet_len.checked_sub(header_size) else {
+        return Err(KittyMCError::InvalidPacketLength);
+    };
+
+    let (packet_size, packet) = by_state(
+        state,
+        &mut &data[..packet_data_len],
+        full_packet_len,
+        packet_id,
+    )?;
 
-    // set total length
-    let total_len = packet.len().encode_var_vec();
-    packet.splice(0..0, total_len);
+    let full_size = header_size + packet_size;
+    Ok((full_size, packet))
 }
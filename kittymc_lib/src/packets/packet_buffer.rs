@@ -0,0 +1,231 @@
+//! Serializing packets into memory that is reused from one packet to the next.
+//!
+//! A frame starts with its length, which isn't known before the packet is written. Instead of
+//! moving the whole packet back to make room for it afterwards, [`PacketBuffer`] leaves room for
+//! the longest possible header in front of the packet and fills in the header once the packet is
+//! written. The frame then starts somewhere inside that room, right where the header begins.
+
+use crate::error::KittyMCError;
+use crate::packets::packet_serialization::{write_varint_u32, SerializablePacket};
+use crate::packets::CompressionInfo;
+use integer_encoding::VarInt;
+use miniz_oxide::deflate::core::{
+    compress, create_comp_flags_from_zip_params, CompressorOxide, TDEFLFlush, TDEFLStatus,
+};
+use std::fmt::{Debug, Formatter};
+
+const MAX_VARINT_LEN: usize = 5;
+/// The frame length and, with compression, the uncompressed length
+const HEADER_SPACE: usize = 2 * MAX_VARINT_LEN;
+
+const COMPRESSION_LEVEL: i32 = 5;
+/// Makes the compressor write the zlib header and checksum
+const ZLIB_WINDOW_BITS: i32 = 1;
+
+/// Where the frame that was written last is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FrameLocation {
+    Packet(usize),
+    Compressed(usize),
+}
+
+/// Serializes packets into frames without allocating once it has grown to fit the largest packet.
+///
+/// Every write replaces the frame written before, so a single buffer can be kept around for a
+/// whole connection.
+pub struct PacketBuffer {
+    /// The packet id and fields, with [`HEADER_SPACE`] bytes in front
+    packet: Vec<u8>,
+    /// The compressed packet id and fields, also with [`HEADER_SPACE`] bytes in front
+    compressed: Vec<u8>,
+    /// Created the first time a packet has to be compressed, as it takes a few hundred KB
+    compressor: Option<Box<CompressorOxide>>,
+    frame: FrameLocation,
+}
+
+impl PacketBuffer {
+    /// Doesn't allocate anything before the first packet is written
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    /// Makes room for packets of up to `capacity` bytes
+    pub fn with_capacity(capacity: usize) -> Self {
+        PacketBuffer {
+            packet: Vec::with_capacity(capacity),
+            compressed: vec![],
+            compressor: None,
+            frame: FrameLocation::Packet(0),
+        }
+    }
+
+    /// Serializes `packet` into an uncompressed frame and returns it
+    pub fn write_packet<T: SerializablePacket + ?Sized>(&mut self, packet: &T) -> &mut [u8] {
+        self.write_body(packet);
+        self.finish()
+    }
+
+    /// Serializes `packet` into a frame for a connection with compression enabled and returns
+    /// it. Only packets of at least `threshold` bytes actually get compressed.
+    pub fn write_packet_compressed<T: SerializablePacket + ?Sized>(
+        &mut self,
+        packet: &T,
+        threshold: u32,
+    ) -> Result<&mut [u8], KittyMCError> {
+        self.write_body(packet);
+        self.finish_compressed(threshold)
+    }
+
+    /// Picks [`Self::write_packet`] or [`Self::write_packet_compressed`] depending on `compression`
+    pub fn write_packet_with<T: SerializablePacket + ?Sized>(
+        &mut self,
+        packet: &T,
+        compression: &CompressionInfo,
+    ) -> Result<&mut [u8], KittyMCError> {
+        self.write_body(packet);
+        self.finish_with(compression)
+    }
+
+    /// Frames an already serialized packet. `packet` is the packet id followed by the fields.
+    pub fn write_raw_packet(
+        &mut self,
+        packet: &[u8],
+        compression: &CompressionInfo,
+    ) -> Result<&mut [u8], KittyMCError> {
+        self.clear_body();
+        self.packet.extend_from_slice(packet);
+        self.finish_with(compression)
+    }
+
+    /// The frame that was written last. Empty if nothing was written yet.
+    pub fn frame(&self) -> &[u8] {
+        match self.frame {
+            FrameLocation::Packet(start) => &self.packet[start..],
+            FrameLocation::Compressed(start) => &self.compressed[start..],
+        }
+    }
+
+    /// Gives access to the frame e.g. for encrypting it in place
+    pub fn frame_mut(&mut self) -> &mut [u8] {
+        match self.frame {
+            FrameLocation::Packet(start) => &mut self.packet[start..],
+            FrameLocation::Compressed(start) => &mut self.compressed[start..],
+        }
+    }
+
+    /// Turns the buffer into the frame that was written last
+    pub fn into_frame(self) -> Vec<u8> {
+        let (mut frame, start) = match self.frame {
+            FrameLocation::Packet(start) => (self.packet, start),
+            FrameLocation::Compressed(start) => (self.compressed, start),
+        };
+
+        frame.drain(..start);
+        frame
+    }
+
+    fn finish(&mut self) -> &mut [u8] {
+        let frame_len = self.packet.len() - HEADER_SPACE;
+        let start = prepend_varint(&mut self.packet, HEADER_SPACE, frame_len as u32);
+        self.frame = FrameLocation::Packet(start);
+
+        self.frame_mut()
+    }
+
+    fn finish_compressed(&mut self, threshold: u32) -> Result<&mut [u8], KittyMCError> {
+        let body_len = (self.packet.len() - HEADER_SPACE) as u32;
+        self.frame = if body_len >= threshold {
+            self.compress_body()?;
+            let start = prepend_varint(&mut self.compressed, HEADER_SPACE, body_len);
+            let frame_len = self.compressed.len() - start;
+            FrameLocation::Compressed(prepend_varint(&mut self.compressed, start, frame_len as u32))
+        } else {
+            // An uncompressed length of 0 marks the packet as not compressed
+            let start = prepend_varint(&mut self.packet, HEADER_SPACE, 0);
+            let frame_len = self.packet.len() - start;
+            FrameLocation::Packet(prepend_varint(&mut self.packet, start, frame_len as u32))
+        };
+
+        Ok(self.frame_mut())
+    }
+
+    fn finish_with(&mut self, compression: &CompressionInfo) -> Result<&mut [u8], KittyMCError> {
+        if compression.enabled {
+            self.finish_compressed(compression.compression_threshold)
+        } else {
+            Ok(self.finish())
+        }
+    }
+
+    fn clear_body(&mut self) {
+        self.packet.clear();
+        self.packet.resize(HEADER_SPACE, 0);
+    }
+
+    fn write_body<T: SerializablePacket + ?Sized>(&mut self, packet: &T) {
+        self.clear_body();
+        write_varint_u32(&mut self.packet, T::id());
+        packet.serialize_fields(&mut self.packet);
+    }
+
+    /// Compresses the packet id and fields into `compressed`, behind the header space
+    fn compress_body(&mut self) -> Result<(), KittyMCError> {
+        let compressor = self.compressor.get_or_insert_with(|| {
+            let flags = create_comp_flags_from_zip_params(COMPRESSION_LEVEL, ZLIB_WINDOW_BITS, 0);
+            Box::new(CompressorOxide::new(flags))
+        });
+        compressor.reset();
+
+        let mut input = &self.packet[HEADER_SPACE..];
+        let mut out_pos = HEADER_SPACE;
+        self.compressed
+            .resize(HEADER_SPACE + (input.len() / 2).max(64), 0);
+
+        loop {
+            let (status, bytes_in, bytes_out) = compress(
+                compressor,
+                input,
+                &mut self.compressed[out_pos..],
+                TDEFLFlush::Finish,
+            );
+            out_pos += bytes_out;
+            input = &input[bytes_in..];
+
+            match status {
+                TDEFLStatus::Done => break,
+                TDEFLStatus::Okay => {
+                    if self.compressed.len() - out_pos < 64 {
+                        let new_len = self.compressed.len() * 2;
+                        self.compressed.resize(new_len, 0);
+                    }
+                }
+                status => return Err(KittyMCError::ZlibCompressionError(status)),
+            }
+        }
+
+        self.compressed.truncate(out_pos);
+
+        Ok(())
+    }
+}
+
+impl Default for PacketBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for PacketBuffer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PacketBuffer")
+            .field("frame", &self.frame())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Writes `value` into the bytes right before `end` and returns where it starts
+fn prepend_varint(buffer: &mut [u8], end: usize, value: u32) -> usize {
+    let start = end - value.required_space();
+    value.encode_var(&mut buffer[start..end]);
+    start
+}
@@ -0,0 +1,239 @@
+//! Per-field wire codecs used by `#[derive(Packet)]`.
+//!
+//! Every field of a packet with a `#[packet(id = ..)]` attribute is written and read through
+//! [`PacketField`]. The encoding marker picks the wire format when a Rust type has more than one,
+//! e.g. an `i32` can be a fixed width int or a VarInt. The marker is chosen with a field attribute:
+//!
+//! | attribute              | marker       | used for                                    |
+//! |------------------------|--------------|---------------------------------------------|
+//! | _none_                 | [`Plain`]    | fixed width numbers, strings, uuids, ...    |
+//! | `#[packet(varint)]`    | [`VarInt`]   | VarInt / VarLong numbers and enums          |
+//! | `#[packet(angle)]`     | [`Angle`]    | 1/256th of a turn rotations                 |
+//! | `#[packet(position)]`  | [`Position`] | 64 bit packed block positions               |
+//!
+//! `Vec<T>` is written as a VarInt length followed by the elements and `Option<T>` as a bool
+//! followed by the value if present. Both pass the marker on to their inner type, so
+//! `#[packet(varint)] entity_ids: Vec<i32>` is a length-prefixed array of VarInts.
+
+use crate::error::KittyMCError;
+use crate::packets::packet_serialization::*;
+use crate::subtypes::components::Component;
+use crate::subtypes::state::State;
+use crate::subtypes::{Direction, Location, Location2};
+use paste::paste;
+use uuid::Uuid;
+
+pub struct Plain;
+pub struct VarInt;
+pub struct Angle;
+pub struct Position;
+
+pub trait PacketField<E = Plain>: Sized {
+    fn write_field(&self, buffer: &mut Vec<u8>);
+
+    fn read_field(data: &mut &[u8], total_size: &mut usize) -> Result<Self, KittyMCError>;
+}
+
+macro_rules! plain_field_impl {
+    ($($ty:ty$(,)?)*) => {
+        $(
+        paste! {
+            impl PacketField<Plain> for $ty {
+                fn write_field(&self, buffer: &mut Vec<u8>) {
+                    [<write_ $ty>](buffer, *self);
+                }
+
+                fn read_field(data: &mut &[u8], total_size: &mut usize) -> Result<Self, KittyMCError> {
+                    [<read_ $ty>](data, total_size)
+                }
+            }
+        }
+    )*};
+}
+
+macro_rules! varint_field_impl {
+    ($($ty:ty$(,)?)*) => {
+        $(
+        paste! {
+            impl PacketField<VarInt> for $ty {
+                fn write_field(&self, buffer: &mut Vec<u8>) {
+                    [<write_varint_ $ty>](buffer, *self);
+                }
+
+                fn read_field(data: &mut &[u8], total_size: &mut usize) -> Result<Self, KittyMCError> {
+                    [<read_varint_ $ty>](data, total_size)
+                }
+            }
+        }
+    )*};
+}
+
+plain_field_impl!(u128, u64, u32, u16, u8, i128, i64, i32, i16, i8, f64, f32);
+varint_field_impl!(u64, u32, u16, u8, i64, i32, i16, i8);
+
+impl PacketField<Plain> for bool {
+    fn write_field(&self, buffer: &mut Vec<u8>) {
+        write_bool(buffer, *self);
+    }
+
+    fn read_field(data: &mut &[u8], total_size: &mut usize) -> Result<Self, KittyMCError> {
+        read_bool(data, total_size)
+    }
+}
+
+impl PacketField<Plain> for String {
+    fn write_field(&self, buffer: &mut Vec<u8>) {
+        write_length_prefixed_string(buffer, self);
+    }
+
+    fn read_field(data: &mut &[u8], total_size: &mut usize) -> Result<Self, KittyMCError> {
+        read_length_prefixed_string(data, total_size)
+    }
+}
+
+impl PacketField<Plain> for Uuid {
+    fn write_field(&self, buffer: &mut Vec<u8>) {
+        write_uuid(buffer, self);
+    }
+
+    fn read_field(data: &mut &[u8], total_size: &mut usize) -> Result<Self, KittyMCError> {
+        read_uuid(data, total_size)
+    }
+}
+
+impl PacketField<Plain> for Location {
+    fn write_field(&self, buffer: &mut Vec<u8>) {
+        write_location(buffer, self);
+    }
+
+    fn read_field(data: &mut &[u8], total_size: &mut usize) -> Result<Self, KittyMCError> {
+        read_location(data, total_size)
+    }
+}
+
+impl PacketField<Position> for Location {
+    fn write_field(&self, buffer: &mut Vec<u8>) {
+        write_block_location(buffer, self);
+    }
+
+    fn read_field(data: &mut &[u8], total_size: &mut usize) -> Result<Self, KittyMCError> {
+        read_block_location(data, total_size)
+    }
+}
+
+impl PacketField<Plain> for Location2 {
+    fn write_field(&self, buffer: &mut Vec<u8>) {
+        write_location2(buffer, self);
+    }
+
+    fn read_field(data: &mut &[u8], total_size: &mut usize) -> Result<Self, KittyMCError> {
+        read_location2(data, total_size)
+    }
+}
+
+impl PacketField<Plain> for Direction {
+    fn write_field(&self, buffer: &mut Vec<u8>) {
+        write_direction(buffer, self);
+    }
+
+    fn read_field(data: &mut &[u8], total_size: &mut usize) -> Result<Self, KittyMCError> {
+        read_direction(data, total_size)
+    }
+}
+
+// Angles are wrapped into -180..=180 first since anything outside would saturate the i8
+impl PacketField<Angle> for f32 {
+    fn write_field(&self, buffer: &mut Vec<u8>) {
+        write_angle(buffer, (*self + 180.).rem_euclid(360.) - 180.);
+    }
+
+    fn read_field(data: &mut &[u8], total_size: &mut usize) -> Result<Self, KittyMCError> {
+        read_angle(data, total_size)
+    }
+}
+
+impl PacketField<Angle> for Direction {
+    fn write_field(&self, buffer: &mut Vec<u8>) {
+        PacketField::<Angle>::write_field(&self.x, buffer);
+        PacketField::<Angle>::write_field(&self.y, buffer);
+    }
+
+    fn read_field(data: &mut &[u8], total_size: &mut usize) -> Result<Self, KittyMCError> {
+        read_direction_as_angles(data, total_size)
+    }
+}
+
+impl PacketField<Plain> for Component {
+    fn write_field(&self, buffer: &mut Vec<u8>) {
+        self.write(buffer);
+    }
+
+    fn read_field(data: &mut &[u8], total_size: &mut usize) -> Result<Self, KittyMCError> {
+        Component::read(data, total_size)
+    }
+}
+
+impl PacketField<Plain> for fastnbt::Value {
+    fn write_field(&self, buffer: &mut Vec<u8>) {
+        write_nbt(buffer, self);
+    }
+
+    fn read_field(data: &mut &[u8], total_size: &mut usize) -> Result<Self, KittyMCError> {
+        read_nbt(data, total_size)
+    }
+}
+
+impl PacketField<VarInt> for State {
+    fn write_field(&self, buffer: &mut Vec<u8>) {
+        write_varint_u32(buffer, *self as u32);
+    }
+
+    fn read_field(data: &mut &[u8], total_size: &mut usize) -> Result<Self, KittyMCError> {
+        Ok(State::from(read_varint_u32(data, total_size)?))
+    }
+}
+
+impl<E, T: PacketField<E>> PacketField<E> for Vec<T> {
+    fn write_field(&self, buffer: &mut Vec<u8>) {
+        write_varint_u32(buffer, self.len() as u32);
+        for element in self {
+            element.write_field(buffer);
+        }
+    }
+
+    fn read_field(data: &mut &[u8], total_size: &mut usize) -> Result<Self, KittyMCError> {
+        let len = read_varint_u32(data, total_size)? as usize;
+        if data.len() < len {
+            // every element takes at least one byte, so this can't be right
+            return Err(KittyMCError::NotEnoughBytesToDeserialize(
+                "Array",
+                len,
+                data.len(),
+            ));
+        }
+
+        let mut elements = Vec::with_capacity(len);
+        for _ in 0..len {
+            elements.push(T::read_field(data, total_size)?);
+        }
+
+        Ok(elements)
+    }
+}
+
+impl<E, T: PacketField<E>> PacketField<E> for Option<T> {
+    fn write_field(&self, buffer: &mut Vec<u8>) {
+        write_bool(buffer, self.is_some());
+        if let Some(value) = self {
+            value.write_field(buffer);
+        }
+    }
+
+    fn read_field(data: &mut &[u8], total_size: &mut usize) -> Result<Self, KittyMCError> {
+        if read_bool(data, total_size)? {
+            Ok(Some(T::read_field(data, total_size)?))
+        } else {
+            Ok(None)
+        }
+    }
+}
@@ -1,7 +1,9 @@
 use crate::error::KittyMCError;
-use crate::packets::Packet;
+use crate::packets::packet_buffer::PacketBuffer;
+use crate::packets::{ClientboundPacket, Packet, PacketDirection};
+use crate::subtypes::state::State;
 use crate::subtypes::{Direction, Location, Location2, Rotation};
-use crate::utils::axis_to_angle;
+use crate::utils::{angle_to_axis, axis_to_angle};
 use integer_encoding::VarInt;
 use log::warn;
 use miniz_oxide::deflate::compress_to_vec_zlib;
@@ -17,9 +19,22 @@ pub trait NamedPacket {
     }
 }
 
+/// Where a packet lives in the protocol. Derived from `#[packet(state = .., direction = ..)]`.
+pub trait PacketInfo {
+    const STATE: State;
+    const DIRECTION: PacketDirection;
+}
+
 pub trait SerializablePacket {
+    // not including length or packet id
+    fn serialize_fields(&self, _buffer: &mut Vec<u8>) {}
+
+    /// Serializes the whole packet into a new `Vec`. Use a [`PacketBuffer`] to serialize a lot of
+    /// packets without allocating for each one.
     fn serialize(&self) -> Vec<u8> {
-        vec![0]
+        let mut buffer = PacketBuffer::new();
+        buffer.write_packet(self);
+        buffer.into_frame()
     }
 
     // not including length or packet id
@@ -30,6 +45,14 @@ pub trait SerializablePacket {
         ))
     }
 
+    // not including length or packet id
+    fn deserialize_clientbound(data: &[u8]) -> Result<(usize, ClientboundPacket), KittyMCError> {
+        Err(KittyMCError::NotImplemented(
+            Self::id() as usize,
+            data.len(),
+        ))
+    }
+
     fn id() -> u32;
 }
 
@@ -120,7 +143,9 @@ macro_rules! type_rw_varint_impl {
             }
 
             pub fn [<write_varint_ $ty>](buffer: &mut Vec<u8>, value: $ty) {
-                buffer.extend_from_slice(&value.encode_var_vec());
+                let mut bytes = [0u8; 10];
+                let len = value.encode_var(&mut bytes);
+                buffer.extend_from_slice(&bytes[..len]);
             }
 
             pub fn [<write_varint_ $ty _splice>]<R: RangeBounds<usize>>(buffer: &mut Vec<u8>, value: $ty, at: R) {
@@ -134,7 +159,7 @@ type_rw_impl!(u128, u64, u32, u16, u8, i128, i64, i32, i16, i8, f64, f32);
 type_rw_varint_impl!(u64, u32, u16, u8, i64, i32, i16, i8);
 
 pub fn read_bool(data: &mut &[u8], total_size: &mut usize) -> Result<bool, KittyMCError> {
-    if data.len() < 1 {
+    if data.is_empty() {
         return Err(KittyMCError::NotEnoughBytesToDeserialize(
             "Bool",
             1,
@@ -147,6 +172,18 @@ pub fn read_bool(data: &mut &[u8], total_size: &mut usize) -> Result<bool, Kitty
     Ok(value)
 }
 
+pub fn read_uuid(data: &mut &[u8], total_size: &mut usize) -> Result<Uuid, KittyMCError> {
+    Ok(Uuid::from_u128(read_u128(data, total_size)?))
+}
+
+pub fn read_location(data: &mut &[u8], total_size: &mut usize) -> Result<Location, KittyMCError> {
+    let x = read_f32(data, total_size)?;
+    let y = read_f32(data, total_size)?;
+    let z = read_f32(data, total_size)?;
+
+    Ok(Location::new(x, y, z))
+}
+
 pub fn read_location2(data: &mut &[u8], total_size: &mut usize) -> Result<Location2, KittyMCError> {
     let x = read_f64(data, total_size)?;
     let y = read_f64(data, total_size)?;
@@ -215,6 +252,28 @@ pub fn read_direction(data: &mut &[u8], total_size: &mut usize) -> Result<Direct
     Ok(Direction::new(yaw, pitch))
 }
 
+pub fn read_rotation(data: &mut &[u8], total_size: &mut usize) -> Result<Rotation, KittyMCError> {
+    let roll = read_f32(data, total_size)?;
+    let pitch = read_f32(data, total_size)?;
+    let yaw = read_f32(data, total_size)?;
+
+    Ok(Rotation::from_euler_angles(roll, pitch, yaw))
+}
+
+pub fn read_angle(data: &mut &[u8], total_size: &mut usize) -> Result<f32, KittyMCError> {
+    Ok(angle_to_axis(read_i8(data, total_size)?))
+}
+
+pub fn read_direction_as_angles(
+    data: &mut &[u8],
+    total_size: &mut usize,
+) -> Result<Direction, KittyMCError> {
+    let yaw = read_angle(data, total_size)?;
+    let pitch = read_angle(data, total_size)?;
+
+    Ok(Direction::new(yaw, pitch))
+}
+
 pub fn read_nbt(buffer: &mut &[u8], total_size: &mut usize) -> Result<fastnbt::Value, KittyMCError> {
     let len_before = buffer.len();
     let nbt = fastnbt::from_reader(&mut *buffer);
@@ -345,7 +404,7 @@ pub fn decompress_packet(mut compressed_packet: &[u8]) -> Result<(usize, Vec<u8>
 
     let uncompressed_packet =
         decompress_to_vec_zlib_with_limit(compressed_packet, uncompressed_data_length as usize)
-            .map_err(|e| KittyMCError::ZlibDecompressionError(e))?;
+            .map_err(KittyMCError::ZlibDecompressionError)?;
 
     Ok((total_size, uncompressed_packet))
 }
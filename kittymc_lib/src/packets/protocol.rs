@@ -0,0 +1,270 @@
+//! Protocol versions and how they differ from the one the packets are written for.
+//!
+//! Every packet type models 1.12.2 ([`ProtocolVersion::LATEST`]). Other versions are supported by
+//! translating their packets into the 1.12.2 format when reading and back when writing. Packets
+//! that moved get their id translated through an `IdShift` and packets whose fields changed are
+//! rewritten by a `LayoutRewrite`. [`PacketCodec`](crate::packets::codec::PacketCodec) does all of
+//! this by itself once it saw the version in the handshake.
+
+use crate::error::KittyMCError;
+use crate::packets::packet_serialization::{
+    read_u64, read_varint_u32, write_u64, write_varint_u32,
+};
+use crate::packets::PacketDirection;
+use crate::subtypes::state::State;
+use std::fmt::{Display, Formatter};
+use std::ops::RangeInclusive;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ProtocolVersion {
+    V1_12 = 335,
+    V1_12_1 = 338,
+    V1_12_2 = 340,
+}
+
+impl ProtocolVersion {
+    /// The version all packet types are modelled after
+    pub const LATEST: ProtocolVersion = ProtocolVersion::V1_12_2;
+    pub const SUPPORTED: [ProtocolVersion; 3] = [
+        ProtocolVersion::V1_12,
+        ProtocolVersion::V1_12_1,
+        ProtocolVersion::V1_12_2,
+    ];
+
+    pub fn from_id(id: u32) -> Option<Self> {
+        Self::SUPPORTED
+            .into_iter()
+            .find(|version| version.id() == id)
+    }
+
+    pub fn id(self) -> u32 {
+        self as u32
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ProtocolVersion::V1_12 => "1.12",
+            ProtocolVersion::V1_12_1 => "1.12.1",
+            ProtocolVersion::V1_12_2 => "1.12.2",
+        }
+    }
+
+    fn table(self) -> &'static VersionTable {
+        match self {
+            ProtocolVersion::V1_12 => &V1_12_TABLE,
+            ProtocolVersion::V1_12_1 => &V1_12_1_TABLE,
+            ProtocolVersion::V1_12_2 => &V1_12_2_TABLE,
+        }
+    }
+
+    /// Translates a packet id of this version into the 1.12.2 one. Returns `None` if the packet
+    /// doesn't exist in 1.12.2.
+    pub fn latest_packet_id(self, state: State, direction: PacketDirection, id: u32) -> Option<u32> {
+        let table = self.table();
+        if table.only_here.contains(&(state, direction, id)) {
+            return None;
+        }
+
+        let shifted = table
+            .shifts
+            .iter()
+            .filter(|shift| shift.state == state && shift.direction == direction)
+            .find_map(|shift| {
+                let offset = id.checked_sub(shift.start)?;
+                (offset <= shift.latest.end() - shift.latest.start())
+                    .then_some(shift.latest.start() + offset)
+            });
+
+        Some(shifted.unwrap_or(id))
+    }
+
+    /// Translates a 1.12.2 packet id into the one of this version. Returns `None` if this version
+    /// doesn't have the packet.
+    pub fn packet_id(self, state: State, direction: PacketDirection, latest_id: u32) -> Option<u32> {
+        let table = self.table();
+        if table.only_latest.contains(&(state, direction, latest_id)) {
+            return None;
+        }
+
+        let shifted = table
+            .shifts
+            .iter()
+            .filter(|shift| shift.state == state && shift.direction == direction)
+            .find(|shift| shift.latest.contains(&latest_id))
+            .map(|shift| shift.start + (latest_id - shift.latest.start()));
+
+        Some(shifted.unwrap_or(latest_id))
+    }
+
+    fn rewrite(
+        self,
+        state: State,
+        direction: PacketDirection,
+        latest_id: u32,
+    ) -> Option<&'static LayoutRewrite> {
+        self.table().rewrites.iter().find(|rewrite| {
+            rewrite.state == state && rewrite.direction == direction && rewrite.latest_id == latest_id
+        })
+    }
+
+    /// Turns a packet of this version into its 1.12.2 form. `packet` is the packet id followed by
+    /// the fields, without the length in front.
+    pub fn upgrade_packet(
+        self,
+        state: State,
+        direction: PacketDirection,
+        mut packet: &[u8],
+    ) -> Result<Vec<u8>, KittyMCError> {
+        let full_len = packet.len();
+        let mut size = 0;
+        let id = read_varint_u32(&mut packet, &mut size)?;
+
+        let latest_id = self
+            .latest_packet_id(state, direction, id)
+            .ok_or(KittyMCError::NotImplemented(id as usize, full_len))?;
+
+        let mut upgraded = vec![];
+        write_varint_u32(&mut upgraded, latest_id);
+        match self.rewrite(state, direction, latest_id) {
+            Some(rewrite) => (rewrite.upgrade)(packet, &mut upgraded)?,
+            None => upgraded.extend_from_slice(packet),
+        }
+
+        Ok(upgraded)
+    }
+
+    /// Turns a 1.12.2 packet into the form this version expects. Same format as
+    /// [`Self::upgrade_packet`].
+    pub fn downgrade_packet(
+        self,
+        state: State,
+        direction: PacketDirection,
+        mut packet: &[u8],
+    ) -> Result<Vec<u8>, KittyMCError> {
+        let full_len = packet.len();
+        let mut size = 0;
+        let latest_id = read_varint_u32(&mut packet, &mut size)?;
+
+        let id = self
+            .packet_id(state, direction, latest_id)
+            .ok_or(KittyMCError::NotImplemented(latest_id as usize, full_len))?;
+
+        let mut downgraded = vec![];
+        write_varint_u32(&mut downgraded, id);
+        match self.rewrite(state, direction, latest_id) {
+            Some(rewrite) => (rewrite.downgrade)(packet, &mut downgraded)?,
+            None => downgraded.extend_from_slice(packet),
+        }
+
+        Ok(downgraded)
+    }
+}
+
+impl Display for ProtocolVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.name(), self.id())
+    }
+}
+
+type PacketKey = (State, PacketDirection, u32);
+type FieldRewriter = fn(&[u8], &mut Vec<u8>) -> Result<(), KittyMCError>;
+
+/// The packets with the 1.12.2 ids in `latest` start at `start` in another version
+struct IdShift {
+    state: State,
+    direction: PacketDirection,
+    latest: RangeInclusive<u32>,
+    start: u32,
+}
+
+/// Converts the fields of a packet, identified by its 1.12.2 id, between the layouts
+struct LayoutRewrite {
+    state: State,
+    direction: PacketDirection,
+    latest_id: u32,
+    upgrade: FieldRewriter,
+    downgrade: FieldRewriter,
+}
+
+struct VersionTable {
+    shifts: &'static [IdShift],
+    /// Packets 1.12.2 has, but this version doesn't, by their 1.12.2 id
+    only_latest: &'static [PacketKey],
+    /// Packets this version has, but 1.12.2 doesn't, by their id in this version
+    only_here: &'static [PacketKey],
+    rewrites: &'static [LayoutRewrite],
+}
+
+static V1_12_2_TABLE: VersionTable = VersionTable {
+    shifts: &[],
+    only_latest: &[],
+    only_here: &[],
+    rewrites: &[],
+};
+
+// Keep Alive ids became Longs in 1.12.2. The ids kittymc sends are cut down to 32 bits, so
+// they won't come back the same.
+static KEEP_ALIVE_REWRITES: &[LayoutRewrite] = &[
+    LayoutRewrite {
+        state: State::Play,
+        direction: PacketDirection::Serverbound,
+        latest_id: 0x0B,
+        upgrade: varint_keep_alive_to_long,
+        downgrade: long_keep_alive_to_varint,
+    },
+    LayoutRewrite {
+        state: State::Play,
+        direction: PacketDirection::Clientbound,
+        latest_id: 0x1F,
+        upgrade: varint_keep_alive_to_long,
+        downgrade: long_keep_alive_to_varint,
+    },
+];
+
+static V1_12_1_TABLE: VersionTable = VersionTable {
+    shifts: &[],
+    only_latest: &[],
+    only_here: &[],
+    rewrites: KEEP_ALIVE_REWRITES,
+};
+
+// 1.12.1 replaced Prepare Crafting Grid (serverbound 0x01) with Craft Recipe Request
+// (serverbound 0x12) and added Craft Recipe Response (clientbound 0x2B)
+static V1_12_TABLE: VersionTable = VersionTable {
+    shifts: &[
+        IdShift {
+            state: State::Play,
+            direction: PacketDirection::Serverbound,
+            latest: 0x01..=0x11,
+            start: 0x02,
+        },
+        IdShift {
+            state: State::Play,
+            direction: PacketDirection::Clientbound,
+            latest: 0x2C..=0x4F,
+            start: 0x2B,
+        },
+    ],
+    only_latest: &[
+        (State::Play, PacketDirection::Serverbound, 0x12),
+        (State::Play, PacketDirection::Clientbound, 0x2B),
+    ],
+    only_here: &[(State::Play, PacketDirection::Serverbound, 0x01)],
+    rewrites: KEEP_ALIVE_REWRITES,
+};
+
+fn varint_keep_alive_to_long(mut fields: &[u8], buffer: &mut Vec<u8>) -> Result<(), KittyMCError> {
+    let mut size = 0;
+    let id = read_varint_u32(&mut fields, &mut size)?;
+    write_u64(buffer, id as u64);
+
+    Ok(())
+}
+
+fn long_keep_alive_to_varint(mut fields: &[u8], buffer: &mut Vec<u8>) -> Result<(), KittyMCError> {
+    let mut size = 0;
+    let id = read_u64(&mut fields, &mut size)?;
+    write_varint_u32(buffer, id as u32);
+
+    Ok(())
+}
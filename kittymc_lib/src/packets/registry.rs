@@ -0,0 +1,206 @@
+//! Lookup table of every packet kittymc knows how to decode.
+//!
+//! The id, state, direction and name of every entry come from the packet type itself
+//! (`SerializablePacket::id`, `PacketInfo` and `NamedPacket`), so the table can't drift away
+//! from the packets. Adding a packet only means adding its type to [`registered_packets`].
+
+use crate::error::KittyMCError;
+use crate::packets::client::login::*;
+use crate::packets::client::play::*;
+use crate::packets::client::status::*;
+use crate::packets::packet_serialization::{NamedPacket, PacketInfo, SerializablePacket};
+use crate::packets::server::handshake::*;
+use crate::packets::server::login::*;
+use crate::packets::server::play::entity_action_15::EntityActionPacket;
+use crate::packets::server::play::*;
+use crate::packets::server::status::*;
+use crate::packets::{ClientboundPacket, Packet, PacketDirection};
+use crate::subtypes::metadata::MetadataMap;
+use crate::subtypes::state::State;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+
+pub type ServerboundDecoder = fn(&[u8]) -> Result<(usize, Packet), KittyMCError>;
+pub type ClientboundDecoder = fn(&[u8]) -> Result<(usize, ClientboundPacket), KittyMCError>;
+
+#[derive(Clone, Copy)]
+pub enum PacketDecoder {
+    Serverbound(ServerboundDecoder),
+    Clientbound(ClientboundDecoder),
+}
+
+#[derive(Clone, Copy)]
+pub struct PacketEntry {
+    pub name: &'static str,
+    pub id: u32,
+    pub state: State,
+    pub direction: PacketDirection,
+    pub decoder: PacketDecoder,
+}
+
+impl PacketEntry {
+    /// Registers `P` in the direction it declares through `PacketInfo`
+    pub fn of<P: SerializablePacket + NamedPacket + PacketInfo>() -> Self {
+        Self::with_direction::<P>(P::DIRECTION)
+    }
+
+    /// Registers `P` in the given direction. Only needed for the few packets that are sent both
+    /// ways with the same layout, like the status ping.
+    pub fn with_direction<P: SerializablePacket + NamedPacket + PacketInfo>(
+        direction: PacketDirection,
+    ) -> Self {
+        let decoder = match direction {
+            PacketDirection::Serverbound => PacketDecoder::Serverbound(P::deserialize),
+            PacketDirection::Clientbound => PacketDecoder::Clientbound(P::deserialize_clientbound),
+        };
+
+        PacketEntry {
+            name: P::name(),
+            id: P::id(),
+            state: P::STATE,
+            direction,
+            decoder,
+        }
+    }
+}
+
+impl Debug for PacketEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PacketEntry")
+            .field("name", &self.name)
+            .field("id", &format_args!("0x{:02X}", self.id))
+            .field("state", &self.state)
+            .field("direction", &self.direction)
+            .finish()
+    }
+}
+
+pub struct PacketRegistry {
+    entries: Vec<PacketEntry>,
+    by_id: HashMap<(State, PacketDirection, u32), usize>,
+    by_name: HashMap<(PacketDirection, &'static str), usize>,
+}
+
+impl PacketRegistry {
+    /// If two entries share a (state, direction, id) the first one wins. The registry tests make
+    /// sure that never happens for the built-in packets.
+    pub fn new(entries: Vec<PacketEntry>) -> Self {
+        let mut by_id = HashMap::new();
+        let mut by_name = HashMap::new();
+
+        for (i, entry) in entries.iter().enumerate() {
+            by_id
+                .entry((entry.state, entry.direction, entry.id))
+                .or_insert(i);
+            by_name.entry((entry.direction, entry.name)).or_insert(i);
+        }
+
+        PacketRegistry {
+            entries,
+            by_id,
+            by_name,
+        }
+    }
+
+    pub fn get(&self, state: State, direction: PacketDirection, id: u32) -> Option<&PacketEntry> {
+        self.by_id
+            .get(&(state, direction, id))
+            .map(|i| &self.entries[*i])
+    }
+
+    pub fn get_by_name(&self, direction: PacketDirection, name: &str) -> Option<&PacketEntry> {
+        self.by_name
+            .get(&(direction, name))
+            .map(|i| &self.entries[*i])
+    }
+
+    pub fn entries(&self) -> &[PacketEntry] {
+        &self.entries
+    }
+
+    pub fn serverbound_decoder(&self, state: State, id: u32) -> Option<ServerboundDecoder> {
+        match self.get(state, PacketDirection::Serverbound, id)?.decoder {
+            PacketDecoder::Serverbound(decoder) => Some(decoder),
+            PacketDecoder::Clientbound(_) => None,
+        }
+    }
+
+    pub fn clientbound_decoder(&self, state: State, id: u32) -> Option<ClientboundDecoder> {
+        match self.get(state, PacketDirection::Clientbound, id)?.decoder {
+            PacketDecoder::Clientbound(decoder) => Some(decoder),
+            PacketDecoder::Serverbound(_) => None,
+        }
+    }
+}
+
+pub fn registered_packets() -> Vec<PacketEntry> {
+    vec![
+        // Handshake
+        PacketEntry::of::<HandshakePacket>(),
+        // Status
+        PacketEntry::of::<StatusRequestPacket>(),
+        PacketEntry::of::<StatusPingPongPacket>(),
+        PacketEntry::of::<StatusResponsePacket>(),
+        PacketEntry::with_direction::<StatusPingPongPacket>(PacketDirection::Clientbound),
+        // Login
+        PacketEntry::of::<LoginStartPacket>(),
+        PacketEntry::of::<EncryptionResponsePacket>(),
+        PacketEntry::of::<DisconnectLoginPacket>(),
+        PacketEntry::of::<EncryptionRequestPacket>(),
+        PacketEntry::of::<LoginSuccessPacket>(),
+        PacketEntry::of::<SetCompressionPacket>(),
+        // Play, serverbound
+        PacketEntry::of::<TeleportConfirmPacket>(),
+        PacketEntry::of::<ServerChatMessagePacket>(),
+        PacketEntry::of::<ClientSettingsPacket>(),
+        PacketEntry::of::<ClientPluginMessagePacket>(),
+        PacketEntry::of::<ClientKeepAlivePacket>(),
+        PacketEntry::of::<PlayerPositionPacket>(),
+        PacketEntry::of::<ClientPlayerPositionAndLookPacket>(),
+        PacketEntry::of::<PlayerLookPacket>(),
+        PacketEntry::of::<PlayerDiggingPacket>(),
+        PacketEntry::of::<EntityActionPacket>(),
+        PacketEntry::of::<ClientHeldItemChangePacket>(),
+        PacketEntry::of::<CreativeInventoryActionPacket>(),
+        PacketEntry::of::<ClientAnimationPacket>(),
+        PacketEntry::of::<PlayerBlockPlacementPacket>(),
+        PacketEntry::of::<ServerTabCompletePacket>(),
+        // Play, clientbound
+        PacketEntry::of::<SpawnPlayerPacket<MetadataMap>>(),
+        PacketEntry::of::<ServerAnimationPacket>(),
+        PacketEntry::of::<BlockBreakAnimationPacket>(),
+        PacketEntry::of::<BlockChangePacket>(),
+        PacketEntry::of::<ServerDifficultyPacket>(),
+        PacketEntry::of::<ClientChatMessagePacket>(),
+        PacketEntry::of::<WindowItemsPacket>(),
+        PacketEntry::of::<ServerPluginMessagePacket>(),
+        PacketEntry::of::<DisconnectPlayPacket>(),
+        PacketEntry::of::<EntityStatusPacket>(),
+        PacketEntry::of::<UnloadChunkPacket>(),
+        PacketEntry::of::<ServerKeepAlivePacket>(),
+        PacketEntry::of::<ChunkDataPacket<'static>>(),
+        PacketEntry::of::<JoinGamePacket>(),
+        PacketEntry::of::<EntityRelativeMovePacket>(),
+        PacketEntry::of::<EntityLookPacket>(),
+        PacketEntry::of::<EntityLookAndRelativeMovePacket>(),
+        PacketEntry::of::<EntityTeleportPacket>(),
+        PacketEntry::of::<PlayerAbilitiesPacket>(),
+        PacketEntry::of::<PlayerListItemPacket>(),
+        PacketEntry::of::<ServerPlayerPositionAndLookPacket>(),
+        PacketEntry::of::<UnlockRecipesPacket>(),
+        PacketEntry::of::<DestroyEntitiesPacket>(),
+        PacketEntry::of::<EntityHeadLookPacket>(),
+        PacketEntry::of::<ServerHeldItemChangePacket>(),
+        PacketEntry::of::<EntityMetadataPacket<MetadataMap>>(),
+        PacketEntry::of::<SpawnPositionPacket>(),
+        PacketEntry::of::<TimeUpdatePacket>(),
+        PacketEntry::of::<ClientTabCompletePacket>(),
+        PacketEntry::of::<ChangeGameStatePacket>(),
+        PacketEntry::of::<UpdateHealthPacket>(),
+    ]
+}
+
+lazy_static! {
+    pub static ref PACKET_REGISTRY: PacketRegistry = PacketRegistry::new(registered_packets());
+}
@@ -0,0 +1,9 @@
+use kittymc_macros::Packet;
+
+/// Both fields are encrypted with the server's public key
+#[derive(PartialEq, Debug, Clone, Packet)]
+#[packet(id = 0x01, state = Login, direction = Serverbound)]
+pub struct EncryptionResponsePacket {
+    pub shared_secret: Vec<u8>,
+    pub verify_token: Vec<u8>,
+}
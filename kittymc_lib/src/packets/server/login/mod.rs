@@ -1,3 +1,5 @@
+pub mod encryption_response_01;
 pub mod login_start_00;
 
+pub use encryption_response_01::EncryptionResponsePacket;
 pub use login_start_00::LoginStartPacket;
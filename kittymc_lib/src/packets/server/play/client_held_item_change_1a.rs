@@ -0,0 +1,7 @@
+use kittymc_macros::Packet;
+
+#[derive(PartialEq, Debug, Clone, Packet)]
+#[packet(id = 0x1A, state = Play, direction = Serverbound)]
+pub struct ClientHeldItemChangePacket {
+    pub slot: i16,
+}
@@ -1,9 +1,7 @@
-use crate::error::KittyMCError;
-use crate::packets::packet_serialization::{read_u64, SerializablePacket};
-use crate::packets::Packet;
 use kittymc_macros::Packet;
 
 #[derive(PartialEq, Debug, Clone, Packet)]
+#[packet(id = 0x0B, state = Play, direction = Serverbound)]
 pub struct ClientKeepAlivePacket {
     pub id: u64,
 }
@@ -13,17 +11,3 @@ impl ClientKeepAlivePacket {
         ClientKeepAlivePacket { id }
     }
 }
-
-impl SerializablePacket for ClientKeepAlivePacket {
-    fn deserialize(mut data: &[u8]) -> Result<(usize, Packet), KittyMCError> {
-        let mut size = 0;
-
-        let id = read_u64(&mut data, &mut size)?;
-
-        Ok((size, Packet::KeepAlive(ClientKeepAlivePacket { id })))
-    }
-
-    fn id() -> u32 {
-        0x0B
-    }
-}
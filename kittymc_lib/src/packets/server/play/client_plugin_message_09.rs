@@ -7,6 +7,7 @@ use kittymc_macros::Packet;
 use std::fmt::{Debug, Formatter};
 
 #[derive(PartialEq, Clone, Packet)]
+#[packet(state = Play, direction = Serverbound)]
 pub struct ClientPluginMessagePacket {
     pub channel: String,
     pub data: Vec<u8>,
@@ -37,6 +38,6 @@ impl SerializablePacket for ClientPluginMessagePacket {
     }
 
     fn id() -> u32 {
-        0x18
+        0x09
     }
 }
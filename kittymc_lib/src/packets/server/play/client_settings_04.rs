@@ -1,6 +1,8 @@
 use crate::error::KittyMCError;
+use crate::packets::packet_fields::{PacketField, VarInt};
 use crate::packets::packet_serialization::{
-    read_bool, read_length_prefixed_string, read_u8, read_varint_u32, write_u8, SerializablePacket,
+    read_bool, read_length_prefixed_string, read_u8, read_varint_u32, write_u8, write_varint_u32,
+    SerializablePacket,
 };
 use crate::packets::Packet;
 use bitflags::bitflags;
@@ -64,7 +66,18 @@ impl From<u32> for Hand {
     }
 }
 
+impl PacketField<VarInt> for Hand {
+    fn write_field(&self, buffer: &mut Vec<u8>) {
+        write_varint_u32(buffer, self.clone() as u32);
+    }
+
+    fn read_field(data: &mut &[u8], total_size: &mut usize) -> Result<Self, KittyMCError> {
+        Ok(read_varint_u32(data, total_size)?.into())
+    }
+}
+
 #[derive(PartialEq, Debug, Clone, Packet)]
+#[packet(state = Play, direction = Serverbound)]
 pub struct ClientSettingsPacket {
     pub locale: String,
     pub view_distance: u8,
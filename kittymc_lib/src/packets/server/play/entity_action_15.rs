@@ -35,6 +35,7 @@ impl From<u32> for EntityAction {
 }
 
 #[derive(PartialEq, Debug, Clone, Packet)]
+#[packet(state = Play, direction = Serverbound)]
 pub struct EntityActionPacket {
     pub entity_id: u32,
     pub action: EntityAction,
@@ -1,6 +1,6 @@
 pub mod animation_1d;
 pub mod chat_message_02;
-pub mod client_held_item_change_a1;
+pub mod client_held_item_change_1a;
 pub mod client_keep_alive_0b;
 pub mod client_player_position_and_look_0e;
 pub mod client_plugin_message_09;
@@ -12,10 +12,11 @@ pub mod player_position_0d;
 pub mod teleport_confirm_00;
 pub mod creative_inventory_action_1b;
 pub mod player_block_placement_1f;
+pub mod tab_complete_01;
 
 pub use animation_1d::ClientAnimationPacket;
 pub use chat_message_02::ServerChatMessagePacket;
-pub use client_held_item_change_a1::ClientHeldItemChangePacket;
+pub use client_held_item_change_1a::ClientHeldItemChangePacket;
 pub use client_keep_alive_0b::ClientKeepAlivePacket;
 pub use client_player_position_and_look_0e::ClientPlayerPositionAndLookPacket;
 pub use client_plugin_message_09::ClientPluginMessagePacket;
@@ -26,3 +27,4 @@ pub use player_position_0d::PlayerPositionPacket;
 pub use teleport_confirm_00::TeleportConfirmPacket;
 pub use creative_inventory_action_1b::CreativeInventoryActionPacket;
 pub use player_block_placement_1f::PlayerBlockPlacementPacket;
+pub use tab_complete_01::ServerTabCompletePacket;
@@ -7,6 +7,7 @@ use crate::packets::server::play::player_digging_14::BlockFace;
 use crate::subtypes::Location;
 
 #[derive(Debug, Clone, PartialEq, Packet)]
+#[packet(state = Play, direction = Serverbound)]
 pub struct PlayerBlockPlacementPacket {
     pub location: Location,
     pub face: BlockFace,
@@ -38,6 +39,6 @@ impl SerializablePacket for PlayerBlockPlacementPacket {
     }
 
     fn id() -> u32 {
-        0x1B
+        0x1F
     }
 }
\ No newline at end of file
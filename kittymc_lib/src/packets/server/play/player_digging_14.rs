@@ -3,7 +3,7 @@ use crate::packets::packet_serialization::{
     read_block_location, read_u8, read_varint_u32, write_block_location, write_u8,
     write_varint_u32, SerializablePacket,
 };
-use crate::packets::{wrap_packet, Packet};
+use crate::packets::Packet;
 use crate::subtypes::Location;
 use kittymc_macros::Packet;
 
@@ -80,6 +80,7 @@ impl From<u32> for BlockFace {
 }
 
 #[derive(PartialEq, Debug, Clone, Packet)]
+#[packet(state = Play, direction = Serverbound)]
 pub struct PlayerDiggingPacket {
     pub status: PlayerDiggingStatus,
     pub location: Location,
@@ -104,16 +105,10 @@ impl SerializablePacket for PlayerDiggingPacket {
         ))
     }
 
-    fn serialize(&self) -> Vec<u8> {
-        let mut packet = vec![];
-
-        write_varint_u32(&mut packet, self.status as u32);
-        write_block_location(&mut packet, &self.location);
-        write_u8(&mut packet, self.face as u8);
-
-        wrap_packet(&mut packet, Self::id());
-
-        packet
+    fn serialize_fields(&self, packet: &mut Vec<u8>) {
+        write_varint_u32(packet, self.status as u32);
+        write_block_location(packet, &self.location);
+        write_u8(packet, self.face as u8);
     }
 
     fn id() -> u32 {
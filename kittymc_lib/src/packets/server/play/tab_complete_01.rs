@@ -0,0 +1,14 @@
+use crate::subtypes::Location;
+use kittymc_macros::Packet;
+
+/// Sent while typing into the chat whenever the player presses tab
+#[derive(PartialEq, Debug, Clone, Packet)]
+#[packet(id = 0x01, state = Play, direction = Serverbound)]
+pub struct ServerTabCompletePacket {
+    /// Everything in front of the cursor
+    pub text: String,
+    /// Set by command blocks, where the text is always a command even without a leading `/`
+    pub assume_command: bool,
+    #[packet(position)]
+    pub looked_at_block: Option<Location>,
+}
@@ -1,23 +1,18 @@
 use crate::error::KittyMCError;
 use crate::packets::packet_serialization::{read_i64, write_i64, SerializablePacket};
-use crate::packets::{wrap_packet, Packet};
+use crate::packets::{ClientboundPacket, Packet};
 use kittymc_macros::Packet;
 
 // Special Packet. Is being used for serializing the clientbound Ping and deserializing the serverbound Pong
 #[derive(PartialEq, Debug, Clone, Packet)]
+#[packet(state = Status, direction = Serverbound)]
 pub struct StatusPingPongPacket {
-    payload: i64,
+    pub payload: i64,
 }
 
 impl SerializablePacket for StatusPingPongPacket {
-    fn serialize(&self) -> Vec<u8> {
-        let mut packet = vec![];
-
-        write_i64(&mut packet, self.payload);
-
-        wrap_packet(&mut packet, Self::id());
-
-        packet
+    fn serialize_fields(&self, packet: &mut Vec<u8>) {
+        write_i64(packet, self.payload);
     }
     fn deserialize(mut data: &[u8]) -> Result<(usize, Packet), KittyMCError> {
         let mut size = 0;
@@ -26,6 +21,13 @@ impl SerializablePacket for StatusPingPongPacket {
         Ok((size, Packet::StatusPing(StatusPingPongPacket { payload })))
     }
 
+    fn deserialize_clientbound(mut data: &[u8]) -> Result<(usize, ClientboundPacket), KittyMCError> {
+        let mut size = 0;
+        let payload = read_i64(&mut data, &mut size)?;
+
+        Ok((size, ClientboundPacket::StatusPong(StatusPingPongPacket { payload })))
+    }
+
     fn id() -> u32 {
         1
     }
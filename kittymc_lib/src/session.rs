@@ -0,0 +1,86 @@
+//! Checking with a session server that a client really owns the account it logs in with.
+//!
+//! Before sending its Encryption Response the client tells the session server that it joined a
+//! server with a given [`server_id_hash`](crate::crypto::server_id_hash). The server then asks the
+//! session server whether that's true through a [`SessionVerifier`] and gets the player's profile
+//! back if it is.
+
+use crate::error::KittyMCError;
+use crate::packets::client::play::player_list_item_2e::PlayerListItemProperties;
+use serde::Deserialize;
+use std::fmt::Debug;
+use std::time::Duration;
+use uuid::Uuid;
+
+pub const MOJANG_SESSION_SERVER: &str = "https://sessionserver.mojang.com";
+
+const SESSION_SERVER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The profile of an authenticated player, including the signed skin textures.
+#[derive(PartialEq, Debug, Clone, Deserialize)]
+pub struct GameProfile {
+    pub id: Uuid,
+    pub name: String,
+    #[serde(default)]
+    pub properties: Vec<PlayerListItemProperties>,
+}
+
+pub trait SessionVerifier: Send + Sync + Debug {
+    /// Returns the profile of `username` if it has announced joining the server identified by
+    /// `server_hash`, or [`KittyMCError::NotAuthenticated`] if it hasn't.
+    fn has_joined(&self, username: &str, server_hash: &str) -> Result<GameProfile, KittyMCError>;
+}
+
+/// Asks a session server speaking Mojang's `hasJoined` HTTP API. Pointing it at a different base
+/// url allows using a local stand-in.
+#[derive(Debug, Clone)]
+pub struct HttpSessionVerifier {
+    base_url: String,
+    agent: ureq::Agent,
+}
+
+impl HttpSessionVerifier {
+    pub fn new(base_url: &str) -> Self {
+        HttpSessionVerifier {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            agent: ureq::AgentBuilder::new()
+                .timeout(SESSION_SERVER_TIMEOUT)
+                .build(),
+        }
+    }
+
+    pub fn mojang() -> Self {
+        Self::new(MOJANG_SESSION_SERVER)
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+}
+
+impl Default for HttpSessionVerifier {
+    fn default() -> Self {
+        Self::mojang()
+    }
+}
+
+impl SessionVerifier for HttpSessionVerifier {
+    fn has_joined(&self, username: &str, server_hash: &str) -> Result<GameProfile, KittyMCError> {
+        let response = self
+            .agent
+            .get(&format!("{}/session/minecraft/hasJoined", self.base_url))
+            .query("username", username)
+            .query("serverId", server_hash)
+            .call()
+            .map_err(|e| KittyMCError::SessionServerError(e.to_string()))?;
+
+        // The session server answers with 204 No Content if the client never joined
+        if response.status() != 200 {
+            return Err(KittyMCError::NotAuthenticated(username.to_string()));
+        }
+
+        let body = response.into_string()?;
+
+        Ok(serde_json::from_str(&body)?)
+    }
+}
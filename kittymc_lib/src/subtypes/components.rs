@@ -1,11 +1,23 @@
 use std::error::Error;
-use crate::packets::packet_serialization::write_length_prefixed_string;
+use crate::error::KittyMCError;
+use crate::packets::packet_serialization::{
+    read_length_prefixed_string, write_length_prefixed_string,
+};
+use crate::packets::client::play::window_items_14::SlotData;
+use crate::subtypes::item_nbt::ItemNbt;
+use crate::subtypes::snbt::{from_snbt, to_snbt};
 use crate::subtypes::Color;
 use crate::utils::{rainbowize_cool_people, to_mc_rainbow, KITTYMC_TAG};
-use serde::{Deserialize, Serialize};
+use fastnbt::Value;
+use serde::de;
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use std::str::FromStr;
 use typed_builder::TypedBuilder;
+use uuid::Uuid;
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TypedBuilder)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TypedBuilder, Default)]
 pub struct ClickEvent {
     #[serde(skip_serializing_if = "String::is_empty", default)]
     #[builder(default, setter(into))]
@@ -24,25 +36,236 @@ pub struct ClickEvent {
     pub change_page: Option<u32>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TypedBuilder)]
+/// The tooltip of an item. `id` is either a name like `minecraft:stone` or a numeric id, which
+/// 1.12 looks up as well.
+#[derive(Clone, Debug, PartialEq, TypedBuilder)]
+pub struct HoverItem {
+    #[builder(setter(into))]
+    pub id: String,
+    #[builder(default = 1)]
+    pub count: i8,
+    #[builder(default)]
+    pub damage: i16,
+    #[builder(setter(strip_option), default)]
+    pub tag: Option<ItemNbt>,
+}
+
+impl HoverItem {
+    /// The tooltip of the item in `slot`, or `None` if the slot is empty
+    pub fn from_slot(slot: &SlotData) -> Option<Self> {
+        if slot.id == u16::MAX {
+            return None;
+        }
+
+        Some(HoverItem {
+            id: slot.id.to_string(),
+            count: slot.item_count as i8,
+            damage: slot.item_damage as i16,
+            tag: slot.nbt.clone(),
+        })
+    }
+
+    /// Turns the tooltip back into a slot. Only works for numeric ids.
+    pub fn to_slot(&self) -> Option<SlotData> {
+        Some(SlotData {
+            id: self.id.parse().ok()?,
+            item_count: self.count as u8,
+            item_damage: self.damage as u16,
+            nbt: self.tag.clone(),
+        })
+    }
+
+    pub fn to_nbt(&self) -> Value {
+        let mut item = HashMap::from([
+            ("id".to_string(), Value::String(self.id.clone())),
+            ("Count".to_string(), Value::Byte(self.count)),
+            ("Damage".to_string(), Value::Short(self.damage)),
+        ]);
+        if let Some(tag) = &self.tag {
+            item.insert("tag".to_string(), tag.to_value());
+        }
+
+        Value::Compound(item)
+    }
+
+    pub fn from_nbt(value: Value) -> Result<Self, KittyMCError> {
+        let Value::Compound(mut item) = value else {
+            return Err(KittyMCError::DeserializationError);
+        };
+
+        let id = match item.remove("id") {
+            Some(Value::String(id)) => id,
+            Some(Value::Short(id)) => id.to_string(),
+            _ => return Err(KittyMCError::DeserializationError),
+        };
+        let count = match item.remove("Count") {
+            Some(Value::Byte(count)) => count,
+            Some(Value::Short(count)) => count as i8,
+            Some(Value::Int(count)) => count as i8,
+            _ => 1,
+        };
+        let damage = match item.remove("Damage") {
+            Some(Value::Short(damage)) => damage,
+            Some(Value::Byte(damage)) => damage as i16,
+            Some(Value::Int(damage)) => damage as i16,
+            _ => 0,
+        };
+        let tag = item.remove("tag").map(ItemNbt::from_value).transpose()?;
+
+        Ok(HoverItem {
+            id,
+            count,
+            damage,
+            tag,
+        })
+    }
+}
+
+/// The card of an entity, showing its name, type and UUID
+#[derive(Clone, Debug, PartialEq, TypedBuilder)]
+pub struct HoverEntity {
+    pub id: Uuid,
+    /// E.g. `minecraft:pig`
+    #[builder(setter(into, strip_option), default)]
+    pub entity_type: Option<String>,
+    #[builder(setter(into))]
+    pub name: String,
+}
+
+impl HoverEntity {
+    pub fn to_nbt(&self) -> Value {
+        let mut entity = HashMap::from([
+            ("id".to_string(), Value::String(self.id.to_string())),
+            ("name".to_string(), Value::String(self.name.clone())),
+        ]);
+        if let Some(entity_type) = &self.entity_type {
+            entity.insert("type".to_string(), Value::String(entity_type.clone()));
+        }
+
+        Value::Compound(entity)
+    }
+
+    pub fn from_nbt(value: Value) -> Result<Self, KittyMCError> {
+        let Value::Compound(mut entity) = value else {
+            return Err(KittyMCError::DeserializationError);
+        };
+
+        let Some(Value::String(id)) = entity.remove("id") else {
+            return Err(KittyMCError::DeserializationError);
+        };
+        let entity_type = match entity.remove("type") {
+            Some(Value::String(entity_type)) => Some(entity_type),
+            _ => None,
+        };
+        let name = match entity.remove("name") {
+            Some(Value::String(name)) => name,
+            _ => String::new(),
+        };
+
+        Ok(HoverEntity {
+            id: Uuid::from_str(&id)?,
+            entity_type,
+            name,
+        })
+    }
+}
+
+/// What is shown when hovering over a component. Only one of the fields should be set, the first
+/// one that is wins. Sent as `{"action":"show_item","value":"{id:\"1\",Count:1b}"}`.
+#[derive(Clone, Debug, PartialEq, TypedBuilder, Default)]
 pub struct HoverEvent {
-    #[serde(skip_serializing_if = "Option::is_none", default)]
     #[builder(setter(into, strip_option), default)]
     pub show_text: Option<String>,
 
-    #[serde(skip_serializing_if = "Option::is_none", default)]
     #[builder(setter(strip_option), default)]
-    pub show_item: Option<()>, // TODO: NBT
+    pub show_item: Option<HoverItem>,
 
-    #[serde(skip_serializing_if = "Option::is_none", default)]
     #[builder(setter(strip_option), default)]
-    pub show_entity: Option<()>, // TODO: NBT
+    pub show_entity: Option<HoverEntity>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct RawHoverEvent {
+    #[serde(default)]
+    action: String,
+    #[serde(default)]
+    value: serde_json::Value,
+}
+
+const SHOW_TEXT: &str = "show_text";
+const SHOW_ITEM: &str = "show_item";
+const SHOW_ENTITY: &str = "show_entity";
+
+impl Serialize for HoverEvent {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (action, value) = if let Some(text) = &self.show_text {
+            (SHOW_TEXT, text.clone())
+        } else if let Some(item) = &self.show_item {
+            (SHOW_ITEM, to_snbt(&item.to_nbt()))
+        } else if let Some(entity) = &self.show_entity {
+            (SHOW_ENTITY, to_snbt(&entity.to_nbt()))
+        } else {
+            return serializer.serialize_map(Some(0))?.end();
+        };
+
+        RawHoverEvent {
+            action: action.to_string(),
+            value: serde_json::Value::String(value),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for HoverEvent {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = RawHoverEvent::deserialize(deserializer)?;
+
+        // The value is a component, though mostly just a string
+        let value = match raw.value {
+            serde_json::Value::String(value) => {
+                Component::Text(TextComponent::builder().text(value).build())
+            }
+            value => Component::deserialize(value).map_err(de::Error::custom)?,
+        };
+
+        let mut event = HoverEvent::default();
+        match raw.action.as_str() {
+            "" => {}
+            SHOW_TEXT => event.show_text = Some(value.to_legacy()),
+            SHOW_ITEM => {
+                let nbt = from_snbt(&value.to_plain_text()).map_err(de::Error::custom)?;
+                event.show_item = Some(HoverItem::from_nbt(nbt).map_err(de::Error::custom)?);
+            }
+            SHOW_ENTITY => {
+                let nbt = from_snbt(&value.to_plain_text()).map_err(de::Error::custom)?;
+                event.show_entity = Some(HoverEntity::from_nbt(nbt).map_err(de::Error::custom)?);
+            }
+            action => {
+                return Err(de::Error::unknown_variant(
+                    action,
+                    &[SHOW_TEXT, SHOW_ITEM, SHOW_ENTITY],
+                ))
+            }
+        }
+
+        Ok(event)
+    }
 }
 
 fn is_false(b: &bool) -> bool {
     !*b
 }
 
+// Flattened options always deserialize to Some, even if none of their fields were present
+fn none_if_default<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + Default + PartialEq,
+{
+    let value = Option::<T>::deserialize(deserializer)?;
+    Ok(value.filter(|value| *value != T::default()))
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TypedBuilder, Default)]
 pub struct BaseComponent {
     #[serde(skip_serializing_if = "is_false", default)]
@@ -73,13 +296,19 @@ pub struct BaseComponent {
     #[builder(default)]
     pub insertion: String,
 
-    #[serde(skip_serializing_if = "Option::is_none", flatten, default)]
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        flatten,
+        default,
+        deserialize_with = "none_if_default"
+    )]
     #[builder(setter(strip_option), default)]
     pub click_event: Option<ClickEvent>,
 
-    #[serde(skip_serializing_if = "Option::is_none", flatten, default)]
-    #[builder(setter(strip_option), default)]
-    pub hover_event: Option<HoverEvent>,
+    // Boxed because it's rarely there and would make every component a lot bigger
+    #[serde(rename = "hoverEvent", skip_serializing_if = "Option::is_none", default)]
+    #[builder(setter(transform = |hover_event: HoverEvent| Some(Box::new(hover_event))), default)]
+    pub hover_event: Option<Box<HoverEvent>>,
 
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     #[builder(default)]
@@ -102,6 +331,11 @@ impl TextComponent {
             &serde_json::to_string(self).unwrap_or_else(|_| "INVALID".to_string()),
         )
     }
+
+    pub fn read(data: &mut &[u8], size: &mut usize) -> Result<Self, KittyMCError> {
+        let json = read_length_prefixed_string(data, size)?;
+        Ok(serde_json::from_str(&json)?)
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TypedBuilder)]
@@ -119,17 +353,191 @@ impl TranslationComponent {
     }
 }
 
+/// Shows the key a control is bound to on the client, like `key.inventory`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TypedBuilder)]
+pub struct KeyBindComponent {
+    #[builder(setter(into))]
+    pub keybind: String,
+    #[serde(flatten, default)]
+    #[builder(default)]
+    pub options: BaseComponent,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TypedBuilder)]
+pub struct Score {
+    /// A player name, or `*` for whoever reads it
+    #[builder(setter(into))]
+    pub name: String,
+    #[builder(setter(into))]
+    pub objective: String,
+    /// Filled in by the server before sending it
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    #[builder(setter(into, strip_option), default)]
+    pub value: Option<String>,
+}
+
+/// Shows the score of an entity on a scoreboard objective
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TypedBuilder)]
+pub struct ScoreComponent {
+    pub score: Score,
+    #[serde(flatten, default)]
+    #[builder(default)]
+    pub options: BaseComponent,
+}
+
+/// Shows the names of the entities an entity selector like `@p` matches
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TypedBuilder)]
+pub struct SelectorComponent {
+    #[builder(setter(into))]
+    pub selector: String,
+    #[serde(flatten, default)]
+    #[builder(default)]
+    pub options: BaseComponent,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(untagged)]
 pub enum Component {
     Text(TextComponent),
     Translation(TranslationComponent),
-    KeyBind,  // TODO
-    Score,    // TODO
-    Selector, // TODO
+    KeyBind(KeyBindComponent),
+    Score(ScoreComponent),
+    Selector(SelectorComponent),
+}
+
+const CHAT_TRANSLATION_TAG: &str = "chat.type.text";
+
+const LEGACY_CODE_PREFIX: char = '§';
+
+/// The styling that legacy `§` codes can express
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+struct LegacyStyle {
+    color: Option<Color>,
+    bold: bool,
+    italic: bool,
+    underlined: bool,
+    strikethrough: bool,
+    obfuscated: bool,
+}
+
+impl LegacyStyle {
+    /// The style of a component whose parent has this style
+    fn inherit(self, options: &BaseComponent) -> Self {
+        LegacyStyle {
+            color: match options.color {
+                Some(Color::Reset) => None,
+                Some(color) if color.is_color() => Some(color),
+                _ => self.color,
+            },
+            bold: self.bold || options.bold,
+            italic: self.italic || options.italic,
+            underlined: self.underlined || options.underlined,
+            strikethrough: self.strikethrough || options.strikethrough,
+            obfuscated: self.obfuscated || options.obfuscated,
+        }
+    }
+
+    /// Applies a code like the client does. Colors turn all formatting off.
+    fn apply(&mut self, code: Color) {
+        match code {
+            Color::Reset => *self = LegacyStyle::default(),
+            Color::Bold => self.bold = true,
+            Color::Italic => self.italic = true,
+            Color::Underline => self.underlined = true,
+            Color::Strikethrough => self.strikethrough = true,
+            Color::Obfuscated => self.obfuscated = true,
+            color => {
+                *self = LegacyStyle {
+                    color: Some(color),
+                    ..Default::default()
+                }
+            }
+        }
+    }
+
+    fn formatting(&self) -> [(bool, Color); 5] {
+        [
+            (self.obfuscated, Color::Obfuscated),
+            (self.bold, Color::Bold),
+            (self.strikethrough, Color::Strikethrough),
+            (self.underlined, Color::Underline),
+            (self.italic, Color::Italic),
+        ]
+    }
+
+    /// Writes the codes that switch from `previous` to this style
+    fn write_codes(&self, previous: &LegacyStyle, out: &mut String) {
+        let only_adds_formatting = self.color == previous.color
+            && self
+                .formatting()
+                .iter()
+                .zip(previous.formatting())
+                .all(|((on, _), (was_on, _))| *on || !was_on);
+
+        if !only_adds_formatting {
+            out.push_str(self.color.unwrap_or(Color::Reset).as_color_code());
+        }
+
+        for ((on, code), (was_on, _)) in self.formatting().iter().zip(previous.formatting()) {
+            if *on && (!was_on || !only_adds_formatting) {
+                out.push_str(code.as_color_code());
+            }
+        }
+    }
+
+    fn to_component(self, text: String) -> Component {
+        let mut options = BaseComponent::builder()
+            .bold(self.bold)
+            .italic(self.italic)
+            .underlined(self.underlined)
+            .strikethrough(self.strikethrough)
+            .obfuscated(self.obfuscated)
+            .build();
+        options.color = self.color;
+
+        Component::Text(TextComponent::builder().text(text).options(options).build())
+    }
 }
 
-const CHAT_TRANSLATION_TAG: &'static str = "chat.type.text";
+/// Splits text with legacy codes into runs of the same style, starting out with `style`
+fn split_legacy(text: &str, mut style: LegacyStyle, f: &mut impl FnMut(&str, LegacyStyle)) {
+    let mut run = String::new();
+    let mut run_style = style;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c == LEGACY_CODE_PREFIX {
+            if let Some(code) = chars.peek().copied().and_then(Color::from_code) {
+                chars.next();
+                style.apply(code);
+                continue;
+            }
+        }
+
+        if style != run_style && !run.is_empty() {
+            f(&run, run_style);
+            run.clear();
+        }
+        run_style = style;
+        run.push(c);
+    }
+
+    if !run.is_empty() {
+        f(&run, run_style);
+    }
+}
+
+/// The English text of the translation keys kittymc sends, with `%s` where the arguments go
+fn translation_format(key: &str) -> Option<&'static str> {
+    Some(match key {
+        CHAT_TRANSLATION_TAG => "<%s> %s",
+        "chat.type.announcement" => "[%s] %s",
+        "chat.type.emote" => "* %s %s",
+        "multiplayer.player.joined" => "%s joined the game",
+        "multiplayer.player.left" => "%s left the game",
+        _ => return None,
+    })
+}
 
 impl Component {
     pub fn write(&self, buffer: &mut Vec<u8>) {
@@ -139,6 +547,103 @@ impl Component {
         );
     }
 
+    pub fn read(data: &mut &[u8], size: &mut usize) -> Result<Self, KittyMCError> {
+        let json = read_length_prefixed_string(data, size)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    /// Turns text with legacy `§` codes into components styled the same way. Unknown codes stay
+    /// in the text.
+    pub fn from_legacy(text: &str) -> Self {
+        let mut runs = vec![];
+        split_legacy(text, LegacyStyle::default(), &mut |run, style| {
+            runs.push(style.to_component(run.to_string()))
+        });
+
+        match runs.len() {
+            0 => Component::Text(TextComponent::builder().build()),
+            1 => runs.remove(0),
+            _ => Component::Text(
+                TextComponent::builder()
+                    .options(BaseComponent::builder().extra(runs).build())
+                    .build(),
+            ),
+        }
+    }
+
+    /// Flattens this component into text with legacy `§` codes. Click and hover events are lost.
+    pub fn to_legacy(&self) -> String {
+        let mut legacy = String::new();
+        let mut current = LegacyStyle::default();
+        self.flatten(LegacyStyle::default(), &mut |text, style| {
+            if style != current {
+                style.write_codes(&current, &mut legacy);
+                current = style;
+            }
+            legacy.push_str(text);
+        });
+
+        legacy
+    }
+
+    /// The text this component shows, without any styling
+    pub fn to_plain_text(&self) -> String {
+        let mut plain = String::new();
+        self.flatten(LegacyStyle::default(), &mut |text, _| plain.push_str(text));
+        plain
+    }
+
+    /// Calls `f` with every piece of text in the order it's shown, along with its style
+    fn flatten(&self, style: LegacyStyle, f: &mut impl FnMut(&str, LegacyStyle)) {
+        match self {
+            Component::Text(text) => Self::flatten_styled(&text.text, &text.options, style, f),
+            Component::Translation(translation) => {
+                // The client shows the key of translations it doesn't know, so do the same
+                let Some(format) = translation_format(&translation.translate) else {
+                    split_legacy(&translation.translate, style, f);
+                    return;
+                };
+
+                let mut args = translation.with.iter();
+                for (i, literal) in format.split("%s").enumerate() {
+                    if i > 0 {
+                        if let Some(arg) = args.next() {
+                            arg.flatten(style, f);
+                        }
+                    }
+                    split_legacy(literal, style, f);
+                }
+            }
+            // The client shows the key name and the server fills in scores and selectors, so
+            // this is only what they look like before that happened
+            Component::KeyBind(keybind) => {
+                Self::flatten_styled(&keybind.keybind, &keybind.options, style, f)
+            }
+            Component::Score(score) => Self::flatten_styled(
+                score.score.value.as_deref().unwrap_or_default(),
+                &score.options,
+                style,
+                f,
+            ),
+            Component::Selector(selector) => {
+                Self::flatten_styled(&selector.selector, &selector.options, style, f)
+            }
+        }
+    }
+
+    fn flatten_styled(
+        text: &str,
+        options: &BaseComponent,
+        style: LegacyStyle,
+        f: &mut impl FnMut(&str, LegacyStyle),
+    ) {
+        let style = style.inherit(options);
+        split_legacy(text, style, f);
+        for child in &options.extra {
+            child.flatten(style, f);
+        }
+    }
+
     pub fn default_join(player: &str) -> Self {
         Self::default_state_message(player, "joined")
     }
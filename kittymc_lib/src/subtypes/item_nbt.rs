@@ -0,0 +1,205 @@
+use crate::error::KittyMCError;
+use fastnbt::Value;
+use std::collections::HashMap;
+
+const DISPLAY: &str = "display";
+const DISPLAY_NAME: &str = "Name";
+const DISPLAY_LORE: &str = "Lore";
+const DISPLAY_COLOR: &str = "color";
+const ENCHANTMENTS: &str = "ench";
+const ENCHANTMENT_ID: &str = "id";
+const ENCHANTMENT_LEVEL: &str = "lvl";
+const UNBREAKABLE: &str = "Unbreakable";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Enchantment {
+    pub id: i16,
+    pub level: i16,
+}
+
+/// The NBT compound an item carries around (`tag` in vanilla).
+///
+/// Everything is kept as is, so tags without a typed helper (book pages, CanDestroy, ...) survive
+/// reading and writing the item.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ItemNbt {
+    tags: HashMap<String, Value>,
+}
+
+impl ItemNbt {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_value(value: Value) -> Result<Self, KittyMCError> {
+        match value {
+            Value::Compound(tags) => Ok(ItemNbt { tags }),
+            _ => Err(KittyMCError::DeserializationError),
+        }
+    }
+
+    pub fn to_value(&self) -> Value {
+        Value::Compound(self.tags.clone())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.tags.get(key)
+    }
+
+    pub fn insert(&mut self, key: &str, value: Value) -> Option<Value> {
+        self.tags.insert(key.to_string(), value)
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        self.tags.remove(key)
+    }
+
+    fn display(&self) -> Option<&HashMap<String, Value>> {
+        match self.tags.get(DISPLAY)? {
+            Value::Compound(display) => Some(display),
+            _ => None,
+        }
+    }
+
+    /// Sets or removes a tag of the display compound, which is dropped once it's empty
+    fn set_display_tag(&mut self, key: &str, value: Option<Value>) {
+        let mut display = match self.tags.remove(DISPLAY) {
+            Some(Value::Compound(display)) => display,
+            _ => HashMap::new(),
+        };
+
+        match value {
+            Some(value) => {
+                display.insert(key.to_string(), value);
+            }
+            None => {
+                display.remove(key);
+            }
+        }
+
+        if !display.is_empty() {
+            self.tags.insert(DISPLAY.to_string(), Value::Compound(display));
+        }
+    }
+
+    pub fn display_name(&self) -> Option<&str> {
+        match self.display()?.get(DISPLAY_NAME)? {
+            Value::String(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    pub fn set_display_name(&mut self, name: Option<&str>) {
+        self.set_display_tag(DISPLAY_NAME, name.map(|name| Value::String(name.to_string())));
+    }
+
+    pub fn lore(&self) -> Vec<&str> {
+        let Some(Value::List(lines)) = self.display().and_then(|d| d.get(DISPLAY_LORE)) else {
+            return vec![];
+        };
+
+        lines
+            .iter()
+            .filter_map(|line| match line {
+                Value::String(line) => Some(line.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn set_lore(&mut self, lore: &[&str]) {
+        let lore = (!lore.is_empty()).then(|| {
+            Value::List(
+                lore.iter()
+                    .map(|line| Value::String(line.to_string()))
+                    .collect(),
+            )
+        });
+        self.set_display_tag(DISPLAY_LORE, lore);
+    }
+
+    /// The RGB color of dyed leather armor
+    pub fn color(&self) -> Option<i32> {
+        match self.display()?.get(DISPLAY_COLOR)? {
+            Value::Int(color) => Some(*color),
+            _ => None,
+        }
+    }
+
+    pub fn set_color(&mut self, color: Option<i32>) {
+        self.set_display_tag(DISPLAY_COLOR, color.map(Value::Int));
+    }
+
+    pub fn enchantments(&self) -> Vec<Enchantment> {
+        let Some(Value::List(enchantments)) = self.tags.get(ENCHANTMENTS) else {
+            return vec![];
+        };
+
+        enchantments
+            .iter()
+            .filter_map(|enchantment| {
+                let Value::Compound(enchantment) = enchantment else {
+                    return None;
+                };
+                Some(Enchantment {
+                    id: short_tag(enchantment.get(ENCHANTMENT_ID)?)?,
+                    level: short_tag(enchantment.get(ENCHANTMENT_LEVEL)?)?,
+                })
+            })
+            .collect()
+    }
+
+    pub fn set_enchantments(&mut self, enchantments: &[Enchantment]) {
+        if enchantments.is_empty() {
+            self.tags.remove(ENCHANTMENTS);
+            return;
+        }
+
+        let enchantments = enchantments
+            .iter()
+            .map(|enchantment| {
+                Value::Compound(HashMap::from([
+                    (ENCHANTMENT_ID.to_string(), Value::Short(enchantment.id)),
+                    (ENCHANTMENT_LEVEL.to_string(), Value::Short(enchantment.level)),
+                ]))
+            })
+            .collect();
+        self.tags.insert(ENCHANTMENTS.to_string(), Value::List(enchantments));
+    }
+
+    /// Adds an enchantment or replaces the level of one the item already has
+    pub fn add_enchantment(&mut self, enchantment: Enchantment) {
+        let mut enchantments = self.enchantments();
+        match enchantments.iter_mut().find(|e| e.id == enchantment.id) {
+            Some(existing) => existing.level = enchantment.level,
+            None => enchantments.push(enchantment),
+        }
+        self.set_enchantments(&enchantments);
+    }
+
+    pub fn is_unbreakable(&self) -> bool {
+        matches!(self.tags.get(UNBREAKABLE), Some(Value::Byte(b)) if *b != 0)
+    }
+
+    pub fn set_unbreakable(&mut self, unbreakable: bool) {
+        if unbreakable {
+            self.tags.insert(UNBREAKABLE.to_string(), Value::Byte(1));
+        } else {
+            self.tags.remove(UNBREAKABLE);
+        }
+    }
+}
+
+// Vanilla always writes these as shorts, but doesn't mind other integer types
+fn short_tag(value: &Value) -> Option<i16> {
+    match value {
+        Value::Byte(v) => Some(*v as i16),
+        Value::Short(v) => Some(*v),
+        Value::Int(v) => i16::try_from(*v).ok(),
+        _ => None,
+    }
+}
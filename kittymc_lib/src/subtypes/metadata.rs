@@ -1,7 +1,10 @@
+use crate::error::KittyMCError;
 use crate::packets::client::play::window_items_14::SlotData;
 use crate::packets::packet_serialization::{
-    write_bool, write_direction, write_f32, write_length_prefixed_string, write_location2,
-    write_nbt, write_rotation, write_u8, write_uuid, write_varint_u32,
+    read_bool, read_direction, read_f32, read_length_prefixed_string, read_location2, read_nbt,
+    read_rotation, read_u8, read_uuid, read_varint_u32, write_bool, write_direction, write_f32,
+    write_length_prefixed_string, write_location2, write_nbt, write_rotation, write_u8,
+    write_uuid, write_varint_u32,
 };
 use crate::packets::server::play::client_settings_04::DisplayedSkinParts;
 use crate::subtypes::components::Component;
@@ -11,6 +14,7 @@ use std::collections::{BTreeMap, HashMap};
 use typed_builder::TypedBuilder;
 use uuid::Uuid;
 
+#[derive(PartialEq, Debug, Clone)]
 pub enum MetaData {
     Byte(u8),
     VarInt(u32),
@@ -81,8 +85,40 @@ impl MetaData {
             MetaData::NBTTag(tag) => write_nbt(buffer, tag),
         }
     }
+
+    pub fn read(type_id: u8, data: &mut &[u8], size: &mut usize) -> Result<Self, KittyMCError> {
+        Ok(match type_id {
+            0 => MetaData::Byte(read_u8(data, size)?),
+            1 => MetaData::VarInt(read_varint_u32(data, size)?),
+            2 => MetaData::Float(read_f32(data, size)?),
+            3 => MetaData::String(read_length_prefixed_string(data, size)?),
+            4 => MetaData::Chat(Component::read(data, size)?),
+            5 => MetaData::Slot(SlotData::read(data, size)?),
+            6 => MetaData::Boolean(read_bool(data, size)?),
+            7 => MetaData::Rotation(read_rotation(data, size)?),
+            8 => MetaData::Position(read_location2(data, size)?),
+            9 => MetaData::OptPosition(match read_bool(data, size)? {
+                true => Some(read_location2(data, size)?),
+                false => None,
+            }),
+            10 => MetaData::Direction(read_direction(data, size)?),
+            11 => MetaData::OptUuid(match read_bool(data, size)? {
+                true => Some(read_uuid(data, size)?),
+                false => None,
+            }),
+            12 => MetaData::OptBlockId(match read_bool(data, size)? {
+                true => Some(read_varint_u32(data, size)?),
+                false => None,
+            }),
+            13 => MetaData::NBTTag(read_nbt(data, size)?),
+            _ => return Err(KittyMCError::InvalidEnumValue("MetaData", type_id as i64)),
+        })
+    }
 }
 
+/// Raw entity metadata as it appears on the wire, keyed by index.
+pub type MetadataMap = BTreeMap<u8, MetaData>;
+
 pub fn write_metadata(buffer: &mut Vec<u8>, meta_data: &BTreeMap<u8, MetaData>) {
     for (index, value) in meta_data {
         write_u8(buffer, *index);
@@ -92,6 +128,19 @@ pub fn write_metadata(buffer: &mut Vec<u8>, meta_data: &BTreeMap<u8, MetaData>)
     write_u8(buffer, 0xFF);
 }
 
+pub fn read_metadata(data: &mut &[u8], size: &mut usize) -> Result<MetadataMap, KittyMCError> {
+    let mut meta_data = BTreeMap::new();
+    loop {
+        let index = read_u8(data, size)?;
+        if index == 0xFF {
+            break;
+        }
+        let type_id = read_u8(data, size)?;
+        meta_data.insert(index, MetaData::read(type_id, data, size)?);
+    }
+    Ok(meta_data)
+}
+
 bitflags! {
     #[derive(PartialEq, Eq, Debug, Copy, Clone)]
     pub struct EntityMetaState : u8 {
@@ -129,6 +178,16 @@ pub trait MetadataObject {
     fn write_metadata(&self, buffer: &mut Vec<u8>);
 }
 
+impl MetadataObject for MetadataMap {
+    fn write_to_metadata(&self, meta_data: &mut BTreeMap<u8, MetaData>) {
+        meta_data.extend(self.iter().map(|(index, value)| (*index, value.clone())));
+    }
+
+    fn write_metadata(&self, buffer: &mut Vec<u8>) {
+        write_metadata(buffer, self);
+    }
+}
+
 #[derive(PartialEq, Debug, Clone, TypedBuilder)]
 pub struct EntityMetadata {
     pub meta_state: EntityMetaState,
@@ -153,8 +212,8 @@ impl Default for EntityMetadata {
 }
 
 impl MetadataObject for EntityMetadata {
-    fn write_to_metadata(&self, mut meta_data: &mut BTreeMap<u8, MetaData>) {
-        self.meta_state.write_to_metadata(&mut meta_data, 0);
+    fn write_to_metadata(&self, meta_data: &mut BTreeMap<u8, MetaData>) {
+        self.meta_state.write_to_metadata(meta_data, 0);
         meta_data.insert(1, MetaData::VarInt(self.air));
         meta_data.insert(2, MetaData::String(self.custom_name.clone()));
         meta_data.insert(3, MetaData::Boolean(self.is_custom_name_visible));
@@ -193,9 +252,9 @@ impl Default for LivingMetadata {
 }
 
 impl MetadataObject for LivingMetadata {
-    fn write_to_metadata(&self, mut meta_data: &mut BTreeMap<u8, MetaData>) {
-        self.entity.write_to_metadata(&mut meta_data);
-        self.hand_state.write_to_metadata(&mut meta_data, 6);
+    fn write_to_metadata(&self, meta_data: &mut BTreeMap<u8, MetaData>) {
+        self.entity.write_to_metadata(meta_data);
+        self.hand_state.write_to_metadata(meta_data, 6);
         meta_data.insert(6, MetaData::Float(self.health));
         meta_data.insert(7, MetaData::VarInt(self.potion_effect_color));
         meta_data.insert(8, MetaData::Boolean(self.is_potion_effect_ambient));
@@ -235,8 +294,8 @@ impl Default for PlayerMetadata {
 }
 
 impl MetadataObject for PlayerMetadata {
-    fn write_to_metadata(&self, mut meta_data: &mut BTreeMap<u8, MetaData>) {
-        self.living.write_to_metadata(&mut meta_data);
+    fn write_to_metadata(&self, meta_data: &mut BTreeMap<u8, MetaData>) {
+        self.living.write_to_metadata(meta_data);
         meta_data.insert(11, MetaData::Float(self.additional_hearts));
         meta_data.insert(12, MetaData::VarInt(self.score));
         meta_data.insert(13, MetaData::Byte(self.displayed_skin_parts.bits()));
@@ -4,7 +4,9 @@ use std::fmt::Debug;
 use std::ops::{Add, AddAssign};
 
 pub mod components;
+pub mod item_nbt;
 pub mod metadata;
+pub mod snbt;
 pub mod state;
 
 #[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
@@ -83,6 +85,48 @@ impl Color {
             Color::Italic => "§o",
         }
     }
+
+    /// Looks up the character following a `§`. Legacy codes don't care about case.
+    pub fn from_code(code: char) -> Option<Color> {
+        Some(match code.to_ascii_lowercase() {
+            '0' => Color::Black,
+            '1' => Color::DarkBlue,
+            '2' => Color::DarkGreen,
+            '3' => Color::DarkAqua,
+            '4' => Color::DarkRed,
+            '5' => Color::DarkPurple,
+            '6' => Color::Gold,
+            '7' => Color::Gray,
+            '8' => Color::DarkGray,
+            '9' => Color::Blue,
+            'a' => Color::Green,
+            'b' => Color::Aqua,
+            'c' => Color::Red,
+            'd' => Color::LightPurple,
+            'e' => Color::Yellow,
+            'f' => Color::White,
+            'r' => Color::Reset,
+            'k' => Color::Obfuscated,
+            'l' => Color::Bold,
+            'm' => Color::Strikethrough,
+            'n' => Color::Underline,
+            'o' => Color::Italic,
+            _ => return None,
+        })
+    }
+
+    /// Whether this is an actual color and not a formatting code or [`Color::Reset`]
+    pub fn is_color(&self) -> bool {
+        !matches!(
+            self,
+            Color::Reset
+                | Color::Bold
+                | Color::Obfuscated
+                | Color::Strikethrough
+                | Color::Underline
+                | Color::Italic
+        )
+    }
 }
 
 pub const CHUNK_WIDTH: isize = 16;
@@ -169,7 +213,7 @@ impl ChunkPositionIterator {
     }
 
     pub fn new(center: &Location, radius: f32, xz_only: bool) -> Self {
-        let center_chunk = ChunkPosition::from(center.clone());
+        let center_chunk = ChunkPosition::from(*center);
 
         let radius_in_chunks = (radius / CHUNK_WIDTH as f32).ceil() as isize;
 
@@ -0,0 +1,331 @@
+//! The text form of NBT (`{id:"1",Count:1b}`), which is what item and entity hover events carry.
+//!
+//! Only the syntax 1.12 understands is written, but reading also accepts `true` and `false`.
+
+use crate::error::KittyMCError;
+use fastnbt::{ByteArray, IntArray, LongArray, Value};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// How deep compounds and lists can be nested, like in later vanilla versions. Every level is a
+/// few calls deeper, so without a limit a long enough `[[[[` runs out of stack.
+pub const MAX_DEPTH: usize = 512;
+
+/// Writes `value` as SNBT. Compound keys are sorted so the same value always gives the same text.
+pub fn to_snbt(value: &Value) -> String {
+    let mut out = String::new();
+    write_value(value, &mut out);
+    out
+}
+
+/// Parses SNBT like the 1.12 `JsonToNBT` does
+pub fn from_snbt(snbt: &str) -> Result<Value, KittyMCError> {
+    let mut reader = SnbtReader {
+        input: snbt,
+        pos: 0,
+        depth: 0,
+    };
+    let value = reader.value()?;
+    reader.skip_whitespace();
+    if reader.pos != snbt.len() {
+        return Err(reader.error("Trailing data"));
+    }
+
+    Ok(value)
+}
+
+fn is_unquoted_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '+')
+}
+
+fn write_string(string: &str, out: &mut String) {
+    out.push('"');
+    for c in string.chars() {
+        if c == '"' || c == '\\' {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push('"');
+}
+
+fn write_list<T>(
+    prefix: &str,
+    items: &[T],
+    out: &mut String,
+    mut write_item: impl FnMut(&T, &mut String),
+) {
+    out.push('[');
+    out.push_str(prefix);
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        write_item(item, out);
+    }
+    out.push(']');
+}
+
+fn write_value(value: &Value, out: &mut String) {
+    // Writing into a String can't fail
+    let _ = match value {
+        Value::Byte(v) => write!(out, "{v}b"),
+        Value::Short(v) => write!(out, "{v}s"),
+        Value::Int(v) => write!(out, "{v}"),
+        Value::Long(v) => write!(out, "{v}L"),
+        Value::Float(v) => write!(out, "{v}f"),
+        Value::Double(v) => write!(out, "{v}d"),
+        Value::String(v) => {
+            write_string(v, out);
+            Ok(())
+        }
+        Value::ByteArray(v) => {
+            write_list("B;", v, out, |b, out| {
+                let _ = write!(out, "{b}b");
+            });
+            Ok(())
+        }
+        Value::IntArray(v) => {
+            write_list("I;", v, out, |i, out| {
+                let _ = write!(out, "{i}");
+            });
+            Ok(())
+        }
+        Value::LongArray(v) => {
+            write_list("L;", v, out, |l, out| {
+                let _ = write!(out, "{l}L");
+            });
+            Ok(())
+        }
+        Value::List(v) => {
+            write_list("", v, out, write_value);
+            Ok(())
+        }
+        Value::Compound(v) => {
+            let mut keys: Vec<&String> = v.keys().collect();
+            keys.sort();
+
+            out.push('{');
+            for (i, key) in keys.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                if !key.is_empty() && key.chars().all(is_unquoted_char) {
+                    out.push_str(key);
+                } else {
+                    write_string(key, out);
+                }
+                out.push(':');
+                write_value(&v[key], out);
+            }
+            out.push('}');
+            Ok(())
+        }
+    };
+}
+
+struct SnbtReader<'a> {
+    input: &'a str,
+    pos: usize,
+    /// How many compounds and lists the reader is in
+    depth: usize,
+}
+
+impl SnbtReader<'_> {
+    fn error(&self, reason: &'static str) -> KittyMCError {
+        KittyMCError::SnbtError(self.pos, reason)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek().filter(|c| c.is_whitespace()) {
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn expect(&mut self, expected: char, reason: &'static str) -> Result<(), KittyMCError> {
+        self.skip_whitespace();
+        if self.peek() != Some(expected) {
+            return Err(self.error(reason));
+        }
+        self.pos += expected.len_utf8();
+        Ok(())
+    }
+
+    /// Consumes `c` if it comes next
+    fn accept(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            return true;
+        }
+        false
+    }
+
+    fn value(&mut self) -> Result<Value, KittyMCError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(c @ ('{' | '[')) => {
+                if self.depth == MAX_DEPTH {
+                    return Err(self.error("Nested too deeply"));
+                }
+
+                self.depth += 1;
+                let value = match c {
+                    '{' => self.compound(),
+                    _ => self.list(),
+                };
+                self.depth -= 1;
+                value
+            }
+            Some('"' | '\'') => Ok(Value::String(self.quoted_string()?)),
+            Some(_) => {
+                let token = self.unquoted_string()?;
+                Ok(parse_scalar(token))
+            }
+            None => Err(self.error("Expected a value")),
+        }
+    }
+
+    fn quoted_string(&mut self) -> Result<String, KittyMCError> {
+        let Some(quote) = self.peek() else {
+            return Err(self.error("Expected a string"));
+        };
+        self.pos += quote.len_utf8();
+
+        let mut string = String::new();
+        let mut escaped = false;
+        while let Some(c) = self.peek() {
+            self.pos += c.len_utf8();
+            match c {
+                _ if escaped => {
+                    string.push(c);
+                    escaped = false;
+                }
+                '\\' => escaped = true,
+                _ if c == quote => return Ok(string),
+                _ => string.push(c),
+            }
+        }
+
+        Err(self.error("Unterminated string"))
+    }
+
+    fn unquoted_string(&mut self) -> Result<&str, KittyMCError> {
+        self.skip_whitespace();
+        let start = self.pos;
+        while let Some(c) = self.peek().filter(|c| is_unquoted_char(*c)) {
+            self.pos += c.len_utf8();
+        }
+
+        if start == self.pos {
+            return Err(self.error("Expected a value"));
+        }
+        Ok(&self.input[start..self.pos])
+    }
+
+    fn key(&mut self) -> Result<String, KittyMCError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('"' | '\'') => self.quoted_string(),
+            _ => Ok(self.unquoted_string()?.to_string()),
+        }
+    }
+
+    fn compound(&mut self) -> Result<Value, KittyMCError> {
+        self.expect('{', "Expected a compound")?;
+
+        let mut compound = HashMap::new();
+        if self.accept('}') {
+            return Ok(Value::Compound(compound));
+        }
+
+        loop {
+            let key = self.key()?;
+            self.expect(':', "Expected ':' after a key")?;
+            compound.insert(key, self.value()?);
+
+            if self.accept('}') {
+                return Ok(Value::Compound(compound));
+            }
+            self.expect(',', "Expected ',' or '}'")?;
+        }
+    }
+
+    fn list(&mut self) -> Result<Value, KittyMCError> {
+        self.expect('[', "Expected a list")?;
+
+        let rest = &self.input[self.pos..];
+        let array_type = match rest.get(..2) {
+            Some("B;") => Some('B'),
+            Some("I;") => Some('I'),
+            Some("L;") => Some('L'),
+            _ => None,
+        };
+        if array_type.is_some() {
+            self.pos += 2;
+        }
+
+        let mut items = vec![];
+        if !self.accept(']') {
+            loop {
+                items.push(self.value()?);
+                if self.accept(']') {
+                    break;
+                }
+                self.expect(',', "Expected ',' or ']'")?;
+            }
+        }
+
+        let Some(array_type) = array_type else {
+            return Ok(Value::List(items));
+        };
+
+        let mut longs = Vec::with_capacity(items.len());
+        for item in items {
+            longs.push(match item {
+                Value::Byte(v) => v as i64,
+                Value::Short(v) => v as i64,
+                Value::Int(v) => v as i64,
+                Value::Long(v) => v,
+                _ => return Err(self.error("Arrays can only hold numbers")),
+            });
+        }
+
+        Ok(match array_type {
+            'B' => Value::ByteArray(ByteArray::new(longs.into_iter().map(|v| v as i8).collect())),
+            'I' => Value::IntArray(IntArray::new(longs.into_iter().map(|v| v as i32).collect())),
+            _ => Value::LongArray(LongArray::new(longs)),
+        })
+    }
+}
+
+/// Anything that doesn't look like a number is a string
+fn parse_scalar(token: &str) -> Value {
+    match token {
+        "true" => return Value::Byte(1),
+        "false" => return Value::Byte(0),
+        _ => {}
+    }
+
+    let (number, suffix) = match token.char_indices().last() {
+        Some((i, c)) if c.is_ascii_alphabetic() => (&token[..i], Some(c.to_ascii_lowercase())),
+        _ => (token, None),
+    };
+
+    let parsed = match suffix {
+        Some('b') => number.parse().ok().map(Value::Byte),
+        Some('s') => number.parse().ok().map(Value::Short),
+        Some('l') => number.parse().ok().map(Value::Long),
+        Some('f') => number.parse().ok().map(Value::Float),
+        Some('d') => number.parse().ok().map(Value::Double),
+        Some(_) => None,
+        None if number.contains('.') => number.parse().ok().map(Value::Double),
+        None => number.parse().ok().map(Value::Int),
+    };
+
+    parsed.unwrap_or_else(|| Value::String(token.to_string()))
+}
@@ -1,5 +1,8 @@
 use crate::error::KittyMCError;
 use crate::subtypes::components::TextComponent;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use uuid::{Builder, Uuid};
 
 pub const KITTYMC_TAG: &str = "§4[§5K§6I§eT§aT§bY §dMC§4]§r";
@@ -14,8 +17,76 @@ pub fn generate_cracked_uuid(name: &str) -> Result<Uuid, KittyMCError> {
     Ok(Builder::from_md5_bytes(md5.0).into_uuid())
 }
 
+/// Where a file is written before it's moved to `path`, so it's never seen half written
+pub fn temp_file_path(path: &Path) -> PathBuf {
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    temp_path.into()
+}
+
+/// Writes `data` to a temporary file and then moves it to `path`, so the file at `path` is either
+/// the old or the new one, even if the server or the whole machine crashes half way through
+pub fn write_file_atomically(path: &Path, data: &[u8]) -> io::Result<()> {
+    let temp_path = temp_file_path(path);
+    let mut file = File::create(&temp_path)?;
+    file.write_all(data)?;
+    // Otherwise the rename can reach the disk before the data does
+    file.sync_all()?;
+    drop(file);
+
+    fs::rename(&temp_path, path)?;
+    sync_parent_dir(path)
+}
+
+/// Makes sure the entries of the directory `path` is in are on disk
+#[cfg(unix)]
+pub fn sync_parent_dir(path: &Path) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    File::open(dir)?.sync_all()
+}
+
+/// Directories can't be opened like files on other platforms
+#[cfg(not(unix))]
+pub fn sync_parent_dir(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// A new directory in the system's temporary directory that's deleted again when it's dropped, so
+/// it's cleaned up even if whatever used it panicked
+#[derive(Debug)]
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// The name of the directory starts with `prefix`
+    pub fn new(prefix: &str) -> io::Result<TempDir> {
+        let path = std::env::temp_dir().join(format!("{prefix}-{}", Uuid::new_v4()));
+        fs::create_dir_all(&path)?;
+
+        Ok(TempDir { path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn join(&self, path: impl AsRef<Path>) -> PathBuf {
+        self.path.join(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
 pub fn is_cool(name: &str) -> bool {
-    const COOL_PEOPLE: [&'static str; 3] = ["will_owo", "IT0NA31", "OnlyAfro"];
+    const COOL_PEOPLE: [&str; 3] = ["will_owo", "IT0NA31", "OnlyAfro"];
 
     COOL_PEOPLE.contains(&name)
 }
@@ -33,7 +104,7 @@ pub fn rainbowize_cool_people_textcomp(name: &str, bold: bool) -> Option<TextCom
 }
 
 pub fn rainbowize_cool_people(name: &str, bold: bool) -> String {
-    if is_cool(&name) {
+    if is_cool(name) {
         to_mc_rainbow(name, bold)
     } else {
         name.to_string()
@@ -60,6 +131,10 @@ pub fn axis_to_angle(angle: f32) -> i8 {
     (angle / 360. * 256.) as i8
 }
 
+pub fn angle_to_axis(angle: i8) -> f32 {
+    angle as f32 / 256. * 360.
+}
+
 #[test]
 fn test_cracked_uuid() {
     use std::str::FromStr;
@@ -77,3 +152,25 @@ fn test_cracked_uuid() {
         Uuid::from_str("fe86cee2-9d18-3100-bc41-6740712ec780").unwrap()
     );
 }
+
+#[test]
+fn test_write_file_atomically() {
+    let dir = TempDir::new("kittymc-atomic").unwrap();
+    let path = dir.join("meow.txt");
+
+    write_file_atomically(&path, b"meow").unwrap();
+    write_file_atomically(&path, b"purr").unwrap();
+    assert_eq!(fs::read(&path).unwrap(), b"purr");
+    assert!(!temp_file_path(&path).exists());
+}
+
+#[test]
+fn test_temp_dir_is_removed() {
+    let dir = TempDir::new("kittymc-temp").unwrap();
+    let path = dir.path().to_path_buf();
+    fs::write(dir.join("meow.txt"), "meow").unwrap();
+    assert!(path.is_dir());
+
+    drop(dir);
+    assert!(!path.exists());
+}
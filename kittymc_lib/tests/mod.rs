@@ -1,2 +1,3 @@
 mod packet_tests;
+mod session_tests;
 mod type_tests;
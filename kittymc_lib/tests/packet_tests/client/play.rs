@@ -1,12 +1,37 @@
+use crate::packet_tests::utils::check_clientbound_round_trip;
 use kittymc_lib::packets::client::login::set_compression_03::SetCompressionPacket;
 use kittymc_lib::packets::client::login::success_02::LoginSuccessPacket;
-use kittymc_lib::packets::packet_serialization::SerializablePacket;
+use kittymc_lib::packets::client::login::{DisconnectLoginPacket, EncryptionRequestPacket};
+use kittymc_lib::packets::client::play::chunk_data_20::Chunk;
+use kittymc_lib::packets::client::play::entity_relative_move_26::relative_move_delta;
+use kittymc_lib::packets::client::play::player_list_item_2e::{
+    PlayerListItemAction, PlayerListItemProperties,
+};
+use kittymc_lib::packets::client::play::unlock_recipes_31::UnlockAction;
+use kittymc_lib::packets::client::play::window_items_14::SlotData;
+use kittymc_lib::packets::client::play::{
+    ChangeGameStatePacket, ChunkDataPacket, ClientTabCompletePacket, DestroyEntitiesPacket,
+    Difficulty, Dimension, EntityLookAndRelativeMovePacket, EntityLookPacket,
+    EntityTeleportPacket, GameMode, JoinGamePacket, LevelType, PlayerListItemPacket,
+    SpawnPlayerPacket, UnlockRecipesPacket, UpdateHealthPacket, WindowItemsPacket,
+};
+use kittymc_lib::packets::packet_serialization::{compress_packet, SerializablePacket};
+use kittymc_lib::packets::{ClientboundPacket, CompressionInfo};
+use kittymc_lib::subtypes::components::TextComponent;
+use kittymc_lib::subtypes::item_nbt::ItemNbt;
+use kittymc_lib::subtypes::metadata::{EntityMetadata, MetadataMap, MetadataObject};
+use kittymc_lib::subtypes::state::State;
+use kittymc_lib::subtypes::{Direction, Location2};
+use kittymc_lib::utils::{temp_file_path, TempDir};
+use std::borrow::Cow;
+use std::sync::Arc;
+use uuid::Uuid;
 
 #[test]
 fn test_03_set_compression() {
     let packet = SetCompressionPacket { threshold: 256 }.serialize();
 
-    assert_eq!(&packet, &[03, 03, 0x80, 02]);
+    assert_eq!(&packet, &[0x03, 0x03, 0x80, 0x02]);
 }
 
 #[test]
@@ -20,3 +45,544 @@ fn test_02_login_success() {
     let packet = LoginSuccessPacket::deserialize(&raw_packet[2..]).unwrap();
     assert_eq!(&packet.1.serialize(), raw_packet) // Here the implementation of integer-encodings varint encode is different from the minecraft one
 }
+
+#[test]
+fn test_03_set_compression_clientbound() {
+    let packet = SetCompressionPacket { threshold: 256 };
+
+    check_clientbound_round_trip(
+        State::Login,
+        &packet.serialize(),
+        ClientboundPacket::SetCompression(packet),
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_00_login_disconnect_clientbound() {
+    let packet = DisconnectLoginPacket::wrong_version();
+
+    check_clientbound_round_trip(
+        State::Login,
+        &packet.serialize(),
+        ClientboundPacket::LoginDisconnect(packet),
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_01_encryption_request_clientbound() {
+    let packet = EncryptionRequestPacket::new(&[0x30; 162], &[1, 2, 3, 4]);
+    let serialized = packet.serialize();
+
+    // Empty server id, then the key with a two byte length
+    assert_eq!(&serialized[3..6], &[0x00, 0xA2, 0x01]);
+
+    check_clientbound_round_trip(
+        State::Login,
+        &serialized,
+        ClientboundPacket::EncryptionRequest(packet),
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_23_join_game_clientbound() {
+    let packet = JoinGamePacket {
+        entity_id: 1337,
+        gamemode: GameMode::SurvivalH,
+        dimension: Dimension::Nether,
+        difficulty: Difficulty::Hard,
+        max_players: 69,
+        level_type: LevelType::LargeBiomes,
+        reduced_debug_info: true,
+    };
+
+    check_clientbound_round_trip(
+        State::Play,
+        &packet.serialize(),
+        ClientboundPacket::JoinGame(packet),
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_2e_player_list_item_clientbound() {
+    let packet = PlayerListItemPacket {
+        actions: vec![(
+            Uuid::from_u128(0x0e22d127347735f9a65a6fb3611c78fb),
+            PlayerListItemAction::AddPlayer {
+                name: "will_owo".to_string(),
+                properties: vec![PlayerListItemProperties {
+                    name: "textures".to_string(),
+                    value: "bWVvdw==".to_string(),
+                    signature: Some("c2lnbmVk".to_string()),
+                }],
+                game_mode: GameMode::Creative,
+                ping: 42,
+                display_name: Some(TextComponent::builder().text("§dwillow").build()),
+            },
+        )],
+    };
+
+    check_clientbound_round_trip(
+        State::Play,
+        &packet.serialize(),
+        ClientboundPacket::PlayerListItem(packet),
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_05_spawn_player_clientbound() {
+    let metadata = EntityMetadata::default();
+    let packet = SpawnPlayerPacket {
+        entity_id: 5,
+        player_uuid: Uuid::from_u128(1),
+        location: Location2::new(1.5, 4., -20.25),
+        direction: Direction::new(90., -45.),
+        metadata: metadata.clone(),
+    };
+
+    let mut metadata_map = MetadataMap::new();
+    metadata.write_to_metadata(&mut metadata_map);
+
+    check_clientbound_round_trip(
+        State::Play,
+        &packet.serialize(),
+        ClientboundPacket::SpawnPlayer(SpawnPlayerPacket {
+            entity_id: packet.entity_id,
+            player_uuid: packet.player_uuid,
+            location: packet.location,
+            direction: packet.direction,
+            metadata: metadata_map,
+        }),
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_14_window_items_clientbound() {
+    let packet = WindowItemsPacket::default();
+
+    check_clientbound_round_trip(
+        State::Play,
+        &packet.serialize(),
+        ClientboundPacket::WindowItems(packet),
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_14_window_items_with_nbt_clientbound() {
+    // Compounds are written in map order, so every compound only has one tag to keep the bytes
+    // stable between serializations
+    let mut named = ItemNbt::new();
+    named.set_display_name(Some("Meow"));
+    let mut unbreakable = ItemNbt::new();
+    unbreakable.set_unbreakable(true);
+
+    let mut packet = WindowItemsPacket::default();
+    packet.slot_data[36] = SlotData {
+        id: 276,
+        item_count: 1,
+        item_damage: 0,
+        nbt: Some(named),
+    };
+    packet.slot_data[37] = SlotData {
+        id: 278,
+        item_count: 1,
+        item_damage: 12,
+        nbt: Some(unbreakable),
+    };
+    packet.slot_data[38] = SlotData {
+        id: 1,
+        item_count: 64,
+        item_damage: 0,
+        nbt: None,
+    };
+
+    check_clientbound_round_trip(
+        State::Play,
+        &packet.serialize(),
+        ClientboundPacket::WindowItems(packet),
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_slot_data_without_nbt_ends_with_tag_end() {
+    let slot = SlotData {
+        id: 1,
+        item_count: 64,
+        item_damage: 2,
+        nbt: None,
+    };
+
+    let mut buffer = vec![];
+    slot.write(&mut buffer);
+
+    assert_eq!(&buffer, &[0x00, 0x01, 64, 0x00, 0x02, 0x00]);
+}
+
+#[test]
+fn test_31_unlock_recipes_clientbound() {
+    let packet = UnlockRecipesPacket {
+        action: UnlockAction::Add,
+        crafting_book_open: true,
+        filtering_craftable: false,
+        recipe_ids: vec![1, 300, 70000],
+        recipe_ids_2: vec![],
+    };
+
+    check_clientbound_round_trip(
+        State::Play,
+        &packet.serialize(),
+        ClientboundPacket::UnlockRecipes(packet),
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_20_chunk_data_clientbound() {
+    let mut chunk = Chunk::default();
+    // More than 16 distinct states so the palette entries don't line up with the longs
+    for x in 0..16 {
+        for z in 0..16 {
+            chunk.set_block(x, 0, z, ((x + z) as u32 % 20 + 1) << 4).unwrap();
+        }
+    }
+    chunk.set_block(3, 70, 9, 35 << 4 | 14).unwrap();
+
+    let packet = ChunkDataPacket::new(&chunk, 3, -2);
+    let expected = ClientboundPacket::ChunkData(ChunkDataPacket {
+        x: 3,
+        z: -2,
+        ground_up_continuous: true,
+        data: Cow::Owned(chunk.clone()),
+        block_entities: vec![],
+    });
+    let serialized = packet.serialize();
+
+    check_clientbound_round_trip(State::Play, &serialized, expected.clone()).unwrap();
+
+    let compression = CompressionInfo {
+        enabled: true,
+        compression_threshold: 256,
+    };
+    let compressed = compress_packet(&serialized, compression.compression_threshold).unwrap();
+    let (len, packet) =
+        ClientboundPacket::deserialize(State::Play, &compressed, &compression).unwrap();
+    assert_eq!(len, compressed.len());
+    assert_eq!(packet, expected);
+}
+
+#[test]
+fn test_20_chunk_data_cache_follows_set_block() {
+    let mut chunk = Chunk::default();
+    chunk.set_block(1, 2, 3, 1 << 4).unwrap();
+    chunk.set_block(4, 70, 6, 2 << 4).unwrap();
+
+    let first = ChunkDataPacket::new(&chunk, 0, 0).serialize();
+    assert_eq!(chunk.encoding_cache().cached_sections(), 16);
+
+    // Setting a block to what it already is keeps everything
+    chunk.set_block(1, 2, 3, 1 << 4).unwrap();
+    assert_eq!(chunk.encoding_cache().cached_sections(), 16);
+
+    chunk.set_block(5, 71, 6, 3 << 4).unwrap();
+    assert_eq!(chunk.encoding_cache().cached_sections(), 15);
+
+    let mut fresh = Chunk::default();
+    fresh.set_block(1, 2, 3, 1 << 4).unwrap();
+    fresh.set_block(4, 70, 6, 2 << 4).unwrap();
+    fresh.set_block(5, 71, 6, 3 << 4).unwrap();
+
+    let second = ChunkDataPacket::new(&chunk, 0, 0).serialize();
+    assert_ne!(first, second);
+    assert_eq!(second, ChunkDataPacket::new(&fresh, 0, 0).serialize());
+    assert_eq!(chunk, fresh);
+}
+
+#[test]
+fn test_20_chunk_remembers_unsaved_changes() {
+    let dir = TempDir::new("kittymc-chunk").unwrap();
+    let path = dir.join("0me0ow0.kitty");
+
+    let mut chunk = Chunk::default();
+    assert!(!chunk.is_dirty());
+    chunk.set_block(1, 2, 3, 1 << 4).unwrap();
+    assert!(chunk.is_dirty());
+
+    chunk.save_to(&path).unwrap();
+    assert!(!chunk.is_dirty());
+    assert!(!temp_file_path(&path).exists());
+
+    let mut loaded = Chunk::load_from(&path).unwrap();
+    assert!(!loaded.is_dirty());
+    assert_eq!(*loaded, chunk);
+
+    // Light isn't saved, so it doesn't make the chunk dirty
+    loaded.set_block_light(1, 2, 3, 7).unwrap();
+    assert!(!loaded.is_dirty());
+    // Neither does a block that stays the same
+    loaded.set_block(1, 2, 3, 1 << 4).unwrap();
+    assert!(!loaded.is_dirty());
+    loaded.set_block(1, 3, 3, 1 << 4).unwrap();
+    assert!(loaded.is_dirty());
+}
+
+#[test]
+fn test_20_chunk_blocks_stay_in_the_chunk() {
+    let mut chunk = Chunk::default();
+    chunk.set_block(15, 255, 15, 1 << 4).unwrap();
+    assert_eq!(chunk.get_block(15, 255, 15), Some(1 << 4));
+
+    // These would land on another block if only the index was checked
+    for (x, y, z) in [(16, 0, 0), (0, 0, 16), (17, 3, 15), (0, 256, 0)] {
+        assert!(chunk.set_block(x, y, z, 2 << 4).is_err(), "{x}, {y}, {z} was set");
+        assert_eq!(chunk.get_block(x, y, z), None);
+    }
+    for (x, y, z) in [(0, 0, 1), (0, 1, 0), (1, 4, 0)] {
+        assert_eq!(chunk.get_block(x, y, z), Some(0));
+    }
+}
+
+#[test]
+fn test_20_chunk_section_lengths_are_checked() {
+    // A palette or block data length far beyond what the packet has must not be allocated
+    let huge = [0xFF, 0xFF, 0xFF, 0xFF, 0x0F];
+    let huge_palette = [&[4][..], &huge].concat();
+    let huge_data = [&[13, 0][..], &huge].concat();
+
+    assert!(Chunk::read(&huge_palette, false, 1).is_err());
+    assert!(Chunk::read(&huge_data, false, 1).is_err());
+}
+
+#[test]
+fn test_20_chunk_data_carries_light() {
+    let mut chunk = Chunk::default();
+    chunk.set_block(4, 20, 6, 89 << 4).unwrap();
+    chunk.set_block_light(4, 20, 6, 15).unwrap();
+    chunk.set_block_light(5, 20, 6, 14).unwrap();
+    chunk.set_sky_light(4, 19, 6, 0).unwrap();
+
+    let _ = ChunkDataPacket::new(&chunk, 0, 0).serialize();
+    assert_eq!(chunk.encoding_cache().cached_sections(), 16);
+    chunk.set_sky_light(4, 18, 6, 0).unwrap();
+    assert_eq!(chunk.encoding_cache().cached_sections(), 15);
+
+    let expected = ClientboundPacket::ChunkData(ChunkDataPacket {
+        x: 0,
+        z: 0,
+        ground_up_continuous: true,
+        data: Cow::Owned(chunk.clone()),
+        block_entities: vec![],
+    });
+    let serialized = ChunkDataPacket::new(&chunk, 0, 0).serialize();
+    check_clientbound_round_trip(State::Play, &serialized, expected).unwrap();
+
+    assert_eq!(chunk.block_light(4, 20, 6), Some(15));
+    assert_eq!(chunk.block_light(5, 20, 6), Some(14));
+    assert_eq!(chunk.block_light(6, 20, 6), Some(0));
+    assert_eq!(chunk.sky_light(4, 19, 6), Some(0));
+    assert_eq!(chunk.sky_light(4, 21, 6), Some(15));
+    assert!(chunk.set_sky_light(16, 0, 0, 0).is_err());
+}
+
+#[test]
+fn test_20_chunk_data_frame_is_shared() {
+    let mut chunk = Chunk::default();
+    chunk.set_block(1, 2, 3, 1 << 4).unwrap();
+    let compression = CompressionInfo {
+        enabled: true,
+        compression_threshold: 256,
+    };
+
+    let frame = chunk.encoded_frame(3, -2, &compression).unwrap();
+    assert!(Arc::ptr_eq(&frame, &chunk.encoded_frame(3, -2, &compression).unwrap()));
+    assert_eq!(
+        &*frame,
+        compress_packet(
+            &ChunkDataPacket::new(&chunk, 3, -2).serialize(),
+            compression.compression_threshold
+        )
+        .unwrap()
+    );
+
+    let uncompressed = chunk.encoded_frame(3, -2, &CompressionInfo::default()).unwrap();
+    assert_eq!(&*uncompressed, ChunkDataPacket::new(&chunk, 3, -2).serialize());
+
+    chunk.set_block(1, 2, 3, 2 << 4).unwrap();
+    let changed = chunk.encoded_frame(3, -2, &CompressionInfo::default()).unwrap();
+    assert_ne!(changed, uncompressed);
+    assert_eq!(&*changed, ChunkDataPacket::new(&chunk, 3, -2).serialize());
+}
+
+#[test]
+fn test_32_destroy_entities_clientbound() {
+    let packet = DestroyEntitiesPacket::new(vec![1, 300, 70_000]);
+    let serialized = packet.serialize();
+
+    // length, packet id, array length, then three varints of 1, 2 and 3 bytes
+    assert_eq!(serialized.len(), 1 + 1 + 1 + 1 + 2 + 3);
+    check_clientbound_round_trip(
+        State::Play,
+        &serialized,
+        ClientboundPacket::DestroyEntities(packet),
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_28_entity_look_wraps_angles() {
+    let packet = EntityLookPacket {
+        entity_id: 5,
+        direction: Direction::new(270., -45.),
+        on_ground: true,
+    };
+    let serialized = packet.serialize();
+
+    check_clientbound_round_trip(
+        State::Play,
+        &serialized,
+        ClientboundPacket::EntityLook(EntityLookPacket {
+            direction: Direction::new(-90., -45.),
+            ..packet
+        }),
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_28_entity_look_with_huge_angles() {
+    for yaw in [1e10, -1e30, f32::MAX, f32::INFINITY, f32::NAN] {
+        let packet = EntityLookPacket {
+            entity_id: 5,
+            direction: Direction::new(yaw, 720.),
+            on_ground: true,
+        };
+
+        // length, packet id, entity id, two angles, on ground
+        assert_eq!(packet.serialize().len(), 1 + 1 + 1 + 2 + 1);
+    }
+}
+
+#[test]
+fn test_27_entity_look_and_relative_move_clientbound() {
+    let packet = EntityLookAndRelativeMovePacket {
+        entity_id: 12,
+        delta_x: 4096,
+        delta_y: -2048,
+        delta_z: i16::MIN,
+        direction: Direction::new(90., 45.),
+        on_ground: false,
+    };
+    let serialized = packet.serialize();
+
+    // length, packet id, entity id, three deltas, two angles, on ground
+    assert_eq!(serialized.len(), 1 + 1 + 1 + 6 + 2 + 1);
+    check_clientbound_round_trip(
+        State::Play,
+        &serialized,
+        ClientboundPacket::EntityLookAndRelativeMove(packet),
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_4c_entity_teleport_clientbound() {
+    let packet = EntityTeleportPacket {
+        entity_id: 300,
+        location: Location2::new(-1234.5, 70., 98765.25),
+        direction: Direction::new(-90., 0.),
+        on_ground: true,
+    };
+    let serialized = packet.serialize();
+
+    // length, packet id, entity id, three doubles, two angles, on ground
+    assert_eq!(serialized.len(), 1 + 1 + 2 + 24 + 2 + 1);
+    check_clientbound_round_trip(
+        State::Play,
+        &serialized,
+        ClientboundPacket::EntityTeleport(packet),
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_relative_move_delta() {
+    let origin = Location2::new(0.5, 64., -0.5);
+
+    assert_eq!(
+        relative_move_delta(&origin, &Location2::new(1.5, 63.5, -0.25)),
+        Some([4096, -2048, 1024])
+    );
+    // Just under 8 blocks is the furthest a relative move can go
+    assert_eq!(
+        relative_move_delta(&origin, &Location2::new(-7.4, 64., -0.5)),
+        Some([-32359, 0, 0])
+    );
+    assert_eq!(relative_move_delta(&origin, &Location2::new(8.5, 64., -0.5)), None);
+    assert_eq!(relative_move_delta(&origin, &Location2::new(0.5, 64., 1000.)), None);
+
+    // Many small steps end up exactly where the client would be after one big one
+    let mut position = origin;
+    let mut total = [0i64; 3];
+    for _ in 0..1000 {
+        let next = position + Location2::new(0.0001, -0.00037, 0.00011);
+        let delta = relative_move_delta(&position, &next).unwrap();
+        for (total, delta) in total.iter_mut().zip(delta) {
+            *total += delta as i64;
+        }
+        position = next;
+    }
+    let expected = [
+        ((position.x * 4096.).floor() - (origin.x * 4096.).floor()) as i64,
+        ((position.y * 4096.).floor() - (origin.y * 4096.).floor()) as i64,
+        ((position.z * 4096.).floor() - (origin.z * 4096.).floor()) as i64,
+    ];
+    assert_eq!(total, expected);
+}
+
+#[test]
+fn test_0e_tab_complete_clientbound() {
+    let packet = ClientTabCompletePacket::new(vec!["MrMeow".to_string(), "MrPurr".to_string()]);
+    let serialized = packet.serialize();
+
+    // length, packet id, match count, then two length prefixed names
+    assert_eq!(serialized.len(), 1 + 1 + 1 + 7 + 7);
+    check_clientbound_round_trip(State::Play, &serialized, ClientboundPacket::TabComplete(packet))
+        .unwrap();
+}
+
+#[test]
+fn test_1e_change_game_state_game_mode() {
+    let packet = ChangeGameStatePacket::change_game_mode(GameMode::SpectatorH);
+    let serialized = packet.serialize();
+
+    assert_eq!(packet.reason, 3);
+    assert_eq!(packet.value, 3.);
+    assert_eq!(serialized.len(), 1 + 1 + 1 + 4);
+    check_clientbound_round_trip(
+        State::Play,
+        &serialized,
+        ClientboundPacket::ChangeGameState(packet),
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_41_update_health() {
+    let packet = UpdateHealthPacket {
+        health: 13.5,
+        food: 20,
+        food_saturation: 5.,
+    };
+    let serialized = packet.serialize();
+
+    // length, packet id, health, food, saturation
+    assert_eq!(serialized.len(), 1 + 1 + 4 + 1 + 4);
+    check_clientbound_round_trip(State::Play, &serialized, ClientboundPacket::UpdateHealth(packet))
+        .unwrap();
+}
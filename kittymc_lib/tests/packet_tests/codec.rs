@@ -0,0 +1,148 @@
+use crate::packet_tests::utils::Pipe;
+use kittymc_lib::crypto::{encrypt_with_public_key, ServerKeyPair};
+use kittymc_lib::error::KittyMCError;
+use kittymc_lib::packets::client::login::{
+    EncryptionRequestPacket, LoginSuccessPacket, SetCompressionPacket,
+};
+use kittymc_lib::packets::client::play::ServerPluginMessagePacket;
+use kittymc_lib::packets::codec::PacketCodec;
+use kittymc_lib::packets::packet_serialization::SerializablePacket;
+use kittymc_lib::packets::server::handshake::HandshakePacket;
+use kittymc_lib::packets::server::login::{EncryptionResponsePacket, LoginStartPacket};
+use kittymc_lib::packets::{ClientboundPacket, Packet};
+use kittymc_lib::subtypes::state::State;
+
+fn login_handshake() -> HandshakePacket {
+    HandshakePacket {
+        protocol_version: 340,
+        server_address: "localhost".to_string(),
+        server_port: 25565,
+        next_state: State::Login,
+    }
+}
+
+#[test]
+fn test_codec_reads_split_frames_and_follows_handshake() {
+    let handshake = login_handshake();
+    let login = LoginStartPacket {
+        name: "will_owo".to_string(),
+    };
+    let mut input = handshake.serialize();
+    input.extend(login.serialize());
+
+    let mut codec: PacketCodec<_, Packet> = PacketCodec::new(Pipe::new(input, 1));
+
+    assert_eq!(
+        codec.read_packet().unwrap(),
+        Some(Packet::Handshake(handshake))
+    );
+    assert_eq!(codec.state(), State::Login);
+    assert_eq!(codec.read_packet().unwrap(), Some(Packet::LoginStart(login)));
+    assert_eq!(codec.read_packet().unwrap(), None);
+}
+
+#[test]
+fn test_codec_follows_outgoing_compression_and_login() {
+    let compression = SetCompressionPacket { threshold: 64 };
+    let success = LoginSuccessPacket::from_name_cracked("will_owo").unwrap();
+    let brand = ServerPluginMessagePacket {
+        channel: "MC|Brand".to_string(),
+        data: vec![b'k'; 200],
+    };
+
+    let mut server: PacketCodec<_, Packet> = PacketCodec::new(Pipe::new(vec![], 1));
+    server.set_state(State::Login);
+    server.write_packet(&compression).unwrap();
+    assert!(server.compression().enabled);
+    server.write_packet(&success).unwrap();
+    assert_eq!(server.state(), State::Play);
+    server.write_packet(&brand).unwrap();
+
+    let wire = server.into_inner().output;
+    let mut client: PacketCodec<_, ClientboundPacket> = PacketCodec::new(Pipe::new(wire, 7));
+    client.set_state(State::Login);
+
+    assert_eq!(
+        client.read_packet().unwrap(),
+        Some(ClientboundPacket::SetCompression(compression))
+    );
+    assert_eq!(client.compression().compression_threshold, 64);
+    assert_eq!(
+        client.read_packet().unwrap(),
+        Some(ClientboundPacket::LoginSuccess(success))
+    );
+    assert_eq!(client.state(), State::Play);
+    assert_eq!(
+        client.read_packet().unwrap(),
+        Some(ClientboundPacket::PluginMessage(brand))
+    );
+}
+
+#[test]
+fn test_codec_encrypted_login() {
+    let key_pair = ServerKeyPair::generate().unwrap();
+    let shared_secret = [7u8; 16];
+    let request = EncryptionRequestPacket::new(key_pair.public_key_der(), &[1, 2, 3, 4]);
+    let response = EncryptionResponsePacket {
+        shared_secret: encrypt_with_public_key(&request.public_key, &shared_secret).unwrap(),
+        verify_token: encrypt_with_public_key(&request.public_key, &request.verify_token).unwrap(),
+    };
+    let login = LoginStartPacket {
+        name: "will_owo".to_string(),
+    };
+
+    // The client sends its response in plain text and only encrypts what comes after it
+    let mut client: PacketCodec<_, ClientboundPacket> = PacketCodec::new(Pipe::new(vec![], 1));
+    client.set_state(State::Login);
+    client.write_packet(&response).unwrap();
+    client.enable_encryption(&shared_secret).unwrap();
+    client.write_packet(&login).unwrap();
+    let wire = client.into_inner().output;
+
+    assert!(!wire.ends_with(&login.serialize()));
+
+    let mut server: PacketCodec<_, Packet> = PacketCodec::new(Pipe::new(wire, 1024));
+    server.set_state(State::Login);
+    let Some(Packet::EncryptionResponse(received)) = server.read_packet().unwrap() else {
+        panic!("Expected the Encryption Response first");
+    };
+    assert_eq!(key_pair.decrypt(&received.verify_token).unwrap(), request.verify_token);
+
+    // Whatever was read past the response is still encrypted and has to be fixed up
+    let secret = key_pair.decrypt(&received.shared_secret).unwrap();
+    server.enable_encryption(&secret).unwrap();
+    assert!(server.is_encrypted());
+
+    assert_eq!(server.read_packet().unwrap(), Some(Packet::LoginStart(login)));
+}
+
+#[test]
+fn test_codec_refuses_huge_frames() {
+    // A frame of 2^21 bytes, one more than a three byte length can announce
+    let input = vec![0x80, 0x80, 0x80, 0x01];
+    let mut codec: PacketCodec<_, Packet> = PacketCodec::new(Pipe::new(input, 1));
+    assert!(matches!(codec.read_packet(), Err(KittyMCError::InvalidPacketLength)));
+
+    // The largest allowed length is only waited for
+    let input = vec![0xFF, 0xFF, 0x7F, 0x00];
+    let mut codec: PacketCodec<_, Packet> = PacketCodec::new(Pipe::new(input, 4));
+    assert_eq!(codec.read_packet().unwrap(), None);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn test_codec_async_round_trip() {
+    let (client_stream, server_stream) = tokio::io::duplex(64);
+    let mut client: PacketCodec<_, ClientboundPacket> = PacketCodec::new(client_stream);
+    let mut server: PacketCodec<_, Packet> = PacketCodec::new(server_stream);
+
+    let handshake = login_handshake();
+    client.write_packet_async(&handshake).await.unwrap();
+    assert_eq!(client.state(), State::Login);
+
+    assert_eq!(
+        server.read_packet_async().await.unwrap(),
+        Packet::Handshake(handshake)
+    );
+    assert_eq!(server.state(), State::Login);
+}
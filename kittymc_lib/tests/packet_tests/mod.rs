@@ -1,3 +1,7 @@
 mod client;
+mod codec;
+mod packet_buffer;
+mod protocol;
+mod registry;
 mod server;
 mod utils;
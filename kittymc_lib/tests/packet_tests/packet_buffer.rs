@@ -0,0 +1,89 @@
+use kittymc_lib::packets::client::login::SetCompressionPacket;
+use kittymc_lib::packets::client::play::chunk_data_20::Chunk;
+use kittymc_lib::packets::client::play::{ChunkDataPacket, ServerKeepAlivePacket};
+use kittymc_lib::packets::packet_buffer::PacketBuffer;
+use kittymc_lib::packets::packet_serialization::{compress_packet, SerializablePacket};
+use kittymc_lib::packets::{ClientboundPacket, CompressionInfo};
+use kittymc_lib::subtypes::state::State;
+use std::borrow::Cow;
+
+fn filled_chunk() -> Chunk {
+    let mut chunk = Chunk::default();
+    for x in 0..16 {
+        for z in 0..16 {
+            for y in 0..40 {
+                chunk.set_block(x, y, z, ((x + y) as u32 % 5 + 1) << 4).unwrap();
+            }
+        }
+    }
+    chunk
+}
+
+#[test]
+fn test_packet_buffer_fills_in_header() {
+    let mut buffer = PacketBuffer::new();
+    assert!(buffer.frame().is_empty());
+
+    let frame = buffer.write_packet(&SetCompressionPacket { threshold: 256 });
+    assert_eq!(frame, &[0x03, 0x03, 0x80, 0x02]);
+
+    // Long enough for the length to take three bytes
+    let chunk = filled_chunk();
+    let packet = ChunkDataPacket::new(&chunk, 1, 2);
+    let frame = buffer.write_packet(&packet).to_vec();
+    assert!(frame.len() > 0x4000);
+
+    let (len, decoded) =
+        ClientboundPacket::deserialize(State::Play, &frame, &CompressionInfo::default()).unwrap();
+    assert_eq!(len, frame.len());
+    assert_eq!(
+        decoded,
+        ClientboundPacket::ChunkData(ChunkDataPacket {
+            data: Cow::Owned(chunk.clone()),
+            ..packet
+        })
+    );
+}
+
+#[test]
+fn test_packet_buffer_compresses_like_compress_packet() {
+    let chunk = filled_chunk();
+    let chunk_packet = ChunkDataPacket::new(&chunk, 1, 2);
+    let keep_alive = ServerKeepAlivePacket::new(12345);
+    let compression = CompressionInfo {
+        enabled: true,
+        compression_threshold: 256,
+    };
+
+    let mut buffer = PacketBuffer::new();
+    for (frame, serialized) in [
+        (
+            buffer.write_packet_with(&chunk_packet, &compression).unwrap().to_vec(),
+            chunk_packet.serialize(),
+        ),
+        (
+            buffer.write_packet_with(&keep_alive, &compression).unwrap().to_vec(),
+            keep_alive.serialize(),
+        ),
+    ] {
+        let expected = compress_packet(&serialized, compression.compression_threshold).unwrap();
+        assert_eq!(frame, expected);
+    }
+}
+
+#[test]
+fn test_packet_buffer_reuses_its_memory() {
+    let chunk = filled_chunk();
+    let packet = ChunkDataPacket::new(&chunk, 1, 2);
+    let compression = CompressionInfo {
+        enabled: true,
+        compression_threshold: 256,
+    };
+
+    let mut buffer = PacketBuffer::new();
+    let first = buffer.write_packet_with(&packet, &compression).unwrap().as_ptr();
+    buffer.write_packet(&ServerKeepAlivePacket::new(1));
+    let second = buffer.write_packet_with(&packet, &compression).unwrap().as_ptr();
+
+    assert_eq!(first, second);
+}
@@ -0,0 +1,109 @@
+use crate::packet_tests::utils::Pipe;
+use kittymc_lib::packets::client::login::SetCompressionPacket;
+use kittymc_lib::packets::client::play::{ServerKeepAlivePacket, ServerPlayerPositionAndLookPacket};
+use kittymc_lib::packets::codec::PacketCodec;
+use kittymc_lib::packets::packet_serialization::{decompress_packet, SerializablePacket};
+use kittymc_lib::packets::protocol::ProtocolVersion;
+use kittymc_lib::packets::server::handshake::HandshakePacket;
+use kittymc_lib::packets::server::play::ClientKeepAlivePacket;
+use kittymc_lib::packets::{ClientboundPacket, Packet, PacketDirection};
+use kittymc_lib::subtypes::state::State;
+
+fn handshake(protocol_version: u32) -> HandshakePacket {
+    HandshakePacket {
+        protocol_version,
+        server_address: "localhost".to_string(),
+        server_port: 25565,
+        next_state: State::Login,
+    }
+}
+
+#[test]
+fn test_protocol_versions_by_id() {
+    assert_eq!(ProtocolVersion::from_id(340), Some(ProtocolVersion::V1_12_2));
+    assert_eq!(ProtocolVersion::from_id(338), Some(ProtocolVersion::V1_12_1));
+    assert_eq!(ProtocolVersion::from_id(335), Some(ProtocolVersion::V1_12));
+    assert_eq!(ProtocolVersion::from_id(47), None);
+    assert_eq!(ProtocolVersion::LATEST.id(), 340);
+}
+
+#[test]
+fn test_1_12_packet_ids() {
+    let v1_12 = ProtocolVersion::V1_12;
+    let serverbound = PacketDirection::Serverbound;
+    let clientbound = PacketDirection::Clientbound;
+
+    // Keep Alive and Player Position And Look moved by one
+    assert_eq!(v1_12.packet_id(State::Play, serverbound, 0x0B), Some(0x0C));
+    assert_eq!(v1_12.packet_id(State::Play, clientbound, 0x2F), Some(0x2E));
+    // Teleport Confirm, Player Digging and Keep Alive (clientbound) didn't
+    assert_eq!(v1_12.packet_id(State::Play, serverbound, 0x00), Some(0x00));
+    assert_eq!(v1_12.packet_id(State::Play, serverbound, 0x14), Some(0x14));
+    assert_eq!(v1_12.packet_id(State::Play, clientbound, 0x1F), Some(0x1F));
+    // Craft Recipe Request / Response and Prepare Crafting Grid only exist on one side
+    assert_eq!(v1_12.packet_id(State::Play, serverbound, 0x12), None);
+    assert_eq!(v1_12.packet_id(State::Play, clientbound, 0x2B), None);
+    assert_eq!(v1_12.latest_packet_id(State::Play, serverbound, 0x01), None);
+    // Nothing changed outside of Play
+    assert_eq!(v1_12.packet_id(State::Login, clientbound, 0x02), Some(0x02));
+
+    for direction in [serverbound, clientbound] {
+        for latest_id in 0..=0x4F {
+            if let Some(id) = v1_12.packet_id(State::Play, direction, latest_id) {
+                assert_eq!(
+                    v1_12.latest_packet_id(State::Play, direction, id),
+                    Some(latest_id),
+                    "{direction:?} 0x{latest_id:02X} didn't translate back"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_codec_negotiates_1_12_1_keep_alive() {
+    // 1.12.1 still sends Keep Alive ids as VarInts
+    let mut input = handshake(338).serialize();
+    input.extend([0x03, 0x0B, 0xAC, 0x02]);
+
+    let mut server: PacketCodec<_, Packet> = PacketCodec::new(Pipe::new(input, 1));
+    assert!(matches!(server.read_packet().unwrap(), Some(Packet::Handshake(_))));
+    assert_eq!(server.protocol(), ProtocolVersion::V1_12_1);
+
+    server.set_state(State::Play);
+    assert_eq!(
+        server.read_packet().unwrap(),
+        Some(Packet::KeepAlive(ClientKeepAlivePacket::new(300)))
+    );
+
+    server.write_packet(&ServerKeepAlivePacket::new(300)).unwrap();
+    assert_eq!(server.into_inner().output, [0x03, 0x1F, 0xAC, 0x02]);
+}
+
+#[test]
+fn test_codec_translates_1_12_ids_with_compression() {
+    let mut server: PacketCodec<_, Packet> = PacketCodec::new(Pipe::new(vec![], 1));
+    server.set_state(State::Login);
+    server.set_protocol(ProtocolVersion::V1_12);
+    server.write_packet(&SetCompressionPacket { threshold: 0 }).unwrap();
+    server.set_state(State::Play);
+
+    let position = ServerPlayerPositionAndLookPacket::default();
+    server.write_packet(&position).unwrap();
+
+    let mut client: PacketCodec<_, ClientboundPacket> =
+        PacketCodec::new(Pipe::new(server.into_inner().output, 3));
+    client.set_state(State::Login);
+    client.set_protocol(ProtocolVersion::V1_12);
+    client.read_packet().unwrap();
+    assert!(client.compression().enabled);
+    client.set_state(State::Play);
+
+    let frame = client.read_frame().unwrap().unwrap();
+    let (_, wire_packet) = decompress_packet(&frame.data).unwrap();
+    assert_eq!(wire_packet[0], 0x2E);
+    assert_eq!(
+        frame.packet.unwrap(),
+        ClientboundPacket::PlayerPositionAndLook(position)
+    );
+}
@@ -0,0 +1,48 @@
+use kittymc_lib::packets::registry::{PacketDecoder, PACKET_REGISTRY};
+use kittymc_lib::packets::PacketDirection;
+use kittymc_lib::subtypes::state::State;
+use std::collections::HashMap;
+
+#[test]
+fn test_no_id_collisions_within_state() {
+    let mut seen = HashMap::new();
+
+    for entry in PACKET_REGISTRY.entries() {
+        if let Some(other) = seen.insert((entry.state, entry.direction, entry.id), entry.name) {
+            panic!(
+                "{} and {} both use id 0x{:02X} in {:?} ({:?})",
+                other, entry.name, entry.id, entry.state, entry.direction
+            );
+        }
+    }
+}
+
+#[test]
+fn test_decoder_matches_direction() {
+    for entry in PACKET_REGISTRY.entries() {
+        let matches = matches!(
+            (entry.direction, entry.decoder),
+            (PacketDirection::Serverbound, PacketDecoder::Serverbound(_))
+                | (PacketDirection::Clientbound, PacketDecoder::Clientbound(_))
+        );
+        assert!(matches, "{} has a decoder for the wrong direction", entry.name);
+    }
+}
+
+#[test]
+fn test_lookup_by_id_and_name() {
+    let entry = PACKET_REGISTRY
+        .get(State::Play, PacketDirection::Serverbound, 0x1A)
+        .unwrap();
+    assert_eq!(entry.name, "ClientHeldItemChangePacket");
+
+    let entry = PACKET_REGISTRY
+        .get_by_name(PacketDirection::Clientbound, "StatusPingPongPacket")
+        .unwrap();
+    assert_eq!(entry.state, State::Status);
+    assert_eq!(entry.id, 1);
+
+    assert!(PACKET_REGISTRY
+        .get(State::Handshake, PacketDirection::Clientbound, 0)
+        .is_none());
+}
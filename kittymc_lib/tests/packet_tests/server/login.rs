@@ -1,6 +1,7 @@
 use crate::packet_tests::utils::check_serialized_packet;
 use kittymc_lib::packets::packet_serialization::SerializablePacket;
 use kittymc_lib::packets::server::login::login_start_00::LoginStartPacket;
+use kittymc_lib::packets::server::login::EncryptionResponsePacket;
 use kittymc_lib::packets::Packet;
 
 #[test]
@@ -25,3 +26,29 @@ fn test_00_login_serialize() {
     );
     assert_eq!(deserialized_res, Packet::LoginStart(packet));
 }
+
+#[test]
+fn test_01_encryption_response_serialize() {
+    let packet = EncryptionResponsePacket {
+        shared_secret: vec![0xAB; 32],
+        verify_token: vec![0xCD; 32],
+    };
+
+    let serialized = packet.serialize();
+
+    check_serialized_packet(&serialized, 1 + 1 + 32 + 1 + 32, 1, |data| {
+        assert_eq!(data[0], 32); // Shared Secret Length
+        assert!(data[1..33].iter().all(|b| *b == 0xAB));
+        assert_eq!(data[33], 32); // Verify Token Length
+        assert!(data[34..].iter().all(|b| *b == 0xCD));
+    })
+    .unwrap();
+
+    let (len, deserialized_res) = EncryptionResponsePacket::deserialize(&serialized[2..]).unwrap();
+    assert_eq!(
+        len,
+        serialized.len() - 2,
+        "Length of deserialized size didn't match with serialized packet"
+    );
+    assert_eq!(deserialized_res, Packet::EncryptionResponse(packet));
+}
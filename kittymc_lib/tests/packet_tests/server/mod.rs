@@ -1,2 +1,3 @@
 pub mod handshake;
 pub mod login;
+pub mod play;
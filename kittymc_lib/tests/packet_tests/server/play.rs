@@ -0,0 +1,49 @@
+use crate::packet_tests::utils::check_serialized_packet;
+use kittymc_lib::packets::packet_serialization::SerializablePacket;
+use kittymc_lib::packets::server::play::ServerTabCompletePacket;
+use kittymc_lib::packets::{CompressionInfo, Packet};
+use kittymc_lib::subtypes::state::State;
+use kittymc_lib::subtypes::Location;
+
+#[test]
+fn test_01_tab_complete_serialize() {
+    let packet = ServerTabCompletePacket {
+        text: "/tp Mr".to_string(),
+        assume_command: false,
+        looked_at_block: None,
+    };
+
+    let serialized = packet.serialize();
+    check_serialized_packet(&serialized, 1 + 1 + 6 + 1 + 1, 1, |data| {
+        assert_eq!(data[0] as usize, b"/tp Mr".len()); // Text Length
+        assert_eq!(&data[1..7], b"/tp Mr"); // Text
+        assert_eq!(data[7], 0); // Assume Command
+        assert_eq!(data[8], 0); // Has Position
+    })
+    .unwrap();
+
+    let (len, deserialized_res) = ServerTabCompletePacket::deserialize(&serialized[2..]).unwrap();
+    assert_eq!(
+        len,
+        serialized.len() - 2,
+        "Length of deserialized size didn't match with serialized packet"
+    );
+    assert_eq!(deserialized_res, Packet::TabComplete(packet));
+}
+
+#[test]
+fn test_01_tab_complete_with_block() {
+    let packet = ServerTabCompletePacket {
+        text: "/setblock ".to_string(),
+        assume_command: true,
+        looked_at_block: Some(Location::new(10., 64., -3.)),
+    };
+
+    let serialized = packet.serialize();
+    assert_eq!(serialized.len(), 1 + 1 + 1 + 10 + 1 + 1 + 8);
+
+    let (len, deserialized) =
+        Packet::deserialize(State::Play, &serialized, &CompressionInfo::default()).unwrap();
+    assert_eq!(len, serialized.len());
+    assert_eq!(deserialized, Packet::TabComplete(packet));
+}
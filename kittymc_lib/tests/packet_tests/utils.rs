@@ -1,5 +1,8 @@
 use anyhow::format_err;
 use integer_encoding::VarInt;
+use kittymc_lib::packets::{ClientboundPacket, CompressionInfo};
+use kittymc_lib::subtypes::state::State;
+use std::io::{ErrorKind, Read, Write};
 
 pub fn check_serialized_packet(
     actual_data: &[u8],
@@ -35,3 +38,70 @@ pub fn check_serialized_packet(
 
     Ok(())
 }
+
+pub fn check_clientbound_round_trip(
+    state: State,
+    serialized: &[u8],
+    expected: ClientboundPacket,
+) -> anyhow::Result<()> {
+    let (len, packet) =
+        ClientboundPacket::deserialize(state, serialized, &CompressionInfo::default())
+            .map_err(|e| format_err!("Failed to deserialize packet: {e}"))?;
+    assert_eq!(
+        len,
+        serialized.len(),
+        "Length of deserialized size didn't match with serialized packet"
+    );
+    assert_eq!(packet, expected);
+    assert_eq!(
+        packet.serialize(),
+        serialized,
+        "Serializing the deserialized packet again didn't produce the same data"
+    );
+
+    Ok(())
+}
+
+/// Behaves like a non-blocking socket that hands out at most `chunk_size` bytes per read
+pub struct Pipe {
+    input: Vec<u8>,
+    pos: usize,
+    chunk_size: usize,
+    pub output: Vec<u8>,
+}
+
+impl Pipe {
+    pub fn new(input: Vec<u8>, chunk_size: usize) -> Self {
+        Pipe {
+            input,
+            pos: 0,
+            chunk_size,
+            output: vec![],
+        }
+    }
+}
+
+impl Read for Pipe {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let remaining = &self.input[self.pos..];
+        if remaining.is_empty() {
+            return Err(ErrorKind::WouldBlock.into());
+        }
+
+        let n = remaining.len().min(buf.len()).min(self.chunk_size);
+        buf[..n].copy_from_slice(&remaining[..n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+impl Write for Pipe {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.output.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
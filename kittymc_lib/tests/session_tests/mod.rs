@@ -0,0 +1,109 @@
+use kittymc_lib::crypto::server_id_hash;
+use kittymc_lib::error::KittyMCError;
+use kittymc_lib::session::{HttpSessionVerifier, SessionVerifier};
+use sha1::{Digest, Sha1};
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::str::FromStr;
+use std::thread::{self, JoinHandle};
+use uuid::Uuid;
+
+/// Answers a single request like the session server would and returns the request line
+fn stand_in_session_server(status: &'static str, body: &'static str) -> (String, JoinHandle<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    let handle = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).unwrap();
+        loop {
+            let mut header = String::new();
+            reader.read_line(&mut header).unwrap();
+            if header.trim().is_empty() {
+                break;
+            }
+        }
+
+        let mut stream = reader.into_inner();
+        write!(
+            stream,
+            "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )
+        .unwrap();
+
+        request_line
+    });
+
+    (url, handle)
+}
+
+// Hashes of just the name, published on wiki.vg as examples of the number format
+#[test]
+fn test_server_id_hash_format() {
+    assert_eq!(
+        server_id_hash("Notch", &[], &[]),
+        "4ed1f46bbe04bc756bcb17c0c7ce3e4632f06a48"
+    );
+    assert_eq!(
+        server_id_hash("jeb_", &[], &[]),
+        "-7c9d5b0044c130109a5d7b5fb5c317c02b4e28c1"
+    );
+    assert_eq!(
+        server_id_hash("simon", &[], &[]),
+        "88e16a1019277b15d58faf0541e11910eb756f6"
+    );
+}
+
+#[test]
+fn test_server_id_hash_covers_secret_and_key() {
+    let expected = {
+        let digest = Sha1::new()
+            .chain_update(b"")
+            .chain_update([1, 2, 3])
+            .chain_update([4, 5, 6])
+            .finalize();
+        num_bigint::BigInt::from_signed_bytes_be(&digest).to_str_radix(16)
+    };
+
+    assert_eq!(server_id_hash("", &[1, 2, 3], &[4, 5, 6]), expected);
+    assert_ne!(server_id_hash("", &[1, 2, 3], &[4, 5, 7]), expected);
+}
+
+#[test]
+fn test_session_verifier_accepts_joined_player() {
+    let (url, server) = stand_in_session_server(
+        "200 OK",
+        r#"{"id":"069a79f444e94726a5befca90e38aaf5","name":"Notch","properties":[{"name":"textures","value":"e30=","signature":"c2ln"}]}"#,
+    );
+
+    let profile = HttpSessionVerifier::new(&url)
+        .has_joined("Notch", "-7c9d5b0044c130109a5d7b5fb5c317c02b4e28c1")
+        .unwrap();
+
+    assert_eq!(
+        profile.id,
+        Uuid::from_str("069a79f4-44e9-4726-a5be-fca90e38aaf5").unwrap()
+    );
+    assert_eq!(profile.name, "Notch");
+    assert_eq!(profile.properties.len(), 1);
+    assert_eq!(profile.properties[0].signature.as_deref(), Some("c2ln"));
+
+    let request_line = server.join().unwrap();
+    assert!(request_line.starts_with("GET /session/minecraft/hasJoined?"));
+    assert!(request_line.contains("username=Notch"));
+    assert!(request_line.contains("serverId=-7c9d5b0044c130109a5d7b5fb5c317c02b4e28c1"));
+}
+
+#[test]
+fn test_session_verifier_rejects_unknown_player() {
+    let (url, server) = stand_in_session_server("204 No Content", "");
+
+    let result = HttpSessionVerifier::new(&url).has_joined("Notch", "1234");
+
+    assert!(matches!(result, Err(KittyMCError::NotAuthenticated(name)) if name == "Notch"));
+    server.join().unwrap();
+}
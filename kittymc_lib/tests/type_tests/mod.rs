@@ -1,8 +1,14 @@
+use kittymc_lib::packets::client::play::window_items_14::SlotData;
 use kittymc_lib::subtypes::components::{
-    BaseComponent, ClickEvent, Component, HoverEvent, TextComponent, TranslationComponent,
+    BaseComponent, ClickEvent, Component, HoverEntity, HoverEvent, HoverItem, KeyBindComponent,
+    Score, ScoreComponent, SelectorComponent, TextComponent, TranslationComponent,
 };
+use kittymc_lib::subtypes::item_nbt::{Enchantment, ItemNbt};
+use kittymc_lib::subtypes::snbt::{from_snbt, to_snbt, MAX_DEPTH};
 use kittymc_lib::subtypes::Color;
-use serde_json::Value;
+use serde_json::{json, Value};
+use std::str::FromStr;
+use uuid::Uuid;
 
 #[test]
 fn test_click_event_serialize_minimal() {
@@ -48,6 +54,166 @@ fn test_hover_event_round_trip() {
     let serialized = serde_json::to_string(&hover_evt).unwrap();
     let deserialized: HoverEvent = serde_json::from_str(&serialized).unwrap();
     assert_eq!(deserialized.show_text, Some("Hover here".to_string()));
+    assert_eq!(
+        serde_json::to_value(&hover_evt).unwrap(),
+        json!({"action": "show_text", "value": "Hover here"})
+    );
+
+    // Vanilla sends the text as a component
+    let styled: HoverEvent = serde_json::from_value(
+        json!({"action": "show_text", "value": {"text": "Hi", "color": "red"}}),
+    )
+    .unwrap();
+    assert_eq!(styled.show_text.as_deref(), Some("§cHi"));
+}
+
+#[test]
+fn test_hover_event_show_item_round_trip() {
+    let mut nbt = ItemNbt::new();
+    nbt.set_display_name(Some("Kitty \"Sword\""));
+    let slot = SlotData {
+        id: 276,
+        item_count: 1,
+        item_damage: 3,
+        nbt: Some(nbt),
+    };
+
+    let item = HoverItem::from_slot(&slot).unwrap();
+    let hover_evt = HoverEvent::builder().show_item(item.clone()).build();
+    let json = serde_json::to_value(&hover_evt).unwrap();
+    assert_eq!(
+        json,
+        json!({
+            "action": "show_item",
+            "value": r#"{Count:1b,Damage:3s,id:"276",tag:{display:{Name:"Kitty \"Sword\""}}}"#
+        })
+    );
+
+    let deserialized: HoverEvent = serde_json::from_value(json).unwrap();
+    assert_eq!(deserialized, hover_evt);
+    assert_eq!(deserialized.show_item.unwrap().to_slot(), Some(slot));
+    assert_eq!(HoverItem::from_slot(&SlotData::default()), None);
+
+    // Vanilla writes names instead of numeric ids
+    let named: HoverEvent = serde_json::from_value(json!({
+        "action": "show_item",
+        "value": {"text": "{id:\"minecraft:stone\",Count:64b}"}
+    }))
+    .unwrap();
+    assert_eq!(
+        named.show_item,
+        Some(HoverItem::builder().id("minecraft:stone").count(64).build())
+    );
+}
+
+#[test]
+fn test_hover_event_show_entity_round_trip() {
+    let entity = HoverEntity::builder()
+        .id(Uuid::from_str("0e22d127-3477-35f9-a65a-6fb3611c78fb").unwrap())
+        .entity_type("minecraft:player")
+        .name("will_owo")
+        .build();
+    let hover_evt = HoverEvent::builder().show_entity(entity).build();
+
+    let json = serde_json::to_value(&hover_evt).unwrap();
+    assert_eq!(
+        json,
+        json!({
+            "action": "show_entity",
+            "value": r#"{id:"0e22d127-3477-35f9-a65a-6fb3611c78fb",name:"will_owo",type:"minecraft:player"}"#
+        })
+    );
+    assert_eq!(serde_json::from_value::<HoverEvent>(json).unwrap(), hover_evt);
+
+    // The hover event is its own object on the component
+    let component = Component::Text(
+        TextComponent::builder()
+            .text("will_owo")
+            .options(BaseComponent::builder().hover_event(hover_evt).build())
+            .build(),
+    );
+    let json = serde_json::to_value(&component).unwrap();
+    assert_eq!(json["hoverEvent"]["action"], "show_entity");
+    assert_eq!(serde_json::from_value::<Component>(json).unwrap(), component);
+}
+
+#[test]
+fn test_keybind_component_round_trip() {
+    let component = Component::KeyBind(
+        KeyBindComponent::builder()
+            .keybind("key.inventory")
+            .options(BaseComponent::builder().bold(true).build())
+            .build(),
+    );
+
+    let json = serde_json::to_value(&component).unwrap();
+    assert_eq!(json, json!({"keybind": "key.inventory", "bold": true}));
+    assert_eq!(serde_json::from_value::<Component>(json).unwrap(), component);
+    assert_eq!(component.to_plain_text(), "key.inventory");
+}
+
+#[test]
+fn test_score_component_round_trip() {
+    let component = Component::Score(
+        ScoreComponent::builder()
+            .score(
+                Score::builder()
+                    .name("*")
+                    .objective("kills")
+                    .value("12")
+                    .build(),
+            )
+            .options(BaseComponent::builder().color(Color::Gold).build())
+            .build(),
+    );
+
+    let json = serde_json::to_value(&component).unwrap();
+    assert_eq!(
+        json,
+        json!({"score": {"name": "*", "objective": "kills", "value": "12"}, "color": "gold"})
+    );
+    assert_eq!(serde_json::from_value::<Component>(json).unwrap(), component);
+    assert_eq!(component.to_legacy(), "§612");
+
+    let unresolved: Component =
+        serde_json::from_value(json!({"score": {"name": "Notch", "objective": "deaths"}})).unwrap();
+    let Component::Score(unresolved) = unresolved else {
+        panic!("Expected a score component");
+    };
+    assert_eq!(unresolved.score.value, None);
+}
+
+#[test]
+fn test_selector_component_round_trip() {
+    let component = Component::Selector(SelectorComponent::builder().selector("@a[r=10]").build());
+
+    let json = serde_json::to_value(&component).unwrap();
+    assert_eq!(json, json!({"selector": "@a[r=10]"}));
+    assert_eq!(serde_json::from_value::<Component>(json).unwrap(), component);
+}
+
+#[test]
+fn test_snbt_round_trip() {
+    let snbt = r#"{a:[B;1b,-2b],b:[I;3,4],c:[L;5L],d:1.5f,e:2.25d,f:[{g:"h"},{}],"i j":7s}"#;
+    let value = from_snbt(snbt).unwrap();
+    assert_eq!(to_snbt(&value), snbt);
+
+    assert_eq!(
+        from_snbt("{ id : 'single', n: 3 }").unwrap(),
+        from_snbt(r#"{id:"single",n:3}"#).unwrap()
+    );
+    assert!(from_snbt("{id:").is_err());
+    assert!(from_snbt("{} trailing").is_err());
+}
+
+#[test]
+fn test_snbt_nesting_is_limited() {
+    let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+
+    assert!(from_snbt(&nested(MAX_DEPTH)).is_ok());
+    assert!(from_snbt(&nested(MAX_DEPTH + 1)).is_err());
+    // Would overflow the stack if it was parsed all the way down
+    assert!(from_snbt(&"[".repeat(1_000_000)).is_err());
 }
 
 #[test]
@@ -168,3 +334,157 @@ fn test_component_default_join() {
     assert_eq!(joined_text["text"], " joined the game");
     assert_eq!(joined_text["color"], Value::String("gray".to_string()));
 }
+
+#[test]
+fn test_item_nbt_display() {
+    let mut nbt = ItemNbt::new();
+    nbt.set_display_name(Some("Mr. Whiskers"));
+    nbt.set_lore(&["Purrs loudly", "Sheds everywhere"]);
+    nbt.set_color(Some(0xFF8800));
+
+    assert_eq!(nbt.display_name(), Some("Mr. Whiskers"));
+    assert_eq!(nbt.lore(), vec!["Purrs loudly", "Sheds everywhere"]);
+    assert_eq!(nbt.color(), Some(0xFF8800));
+
+    nbt.set_display_name(None);
+    nbt.set_lore(&[]);
+    nbt.set_color(None);
+
+    // The display compound goes away with its last tag
+    assert!(nbt.is_empty());
+}
+
+#[test]
+fn test_item_nbt_enchantments() {
+    let mut nbt = ItemNbt::new();
+    nbt.add_enchantment(Enchantment { id: 16, level: 3 });
+    nbt.add_enchantment(Enchantment { id: 34, level: 1 });
+    nbt.add_enchantment(Enchantment { id: 16, level: 5 });
+
+    assert_eq!(
+        nbt.enchantments(),
+        vec![
+            Enchantment { id: 16, level: 5 },
+            Enchantment { id: 34, level: 1 },
+        ]
+    );
+
+    nbt.set_enchantments(&[]);
+    assert!(nbt.get("ench").is_none());
+}
+
+#[test]
+fn test_item_nbt_unbreakable() {
+    let mut nbt = ItemNbt::new();
+    assert!(!nbt.is_unbreakable());
+
+    nbt.set_unbreakable(true);
+    assert!(nbt.is_unbreakable());
+    assert_eq!(nbt.get("Unbreakable"), Some(&fastnbt::Value::Byte(1)));
+
+    nbt.set_unbreakable(false);
+    assert!(nbt.is_empty());
+}
+
+#[test]
+fn test_item_nbt_keeps_unknown_tags() {
+    let mut nbt = ItemNbt::new();
+    nbt.insert(
+        "CanDestroy",
+        fastnbt::Value::List(vec![fastnbt::Value::String("minecraft:stone".into())]),
+    );
+    nbt.set_unbreakable(true);
+
+    let read = ItemNbt::from_value(nbt.to_value()).unwrap();
+
+    assert_eq!(read, nbt);
+    assert!(read.get("CanDestroy").is_some());
+    assert!(ItemNbt::from_value(fastnbt::Value::Int(3)).is_err());
+}
+
+#[test]
+fn test_component_from_legacy() {
+    let component = Component::from_legacy("§cHello §lWorld§r!");
+    let Component::Text(root) = &component else {
+        panic!("Expected a text component");
+    };
+
+    assert_eq!(root.text, "");
+    assert_eq!(
+        root.options.extra,
+        vec![
+            Component::Text(
+                TextComponent::builder()
+                    .text("Hello ")
+                    .options(BaseComponent::builder().color(Color::Red).build())
+                    .build()
+            ),
+            Component::Text(
+                TextComponent::builder()
+                    .text("World")
+                    .options(BaseComponent::builder().bold(true).color(Color::Red).build())
+                    .build()
+            ),
+            Component::Text(TextComponent::builder().text("!").build()),
+        ]
+    );
+
+    // A single run doesn't need a parent, unknown codes are kept
+    assert_eq!(
+        Component::from_legacy("§Ex§z"),
+        Component::Text(
+            TextComponent::builder()
+                .text("x§z")
+                .options(BaseComponent::builder().color(Color::Yellow).build())
+                .build()
+        )
+    );
+}
+
+#[test]
+fn test_component_to_legacy() {
+    for legacy in ["§cHello §lWorld§r!", "plain", "§9§l§oblue§7 gray", "§a§mgone§r§n under"] {
+        assert_eq!(Component::from_legacy(legacy).to_legacy(), legacy);
+    }
+
+    // Formatting codes are inherited from parents, colors are replaced
+    let nested = Component::Text(
+        TextComponent::builder()
+            .text("a")
+            .options(
+                BaseComponent::builder()
+                    .bold(true)
+                    .color(Color::Gold)
+                    .extra(vec![Component::Text(
+                        TextComponent::builder()
+                            .text("b")
+                            .options(BaseComponent::builder().color(Color::Aqua).build())
+                            .build(),
+                    )])
+                    .build(),
+            )
+            .build(),
+    );
+    assert_eq!(nested.to_legacy(), "§6§la§b§lb");
+}
+
+#[test]
+fn test_component_to_plain_text() {
+    assert_eq!(
+        Component::default_join("PlayerXYZ").to_plain_text(),
+        "PlayerXYZ joined the game"
+    );
+    assert_eq!(
+        Component::default_chat("will_owo", "hi §cthere").to_plain_text(),
+        "<will_owo> hi there"
+    );
+    assert!(Component::default_restart_disconnect()
+        .to_plain_text()
+        .starts_with("[KITTY MC]\n\nThe server is restarting."));
+
+    let unknown = Component::Translation(TranslationComponent {
+        translate: "some.unknown.key".into(),
+        with: vec![],
+    });
+    assert_eq!(unknown.to_plain_text(), "some.unknown.key");
+}
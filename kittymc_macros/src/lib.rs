@@ -1,6 +1,15 @@
 use proc_macro::TokenStream;
 use quote::{quote, TokenStreamExt};
-use syn::{parse_macro_input, Data, DeriveInput, Fields};
+use syn::{parse_macro_input, Attribute, Data, DeriveInput, Field, Fields};
+
+// variant name, inner type, serialize arm, name arm, id arm
+type VariantArms<'a> = (
+    &'a syn::Ident,
+    syn::Type,
+    proc_macro2::TokenStream,
+    proc_macro2::TokenStream,
+    proc_macro2::TokenStream,
+);
 
 #[proc_macro_derive(PacketHelperFuncs)]
 pub fn derive_packet_helper_funcs(input: TokenStream) -> TokenStream {
@@ -28,33 +37,25 @@ pub fn derive_packet_helper_funcs(input: TokenStream) -> TokenStream {
                 return Err(syn::Error::new_spanned(
                     vname,
                     "Packet Enum Variant MUST have an inner struct that does serialization and deserialization.",
-                ).to_compile_error().into());
+                ).to_compile_error());
             }
         };
 
-        let inner_field_ty = (&unnamed_fields.unnamed[0]).ty.clone();
+        let inner_field_ty = unnamed_fields.unnamed[0].ty.clone();
 
-        Ok((quote! {
+        Ok((vname, inner_field_ty.clone(), quote! {
             Self::#vname(inner) => inner.serialize(),
         },
         quote! {
-            Self::#vname(_) => #inner_field_ty::name(),
+            Self::#vname(_) => <#inner_field_ty>::name(),
         },
         quote! {
-            Self::#vname(inner) => #inner_field_ty::id(),
+            Self::#vname(_) => <#inner_field_ty>::id(),
         }))
     });
 
-    let results: Vec<
-        Result<
-            (
-                proc_macro2::TokenStream,
-                proc_macro2::TokenStream,
-                proc_macro2::TokenStream,
-            ),
-            proc_macro2::TokenStream,
-        >,
-    > = variant_arms.clone().collect();
+    let results: Vec<Result<VariantArms, proc_macro2::TokenStream>> =
+        variant_arms.clone().collect();
 
     if results.iter().any(|v| v.is_err()) {
         let mut error_collector = proc_macro2::TokenStream::new();
@@ -72,11 +73,28 @@ pub fn derive_packet_helper_funcs(input: TokenStream) -> TokenStream {
     let mut serializers = vec![];
     let mut names = vec![];
     let mut ids = vec![];
+    let mut froms = vec![];
+    let mut seen_types = vec![];
 
-    for (s, n, i) in results {
+    for (vname, ty, s, n, i) in results {
         serializers.push(s);
         names.push(n);
         ids.push(i);
+
+        // A packet type can show up under multiple variants (e.g. Ping and Pong), in that case
+        // the first variant is the one it converts into.
+        let ty_str = quote!(#ty).to_string();
+        if seen_types.contains(&ty_str) {
+            continue;
+        }
+        seen_types.push(ty_str);
+        froms.push(quote! {
+            impl From<#ty> for #name {
+                fn from(packet: #ty) -> Self {
+                    Self::#vname(packet)
+                }
+            }
+        });
     }
 
     let expanded = quote! {
@@ -99,25 +117,206 @@ pub fn derive_packet_helper_funcs(input: TokenStream) -> TokenStream {
                 }
             }
         }
+
+        #(#froms)*
     };
 
     expanded.into()
 }
 
-#[proc_macro_derive(Packet)]
+#[derive(Default)]
+struct PacketArgs {
+    id: Option<syn::LitInt>,
+    state: Option<syn::Ident>,
+    direction: Option<syn::Ident>,
+}
+
+impl PacketArgs {
+    fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut args = PacketArgs::default();
+
+        for attr in attrs.iter().filter(|a| a.path().is_ident("packet")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("id") {
+                    args.id = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("state") {
+                    args.state = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("direction") {
+                    args.direction = Some(meta.value()?.parse()?);
+                } else {
+                    return Err(meta.error("expected `id`, `state` or `direction`"));
+                }
+                Ok(())
+            })?;
+        }
+
+        Ok(args)
+    }
+}
+
+fn field_encoding(field: &Field) -> syn::Result<proc_macro2::TokenStream> {
+    let mut encoding = None;
+
+    for attr in field.attrs.iter().filter(|a| a.path().is_ident("packet")) {
+        attr.parse_nested_meta(|meta| {
+            let marker = if meta.path.is_ident("varint") {
+                quote!(VarInt)
+            } else if meta.path.is_ident("angle") {
+                quote!(Angle)
+            } else if meta.path.is_ident("position") {
+                quote!(Position)
+            } else {
+                return Err(meta.error("expected `varint`, `angle` or `position`"));
+            };
+
+            if encoding.is_some() {
+                return Err(meta.error("a field can only have one encoding"));
+            }
+            encoding = Some(marker);
+            Ok(())
+        })?;
+    }
+
+    let encoding = encoding.unwrap_or(quote!(Plain));
+    Ok(quote!(crate::packets::packet_fields::#encoding))
+}
+
+fn derive_codec(
+    input: &DeriveInput,
+    id: &syn::LitInt,
+    direction: &syn::Ident,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                name,
+                "Packet codecs can only be derived for structs",
+            ))
+        }
+    };
+
+    let named: Vec<&Field> = match fields {
+        Fields::Named(fields) => fields.named.iter().collect(),
+        Fields::Unit => vec![],
+        Fields::Unnamed(fields) => {
+            return Err(syn::Error::new_spanned(
+                fields,
+                "Packet codecs need named fields",
+            ))
+        }
+    };
+
+    let mut writers = vec![];
+    let mut readers = vec![];
+    let mut idents = vec![];
+
+    for field in named {
+        let ident = field.ident.as_ref().unwrap();
+        let ty = &field.ty;
+        let encoding = field_encoding(field)?;
+
+        writers.push(quote! {
+            <#ty as crate::packets::packet_fields::PacketField<#encoding>>::write_field(&self.#ident, packet);
+        });
+        readers.push(quote! {
+            let #ident = <#ty as crate::packets::packet_fields::PacketField<#encoding>>::read_field(&mut data, &mut size)?;
+        });
+        idents.push(ident);
+    }
+
+    let deserializer = match direction.to_string().as_str() {
+        "Serverbound" => quote!(deserialize),
+        "Clientbound" => quote!(deserialize_clientbound),
+        _ => {
+            return Err(syn::Error::new_spanned(
+                direction,
+                "direction must be `Serverbound` or `Clientbound`",
+            ))
+        }
+    };
+    let packet_enum = match direction.to_string().as_str() {
+        "Serverbound" => quote!(crate::packets::Packet),
+        _ => quote!(crate::packets::ClientboundPacket),
+    };
+
+    Ok(quote! {
+        impl #impl_generics crate::packets::packet_serialization::SerializablePacket for #name #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn serialize_fields(&self, packet: &mut Vec<u8>) {
+                #(#writers)*
+            }
+
+            #[allow(unused_mut)]
+            fn #deserializer(mut data: &[u8]) -> Result<(usize, #packet_enum), crate::error::KittyMCError> {
+                let mut size = 0;
+
+                #(#readers)*
+
+                Ok((size, Self { #(#idents),* }.into()))
+            }
+
+            fn id() -> u32 {
+                #id
+            }
+        }
+    })
+}
+
+/// Derives `NamedPacket`. With `#[packet(id = .., state = .., direction = ..)]` it also derives
+/// `PacketInfo` and a `SerializablePacket` implementation that writes and reads the fields in
+/// declaration order through `PacketField`. Fields can pick their wire format with
+/// `#[packet(varint)]`, `#[packet(angle)]` or `#[packet(position)]`.
+#[proc_macro_derive(Packet, attributes(packet))]
 pub fn derive_packet(input: TokenStream) -> TokenStream {
     let input: DeriveInput = parse_macro_input!(input);
 
-    let name = input.ident;
-    let generics = input.generics.params;
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
 
-    let expanded = quote! {
-        impl<#generics> crate::packets::packet_serialization::NamedPacket for #name<#generics> {
+    let args = match PacketArgs::parse(&input.attrs) {
+        Ok(args) => args,
+        Err(e) => return e.to_compile_error().into(),
+    };
+
+    let mut expanded = quote! {
+        impl #impl_generics crate::packets::packet_serialization::NamedPacket for #name #ty_generics #where_clause {
             fn name() -> &'static str {
                 stringify!(#name)
             }
         }
     };
 
+    match (&args.state, &args.direction) {
+        (Some(state), Some(direction)) => expanded.extend(quote! {
+            impl #impl_generics crate::packets::packet_serialization::PacketInfo for #name #ty_generics #where_clause {
+                const STATE: crate::subtypes::state::State = crate::subtypes::state::State::#state;
+                const DIRECTION: crate::packets::PacketDirection = crate::packets::PacketDirection::#direction;
+            }
+        }),
+        (None, None) => {}
+        _ => {
+            return syn::Error::new_spanned(name, "`state` and `direction` must be given together")
+                .to_compile_error()
+                .into()
+        }
+    }
+
+    if let Some(id) = &args.id {
+        let Some(direction) = &args.direction else {
+            return syn::Error::new_spanned(id, "a packet `id` also needs `state` and `direction`")
+                .to_compile_error()
+                .into();
+        };
+
+        match derive_codec(&input, id, direction) {
+            Ok(codec) => expanded.extend(codec),
+            Err(e) => return e.to_compile_error().into(),
+        }
+    }
+
     expanded.into()
 }
@@ -1,100 +1,62 @@
 use anyhow::{format_err, Context};
-use kittymc_lib::packets::packet_serialization::SerializablePacket;
-use kittymc_lib::packets::{CompressionInfo, Packet};
-use kittymc_lib::subtypes::state::State;
+use kittymc_lib::error::KittyMCError;
+use kittymc_lib::packets::codec::{Frame, PacketCodec};
+use kittymc_lib::packets::{ClientboundPacket, Packet};
 use std::net::SocketAddr;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpListener, TcpStream};
 use tokio::task::JoinHandle;
 
-const NO_COMPRESSION: CompressionInfo = CompressionInfo {
-    enabled: false,
-    compression_threshold: 0,
-};
-
-fn modify_client_data(data: &mut Vec<u8>, mut n: usize) -> anyhow::Result<usize> {
-    let result = Packet::deserialize(State::Handshake, &data, &NO_COMPRESSION);
-
-    if let Ok((size, mut packet)) = result {
-        match packet {
-            Packet::Handshake(ref mut handshake) => {
-                handshake.server_address = "gommehd.net".to_string();
-                let serialized = handshake.serialize();
-                let serialized_len = serialized.len();
-                data.splice(..n, serialized);
-                n = serialized_len;
-            }
-            _ => (),
-        }
-        println!("Client -> Server: Packet of size {size}: {packet:?}");
-        return Ok(n);
-    }
-
-    let result = Packet::deserialize(State::Login, &data, &NO_COMPRESSION);
-
-    if let Ok((size, packet)) = result {
-        match packet {
-            _ => (),
+type ClientCodec = PacketCodec<TcpStream, Packet>;
+type ServerCodec = PacketCodec<TcpStream, ClientboundPacket>;
+
+async fn forward_client_frame(
+    frame: Frame<Packet>,
+    server: &mut ServerCodec,
+) -> Result<(), KittyMCError> {
+    match frame.packet {
+        Ok(Packet::Handshake(mut handshake)) => {
+            println!("Client -> Server: {handshake:?}");
+            handshake.server_address = get_server_address();
+            return server.write_packet_async(&handshake).await;
         }
-        println!("Client -> Server: Packet of size {size}: {packet:?}");
-        return Ok(n);
+        Ok(packet) => println!("Client -> Server: Packet of size {}: {packet:?}", frame.data.len()),
+        Err(e) => println!("Client -> Server: Couldn't parse packet of size {}: {e}", frame.data.len()),
     }
 
-    println!("Couldn't parse packet");
-
-    Err(format_err!("meow"))
+    server.write_frame_async(&frame.data).await
 }
 
-fn modify_server_data(data: &mut Vec<u8>, _n: usize) -> anyhow::Result<usize> {
-    let result = Packet::deserialize(State::Handshake, &data, &NO_COMPRESSION);
-
-    if let Ok((size, packet)) = result {
-        println!("Server -> Client: Packet of size {size}: {packet:?}");
-    }
-
-    let result = Packet::deserialize(State::Login, &data, &NO_COMPRESSION);
-
-    if let Ok((size, packet)) = result {
-        println!("Server -> Client: Packet of size {size}: {packet:?}");
+async fn forward_server_frame(
+    frame: Frame<ClientboundPacket>,
+    client: &mut ClientCodec,
+) -> Result<(), KittyMCError> {
+    match &frame.packet {
+        Ok(packet) => println!("Server -> Client: Packet of size {}: {packet:?}", frame.data.len()),
+        Err(e) => println!("Server -> Client: Couldn't parse packet of size {}: {e}", frame.data.len()),
     }
 
-    println!("Couldn't parse packet");
-
-    Err(format_err!("meow"))
+    client.write_frame_async(&frame.data).await
 }
 
-async fn forward_data(
-    mut reader: TcpStream,
-    mut writer: TcpStream,
-    is_client_to_server: bool,
-) -> anyhow::Result<()> {
-    let mut buffer = vec![0u8; 2048];
-
+async fn forward_data(mut client: ClientCodec, mut server: ServerCodec) -> anyhow::Result<()> {
     loop {
-        let mut n = match reader.read(&mut buffer).await {
-            Ok(0) => {
-                // The other side closed the connection
-                return Ok(());
-            }
-            Ok(n) => n,
-            Err(e) => return Err(e.into()),
-        };
-
-        if is_client_to_server {
-            n = match modify_client_data(&mut buffer, n) {
-                Ok(new_size) => new_size,
-                Err(_) => continue,
-            }
-        } else {
-            n = match modify_server_data(&mut buffer, n) {
-                Ok(new_size) => new_size,
-                Err(_) => continue,
-            }
-        };
-
-        writer.write_all(&buffer[..n]).await?;
-
-        buffer.drain(..n);
+        // Both reads are cancel safe, so whichever side doesn't win keeps its buffered data
+        tokio::select! {
+            frame = client.read_frame_async() => match frame {
+                Ok(frame) => forward_client_frame(frame, &mut server)
+                    .await
+                    .map_err(|e| format_err!("Error writing to server: {e}"))?,
+                Err(KittyMCError::Disconnected) => return Ok(()),
+                Err(e) => return Err(format_err!("Error reading from client: {e}")),
+            },
+            frame = server.read_frame_async() => match frame {
+                Ok(frame) => forward_server_frame(frame, &mut client)
+                    .await
+                    .map_err(|e| format_err!("Error writing to client: {e}"))?,
+                Err(KittyMCError::Disconnected) => return Ok(()),
+                Err(e) => return Err(format_err!("Error reading from server: {e}")),
+            },
+        }
     }
 }
 
@@ -114,30 +76,7 @@ async fn client_loop(client: TcpStream, sockaddr: &SocketAddr) -> anyhow::Result
         sockaddr, server_url
     );
 
-    // ugly tokio::TcpStream::try_clone() https://github.com/tokio-rs/tokio-core/issues/198
-    let std_client = client.into_std()?;
-    let std_server = server.into_std()?;
-    let client_for_server = TcpStream::from_std(std_client.try_clone()?)?;
-    let server_for_client = TcpStream::from_std(std_server.try_clone()?)?;
-    let client = TcpStream::from_std(std_client)?;
-    let server = TcpStream::from_std(std_server)?;
-
-    let client_to_server_task = tokio::spawn(async move {
-        if let Err(e) = forward_data(client_for_server, server, true).await {
-            eprintln!("Error forwarding client->server: {e}");
-        }
-    });
-
-    let server_to_client_task = tokio::spawn(async move {
-        if let Err(e) = forward_data(server_for_client, client, false).await {
-            eprintln!("Error forwarding client->server: {e}");
-        }
-    });
-
-    tokio::select! {
-        _ = client_to_server_task => { Ok(()) }
-        _ = server_to_client_task => { Ok(()) }
-    }
+    forward_data(PacketCodec::new(client), PacketCodec::new(server)).await
 }
 
 async fn new_client_thread(client: TcpStream, sockaddr: SocketAddr) {
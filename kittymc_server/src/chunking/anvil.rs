@@ -0,0 +1,487 @@
+//! Worlds saved by a vanilla 1.12.2 server, which keeps its chunks in Anvil region files called
+//! `region/r.{x}.{z}.mca`.
+//!
+//! Every chunk is compressed NBT. Its blocks come in sections of 16x16x16, each with the low 8 bits
+//! of the block ids in `Blocks`, the metadata in the `Data` nibbles and, only if a block id doesn't
+//! fit into a byte, the high bits in the `Add` nibbles. Blocks are ordered by y, then z, then x,
+//! just like [`Chunk`] keeps them. Sections with nothing but air are left out.
+
+use crate::chunking::region_storage::{RegionChunkStorage, RegionFormat};
+use fastnbt::{ByteArray, IntArray, Value};
+use kittymc_lib::error::KittyMCError;
+use kittymc_lib::packets::client::play::chunk_data_20::{BlockStateId, Chunk, MAX_LIGHT_LEVEL};
+use kittymc_lib::subtypes::ChunkPosition;
+use miniz_oxide::deflate::{compress_to_vec, compress_to_vec_zlib};
+use miniz_oxide::inflate::{decompress_to_vec, decompress_to_vec_zlib};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+const GZIP_COMPRESSION: u8 = 1;
+const ZLIB_COMPRESSION: u8 = 2;
+const NO_COMPRESSION: u8 = 3;
+const COMPRESSION_LEVEL: u8 = 6;
+
+/// The data version of 1.12.2, which tells newer versions which format to upgrade from
+pub const DATA_VERSION: i32 = 1343;
+/// The version of the chunk format itself, which has been 1 since Anvil was introduced
+const CHUNK_VERSION: i8 = 1;
+
+const SECTION_COUNT: i8 = 16;
+const SECTION_BLOCKS: usize = 16 * 16 * 16;
+/// What vanilla stores for columns whose biome wasn't decided yet
+const UNKNOWN_BIOME: u8 = 0xFF;
+const PLAINS_BIOME: u8 = 1;
+
+pub type AnvilChunkStorage = RegionChunkStorage<AnvilFormat>;
+
+impl AnvilChunkStorage {
+    /// The chunks of the vanilla world saved in `world_dir`
+    pub fn open_world(world_dir: &Path) -> AnvilChunkStorage {
+        RegionChunkStorage::with_format(world_dir.join("region"), AnvilFormat)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AnvilChunk {
+    #[serde(default)]
+    data_version: i32,
+    level: AnvilLevel,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AnvilLevel {
+    #[serde(rename = "xPos")]
+    x_pos: i32,
+    #[serde(rename = "zPos")]
+    z_pos: i32,
+    #[serde(rename = "V", default)]
+    version: i8,
+    #[serde(default)]
+    last_update: i64,
+    #[serde(default)]
+    inhabited_time: i64,
+    #[serde(default)]
+    terrain_populated: i8,
+    #[serde(default)]
+    light_populated: i8,
+    #[serde(default)]
+    sections: Vec<AnvilSection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    biomes: Option<ByteArray>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    height_map: Option<IntArray>,
+    #[serde(default)]
+    entities: Vec<Value>,
+    #[serde(default)]
+    tile_entities: Vec<Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AnvilSection {
+    y: i8,
+    blocks: ByteArray,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    add: Option<ByteArray>,
+    data: ByteArray,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    block_light: Option<ByteArray>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sky_light: Option<ByteArray>,
+}
+
+/// Vanilla's chunks as they're stored in 1.12.2
+#[derive(Debug, Default)]
+pub struct AnvilFormat;
+
+impl RegionFormat for AnvilFormat {
+    fn extension(&self) -> &'static str {
+        "mca"
+    }
+
+    fn decode(
+        &self,
+        pos: &ChunkPosition,
+        compression: u8,
+        data: &[u8],
+    ) -> Result<Box<Chunk>, KittyMCError> {
+        let nbt = decompress(compression, data)?;
+        let chunk: AnvilChunk = fastnbt::from_bytes(&nbt)?;
+
+        chunk_from_level(pos, chunk.level)
+    }
+
+    /// Writes the chunk with the light it has right now, so it should be lit before
+    fn encode(&self, pos: &ChunkPosition, chunk: &Chunk) -> Result<(u8, Vec<u8>), KittyMCError> {
+        let chunk = AnvilChunk {
+            data_version: DATA_VERSION,
+            level: level_from_chunk(pos, chunk)?,
+        };
+        let nbt = fastnbt::to_bytes(&chunk)?;
+
+        Ok((ZLIB_COMPRESSION, compress_to_vec_zlib(&nbt, COMPRESSION_LEVEL)))
+    }
+}
+
+fn decompress(compression: u8, data: &[u8]) -> Result<Vec<u8>, KittyMCError> {
+    match compression {
+        GZIP_COMPRESSION => decompress_gzip(data),
+        ZLIB_COMPRESSION => {
+            decompress_to_vec_zlib(data).map_err(KittyMCError::ZlibDecompressionError)
+        }
+        NO_COMPRESSION => Ok(data.to_vec()),
+        _ => Err(KittyMCError::InvalidEnumValue(
+            "Anvil chunk compression",
+            compression as i64,
+        )),
+    }
+}
+
+/// Vanilla never writes gzip chunks itself, but still reads them, so some tools do
+pub fn decompress_gzip(data: &[u8]) -> Result<Vec<u8>, KittyMCError> {
+    const HEADER_CRC: u8 = 0x02;
+    const EXTRA: u8 = 0x04;
+    const NAME: u8 = 0x08;
+    const COMMENT: u8 = 0x10;
+
+    let (Some([0x1F, 0x8B, 8, flags]), Some(mut rest)) = (data.first_chunk(), data.get(10..))
+    else {
+        return Err(KittyMCError::DecompressionError);
+    };
+
+    let skip_string = |rest: &[u8]| -> Option<usize> {
+        Some(rest.iter().position(|byte| *byte == 0)? + 1)
+    };
+    if flags & EXTRA != 0 {
+        let len = u16::from_le_bytes(*rest.first_chunk().ok_or(KittyMCError::DecompressionError)?);
+        rest = rest.get(2 + len as usize..).ok_or(KittyMCError::DecompressionError)?;
+    }
+    for flag in [NAME, COMMENT] {
+        if flags & flag != 0 {
+            let len = skip_string(rest).ok_or(KittyMCError::DecompressionError)?;
+            rest = &rest[len..];
+        }
+    }
+    if flags & HEADER_CRC != 0 {
+        rest = rest.get(2..).ok_or(KittyMCError::DecompressionError)?;
+    }
+
+    // The checksum and size at the end aren't part of the deflate stream and are left alone
+    decompress_to_vec(rest).map_err(KittyMCError::ZlibDecompressionError)
+}
+
+/// Compresses `data` the way level.dat is stored
+pub fn compress_gzip(data: &[u8]) -> Vec<u8> {
+    // No flags, no modification time, no extra flags and an unknown OS
+    let mut gzip = vec![0x1F, 0x8B, 8, 0, 0, 0, 0, 0, 0, 255];
+    gzip.extend(compress_to_vec(data, COMPRESSION_LEVEL));
+    gzip.extend_from_slice(&crc32fast::hash(data).to_le_bytes());
+    gzip.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    gzip
+}
+
+fn nibble(array: &[i8], index: usize) -> u8 {
+    let byte = array[index / 2] as u8;
+    match index % 2 {
+        0 => byte & 0x0F,
+        _ => byte >> 4,
+    }
+}
+
+fn chunk_from_level(pos: &ChunkPosition, level: AnvilLevel) -> Result<Box<Chunk>, KittyMCError> {
+    if (level.x_pos as isize, level.z_pos as isize) != (pos.chunk_x(), pos.chunk_z()) {
+        return Err(KittyMCError::CorruptRegion("a chunk is stored at the wrong position"));
+    }
+
+    let mut chunk = Box::<Chunk>::default();
+    for section in &level.sections {
+        if !(0..SECTION_COUNT).contains(&section.y) {
+            continue;
+        }
+
+        let valid = section.blocks.len() == SECTION_BLOCKS
+            && section.data.len() == SECTION_BLOCKS / 2
+            && section
+                .add
+                .as_ref()
+                .is_none_or(|add| add.len() == SECTION_BLOCKS / 2);
+        if !valid {
+            return Err(KittyMCError::CorruptRegion("a section has the wrong size"));
+        }
+
+        let base_y = section.y as usize * 16;
+        for i in 0..SECTION_BLOCKS {
+            let add = section.add.as_ref().map_or(0, |add| nibble(add, i));
+            let id = section.blocks[i] as u8 as BlockStateId | (add as BlockStateId) << 8;
+            let state = id << 4 | nibble(&section.data, i) as BlockStateId;
+
+            chunk.set_block(i % 16, base_y + i / 256, (i / 16) % 16, state)?;
+        }
+    }
+
+    if let Some(biomes) = level.biomes.filter(|biomes| biomes.len() == chunk.biomes.len()) {
+        chunk.biomes = biomes
+            .iter()
+            .map(|biome| match *biome as u8 {
+                UNKNOWN_BIOME => PLAINS_BIOME,
+                biome => biome,
+            })
+            .collect();
+    }
+
+    // It's exactly what's on disk
+    chunk.mark_saved();
+
+    Ok(chunk)
+}
+
+fn set_nibble(array: &mut [i8], index: usize, value: u8) {
+    let byte = &mut array[index / 2];
+    *byte = match index % 2 {
+        0 => (*byte as u8 & 0xF0 | value & 0x0F) as i8,
+        _ => (*byte as u8 & 0x0F | value << 4) as i8,
+    };
+}
+
+fn level_from_chunk(pos: &ChunkPosition, chunk: &Chunk) -> Result<AnvilLevel, KittyMCError> {
+    let mut sections = vec![];
+    for section_y in 0..SECTION_COUNT {
+        let base = section_y as usize * SECTION_BLOCKS;
+        let states = &chunk.blocks[base..base + SECTION_BLOCKS];
+        if states.iter().all(|state| *state == 0) {
+            continue;
+        }
+
+        let mut blocks = vec![0; SECTION_BLOCKS];
+        let mut add = vec![0; SECTION_BLOCKS / 2];
+        let mut data = vec![0; SECTION_BLOCKS / 2];
+        let mut block_light = vec![0; SECTION_BLOCKS / 2];
+        let mut sky_light = vec![0; SECTION_BLOCKS / 2];
+        for (i, state) in states.iter().enumerate() {
+            let id = state >> 4;
+            if id > 0xFFF {
+                return Err(KittyMCError::InvalidEnumValue("Anvil block id", id as i64));
+            }
+
+            blocks[i] = id as u8 as i8;
+            set_nibble(&mut add, i, (id >> 8) as u8);
+            set_nibble(&mut data, i, (state & 0xF) as u8);
+
+            let (x, y, z) = (i % 16, base / 256 + i / 256, (i / 16) % 16);
+            set_nibble(&mut block_light, i, chunk.block_light(x, y, z).unwrap_or(0));
+            let sky = chunk.sky_light(x, y, z).unwrap_or(MAX_LIGHT_LEVEL);
+            set_nibble(&mut sky_light, i, sky);
+        }
+
+        sections.push(AnvilSection {
+            y: section_y,
+            blocks: ByteArray::new(blocks),
+            add: add.iter().any(|byte| *byte != 0).then(|| ByteArray::new(add)),
+            data: ByteArray::new(data),
+            block_light: Some(ByteArray::new(block_light)),
+            sky_light: Some(ByteArray::new(sky_light)),
+        });
+    }
+
+    // The first block above the highest one that isn't air, for every column
+    let height_map = (0..16 * 16)
+        .map(|column| {
+            let (x, z) = (column % 16, column / 16);
+            (0..SECTION_COUNT as usize * 16)
+                .rev()
+                .find(|y| chunk.get_block(x, *y, z).is_some_and(|state| state != 0))
+                .map_or(0, |y| y as i32 + 1)
+        })
+        .collect();
+
+    Ok(AnvilLevel {
+        x_pos: pos.chunk_x() as i32,
+        z_pos: pos.chunk_z() as i32,
+        version: CHUNK_VERSION,
+        // In ticks of the world, which a chunk doesn't know about
+        last_update: 0,
+        inhabited_time: 0,
+        // Otherwise vanilla would put trees and ores into the chunk
+        terrain_populated: 1,
+        light_populated: 1,
+        sections,
+        biomes: Some(ByteArray::new(chunk.biomes.iter().map(|biome| *biome as i8).collect())),
+        height_map: Some(IntArray::new(height_map)),
+        entities: vec![],
+        tile_entities: vec![],
+    })
+}
+
+/// Whether `world_dir` looks like a vanilla world with at least one region file
+pub fn is_anvil_world(world_dir: &Path) -> bool {
+    world_dir
+        .join("region")
+        .read_dir()
+        .is_ok_and(|mut entries| {
+            entries.any(|entry| {
+                entry.is_ok_and(|entry| entry.file_name().to_string_lossy().ends_with(".mca"))
+            })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunking::chunk_storage::ChunkStorage;
+    use crate::chunking::region_file::RegionFile;
+    use fastnbt::nbt;
+    use kittymc_lib::utils::TempDir;
+    use std::fs;
+
+    const STONE: BlockStateId = 1 << 4;
+    const GRANITE: BlockStateId = 1 << 4 | 1;
+    /// Only vanilla with mods has blocks past 255, but the format allows them
+    const MODDED: BlockStateId = 300 << 4 | 7;
+
+    /// A chunk laid out like vanilla 1.12.2 saves it, with one section of stone and a few blocks
+    /// in a second one
+    fn vanilla_chunk(x: i32, z: i32) -> Vec<u8> {
+        let stone = vec![1i8; SECTION_BLOCKS];
+        let mut blocks = vec![0i8; SECTION_BLOCKS];
+        let mut data = vec![0i8; SECTION_BLOCKS / 2];
+        let mut add = vec![0i8; SECTION_BLOCKS / 2];
+
+        // x 3, y 16 + 2, z 5 is granite
+        let granite = 2 * 256 + 5 * 16 + 3;
+        blocks[granite] = 1;
+        set_nibble(&mut data, granite, 1);
+        // x 4 right next to it is block 300
+        let modded = granite + 1;
+        blocks[modded] = (300 & 0xFF) as i8;
+        set_nibble(&mut add, modded, (300u32 >> 8) as u8);
+        set_nibble(&mut data, modded, 7);
+
+        let mut biomes = vec![4i8; 256];
+        biomes[1] = -1;
+
+        fastnbt::to_bytes(&nbt!({
+            "DataVersion": 1343,
+            "Level": {
+                "xPos": x,
+                "zPos": z,
+                "LastUpdate": 0i64,
+                "TerrainPopulated": 1i8,
+                "Biomes": Value::ByteArray(ByteArray::new(biomes)),
+                "Sections": [
+                    {
+                        "Y": 0i8,
+                        "Blocks": Value::ByteArray(ByteArray::new(stone)),
+                        "Data": Value::ByteArray(ByteArray::new(vec![0; SECTION_BLOCKS / 2])),
+                        "BlockLight": Value::ByteArray(ByteArray::new(vec![0; 2048])),
+                        "SkyLight": Value::ByteArray(ByteArray::new(vec![0; 2048])),
+                    },
+                    {
+                        "Y": 1i8,
+                        "Blocks": Value::ByteArray(ByteArray::new(blocks)),
+                        "Add": Value::ByteArray(ByteArray::new(add)),
+                        "Data": Value::ByteArray(ByteArray::new(data)),
+                        "BlockLight": Value::ByteArray(ByteArray::new(vec![0; 2048])),
+                        "SkyLight": Value::ByteArray(ByteArray::new(vec![0; 2048])),
+                    },
+                ],
+                "Entities": [],
+                "TileEntities": [],
+            },
+        }))
+        .unwrap()
+    }
+
+    /// A gzip stream with a file name in its header
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut gzip = vec![0x1F, 0x8B, 8, 0x08, 0, 0, 0, 0, 0, 255];
+        gzip.extend_from_slice(b"chunk.nbt\0");
+        gzip.extend(compress_to_vec(data, 6));
+        gzip.extend_from_slice(&[0; 4]);
+        gzip.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        gzip
+    }
+
+    /// A vanilla world with chunks at 0, 0 and 1, 0 in zlib, at -1, -1 in gzip and a chunk at
+    /// 2, 0 that claims to be somewhere else
+    fn anvil_world() -> TempDir {
+        let world = TempDir::new("kittymc-anvil").unwrap();
+        fs::create_dir_all(world.join("region")).unwrap();
+
+        let mut region = RegionFile::open(&world.join("region/r.0.0.mca")).unwrap();
+        let data = compress_to_vec_zlib(&vanilla_chunk(0, 0), 6);
+        region.write(0, 0, ZLIB_COMPRESSION, &data).unwrap();
+        let data = compress_to_vec_zlib(&vanilla_chunk(1, 0), 6);
+        region.write(1, 0, ZLIB_COMPRESSION, &data).unwrap();
+        region.write(2, 0, NO_COMPRESSION, &vanilla_chunk(7, 7)).unwrap();
+
+        let mut region = RegionFile::open(&world.join("region/r.-1.-1.mca")).unwrap();
+        region.write(31, 31, GZIP_COMPRESSION, &gzip(&vanilla_chunk(-1, -1))).unwrap();
+
+        world
+    }
+
+    #[test]
+    fn test_vanilla_chunks_are_converted() {
+        let world = anvil_world();
+        assert!(is_anvil_world(world.path()));
+        let storage = AnvilChunkStorage::open_world(world.path());
+
+        for (x, z) in [(0, 0), (1, 0), (-1, -1)] {
+            let chunk = storage.load(&ChunkPosition::new(x, 0, z)).unwrap().unwrap();
+            assert!(!chunk.is_dirty());
+
+            assert_eq!(chunk.get_block(0, 0, 0), Some(STONE));
+            assert_eq!(chunk.get_block(15, 15, 15), Some(STONE));
+            assert_eq!(chunk.get_block(3, 18, 5), Some(GRANITE));
+            assert_eq!(chunk.get_block(4, 18, 5), Some(MODDED));
+            assert_eq!(chunk.get_block(3, 16, 5), Some(0));
+            assert_eq!(chunk.get_block(0, 40, 0), Some(0));
+
+            assert_eq!(chunk.biomes[0], 4);
+            assert_eq!(chunk.biomes[1], PLAINS_BIOME);
+        }
+
+        assert!(storage.load(&ChunkPosition::new(5, 0, 5)).unwrap().is_none());
+        assert!(storage.load(&ChunkPosition::new(-40, 0, 3)).unwrap().is_none());
+        assert!(matches!(
+            storage.load(&ChunkPosition::new(2, 0, 0)),
+            Err(KittyMCError::CorruptRegion(_))
+        ));
+        assert_eq!(storage.positions().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_chunks_are_written_like_vanilla() {
+        let pos = ChunkPosition::new(-3, 0, 8);
+        let mut chunk = Chunk::default();
+        chunk.set_block(2, 0, 3, STONE).unwrap();
+        chunk.set_block(2, 70, 3, MODDED).unwrap();
+        chunk.set_block(5, 17, 0, GRANITE).unwrap();
+        chunk.set_block_light(2, 71, 3, 9).unwrap();
+
+        let (compression, data) = AnvilFormat.encode(&pos, &chunk).unwrap();
+        let nbt = decompress(compression, &data).unwrap();
+        let written: AnvilChunk = fastnbt::from_bytes(&nbt).unwrap();
+        assert_eq!(written.data_version, DATA_VERSION);
+
+        let level = written.level;
+        assert_eq!((level.x_pos, level.z_pos), (-3, 8));
+        // Sections with only air are left out
+        let sections: Vec<i8> = level.sections.iter().map(|section| section.y).collect();
+        assert_eq!(sections, vec![0, 1, 4]);
+        assert!(level.sections[0].add.is_none());
+        assert!(level.sections[2].add.is_some());
+        let light = level.sections[2].block_light.as_ref().unwrap();
+        assert_eq!(nibble(light, 7 * 256 + 3 * 16 + 2), 9);
+
+        let height_map = level.height_map.unwrap();
+        assert_eq!(height_map[3 * 16 + 2], 71);
+        assert_eq!(height_map[5], 18);
+        assert_eq!(height_map[0], 0);
+
+        let decoded = AnvilFormat.decode(&pos, compression, &data).unwrap();
+        assert!(decoded.blocks == chunk.blocks);
+    }
+}
@@ -0,0 +1,144 @@
+//! Turns a kittymc world into one vanilla 1.12.2 can open, with its chunks in Anvil region files
+//! and a level.dat saying how to continue it.
+
+use crate::chunking::anvil::{is_anvil_world, AnvilChunkStorage};
+use crate::chunking::chunk_storage::ChunkStorage;
+use crate::chunking::light_engine;
+use crate::level::LevelInfo;
+use kittymc_lib::error::KittyMCError;
+use std::collections::HashSet;
+use std::io::{self, ErrorKind};
+use std::path::{Component, Path, PathBuf};
+
+/// Exported worlds all go in here, so an export can't touch anything else
+pub const EXPORTS_DIR: &str = "exports";
+
+/// Where the export called `name` goes, or `None` if the name isn't a single plain directory name
+pub fn export_dir(name: &str) -> Option<PathBuf> {
+    let mut components = Path::new(name).components();
+    let is_plain = !name.contains(['/', '\\'])
+        && matches!(components.next(), Some(Component::Normal(_)))
+        && components.next().is_none();
+
+    is_plain.then(|| Path::new(EXPORTS_DIR).join(name))
+}
+
+/// Writes every chunk of `storages` into a new vanilla world at `world_dir` and returns how many
+/// there were. A chunk that's in more than one storage is taken from the first one.
+pub fn export_world(
+    storages: &[&dyn ChunkStorage],
+    world_dir: &Path,
+    level: &LevelInfo,
+) -> Result<usize, KittyMCError> {
+    if is_anvil_world(world_dir) || LevelInfo::file_path(world_dir).exists() {
+        return Err(io::Error::new(
+            ErrorKind::AlreadyExists,
+            format!("{} already has a world", world_dir.display()),
+        )
+        .into());
+    }
+
+    let target = AnvilChunkStorage::open_world(world_dir);
+    let mut seen = HashSet::new();
+    let mut exported = 0;
+    for storage in storages {
+        for pos in storage.positions()? {
+            if !seen.insert(pos.clone()) {
+                continue;
+            }
+            let Some(mut chunk) = storage.load(&pos)? else {
+                continue;
+            };
+
+            // Light isn't saved, and vanilla trusts the light of a chunk it gets
+            light_engine::light_chunk(&mut chunk);
+            target.save(&pos, &chunk)?;
+            exported += 1;
+        }
+    }
+
+    level.save(world_dir)?;
+
+    Ok(exported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunking::chunk_storage::MemoryChunkStorage;
+    use kittymc_lib::packets::client::play::chunk_data_20::{BlockStateId, Chunk};
+    use kittymc_lib::subtypes::ChunkPosition;
+    use kittymc_lib::utils::TempDir;
+
+    fn level_info() -> LevelInfo {
+        LevelInfo {
+            name: "meow".to_string(),
+            seed: -1234,
+            generator_name: "flat".to_string(),
+            generator_options: String::new(),
+            spawn_x: 0,
+            spawn_y: 5,
+            spawn_z: 0,
+            game_type: 1,
+            time: 500,
+            day_time: 300,
+        }
+    }
+
+    fn test_chunk(seed: usize) -> Chunk {
+        let mut chunk = Chunk::default();
+        for x in 0..16 {
+            for z in 0..16 {
+                chunk.set_block(x, 0, z, 7 << 4).unwrap();
+                chunk.set_block(x, 1 + (x + z + seed) % 3, z, 35 << 4 | z as BlockStateId).unwrap();
+            }
+        }
+        // A block id that only fits with the Add nibbles, high up in its own section
+        chunk.set_block(4, 200, 9, 300 << 4 | 3).unwrap();
+        chunk.set_block(15, 255, 15, 1 << 4).unwrap();
+        chunk.biomes[seed % 256] = 4;
+        chunk
+    }
+
+    #[test]
+    fn test_export_reads_back_the_same() {
+        let dir = TempDir::new("kittymc-export").unwrap();
+        let world = dir.join("world");
+        let saved = MemoryChunkStorage::new();
+        let source = MemoryChunkStorage::new();
+        let positions = [(0, 0), (-1, 5), (40, -33)];
+        for (i, (x, z)) in positions.iter().enumerate() {
+            saved.save(&ChunkPosition::new(*x, 0, *z), &test_chunk(i)).unwrap();
+        }
+        // The saved copy wins over the one in the source
+        source.save(&ChunkPosition::new(0, 0, 0), &Chunk::default()).unwrap();
+        source.save(&ChunkPosition::new(7, 0, 7), &test_chunk(9)).unwrap();
+
+        let exported = export_world(&[&saved, &source], &world, &level_info()).unwrap();
+        assert_eq!(exported, 4);
+        assert!(export_world(&[&saved], &world, &level_info()).is_err());
+
+        let anvil = AnvilChunkStorage::open_world(&world);
+        assert_eq!(anvil.positions().unwrap().len(), 4);
+        for (i, (x, z)) in positions.iter().enumerate() {
+            let chunk = anvil.load(&ChunkPosition::new(*x, 0, *z)).unwrap().unwrap();
+            let expected = test_chunk(i);
+            assert!(chunk.blocks == expected.blocks, "chunk {x}, {z} has other blocks");
+            assert_eq!(chunk.biomes, expected.biomes);
+        }
+        let chunk = anvil.load(&ChunkPosition::new(7, 0, 7)).unwrap().unwrap();
+        assert!(chunk.blocks == test_chunk(9).blocks);
+
+        assert_eq!(LevelInfo::load(&world).unwrap(), Some(level_info()));
+    }
+
+    #[test]
+    fn test_export_dirs_stay_in_the_exports() {
+        assert_eq!(export_dir("meow"), Some(Path::new(EXPORTS_DIR).join("meow")));
+        assert_eq!(export_dir("my world"), Some(Path::new(EXPORTS_DIR).join("my world")));
+
+        for name in ["", ".", "..", "../meow", "meow/..", "a/b", "a\\b", "/tmp", "meow/"] {
+            assert_eq!(export_dir(name), None, "{name:?} was accepted");
+        }
+    }
+}
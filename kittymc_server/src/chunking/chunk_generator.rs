@@ -1,16 +1,21 @@
 use crate::chunking::chunk_manager::{
-    make_chunk_file_path, ChunkPriority, SharedChunk, SharedChunkList, SharedQueue,
+    ChunkPriority, SharedChunk, SharedChunkList, SharedChunkSource, SharedQueue,
 };
+use crate::chunking::chunk_storage::SharedChunkStorage;
 use crate::chunking::increasing_ticker::IncreasingTicker;
+use crate::chunking::light_engine;
+use crate::chunking::world_generator::SharedWorldGenerator;
 use kittymc_lib::error::KittyMCError;
-use kittymc_lib::packets::client::play::chunk_data_20::{Chunk, DEFAULT_FLAT_CHUNK, DEFAULT_FLAT_CHUNK_2};
+use kittymc_lib::packets::client::play::chunk_data_20::Chunk;
 use kittymc_lib::subtypes::ChunkPosition;
-use log::{debug, error};
-use std::path::Path;
+use log::{debug, error, warn};
 use std::sync::RwLock;
 
 pub struct ChunkGenerator {
     collector: SharedChunkList,
+    storage: SharedChunkStorage,
+    source: SharedChunkSource,
+    world: SharedWorldGenerator,
     low_queue: SharedQueue,
     middle_queue: SharedQueue,
     high_queue: SharedQueue,
@@ -20,12 +25,18 @@ pub struct ChunkGenerator {
 impl ChunkGenerator {
     pub fn entry_thread(
         collector: SharedChunkList,
+        storage: SharedChunkStorage,
+        source: SharedChunkSource,
+        world: SharedWorldGenerator,
         low: SharedQueue,
         mid: SharedQueue,
         high: SharedQueue,
     ) {
         let mut gen = ChunkGenerator {
             collector,
+            storage,
+            source,
+            world,
             low_queue: low,
             middle_queue: mid,
             high_queue: high,
@@ -43,19 +54,16 @@ impl ChunkGenerator {
     }
 
     fn bite_queue(&mut self) {
-        if self.bite_specific_queue(ChunkPriority::HIGH)
-            || self.bite_specific_queue(ChunkPriority::MID)
-            || self.bite_specific_queue(ChunkPriority::LOW)
-        {
-            return;
-        }
+        let _ = self.bite_specific_queue(ChunkPriority::High)
+            || self.bite_specific_queue(ChunkPriority::Mid)
+            || self.bite_specific_queue(ChunkPriority::Low);
     }
 
     fn bite_specific_queue(&mut self, queue: ChunkPriority) -> bool {
         let mut queue = match queue {
-            ChunkPriority::HIGH => &self.high_queue,
-            ChunkPriority::MID => &self.middle_queue,
-            ChunkPriority::LOW => &self.low_queue,
+            ChunkPriority::High => &self.high_queue,
+            ChunkPriority::Mid => &self.middle_queue,
+            ChunkPriority::Low => &self.low_queue,
         }
         .write()
         .unwrap();
@@ -65,14 +73,13 @@ impl ChunkGenerator {
         drop(queue);
 
         if let Some(chunk_pos) = chunk_pos {
-            let file_path = make_chunk_file_path(&chunk_pos);
-            let exists = file_path.exists();
-            let chunk_res = match exists {
-                true => self.load_chunk(&file_path),
-                false => Ok(self.start_generation(&chunk_pos)),
+            let chunk_res = match self.load_chunk(&chunk_pos) {
+                Ok(Some(chunk)) => Ok(chunk),
+                Ok(None) => Ok(self.start_generation(&chunk_pos)),
+                Err(e) => Err(e),
             };
 
-            let chunk = match chunk_res {
+            let mut chunk = match chunk_res {
                 Ok(chunk) => chunk,
                 Err(e) => {
                     error!("Failed to load chunk: {e}");
@@ -80,10 +87,15 @@ impl ChunkGenerator {
                 }
             };
 
+            light_engine::light_chunk(&mut chunk);
             self.collector
                 .write()
                 .unwrap()
-                .insert(chunk_pos, SharedChunk::new(RwLock::new(chunk)));
+                .insert(chunk_pos.clone(), SharedChunk::new(RwLock::new(chunk)));
+
+            if let Err(e) = light_engine::stitch_chunk(&self.collector, &chunk_pos) {
+                error!("Failed to light chunk: {e}");
+            }
 
             return true;
         }
@@ -91,9 +103,38 @@ impl ChunkGenerator {
         false
     }
 
-    pub fn load_chunk(&mut self, file_path: &Path) -> Result<Box<Chunk>, KittyMCError> {
-        debug!("Loaded chunk from file");
-        Ok(Chunk::load_from(file_path)?)
+    pub fn load_chunk(
+        &mut self,
+        chunk_pos: &ChunkPosition,
+    ) -> Result<Option<Box<Chunk>>, KittyMCError> {
+        let chunk = self.storage.load(chunk_pos)?;
+        if chunk.is_some() {
+            debug!("Loaded chunk from storage");
+            return Ok(chunk);
+        }
+
+        let source = self.source.read().unwrap().clone();
+        let Some(source) = source else {
+            return Ok(None);
+        };
+
+        // A chunk the source can't read is generated instead of leaving a hole in the world
+        match source.load(chunk_pos) {
+            Ok(chunk) => {
+                if chunk.is_some() {
+                    debug!("Loaded chunk from the chunk source");
+                }
+                Ok(chunk)
+            }
+            Err(e) => {
+                warn!(
+                    "Couldn't load chunk {}, {} from the chunk source, generating it: {e}",
+                    chunk_pos.chunk_x(),
+                    chunk_pos.chunk_z()
+                );
+                Ok(None)
+            }
+        }
     }
 
     pub fn start_generation(&mut self, chunk_pos: &ChunkPosition) -> Box<Chunk> {
@@ -102,10 +143,6 @@ impl ChunkGenerator {
     }
 
     pub fn generate(&mut self, chunk_pos: &ChunkPosition) -> Box<Chunk> {
-        if chunk_pos.chunk_z().abs() % 2 == 1 {
-            DEFAULT_FLAT_CHUNK.clone()
-        } else {
-            DEFAULT_FLAT_CHUNK_2.clone()
-        }
+        self.world.generate(chunk_pos)
     }
 }
@@ -3,31 +3,38 @@ use kittymc_lib::error::KittyMCError;
 use kittymc_lib::packets::client::play::chunk_data_20::{BlockStateId, Chunk};
 use kittymc_lib::subtypes::{ChunkPosition, Location};
 use std::collections::{HashMap, VecDeque};
-use std::path::PathBuf;
 use std::sync::{Arc, RwLock};
 use std::thread::JoinHandle;
 use std::time::Instant;
 use crate::chunking::chunk_generator::ChunkGenerator;
+use crate::chunking::chunk_storage::{ChunkStorage, SharedChunkStorage};
 use crate::chunking::chunk_unloader::ChunkUnloader;
+use crate::chunking::light_engine;
+use crate::chunking::world_generator::SharedWorldGenerator;
+use log::error;
 
 pub type SharedChunk = Arc<RwLock<Box<Chunk>>>;
 pub type SharedQueue = Arc<RwLock<VecDeque<ChunkPosition>>>;
 pub type SharedChunkList = Arc<RwLock<HashMap<ChunkPosition, SharedChunk>>>;
 pub type SharedChunkAccessList = Arc<RwLock<HashMap<ChunkPosition, Instant>>>;
+pub type SharedChunkSource = Arc<RwLock<Option<SharedChunkStorage>>>;
 
 const GENERATOR_THREADS: usize = 4;
 const UNLOADER_THREADS: usize = 1;
 
 pub enum ChunkPriority {
-    HIGH,
-    MID,
-    LOW,
+    High,
+    Mid,
+    Low,
 }
 
 #[derive(Debug)]
 pub struct ChunkManager {
     loaded_chunks: SharedChunkList,
     access_list: SharedChunkAccessList,
+    storage: SharedChunkStorage,
+    source: SharedChunkSource,
+    world: SharedWorldGenerator,
 
     generator_threads: Vec<JoinHandle<()>>,
     unloader_threads: Vec<JoinHandle<()>>,
@@ -38,10 +45,15 @@ pub struct ChunkManager {
 }
 
 impl ChunkManager {
-    pub fn new() -> ChunkManager {
+    /// Loads chunks from and saves them to `storage`, and has `world` generate the ones it doesn't
+    /// have
+    pub fn new(storage: SharedChunkStorage, world: SharedWorldGenerator) -> ChunkManager {
         let mut manager = ChunkManager {
             loaded_chunks: Arc::new(Default::default()),
             access_list: Arc::new(Default::default()),
+            storage,
+            source: Arc::new(RwLock::new(None)),
+            world,
 
             generator_threads: Vec::new(),
             unloader_threads: Vec::new(),
@@ -59,21 +71,49 @@ impl ChunkManager {
     fn init_threads(&mut self) {
         for _ in 0..GENERATOR_THREADS {
             let collector = self.loaded_chunks.clone();
+            let storage = self.storage.clone();
+            let source = self.source.clone();
+            let world = self.world.clone();
             let high_queue = self.high_priority_queue.clone();
             let medium_queue = self.medium_priority_queue.clone();
             let low_priority_queue = self.low_priority_queue.clone();
 
-            self.generator_threads.push(std::thread::spawn(|| ChunkGenerator::entry_thread(collector, high_queue, medium_queue, low_priority_queue)));
+            self.generator_threads.push(std::thread::spawn(|| {
+                ChunkGenerator::entry_thread(
+                    collector,
+                    storage,
+                    source,
+                    world,
+                    high_queue,
+                    medium_queue,
+                    low_priority_queue,
+                )
+            }));
         }
 
         for _ in 0..UNLOADER_THREADS {
             let collector = self.loaded_chunks.clone();
             let access_list = self.access_list.clone();
+            let storage = self.storage.clone();
 
-            self.unloader_threads.push(std::thread::spawn(|| ChunkUnloader::entry_thread(collector, access_list)));
+            self.unloader_threads.push(std::thread::spawn(|| {
+                ChunkUnloader::entry_thread(collector, access_list, storage)
+            }));
         }
     }
 
+    /// Chunks that were never saved are taken from `source` before they're generated. The source
+    /// is only read from, changed chunks are saved to the storage like any other chunk.
+    pub fn set_source(&self, source: Option<SharedChunkStorage>) {
+        *self.source.write().unwrap() = source;
+    }
+
+    /// Where the chunks of the world are, the storage first and then the source if there is one
+    pub fn storages(&self) -> Vec<SharedChunkStorage> {
+        let source = self.source.read().unwrap().clone();
+        [self.storage.clone()].into_iter().chain(source).collect()
+    }
+
     #[allow(dead_code)]
     pub fn is_chunk_loaded(&self, pos: &ChunkPosition) -> bool {
         let mut pos = pos.clone();
@@ -110,9 +150,8 @@ impl ChunkManager {
         let mut chunk_pos = chunk_pos.clone();
         chunk_pos.set_chunk_y(0);
 
-        match self.get_chunk_at(&chunk_pos) {
-            Some(chunk) => return Some(chunk),
-            _ => {}
+        if let Some(chunk) = self.get_chunk_at(&chunk_pos) {
+            return Some(chunk);
         }
         if self.is_queued(&chunk_pos) {
             return None;
@@ -179,9 +218,8 @@ impl ChunkManager {
             ChunkPosition::iter_xz_circle_in_range(loc, radius as f32).collect();
 
         for chunk_pos in requested_chunks {
-            match self.request_chunk(&chunk_pos) {
-                Some(chunk) => loaded_chunks.push((chunk_pos.clone(), chunk)),
-                _ => {}
+            if let Some(chunk) = self.request_chunk(&chunk_pos) {
+                loaded_chunks.push((chunk_pos.clone(), chunk));
             }
         }
 
@@ -195,22 +233,75 @@ impl ChunkManager {
         self.access_list.write().unwrap().insert(pos.clone(), Instant::now());
     }
 
-    pub fn set_block(&mut self, loc: &Location, block_id: BlockStateId) -> Result<(), KittyMCError> {
-        let chunk = self.get_chunk_containing_block(loc)
-            .ok_or_else(|| KittyMCError::InvalidChunk(loc.clone()))?;
-        let mut chunk_lock = chunk.write()
-            .map_err(|_| KittyMCError::LockPoisonError)?;
+    /// Makes every chunk look like it was last tapped `age` earlier than it was
+    #[cfg(test)]
+    pub fn age_accesses(&self, age: std::time::Duration) {
+        for time in self.access_list.write().unwrap().values_mut() {
+            *time -= age;
+        }
+    }
+
+    /// Does what the unloader thread does every once in a while, right now
+    #[cfg(test)]
+    pub fn unload_old(&self) {
+        ChunkUnloader::new(
+            self.loaded_chunks.clone(),
+            self.access_list.clone(),
+            self.storage.clone(),
+        )
+        .save_old();
+    }
+
+    /// Writes every loaded chunk that changed since it was last saved, without unloading it. A
+    /// chunk that fails doesn't stop the others from being written. Returns how many were written.
+    pub fn save_dirty(&self) -> Result<usize, KittyMCError> {
+        let chunks: Vec<(ChunkPosition, SharedChunk)> = self.loaded_chunks
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(pos, chunk)| (pos.clone(), chunk.clone()))
+            .collect();
+
+        let mut saved = 0;
+        let mut failure = None;
+        for (pos, chunk) in &chunks {
+            match save_chunk_if_dirty(&*self.storage, pos, chunk) {
+                Ok(true) => saved += 1,
+                Ok(false) => {}
+                Err(e) => {
+                    error!("Failed to save chunk {}, {}: {e}", pos.chunk_x(), pos.chunk_z());
+                    failure = Some(e);
+                }
+            }
+        }
 
-        let chunk_pos = ChunkPosition::from(loc);
+        match failure {
+            Some(e) => Err(e),
+            None => Ok(saved),
+        }
+    }
 
-        let x = (loc.x - chunk_pos.block_x() as f32).floor() as usize;
-        let y = loc.y.floor() as usize;
-        let z = (loc.z - chunk_pos.block_z() as f32).floor() as usize;
+    pub fn set_block(&mut self, loc: &Location, block_id: BlockStateId) -> Result<(), KittyMCError> {
+        if self.get_chunk_containing_block(loc).is_none() {
+            return Err(KittyMCError::InvalidChunk(*loc));
+        }
 
-        chunk_lock.set_block(x, y, z, block_id)
+        light_engine::set_block(&self.loaded_chunks, loc, block_id)
     }
 }
 
-pub fn make_chunk_file_path(chunk_pos: &ChunkPosition) -> PathBuf {
-    format!("world/{}me{}ow{}.kitty", chunk_pos.chunk_x(), chunk_pos.chunk_y(), chunk_pos.chunk_z()).into()
-}
\ No newline at end of file
+/// Writes the chunk to `storage` if it changed since it was last saved and returns whether it did
+pub fn save_chunk_if_dirty(
+    storage: &dyn ChunkStorage,
+    pos: &ChunkPosition,
+    chunk: &SharedChunk,
+) -> Result<bool, KittyMCError> {
+    let mut chunk = chunk.write().map_err(|_| KittyMCError::LockPoisonError)?;
+    if !chunk.is_dirty() {
+        return Ok(false);
+    }
+
+    storage.save(pos, &chunk)?;
+    chunk.mark_saved();
+    Ok(true)
+}
@@ -0,0 +1,260 @@
+//! Where chunks go when they aren't loaded.
+//!
+//! The chunk manager only talks to a [`ChunkStorage`], so the layout on disk can change without
+//! the generator or the unloader knowing. Worlds from before the region layout keep every chunk
+//! in its own file, and [`migrate_chunks`] moves them over.
+
+use kittymc_lib::error::KittyMCError;
+use kittymc_lib::packets::client::play::chunk_data_20::Chunk;
+use kittymc_lib::subtypes::ChunkPosition;
+use kittymc_lib::utils::write_file_atomically;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+pub type SharedChunkStorage = Arc<dyn ChunkStorage>;
+
+/// Saves and loads chunks by their position. Storages are shared by the generator and unloader
+/// threads, so they have to handle being used from several threads at once.
+pub trait ChunkStorage: Debug + Send + Sync {
+    /// Returns `None` if the chunk was never saved
+    fn load(&self, pos: &ChunkPosition) -> Result<Option<Box<Chunk>>, KittyMCError>;
+
+    /// Replaces what was saved for the chunk, without leaving a half written chunk behind if the
+    /// server crashes while doing it. The chunk has to be on disk once this returns, since
+    /// [`migrate_chunks`] deletes the old copy right after.
+    fn save(&self, pos: &ChunkPosition, chunk: &Chunk) -> Result<(), KittyMCError>;
+
+    /// Forgets the chunk. Removing one that was never saved is fine.
+    fn remove(&self, pos: &ChunkPosition) -> Result<(), KittyMCError>;
+
+    /// Every chunk that was saved
+    fn positions(&self) -> Result<Vec<ChunkPosition>, KittyMCError>;
+}
+
+/// Moves every chunk in `from` to `to` and returns how many there were. Each chunk is only
+/// removed from `from` once `to` has it, so an interrupted migration can just be run again.
+pub fn migrate_chunks(
+    from: &dyn ChunkStorage,
+    to: &dyn ChunkStorage,
+) -> Result<usize, KittyMCError> {
+    let positions = from.positions()?;
+
+    for pos in &positions {
+        if let Some(chunk) = from.load(pos)? {
+            to.save(pos, &chunk)?;
+        }
+        from.remove(pos)?;
+    }
+
+    Ok(positions.len())
+}
+
+/// The old layout, where each chunk is a file called `{x}me{y}ow{z}.kitty`
+#[derive(Debug)]
+pub struct FileChunkStorage {
+    dir: PathBuf,
+}
+
+impl FileChunkStorage {
+    pub fn new(dir: impl Into<PathBuf>) -> FileChunkStorage {
+        FileChunkStorage { dir: dir.into() }
+    }
+
+    pub fn file_path(&self, pos: &ChunkPosition) -> PathBuf {
+        self.dir.join(format!(
+            "{}me{}ow{}.kitty",
+            pos.chunk_x(),
+            pos.chunk_y(),
+            pos.chunk_z()
+        ))
+    }
+
+    fn parse_file_name(name: &str) -> Option<ChunkPosition> {
+        let (x, rest) = name.strip_suffix(".kitty")?.split_once("me")?;
+        let (y, z) = rest.split_once("ow")?;
+
+        Some(ChunkPosition::new(x.parse().ok()?, y.parse().ok()?, z.parse().ok()?))
+    }
+}
+
+impl ChunkStorage for FileChunkStorage {
+    fn load(&self, pos: &ChunkPosition) -> Result<Option<Box<Chunk>>, KittyMCError> {
+        match fs::read(self.file_path(pos)) {
+            Ok(data) => Chunk::from_bytes(&data).map(Some),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn save(&self, pos: &ChunkPosition, chunk: &Chunk) -> Result<(), KittyMCError> {
+        fs::create_dir_all(&self.dir)?;
+        write_file_atomically(&self.file_path(pos), &chunk.to_bytes()?)?;
+
+        Ok(())
+    }
+
+    fn remove(&self, pos: &ChunkPosition) -> Result<(), KittyMCError> {
+        match fs::remove_file(self.file_path(pos)) {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn positions(&self) -> Result<Vec<ChunkPosition>, KittyMCError> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut positions = vec![];
+        for entry in entries {
+            let name = entry?.file_name();
+            if let Some(pos) = name.to_str().and_then(Self::parse_file_name) {
+                positions.push(pos);
+            }
+        }
+
+        Ok(positions)
+    }
+}
+
+/// Keeps the saved chunks in memory, for tests that shouldn't touch the disk
+#[derive(Debug, Default)]
+pub struct MemoryChunkStorage {
+    chunks: Mutex<HashMap<ChunkPosition, Vec<u8>>>,
+}
+
+#[allow(dead_code)]
+impl MemoryChunkStorage {
+    pub fn new() -> MemoryChunkStorage {
+        MemoryChunkStorage::default()
+    }
+}
+
+impl ChunkStorage for MemoryChunkStorage {
+    fn load(&self, pos: &ChunkPosition) -> Result<Option<Box<Chunk>>, KittyMCError> {
+        let chunks = self.chunks.lock().map_err(|_| KittyMCError::LockPoisonError)?;
+        chunks.get(pos).map(|data| Chunk::from_bytes(data)).transpose()
+    }
+
+    fn save(&self, pos: &ChunkPosition, chunk: &Chunk) -> Result<(), KittyMCError> {
+        // Encoded like the other storages do it, so a chunk that can't be saved fails here too
+        let data = chunk.to_bytes()?;
+        self.chunks
+            .lock()
+            .map_err(|_| KittyMCError::LockPoisonError)?
+            .insert(pos.clone(), data);
+
+        Ok(())
+    }
+
+    fn remove(&self, pos: &ChunkPosition) -> Result<(), KittyMCError> {
+        self.chunks
+            .lock()
+            .map_err(|_| KittyMCError::LockPoisonError)?
+            .remove(pos);
+
+        Ok(())
+    }
+
+    fn positions(&self) -> Result<Vec<ChunkPosition>, KittyMCError> {
+        let chunks = self.chunks.lock().map_err(|_| KittyMCError::LockPoisonError)?;
+        Ok(chunks.keys().cloned().collect())
+    }
+}
+
+/// Whether there are any files of the old layout in `dir`
+pub fn has_chunk_files(dir: &Path) -> bool {
+    FileChunkStorage::new(dir)
+        .positions()
+        .is_ok_and(|positions| !positions.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunking::region_storage::RegionChunkStorage;
+    use kittymc_lib::utils::TempDir;
+
+    fn temp_dir() -> TempDir {
+        TempDir::new("kittymc-storage").unwrap()
+    }
+
+    fn test_chunk(block: u32) -> Chunk {
+        let mut chunk = Chunk::default();
+        chunk.set_block(1, 2, 3, block << 4).unwrap();
+        chunk
+    }
+
+    fn check_storage(storage: &dyn ChunkStorage) {
+        let near = ChunkPosition::new(3, 0, -4);
+        let far = ChunkPosition::new(-100, 0, 70);
+
+        assert!(storage.load(&near).unwrap().is_none());
+        assert!(storage.positions().unwrap().is_empty());
+
+        storage.save(&near, &test_chunk(1)).unwrap();
+        storage.save(&far, &test_chunk(2)).unwrap();
+        storage.save(&near, &test_chunk(3)).unwrap();
+
+        let loaded = storage.load(&near).unwrap().unwrap();
+        assert_eq!(*loaded, test_chunk(3));
+        assert!(!loaded.is_dirty());
+        assert_eq!(*storage.load(&far).unwrap().unwrap(), test_chunk(2));
+
+        let mut positions = storage.positions().unwrap();
+        positions.sort_by_key(|pos| pos.chunk_x());
+        assert_eq!(positions, vec![far.clone(), near.clone()]);
+
+        storage.remove(&far).unwrap();
+        storage.remove(&far).unwrap();
+        assert!(storage.load(&far).unwrap().is_none());
+        assert_eq!(storage.positions().unwrap(), vec![near]);
+    }
+
+    #[test]
+    fn test_memory_storage() {
+        check_storage(&MemoryChunkStorage::new());
+    }
+
+    #[test]
+    fn test_file_storage() {
+        let dir = temp_dir();
+        check_storage(&FileChunkStorage::new(dir.path()));
+    }
+
+    #[test]
+    fn test_region_storage() {
+        let dir = temp_dir();
+        check_storage(&RegionChunkStorage::new(dir.path()));
+    }
+
+    #[test]
+    fn test_migration_moves_every_chunk() {
+        let dir = temp_dir();
+        let files = FileChunkStorage::new(dir.path());
+        let regions = RegionChunkStorage::new(dir.join("region"));
+
+        for x in -40isize..40 {
+            let chunk = test_chunk(x.unsigned_abs() as u32);
+            files.save(&ChunkPosition::new(x, 0, x * 3), &chunk).unwrap();
+        }
+        // Anything that isn't a chunk is left alone
+        fs::write(dir.join("level.txt"), "meow").unwrap();
+        assert!(has_chunk_files(dir.path()));
+
+        assert_eq!(migrate_chunks(&files, &regions).unwrap(), 80);
+        assert!(!has_chunk_files(dir.path()));
+        assert!(dir.join("level.txt").exists());
+        assert_eq!(regions.positions().unwrap().len(), 80);
+        for x in -40..40 {
+            let chunk = regions.load(&ChunkPosition::new(x, 0, x * 3)).unwrap().unwrap();
+            assert_eq!(*chunk, test_chunk(x.unsigned_abs() as u32));
+        }
+    }
+}
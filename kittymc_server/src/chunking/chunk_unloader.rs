@@ -1,39 +1,55 @@
 use std::collections::HashMap;
-use std::fs;
 use std::sync::{Arc, RwLock};
 use std::thread::sleep;
 use std::time::Duration;
 use log::error;
 use kittymc_lib::error::KittyMCError;
 use kittymc_lib::subtypes::ChunkPosition;
-use crate::chunking::chunk_manager::{make_chunk_file_path, SharedChunk, SharedChunkAccessList, SharedChunkList};
+use crate::chunking::chunk_manager::{
+    save_chunk_if_dirty, SharedChunk, SharedChunkAccessList, SharedChunkList,
+};
+use crate::chunking::chunk_storage::SharedChunkStorage;
 
-const CHUNK_REMOVE_TIME_S: u64 = 30;
+pub const CHUNK_REMOVE_TIME_S: u64 = 30;
 
 pub struct ChunkUnloader {
     collection: SharedChunkList,
     access_list: SharedChunkAccessList,
+    storage: SharedChunkStorage,
 }
 
 impl ChunkUnloader {
-    pub fn entry_thread(collection: Arc<RwLock<HashMap<ChunkPosition, SharedChunk>>>, access_list: SharedChunkAccessList) {
-        let mut unloader = ChunkUnloader {
-            collection,
-            access_list,
-        };
+    pub fn entry_thread(
+        collection: Arc<RwLock<HashMap<ChunkPosition, SharedChunk>>>,
+        access_list: SharedChunkAccessList,
+        storage: SharedChunkStorage,
+    ) {
+        let mut unloader = ChunkUnloader::new(collection, access_list, storage);
 
-        let _ = fs::create_dir("world");
         unloader.run();
     }
 
+    pub fn new(
+        collection: SharedChunkList,
+        access_list: SharedChunkAccessList,
+        storage: SharedChunkStorage,
+    ) -> ChunkUnloader {
+        ChunkUnloader {
+            collection,
+            access_list,
+            storage,
+        }
+    }
+
     fn run(&mut self) {
         loop {
-            self.save_old();
             sleep(Duration::from_secs(CHUNK_REMOVE_TIME_S));
+            self.save_old();
         }
     }
 
-    fn save_old(&self) {
+    /// Saves and unloads the chunks nobody tapped for a while
+    pub fn save_old(&self) {
         let mut to_save = vec![];
 
         for (chunk_pos, time) in self.access_list.write().unwrap().iter() {
@@ -44,8 +60,10 @@ impl ChunkUnloader {
 
         let mut access_list_lock = self.access_list.write().unwrap();
         for pos in to_save {
-            if let Err(e) = self.save_chunk(&pos) {
-                error!("LOST! Failed to save chunk: {}", e);
+            // Chunks that couldn't be saved stay loaded and are tried again later
+            if let Err(e) = self.unload_chunk(&pos) {
+                error!("Failed to save chunk, keeping it loaded: {}", e);
+                continue;
             }
 
             access_list_lock.remove(&pos);
@@ -53,19 +71,66 @@ impl ChunkUnloader {
         drop(access_list_lock);
     }
 
-    fn save_chunk(&self, chunk_pos: &ChunkPosition) -> Result<(), KittyMCError> {
-        let mut collection_lock = self.collection.write().unwrap();
-        let chunk = collection_lock.remove(chunk_pos);
-        drop(collection_lock);
+    fn unload_chunk(&self, chunk_pos: &ChunkPosition) -> Result<(), KittyMCError> {
+        let chunk = self.collection.read().unwrap().get(chunk_pos).cloned();
 
+        // Looking at a chunk counts as an access even if it was never loaded
         let Some(chunk) = chunk else {
-            error!("Uhm.. chunk was so old it got dementia and forgot it existed.");
-            return Err(KittyMCError::InvalidChunk(chunk_pos.block_location()));
+            return Ok(());
         };
 
-        let chunk = chunk.read().unwrap();
-        chunk.save_to(&make_chunk_file_path(chunk_pos))?;
+        // Saved while it's still loaded, so saving the world never misses a chunk on its way out
+        save_chunk_if_dirty(&*self.storage, chunk_pos, &chunk)?;
+        self.collection.write().unwrap().remove(chunk_pos);
+        // Something could have changed it in the meantime
+        if let Err(e) = save_chunk_if_dirty(&*self.storage, chunk_pos, &chunk) {
+            self.collection.write().unwrap().entry(chunk_pos.clone()).or_insert(chunk);
+            return Err(e);
+        }
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunking::chunk_storage::{ChunkStorage, MemoryChunkStorage};
+    use kittymc_lib::packets::client::play::chunk_data_20::Chunk;
+    use std::time::Instant;
+
+    #[test]
+    fn test_old_chunks_are_forgotten() {
+        let storage = Arc::new(MemoryChunkStorage::new());
+        let unloader = ChunkUnloader {
+            collection: Default::default(),
+            access_list: Default::default(),
+            storage: storage.clone(),
+        };
+        let (loaded, never_loaded, recent) = (
+            ChunkPosition::new(1, 0, 2),
+            ChunkPosition::new(-3, 0, 4),
+            ChunkPosition::new(5, 0, 6),
+        );
+
+        let mut chunk = Box::<Chunk>::default();
+        chunk.set_block(1, 2, 3, 1 << 4).unwrap();
+        unloader
+            .collection
+            .write()
+            .unwrap()
+            .insert(loaded.clone(), Arc::new(RwLock::new(chunk)));
+        let long_ago = Instant::now() - Duration::from_secs(CHUNK_REMOVE_TIME_S + 1);
+        let mut access_list = unloader.access_list.write().unwrap();
+        access_list.insert(loaded.clone(), long_ago);
+        access_list.insert(never_loaded.clone(), long_ago);
+        access_list.insert(recent.clone(), Instant::now());
+        drop(access_list);
+
+        unloader.save_old();
+
+        let access_list = unloader.access_list.read().unwrap();
+        assert_eq!(access_list.keys().collect::<Vec<_>>(), vec![&recent]);
+        assert!(unloader.collection.read().unwrap().is_empty());
+        assert!(storage.load(&loaded).unwrap().is_some());
+    }
+}
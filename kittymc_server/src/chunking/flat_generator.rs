@@ -0,0 +1,281 @@
+//! Superflat worlds, configured with the same preset strings as vanilla's "Customize" screen.
+//!
+//! A preset looks like `3;minecraft:bedrock,2*minecraft:dirt,minecraft:grass;1;village`: the
+//! format version, the layers from the bottom up, the biome id and the decorations. Presets from
+//! before version 3 count layers with `x` instead of `*` and use numeric block ids. A preset with
+//! just the layers is taken as version 0.
+//!
+//! Decorations are parsed and kept so the preset survives being saved, but kittymc doesn't place
+//! any of them yet.
+
+use crate::chunking::blocks::parse_block;
+use crate::chunking::world_generator::WorldGenerator;
+use kittymc_lib::error::KittyMCError;
+use kittymc_lib::packets::client::play::chunk_data_20::{BlockStateId, Chunk};
+use kittymc_lib::packets::client::play::LevelType;
+use kittymc_lib::subtypes::ChunkPosition;
+
+/// What vanilla uses when the preset is left empty
+pub const DEFAULT_FLAT_PRESET: &str =
+    "3;minecraft:bedrock,2*minecraft:dirt,minecraft:grass;1;village";
+
+const PRESET_VERSION: u32 = 3;
+const WORLD_HEIGHT: usize = 256;
+const PLAINS_BIOME: u8 = 1;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decoration {
+    pub name: String,
+    /// Like `size` and `distance` in `village(size=1 distance=32)`
+    pub options: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlatPreset {
+    /// One block for every y, from the bottom up
+    pub layers: Vec<BlockStateId>,
+    pub biome: u8,
+    pub decorations: Vec<Decoration>,
+}
+
+fn invalid(reason: impl Into<String>) -> KittyMCError {
+    KittyMCError::InvalidFlatPreset(reason.into())
+}
+
+impl FlatPreset {
+    pub fn parse(preset: &str) -> Result<FlatPreset, KittyMCError> {
+        let parts: Vec<&str> = preset.trim().split(';').collect();
+        let (version, layers, biome, decorations) = match parts.as_slice() {
+            [layers] => (0, *layers, None, None),
+            [version, layers, rest @ ..] if rest.len() <= 2 => {
+                let version: u32 = version
+                    .parse()
+                    .map_err(|_| invalid(format!("{version:?} isn't a version")))?;
+                (version, *layers, rest.first().copied(), rest.get(1).copied())
+            }
+            _ => return Err(invalid("there are too many parts")),
+        };
+        if version > PRESET_VERSION {
+            return Err(invalid(format!("version {version} is newer than 1.12.2")));
+        }
+
+        let biome = match biome {
+            Some(biome) => biome
+                .parse()
+                .map_err(|_| invalid(format!("{biome:?} isn't a biome id")))?,
+            None => PLAINS_BIOME,
+        };
+
+        Ok(FlatPreset {
+            layers: Self::parse_layers(version, layers)?,
+            biome,
+            decorations: decorations.map_or(Ok(vec![]), Self::parse_decorations)?,
+        })
+    }
+
+    fn parse_layers(version: u32, layers: &str) -> Result<Vec<BlockStateId>, KittyMCError> {
+        let count_separator = match version {
+            PRESET_VERSION.. => '*',
+            _ => 'x',
+        };
+
+        let mut blocks = vec![];
+        for layer in layers.split(',').filter(|layer| !layer.is_empty()) {
+            let (count, block) = match layer.split_once(count_separator) {
+                Some((count, block)) => {
+                    let count: usize = count
+                        .parse()
+                        .ok()
+                        .filter(|count| *count > 0)
+                        .ok_or_else(|| invalid(format!("{count:?} isn't a layer count")))?;
+                    (count, block)
+                }
+                None => (1, layer),
+            };
+            let block =
+                parse_block(block).ok_or_else(|| invalid(format!("{block:?} isn't a block")))?;
+
+            if count > WORLD_HEIGHT - blocks.len() {
+                return Err(invalid(format!("the layers are higher than {WORLD_HEIGHT} blocks")));
+            }
+            blocks.extend(std::iter::repeat_n(block, count));
+        }
+
+        Ok(blocks)
+    }
+
+    fn parse_decorations(decorations: &str) -> Result<Vec<Decoration>, KittyMCError> {
+        decorations
+            .split(',')
+            .filter(|decoration| !decoration.is_empty())
+            .map(|decoration| {
+                let Some((name, options)) = decoration.split_once('(') else {
+                    return Ok(Decoration {
+                        name: decoration.to_string(),
+                        options: vec![],
+                    });
+                };
+
+                let options = options
+                    .strip_suffix(')')
+                    .ok_or_else(|| invalid(format!("{decoration:?} isn't closed")))?
+                    .split_whitespace()
+                    .map(|option| {
+                        option
+                            .split_once('=')
+                            .map(|(key, value)| (key.to_string(), value.to_string()))
+                            .ok_or_else(|| invalid(format!("{option:?} has no value")))
+                    })
+                    .collect::<Result<_, _>>()?;
+
+                Ok(Decoration {
+                    name: name.to_string(),
+                    options,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Gives every chunk the layers of a preset
+#[derive(Debug)]
+pub struct FlatGenerator {
+    preset: FlatPreset,
+    chunk: Box<Chunk>,
+}
+
+#[allow(dead_code)]
+impl FlatGenerator {
+    pub fn new(preset: FlatPreset) -> FlatGenerator {
+        let mut chunk = Box::<Chunk>::default();
+        for (y, block) in preset.layers.iter().enumerate() {
+            if *block == 0 {
+                continue;
+            }
+
+            for z in 0..16 {
+                for x in 0..16 {
+                    // Parsing made sure there are no more layers than the world is high
+                    chunk.set_block(x, y, z, *block).unwrap();
+                }
+            }
+        }
+        chunk.biomes.fill(preset.biome);
+
+        FlatGenerator { preset, chunk }
+    }
+
+    /// Takes the preset from the generator options of a world, which are empty for the default
+    pub fn from_options(options: &str) -> Result<FlatGenerator, KittyMCError> {
+        let preset = match options.trim() {
+            "" => DEFAULT_FLAT_PRESET,
+            preset => preset,
+        };
+
+        Ok(FlatGenerator::new(FlatPreset::parse(preset)?))
+    }
+
+    pub fn preset(&self) -> &FlatPreset {
+        &self.preset
+    }
+}
+
+impl WorldGenerator for FlatGenerator {
+    fn generate(&self, _pos: &ChunkPosition) -> Box<Chunk> {
+        self.chunk.clone()
+    }
+
+    fn level_type(&self) -> LevelType {
+        LevelType::Flat
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BEDROCK: BlockStateId = 7 << 4;
+    const DIRT: BlockStateId = 3 << 4;
+    const GRASS: BlockStateId = 2 << 4;
+
+    #[test]
+    fn test_default_preset() {
+        let preset = FlatPreset::parse(DEFAULT_FLAT_PRESET).unwrap();
+        assert_eq!(preset.layers, vec![BEDROCK, DIRT, DIRT, GRASS]);
+        assert_eq!(preset.biome, 1);
+        assert_eq!(
+            preset.decorations,
+            vec![Decoration {
+                name: "village".to_string(),
+                options: vec![],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_presets_of_every_version() {
+        let preset = FlatPreset::parse(
+            "3;minecraft:bedrock,3*stone,minecraft:wool:14;4;village(size=1 distance=32),lake",
+        )
+        .unwrap();
+        assert_eq!(preset.layers, vec![BEDROCK, 1 << 4, 1 << 4, 1 << 4, 35 << 4 | 14]);
+        assert_eq!(preset.biome, 4);
+        assert_eq!(preset.decorations[0].name, "village");
+        assert_eq!(
+            preset.decorations[0].options,
+            vec![
+                ("size".to_string(), "1".to_string()),
+                ("distance".to_string(), "32".to_string())
+            ]
+        );
+        assert_eq!(preset.decorations[1].name, "lake");
+
+        let preset = FlatPreset::parse("2;7,2x3,2;1").unwrap();
+        assert_eq!(preset.layers, vec![BEDROCK, DIRT, DIRT, GRASS]);
+        assert!(preset.decorations.is_empty());
+
+        let preset = FlatPreset::parse("7,2x3:1").unwrap();
+        assert_eq!(preset.layers, vec![BEDROCK, DIRT | 1, DIRT | 1]);
+        assert_eq!(preset.biome, 1);
+    }
+
+    #[test]
+    fn test_invalid_presets() {
+        for preset in [
+            "4;minecraft:bedrock;1",
+            "3;minecraft:kitty;1",
+            "3;0*minecraft:stone;1",
+            "3;257*minecraft:stone;1",
+            "3;200*minecraft:stone,100*minecraft:dirt;1",
+            "3;minecraft:bedrock,18446744073709551615*minecraft:stone;1",
+            "3;minecraft:stone;plains",
+            "3;minecraft:stone;1;village(size)",
+            "3;minecraft:stone;1;village(size=1",
+            "3;minecraft:stone;1;village;meow",
+        ] {
+            assert!(
+                matches!(FlatPreset::parse(preset), Err(KittyMCError::InvalidFlatPreset(_))),
+                "{preset} was accepted"
+            );
+        }
+    }
+
+    #[test]
+    fn test_chunks_have_the_layers() {
+        let generator = FlatGenerator::from_options("3;minecraft:bedrock,minecraft:air,2*dirt;6")
+            .unwrap();
+        let chunk = generator.generate(&ChunkPosition::new(-5, 0, 12));
+
+        for (x, z) in [(0, 0), (15, 3), (8, 15)] {
+            assert_eq!(chunk.get_block(x, 0, z), Some(BEDROCK));
+            assert_eq!(chunk.get_block(x, 1, z), Some(0));
+            assert_eq!(chunk.get_block(x, 3, z), Some(DIRT));
+            assert_eq!(chunk.get_block(x, 4, z), Some(0));
+        }
+        assert!(chunk.biomes.iter().all(|biome| *biome == 6));
+        assert_eq!(generator.level_type(), LevelType::Flat);
+
+        let default = FlatGenerator::from_options("").unwrap();
+        assert_eq!(default.preset(), &FlatPreset::parse(DEFAULT_FLAT_PRESET).unwrap());
+    }
+}
@@ -0,0 +1,634 @@
+//! Block and sky light.
+//!
+//! Sky light comes down from the top of every column without getting weaker until it hits a
+//! block that isn't fully transparent, and spreads sideways from there. Block light spreads out
+//! from emissive blocks. Every other step costs at least one level, so light never travels
+//! further than 15 blocks. Anything that happens in a chunk can only change the light of its
+//! direct neighbours, which is why every update works on the 3x3 chunks around one.
+//!
+//! Chunks get lit on their own when they're loaded and then stitched to the neighbours that are
+//! already loaded. Light is never saved, it's worked out again every time a chunk is loaded.
+
+use crate::chunking::chunk_manager::{SharedChunk, SharedChunkList};
+use kittymc_lib::error::KittyMCError;
+use kittymc_lib::packets::client::play::chunk_data_20::{BlockStateId, Chunk, MAX_LIGHT_LEVEL};
+use kittymc_lib::subtypes::{ChunkPosition, Location};
+use std::collections::VecDeque;
+
+const CHUNK_WIDTH: isize = 16;
+const WORLD_HEIGHT: usize = 256;
+
+const DOWN: usize = 0;
+const DIRECTIONS: [(isize, isize, isize); 6] = [
+    (0, -1, 0),
+    (0, 1, 0),
+    (-1, 0, 0),
+    (1, 0, 0),
+    (0, 0, -1),
+    (0, 0, 1),
+];
+
+/// A block in world coordinates
+type BlockPos = (isize, usize, isize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LightKind {
+    Block,
+    Sky,
+}
+
+/// The light level a block gives off
+pub fn emitted_light(state: BlockStateId) -> u8 {
+    match state >> 4 {
+        10 | 11 => 15, // lava
+        39 => 1,       // brown mushroom
+        50 => 14,      // torch
+        51 => 15,      // fire
+        62 => 13,      // lit furnace
+        74 => 9,       // lit redstone ore
+        76 => 7,       // redstone torch
+        89 => 15,      // glowstone
+        90 => 11,      // nether portal
+        91 => 15,      // jack o'lantern
+        94 => 9,       // powered repeater
+        117 => 1,      // brewing stand
+        119 => 15,     // end portal
+        120 => 1,      // end portal frame
+        122 => 1,      // dragon egg
+        124 => 15,     // lit redstone lamp
+        130 => 7,      // ender chest
+        138 => 15,     // beacon
+        150 => 9,      // powered comparator
+        169 => 15,     // sea lantern
+        198 => 14,     // end rod
+        209 => 15,     // end gateway
+        213 => 3,      // magma block
+        _ => 0,
+    }
+}
+
+/// How many levels light loses going into a block, on top of the one level every step costs.
+/// Light doesn't get into blocks with an opacity of [`MAX_LIGHT_LEVEL`] at all.
+pub fn light_opacity(state: BlockStateId) -> u8 {
+    match state >> 4 {
+        8 | 9 | 79 | 212 => 3, // water and ice
+        18 | 30 | 161 => 1,    // leaves and cobwebs
+        0
+        | 6
+        | 10
+        | 11
+        | 20
+        | 26
+        | 27
+        | 28
+        | 31
+        | 32
+        | 37..=40
+        | 50..=52
+        | 54
+        | 55
+        | 59
+        | 63
+        | 64
+        | 65
+        | 66
+        | 68..=72
+        | 75..=78
+        | 81
+        | 83
+        | 85
+        | 90
+        | 92..=96
+        | 101
+        | 102
+        | 104..=107
+        | 111
+        | 113
+        | 115..=120
+        | 122
+        | 127
+        | 130..=132
+        | 138..=146
+        | 148..=151
+        | 154
+        | 157
+        | 160
+        | 165..=167
+        | 171
+        | 175..=178
+        | 183..=186
+        | 188..=200
+        | 207
+        | 209
+        | 217
+        | 219..=234 => 0,
+        _ => MAX_LIGHT_LEVEL,
+    }
+}
+
+/// What light of `level` turns into after stepping into a block
+fn reach(kind: LightKind, level: u8, down: bool, opacity: u8) -> u8 {
+    if kind == LightKind::Sky && down && level == MAX_LIGHT_LEVEL && opacity == 0 {
+        return MAX_LIGHT_LEVEL;
+    }
+    level.saturating_sub(opacity.max(1))
+}
+
+fn step(pos: BlockPos, direction: (isize, isize, isize)) -> Option<BlockPos> {
+    let y = pos.1.checked_add_signed(direction.1)?;
+    (y < WORLD_HEIGHT).then_some((pos.0 + direction.0, y, pos.2 + direction.2))
+}
+
+/// The chunks around a center chunk that are loaded
+struct LightArea<'a> {
+    center_x: isize,
+    center_z: isize,
+    chunks: [Option<&'a mut Chunk>; 9],
+}
+
+impl<'a> LightArea<'a> {
+    fn new(center: &ChunkPosition) -> Self {
+        LightArea {
+            center_x: center.chunk_x(),
+            center_z: center.chunk_z(),
+            chunks: Default::default(),
+        }
+    }
+
+    fn insert(&mut self, pos: &ChunkPosition, chunk: &'a mut Chunk) {
+        let dx = pos.chunk_x() - self.center_x;
+        let dz = pos.chunk_z() - self.center_z;
+        if dx.abs() <= 1 && dz.abs() <= 1 {
+            self.chunks[((dz + 1) * 3 + dx + 1) as usize] = Some(chunk);
+        }
+    }
+
+    /// Finds the chunk a block is in and where in the chunk it is
+    fn locate(&self, pos: BlockPos) -> Option<(usize, usize, usize)> {
+        let dx = pos.0.div_euclid(CHUNK_WIDTH) - self.center_x;
+        let dz = pos.2.div_euclid(CHUNK_WIDTH) - self.center_z;
+        if dx.abs() > 1 || dz.abs() > 1 {
+            return None;
+        }
+
+        let slot = ((dz + 1) * 3 + dx + 1) as usize;
+        self.chunks[slot].as_ref()?;
+
+        Some((
+            slot,
+            pos.0.rem_euclid(CHUNK_WIDTH) as usize,
+            pos.2.rem_euclid(CHUNK_WIDTH) as usize,
+        ))
+    }
+
+    fn chunk(&self, slot: usize) -> &Chunk {
+        self.chunks[slot].as_deref().unwrap()
+    }
+
+    fn chunk_mut(&mut self, slot: usize) -> &mut Chunk {
+        self.chunks[slot].as_deref_mut().unwrap()
+    }
+
+    fn center_origin(&self) -> (isize, isize) {
+        (self.center_x * CHUNK_WIDTH, self.center_z * CHUNK_WIDTH)
+    }
+
+    fn block(&self, pos: BlockPos) -> Option<BlockStateId> {
+        let (slot, x, z) = self.locate(pos)?;
+        self.chunk(slot).get_block(x, pos.1, z)
+    }
+
+    fn light(&self, kind: LightKind, pos: BlockPos) -> u8 {
+        let Some((slot, x, z)) = self.locate(pos) else {
+            return 0;
+        };
+        let chunk = self.chunk(slot);
+        match kind {
+            LightKind::Block => chunk.block_light(x, pos.1, z),
+            LightKind::Sky => chunk.sky_light(x, pos.1, z),
+        }
+        .unwrap_or(0)
+    }
+
+    fn set_light(&mut self, kind: LightKind, pos: BlockPos, level: u8) {
+        let Some((slot, x, z)) = self.locate(pos) else {
+            return;
+        };
+        let chunk = self.chunk_mut(slot);
+        // Can't fail, the block was just located
+        let _ = match kind {
+            LightKind::Block => chunk.set_block_light(x, pos.1, z, level),
+            LightKind::Sky => chunk.set_sky_light(x, pos.1, z, level),
+        };
+    }
+
+    /// The light a block has no matter what's around it
+    fn source_light(&self, kind: LightKind, pos: BlockPos) -> u8 {
+        let Some(state) = self.block(pos) else {
+            return 0;
+        };
+        match kind {
+            LightKind::Block => emitted_light(state),
+            LightKind::Sky if pos.1 == WORLD_HEIGHT - 1 => {
+                reach(kind, MAX_LIGHT_LEVEL, true, light_opacity(state))
+            }
+            LightKind::Sky => 0,
+        }
+    }
+
+    /// Lights the center chunk as if it had no neighbours
+    fn light_center(&mut self) {
+        let (origin_x, origin_z) = self.center_origin();
+        let mut sky = VecDeque::new();
+        let mut block = VecDeque::new();
+
+        for x in origin_x..origin_x + CHUNK_WIDTH {
+            for z in origin_z..origin_z + CHUNK_WIDTH {
+                let mut level = MAX_LIGHT_LEVEL;
+                for y in (0..WORLD_HEIGHT).rev() {
+                    let pos = (x, y, z);
+                    let state = self.block(pos).unwrap_or(0);
+
+                    level = reach(LightKind::Sky, level, true, light_opacity(state));
+                    self.set_light(LightKind::Sky, pos, level);
+
+                    let emitted = emitted_light(state);
+                    self.set_light(LightKind::Block, pos, emitted);
+                    if emitted > 1 {
+                        block.push_back(pos);
+                    }
+                }
+            }
+        }
+
+        // Columns are lit from above already, only light going sideways is missing
+        for x in origin_x..origin_x + CHUNK_WIDTH {
+            for z in origin_z..origin_z + CHUNK_WIDTH {
+                for y in 0..WORLD_HEIGHT {
+                    let pos = (x, y, z);
+                    let level = self.light(LightKind::Sky, pos);
+                    if level <= 1 {
+                        continue;
+                    }
+
+                    let darker_side = DIRECTIONS[2..].iter().any(|&direction| {
+                        let side = (pos.0 + direction.0, y, pos.2 + direction.2);
+                        self.block(side).is_some() && self.light(LightKind::Sky, side) < level - 1
+                    });
+                    if darker_side {
+                        sky.push_back(pos);
+                    }
+                }
+            }
+        }
+
+        self.spread(LightKind::Sky, sky);
+        self.spread(LightKind::Block, block);
+    }
+
+    /// Lets light flow between the center chunk and its neighbours in both directions
+    fn stitch_center(&mut self) {
+        let (origin_x, origin_z) = self.center_origin();
+        let mut border = vec![];
+        for i in 0..CHUNK_WIDTH {
+            for (inside, outside) in [
+                ((origin_x, origin_z + i), (origin_x - 1, origin_z + i)),
+                (
+                    (origin_x + CHUNK_WIDTH - 1, origin_z + i),
+                    (origin_x + CHUNK_WIDTH, origin_z + i),
+                ),
+                ((origin_x + i, origin_z), (origin_x + i, origin_z - 1)),
+                (
+                    (origin_x + i, origin_z + CHUNK_WIDTH - 1),
+                    (origin_x + i, origin_z + CHUNK_WIDTH),
+                ),
+            ] {
+                if self.block((outside.0, 0, outside.1)).is_some() {
+                    border.push(inside);
+                    border.push(outside);
+                }
+            }
+        }
+
+        for kind in [LightKind::Sky, LightKind::Block] {
+            let mut queue = VecDeque::new();
+            for &(x, z) in &border {
+                for y in 0..WORLD_HEIGHT {
+                    if self.light(kind, (x, y, z)) > 1 {
+                        queue.push_back((x, y, z));
+                    }
+                }
+            }
+            self.spread(kind, queue);
+        }
+    }
+
+    /// Changes a block and fixes up the light around it
+    fn set_block(&mut self, pos: BlockPos, state: BlockStateId) -> Result<(), KittyMCError> {
+        let Some((slot, x, z)) = self.locate(pos) else {
+            return Err(KittyMCError::InvalidBlock(Location::new(
+                pos.0 as f32,
+                pos.1 as f32,
+                pos.2 as f32,
+            )));
+        };
+        self.chunk_mut(slot).set_block(x, pos.1, z, state)?;
+
+        for kind in [LightKind::Sky, LightKind::Block] {
+            let mut queue = VecDeque::new();
+
+            let old_level = self.light(kind, pos);
+            self.set_light(kind, pos, 0);
+            self.darken(kind, pos, old_level, &mut queue);
+
+            let source = self.source_light(kind, pos);
+            if source > 0 {
+                self.set_light(kind, pos, source);
+                queue.push_back(pos);
+            }
+
+            // The block might let light through that it used to stop
+            for direction in DIRECTIONS {
+                if let Some(neighbour) = step(pos, direction) {
+                    if self.light(kind, neighbour) > 1 {
+                        queue.push_back(neighbour);
+                    }
+                }
+            }
+
+            self.spread(kind, queue);
+        }
+
+        Ok(())
+    }
+
+    /// Spreads light out from the blocks in `queue` to everything it can make brighter
+    fn spread(&mut self, kind: LightKind, mut queue: VecDeque<BlockPos>) {
+        while let Some(pos) = queue.pop_front() {
+            let level = self.light(kind, pos);
+            if level <= 1 {
+                continue;
+            }
+
+            for (i, &direction) in DIRECTIONS.iter().enumerate() {
+                let Some(next) = step(pos, direction) else {
+                    continue;
+                };
+                let Some(state) = self.block(next) else {
+                    continue;
+                };
+
+                let reached = reach(kind, level, i == DOWN, light_opacity(state));
+                if reached > self.light(kind, next) {
+                    self.set_light(kind, next, reached);
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+
+    /// Removes the light that came from `pos`, which had `level` before it went dark. Blocks that
+    /// are lit from elsewhere end up in `relight`, to spread their light back into the gap.
+    fn darken(
+        &mut self,
+        kind: LightKind,
+        pos: BlockPos,
+        level: u8,
+        relight: &mut VecDeque<BlockPos>,
+    ) {
+        let mut queue = VecDeque::from([(pos, level)]);
+
+        while let Some((pos, level)) = queue.pop_front() {
+            for (i, &direction) in DIRECTIONS.iter().enumerate() {
+                let Some(next) = step(pos, direction) else {
+                    continue;
+                };
+                let next_level = self.light(kind, next);
+                if next_level == 0 {
+                    continue;
+                }
+
+                // Full sky light going straight down doesn't get weaker
+                let lit_by_pos = next_level < level
+                    || (kind == LightKind::Sky
+                        && i == DOWN
+                        && level == MAX_LIGHT_LEVEL
+                        && next_level == MAX_LIGHT_LEVEL);
+
+                if lit_by_pos {
+                    self.set_light(kind, next, 0);
+                    let source = self.source_light(kind, next);
+                    if source > 0 {
+                        self.set_light(kind, next, source);
+                        relight.push_back(next);
+                    }
+                    queue.push_back((next, next_level));
+                } else {
+                    relight.push_back(next);
+                }
+            }
+        }
+    }
+}
+
+/// Lights a chunk that was just loaded or generated, without looking at its neighbours
+pub fn light_chunk(chunk: &mut Chunk) {
+    let pos = ChunkPosition::new(0, 0, 0);
+    let mut area = LightArea::new(&pos);
+    area.insert(&pos, chunk);
+    area.light_center();
+}
+
+/// Locks the loaded chunks around `center` for writing. They're always locked in the same order,
+/// so two threads working on overlapping areas can't end up waiting on each other.
+fn lock_area(
+    chunks: &SharedChunkList,
+    center: &ChunkPosition,
+) -> Result<Vec<(ChunkPosition, SharedChunk)>, KittyMCError> {
+    let loaded = chunks.read().map_err(|_| KittyMCError::LockPoisonError)?;
+
+    let mut area = vec![];
+    for dz in -1..=1 {
+        for dx in -1..=1 {
+            let pos = ChunkPosition::new(center.chunk_x() + dx, 0, center.chunk_z() + dz);
+            if let Some(chunk) = loaded.get(&pos) {
+                area.push((pos, chunk.clone()));
+            }
+        }
+    }
+    area.sort_by_key(|(pos, _)| (pos.chunk_x(), pos.chunk_z()));
+
+    Ok(area)
+}
+
+/// Runs `f` on the chunks around `center` while they're locked
+fn with_area<R>(
+    chunks: &SharedChunkList,
+    center: &ChunkPosition,
+    f: impl FnOnce(&mut LightArea) -> Result<R, KittyMCError>,
+) -> Result<R, KittyMCError> {
+    let shared = lock_area(chunks, center)?;
+    let mut guards = vec![];
+    for (pos, chunk) in &shared {
+        guards.push((
+            pos,
+            chunk.write().map_err(|_| KittyMCError::LockPoisonError)?,
+        ));
+    }
+
+    let mut area = LightArea::new(center);
+    for (pos, guard) in &mut guards {
+        area.insert(pos, guard);
+    }
+
+    f(&mut area)
+}
+
+/// Lets light flow between a chunk that was just added to `chunks` and its loaded neighbours
+pub fn stitch_chunk(chunks: &SharedChunkList, pos: &ChunkPosition) -> Result<(), KittyMCError> {
+    with_area(chunks, pos, |area| {
+        area.stitch_center();
+        Ok(())
+    })
+}
+
+/// Changes the block at `loc` and updates the light of the chunks around it
+pub fn set_block(
+    chunks: &SharedChunkList,
+    loc: &Location,
+    state: BlockStateId,
+) -> Result<(), KittyMCError> {
+    let mut chunk_pos = ChunkPosition::from(loc);
+    chunk_pos.set_chunk_y(0);
+
+    let pos = (
+        loc.x.floor() as isize,
+        loc.y.floor() as usize,
+        loc.z.floor() as isize,
+    );
+    if loc.y < 0. || pos.1 >= WORLD_HEIGHT {
+        return Err(KittyMCError::InvalidBlock(*loc));
+    }
+
+    with_area(chunks, &chunk_pos, |area| area.set_block(pos, state))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::{Arc, RwLock};
+
+    const STONE: BlockStateId = 1 << 4;
+    const GLOWSTONE: BlockStateId = 89 << 4;
+    const TORCH: BlockStateId = 50 << 4;
+
+    fn floor_chunk(height: usize) -> Box<Chunk> {
+        let mut chunk = Box::<Chunk>::default();
+        for x in 0..16 {
+            for z in 0..16 {
+                for y in 0..height {
+                    chunk.set_block(x, y, z, STONE).unwrap();
+                }
+            }
+        }
+        light_chunk(&mut chunk);
+        chunk
+    }
+
+    fn add_chunk(chunks: &SharedChunkList, x: isize, z: isize, chunk: Box<Chunk>) {
+        let pos = ChunkPosition::new(x, 0, z);
+        chunks
+            .write()
+            .unwrap()
+            .insert(pos.clone(), Arc::new(RwLock::new(chunk)));
+        stitch_chunk(chunks, &pos).unwrap();
+    }
+
+    fn block_light(chunks: &SharedChunkList, x: isize, y: usize, z: isize) -> u8 {
+        let pos = ChunkPosition::new(x.div_euclid(16), 0, z.div_euclid(16));
+        let chunk = chunks.read().unwrap()[&pos].clone();
+        let chunk = chunk.read().unwrap();
+        chunk
+            .block_light(x.rem_euclid(16) as usize, y, z.rem_euclid(16) as usize)
+            .unwrap()
+    }
+
+    fn sky_light(chunks: &SharedChunkList, x: isize, y: usize, z: isize) -> u8 {
+        let pos = ChunkPosition::new(x.div_euclid(16), 0, z.div_euclid(16));
+        let chunk = chunks.read().unwrap()[&pos].clone();
+        let chunk = chunk.read().unwrap();
+        chunk
+            .sky_light(x.rem_euclid(16) as usize, y, z.rem_euclid(16) as usize)
+            .unwrap()
+    }
+
+    #[test]
+    fn sky_light_stops_at_the_ground() {
+        let chunk = floor_chunk(5);
+
+        assert_eq!(chunk.sky_light(3, 255, 3), Some(15));
+        assert_eq!(chunk.sky_light(3, 5, 3), Some(15));
+        assert_eq!(chunk.sky_light(3, 4, 3), Some(0));
+        assert_eq!(chunk.block_light(3, 5, 3), Some(0));
+    }
+
+    #[test]
+    fn sky_light_spreads_under_a_roof() {
+        let mut chunk = floor_chunk(5);
+        for x in 0..16 {
+            for z in 0..8 {
+                chunk.set_block(x, 10, z, STONE).unwrap();
+            }
+        }
+        light_chunk(&mut chunk);
+
+        assert_eq!(chunk.sky_light(4, 9, 8), Some(15));
+        assert_eq!(chunk.sky_light(4, 9, 7), Some(14));
+        assert_eq!(chunk.sky_light(4, 9, 0), Some(7));
+        assert_eq!(chunk.sky_light(4, 10, 0), Some(0));
+    }
+
+    #[test]
+    fn block_light_spreads_across_chunk_borders() {
+        let chunks: SharedChunkList = Arc::new(RwLock::new(HashMap::new()));
+        let mut lit = floor_chunk(5);
+        lit.set_block(15, 5, 0, GLOWSTONE).unwrap();
+        light_chunk(&mut lit);
+
+        add_chunk(&chunks, 0, 0, lit);
+        add_chunk(&chunks, 1, 0, floor_chunk(5));
+        add_chunk(&chunks, 1, -1, floor_chunk(5));
+
+        assert_eq!(block_light(&chunks, 15, 5, 0), 15);
+        assert_eq!(block_light(&chunks, 16, 5, 0), 14);
+        assert_eq!(block_light(&chunks, 20, 5, 0), 10);
+        // Around the corner through the side neighbour
+        assert_eq!(block_light(&chunks, 16, 5, -1), 13);
+        assert_eq!(block_light(&chunks, 15, 4, 0), 0);
+    }
+
+    #[test]
+    fn set_block_updates_light_incrementally() {
+        let chunks: SharedChunkList = Arc::new(RwLock::new(HashMap::new()));
+        add_chunk(&chunks, 0, 0, floor_chunk(5));
+        add_chunk(&chunks, -1, 0, floor_chunk(5));
+
+        set_block(&chunks, &Location::new(0., 5., 8.), TORCH).unwrap();
+        assert_eq!(block_light(&chunks, 0, 5, 8), 14);
+        assert_eq!(block_light(&chunks, -3, 5, 8), 11);
+
+        set_block(&chunks, &Location::new(0., 5., 8.), 0).unwrap();
+        assert_eq!(block_light(&chunks, 0, 5, 8), 0);
+        assert_eq!(block_light(&chunks, -3, 5, 8), 0);
+
+        set_block(&chunks, &Location::new(-4., 20., 8.), STONE).unwrap();
+        assert_eq!(sky_light(&chunks, -4, 19, 8), 14);
+        assert_eq!(sky_light(&chunks, -4, 5, 8), 14);
+        assert_eq!(sky_light(&chunks, -3, 19, 8), 15);
+
+        set_block(&chunks, &Location::new(-4., 20., 8.), 0).unwrap();
+        assert_eq!(sky_light(&chunks, -4, 19, 8), 15);
+        assert_eq!(sky_light(&chunks, -4, 5, 8), 15);
+    }
+}
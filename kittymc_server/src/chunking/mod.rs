@@ -1,4 +1,14 @@
 pub mod chunk_manager;
 pub mod chunk_generator;
 pub mod chunk_unloader;
+pub mod chunk_storage;
+pub mod region_file;
+pub mod region_storage;
+pub mod anvil;
+pub mod anvil_export;
+pub mod light_engine;
+pub mod blocks;
+pub mod world_generator;
+pub mod noise_generator;
+pub mod flat_generator;
 mod increasing_ticker;
\ No newline at end of file
@@ -0,0 +1,261 @@
+//! An overworld of rolling hills and oceans.
+//!
+//! The height of every column comes from a few octaves of Perlin noise. Columns are filled with
+//! stone, a few layers of dirt and grass on top, or sand where the ground is under water. Anything
+//! below the sea level that isn't ground is water, and the bottom of the world is bedrock.
+//!
+//! Everything random is derived from the seed with a hash of its own, so a seed gives the same
+//! world no matter which order chunks are generated in or which version of `rand` is used.
+
+use crate::chunking::world_generator::WorldGenerator;
+use kittymc_lib::packets::client::play::chunk_data_20::{BlockStateId, Chunk};
+use kittymc_lib::subtypes::ChunkPosition;
+
+pub const SEA_LEVEL: usize = 62;
+
+const BASE_HEIGHT: f64 = 64.0;
+/// How far the ground goes above and below the base height at most
+const HEIGHT_VARIATION: f64 = 40.0;
+/// How many blocks one unit of the lowest octave spans
+const HORIZONTAL_SCALE: f64 = 160.0;
+const OCTAVES: usize = 4;
+const MAX_HEIGHT: usize = 255;
+const DIRT_DEPTH: usize = 3;
+/// Bedrock gets more sparse towards the top of these layers
+const BEDROCK_LAYERS: u64 = 5;
+
+const STONE: BlockStateId = 1 << 4;
+const GRASS: BlockStateId = 2 << 4;
+const DIRT: BlockStateId = 3 << 4;
+const BEDROCK: BlockStateId = 7 << 4;
+const WATER: BlockStateId = 9 << 4;
+const SAND: BlockStateId = 12 << 4;
+
+const OCEAN_BIOME: u8 = 0;
+const PLAINS_BIOME: u8 = 1;
+
+/// SplitMix64, which turns similar inputs into unrelated outputs
+fn mix(mut value: u64) -> u64 {
+    value = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    value ^ (value >> 31)
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+fn gradient(hash: u8, x: f64, z: f64) -> f64 {
+    match hash & 7 {
+        0 => x + z,
+        1 => -x + z,
+        2 => x - z,
+        3 => -x - z,
+        4 => x,
+        5 => -x,
+        6 => z,
+        _ => -z,
+    }
+}
+
+/// Two dimensional Perlin noise with a permutation shuffled by the seed
+#[derive(Debug)]
+struct PerlinNoise {
+    permutation: [u8; 512],
+}
+
+impl PerlinNoise {
+    fn new(seed: u64) -> PerlinNoise {
+        let mut shuffled: Vec<u8> = (0..=255).collect();
+        let mut state = seed;
+        for i in (1..shuffled.len()).rev() {
+            state = mix(state);
+            shuffled.swap(i, (state % (i as u64 + 1)) as usize);
+        }
+
+        let mut permutation = [0; 512];
+        for (i, value) in permutation.iter_mut().enumerate() {
+            *value = shuffled[i % 256];
+        }
+
+        PerlinNoise { permutation }
+    }
+
+    fn hash(&self, x: usize, z: usize) -> u8 {
+        self.permutation[self.permutation[x] as usize + z]
+    }
+
+    /// Roughly between -1 and 1
+    fn noise(&self, x: f64, z: f64) -> f64 {
+        let (floor_x, floor_z) = (x.floor(), z.floor());
+        let (cell_x, cell_z) = ((floor_x as i64 & 0xFF) as usize, (floor_z as i64 & 0xFF) as usize);
+        let (x, z) = (x - floor_x, z - floor_z);
+        let (u, v) = (fade(x), fade(z));
+
+        let bottom = lerp(
+            u,
+            gradient(self.hash(cell_x, cell_z), x, z),
+            gradient(self.hash(cell_x + 1, cell_z), x - 1.0, z),
+        );
+        let top = lerp(
+            u,
+            gradient(self.hash(cell_x, cell_z + 1), x, z - 1.0),
+            gradient(self.hash(cell_x + 1, cell_z + 1), x - 1.0, z - 1.0),
+        );
+
+        lerp(v, bottom, top)
+    }
+
+    /// Several octaves added up, each with twice the detail and half the strength of the last
+    fn fractal(&self, x: f64, z: f64) -> f64 {
+        let mut total = 0.0;
+        let mut strength = 1.0;
+        let mut max = 0.0;
+        for octave in 0..OCTAVES {
+            let frequency = (1 << octave) as f64;
+            total += self.noise(x * frequency, z * frequency) * strength;
+            max += strength;
+            strength /= 2.0;
+        }
+
+        total / max
+    }
+}
+
+#[derive(Debug)]
+pub struct NoiseGenerator {
+    seed: u64,
+    height_noise: PerlinNoise,
+}
+
+impl NoiseGenerator {
+    pub fn new(seed: i64) -> NoiseGenerator {
+        NoiseGenerator {
+            seed: seed as u64,
+            height_noise: PerlinNoise::new(seed as u64),
+        }
+    }
+
+    /// The y of the highest block of ground in the column
+    pub fn height_at(&self, x: i64, z: i64) -> usize {
+        let noise = self
+            .height_noise
+            .fractal(x as f64 / HORIZONTAL_SCALE, z as f64 / HORIZONTAL_SCALE);
+        let height = BASE_HEIGHT + noise * HEIGHT_VARIATION;
+
+        height.round().clamp(1.0, MAX_HEIGHT as f64) as usize
+    }
+
+    fn is_bedrock(&self, x: i64, y: usize, z: i64) -> bool {
+        if y as u64 >= BEDROCK_LAYERS {
+            return false;
+        }
+
+        let hash = mix(self.seed ^ mix(x as u64 ^ mix(z as u64 ^ mix(y as u64))));
+        y as u64 <= hash % BEDROCK_LAYERS
+    }
+}
+
+impl WorldGenerator for NoiseGenerator {
+    fn generate(&self, pos: &ChunkPosition) -> Box<Chunk> {
+        let mut chunk = Box::<Chunk>::default();
+        let (base_x, base_z) = (pos.chunk_x() as i64 * 16, pos.chunk_z() as i64 * 16);
+
+        for local_z in 0..16 {
+            for local_x in 0..16 {
+                let (x, z) = (base_x + local_x as i64, base_z + local_z as i64);
+                let height = self.height_at(x, z);
+                let underwater = height < SEA_LEVEL;
+                let (top, filler) = match underwater {
+                    true => (SAND, SAND),
+                    false => (GRASS, DIRT),
+                };
+
+                for y in 0..=height.max(SEA_LEVEL) {
+                    let block = if y == 0 || self.is_bedrock(x, y, z) {
+                        BEDROCK
+                    } else if y == height {
+                        top
+                    } else if y + DIRT_DEPTH >= height && y < height {
+                        filler
+                    } else if y < height {
+                        STONE
+                    } else {
+                        WATER
+                    };
+
+                    // Always in range, the height is clamped to the world
+                    chunk.set_block(local_x, y, local_z, block).unwrap();
+                }
+
+                chunk.biomes[local_z * 16 + local_x] = match underwater {
+                    true => OCEAN_BIOME,
+                    false => PLAINS_BIOME,
+                };
+            }
+        }
+
+        chunk
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_gives_same_chunks() {
+        let pos = ChunkPosition::new(-7, 0, 3);
+        let first = NoiseGenerator::new(1234).generate(&pos);
+        let second = NoiseGenerator::new(1234).generate(&pos);
+        assert!(*first == *second);
+
+        let other_seed = NoiseGenerator::new(4321).generate(&pos);
+        assert!(*first != *other_seed);
+    }
+
+    #[test]
+    fn test_heights_stay_the_same() {
+        let generator = NoiseGenerator::new(1234);
+        let heights: Vec<usize> = (0..16).map(|x| generator.height_at(x * 37, -x * 11)).collect();
+
+        assert_eq!(heights, vec![64, 54, 57, 62, 68, 67, 66, 69, 63, 58, 57, 53, 56, 65, 73, 72]);
+    }
+
+    #[test]
+    fn test_columns_are_layered() {
+        let generator = NoiseGenerator::new(42);
+        let mut saw_land = false;
+        let mut saw_water = false;
+
+        for chunk_x in -4..4 {
+            let chunk = generator.generate(&ChunkPosition::new(chunk_x * 5, 0, chunk_x * 3));
+            for (x, z) in [(0, 0), (7, 3), (15, 15)] {
+                let height = generator.height_at(
+                    chunk_x as i64 * 5 * 16 + x as i64,
+                    chunk_x as i64 * 3 * 16 + z as i64,
+                );
+
+                assert_eq!(chunk.get_block(x, 0, z), Some(BEDROCK));
+                assert_eq!(chunk.get_block(x, 10, z), Some(STONE));
+                assert_eq!(chunk.get_block(x, height.max(SEA_LEVEL) + 1, z), Some(0));
+                if height >= SEA_LEVEL {
+                    saw_land = true;
+                    assert_eq!(chunk.get_block(x, height, z), Some(GRASS));
+                    assert_eq!(chunk.get_block(x, height - 1, z), Some(DIRT));
+                } else {
+                    saw_water = true;
+                    assert_eq!(chunk.get_block(x, height, z), Some(SAND));
+                    assert_eq!(chunk.get_block(x, SEA_LEVEL, z), Some(WATER));
+                }
+            }
+        }
+
+        assert!(saw_land && saw_water);
+    }
+}
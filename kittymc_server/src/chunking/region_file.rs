@@ -0,0 +1,318 @@
+//! A file holding the chunks of a 32x32 chunk region.
+//!
+//! The layout is the one Anvil uses. The file is split into 4 KiB sectors, and the first two hold
+//! where each chunk starts and how many sectors it takes, followed by when it was last written.
+//! Every chunk starts with its length and how it's compressed. Chunks are always written to free
+//! sectors and synced before the header points at them, and the header is synced before the old
+//! sectors are reused, so a crash at any point leaves either the old or the new copy intact.
+
+use kittymc_lib::error::KittyMCError;
+use kittymc_lib::utils::sync_parent_dir;
+use log::warn;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const REGION_WIDTH: usize = 32;
+pub const CHUNKS_PER_REGION: usize = REGION_WIDTH * REGION_WIDTH;
+pub const SECTOR_SIZE: usize = 4096;
+
+const HEADER_SECTORS: usize = 2;
+/// The sector count of a chunk is a single byte
+const MAX_CHUNK_SECTORS: usize = 255;
+/// The length and the compression type in front of every chunk
+const CHUNK_HEADER_SIZE: usize = 5;
+
+#[derive(Debug)]
+pub struct RegionFile {
+    file: File,
+    /// The first sector of each chunk shifted up by a byte, with the sector count in the low byte
+    locations: Vec<u32>,
+    /// The seconds since the epoch each chunk was last written at
+    timestamps: Vec<u32>,
+    used_sectors: Vec<bool>,
+}
+
+fn chunk_index(x: usize, z: usize) -> usize {
+    (x % REGION_WIDTH) + (z % REGION_WIDTH) * REGION_WIDTH
+}
+
+#[allow(dead_code)]
+impl RegionFile {
+    /// Opens the region file at `path`, or creates an empty one if there's none. Chunks the header
+    /// points outside the file or onto another chunk are left out.
+    pub fn open(path: &Path) -> Result<RegionFile, KittyMCError> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+
+        let len = file.metadata()?.len() as usize;
+        if len == 0 {
+            file.write_all(&[0; HEADER_SECTORS * SECTOR_SIZE])?;
+            // Chunks written to a file that isn't in its directory yet would be lost with it
+            file.sync_all()?;
+            sync_parent_dir(path)?;
+        } else if len < HEADER_SECTORS * SECTOR_SIZE {
+            return Err(KittyMCError::CorruptRegion("the header is cut off"));
+        }
+
+        let mut header = vec![0; HEADER_SECTORS * SECTOR_SIZE];
+        file.seek(SeekFrom::Start(0))?;
+        file.read_exact(&mut header)?;
+        let mut words = header
+            .chunks_exact(4)
+            .map(|word| u32::from_be_bytes(word.try_into().unwrap()));
+        let mut locations: Vec<u32> = words.by_ref().take(CHUNKS_PER_REGION).collect();
+        let timestamps: Vec<u32> = words.collect();
+
+        let sector_count = len.max(HEADER_SECTORS * SECTOR_SIZE).div_ceil(SECTOR_SIZE);
+        let mut used_sectors = vec![false; sector_count];
+        used_sectors[..HEADER_SECTORS].fill(true);
+
+        for (index, location) in locations.iter_mut().enumerate() {
+            if *location == 0 {
+                continue;
+            }
+
+            let (offset, sectors) = ((*location >> 8) as usize, (*location & 0xFF) as usize);
+            let sectors_range = offset..offset + sectors;
+            let valid = sectors != 0
+                && offset >= HEADER_SECTORS
+                && sectors_range.end <= sector_count
+                && !used_sectors[sectors_range.clone()].contains(&true);
+            if !valid {
+                warn!("Ignoring chunk {index} of {}, its sectors are invalid", path.display());
+                *location = 0;
+                continue;
+            }
+
+            used_sectors[sectors_range].fill(true);
+        }
+
+        Ok(RegionFile {
+            file,
+            locations,
+            timestamps,
+            used_sectors,
+        })
+    }
+
+    /// The positions within the region of every chunk in the file
+    pub fn chunks(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.locations
+            .iter()
+            .enumerate()
+            .filter(|(_, location)| **location != 0)
+            .map(|(index, _)| (index % REGION_WIDTH, index / REGION_WIDTH))
+    }
+
+    pub fn contains(&self, x: usize, z: usize) -> bool {
+        self.locations[chunk_index(x, z)] != 0
+    }
+
+    /// When the chunk was last written, in seconds since the epoch
+    pub fn timestamp(&self, x: usize, z: usize) -> Option<u32> {
+        self.contains(x, z).then(|| self.timestamps[chunk_index(x, z)])
+    }
+
+    /// Returns how the chunk is compressed and its data, or `None` if it isn't in the file
+    pub fn read(&mut self, x: usize, z: usize) -> Result<Option<(u8, Vec<u8>)>, KittyMCError> {
+        let location = self.locations[chunk_index(x, z)];
+        if location == 0 {
+            return Ok(None);
+        }
+
+        let (offset, sectors) = ((location >> 8) as usize, (location & 0xFF) as usize);
+        let mut header = [0; CHUNK_HEADER_SIZE];
+        self.file.seek(SeekFrom::Start((offset * SECTOR_SIZE) as u64))?;
+        self.file.read_exact(&mut header)?;
+
+        // The length counts the compression type, but not itself
+        let len = u32::from_be_bytes(header[..4].try_into().unwrap()) as usize;
+        if len == 0 || len + 4 > sectors * SECTOR_SIZE {
+            return Err(KittyMCError::CorruptRegion("a chunk doesn't fit its sectors"));
+        }
+
+        let mut data = vec![0; len - 1];
+        self.file.read_exact(&mut data)?;
+
+        Ok(Some((header[4], data)))
+    }
+
+    /// Writes the chunk to free sectors and then points the header at it
+    pub fn write(
+        &mut self,
+        x: usize,
+        z: usize,
+        compression: u8,
+        data: &[u8],
+    ) -> Result<(), KittyMCError> {
+        let sectors = (data.len() + CHUNK_HEADER_SIZE).div_ceil(SECTOR_SIZE);
+        if sectors > MAX_CHUNK_SECTORS {
+            return Err(KittyMCError::TooMuchData(
+                data.len(),
+                MAX_CHUNK_SECTORS * SECTOR_SIZE - CHUNK_HEADER_SIZE,
+            ));
+        }
+
+        let offset = self.allocate(sectors);
+        let mut buf = Vec::with_capacity(sectors * SECTOR_SIZE);
+        buf.extend_from_slice(&(data.len() as u32 + 1).to_be_bytes());
+        buf.push(compression);
+        buf.extend_from_slice(data);
+        buf.resize(sectors * SECTOR_SIZE, 0);
+
+        let written = self
+            .file
+            .seek(SeekFrom::Start((offset * SECTOR_SIZE) as u64))
+            .and_then(|_| self.file.write_all(&buf))
+            // Otherwise the header could reach the disk before the chunk it points at
+            .and_then(|_| self.file.sync_data());
+        if let Err(e) = written {
+            self.free(offset, sectors);
+            return Err(e.into());
+        }
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs() as u32);
+        let old = self.locations[chunk_index(x, z)];
+        self.set_header(chunk_index(x, z), (offset as u32) << 8 | sectors as u32, timestamp)?;
+        self.free_location(old);
+
+        Ok(())
+    }
+
+    /// Takes the chunk out of the header and frees its sectors
+    pub fn remove(&mut self, x: usize, z: usize) -> Result<(), KittyMCError> {
+        let old = self.locations[chunk_index(x, z)];
+        if old == 0 {
+            return Ok(());
+        }
+
+        self.set_header(chunk_index(x, z), 0, 0)?;
+        self.free_location(old);
+
+        Ok(())
+    }
+
+    /// How many sectors the file spans
+    pub fn sector_count(&self) -> usize {
+        self.used_sectors.len()
+    }
+
+    fn set_header(
+        &mut self,
+        index: usize,
+        location: u32,
+        timestamp: u32,
+    ) -> Result<(), KittyMCError> {
+        self.file.seek(SeekFrom::Start((index * 4) as u64))?;
+        self.file.write_all(&location.to_be_bytes())?;
+        self.file.seek(SeekFrom::Start((SECTOR_SIZE + index * 4) as u64))?;
+        self.file.write_all(&timestamp.to_be_bytes())?;
+        // The old sectors are only reused once nothing on disk points at them anymore
+        self.file.sync_data()?;
+
+        self.locations[index] = location;
+        self.timestamps[index] = timestamp;
+
+        Ok(())
+    }
+
+    /// Finds the first run of `sectors` free sectors, growing the file if there's none
+    fn allocate(&mut self, sectors: usize) -> usize {
+        let mut run_start = HEADER_SECTORS;
+        for (sector, used) in self.used_sectors.iter().enumerate().skip(HEADER_SECTORS) {
+            if *used {
+                run_start = sector + 1;
+            } else if sector + 1 - run_start == sectors {
+                break;
+            }
+        }
+
+        let end = run_start + sectors;
+        if end > self.used_sectors.len() {
+            self.used_sectors.resize(end, false);
+        }
+        self.used_sectors[run_start..end].fill(true);
+
+        run_start
+    }
+
+    fn free(&mut self, offset: usize, sectors: usize) {
+        if let Some(used) = self.used_sectors.get_mut(offset..offset + sectors) {
+            used.fill(false);
+        }
+    }
+
+    fn free_location(&mut self, location: u32) {
+        if location != 0 {
+            self.free((location >> 8) as usize, (location & 0xFF) as usize);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kittymc_lib::utils::TempDir;
+
+    #[test]
+    fn test_chunks_survive_reopening() {
+        let dir = TempDir::new("kittymc-region").unwrap();
+        let path = dir.join("r.0.0.kitty");
+        let mut region = RegionFile::open(&path).unwrap();
+        assert_eq!(region.chunks().count(), 0);
+        assert_eq!(region.read(3, 4).unwrap(), None);
+
+        region.write(3, 4, 2, b"meow").unwrap();
+        region.write(31, 31, 3, &[7; 5000]).unwrap();
+        assert!(region.timestamp(3, 4).is_some());
+        drop(region);
+
+        let mut region = RegionFile::open(&path).unwrap();
+        let mut chunks: Vec<_> = region.chunks().collect();
+        chunks.sort_unstable();
+        assert_eq!(chunks, vec![(3, 4), (31, 31)]);
+        assert_eq!(region.read(3, 4).unwrap(), Some((2, b"meow".to_vec())));
+        assert_eq!(region.read(31, 31).unwrap(), Some((3, vec![7; 5000])));
+        assert_eq!(region.sector_count(), HEADER_SECTORS + 1 + 2);
+
+        region.remove(3, 4).unwrap();
+        drop(region);
+        let mut region = RegionFile::open(&path).unwrap();
+        assert_eq!(region.read(3, 4).unwrap(), None);
+    }
+
+    #[test]
+    fn test_sectors_are_reused() {
+        let dir = TempDir::new("kittymc-region").unwrap();
+        let path = dir.join("r.0.0.kitty");
+        let mut region = RegionFile::open(&path).unwrap();
+
+        region.write(0, 0, 3, &[1; 3 * SECTOR_SIZE]).unwrap();
+        region.write(1, 0, 3, &[2; 10]).unwrap();
+        let sectors = region.sector_count();
+
+        // The old sectors are only freed once the chunk was written somewhere else
+        region.write(0, 0, 3, &[3; 10]).unwrap();
+        assert_eq!(region.sector_count(), sectors + 1);
+        let sectors = region.sector_count();
+        region.write(2, 0, 3, &[4; 2 * SECTOR_SIZE]).unwrap();
+        assert_eq!(region.sector_count(), sectors);
+
+        assert_eq!(region.read(0, 0).unwrap(), Some((3, vec![3; 10])));
+        assert_eq!(region.read(1, 0).unwrap(), Some((3, vec![2; 10])));
+        assert_eq!(region.read(2, 0).unwrap(), Some((3, vec![4; 2 * SECTOR_SIZE])));
+
+        assert!(matches!(
+            region.write(3, 0, 3, &vec![0; MAX_CHUNK_SECTORS * SECTOR_SIZE]),
+            Err(KittyMCError::TooMuchData(..))
+        ));
+    }
+}
@@ -0,0 +1,228 @@
+//! Chunks packed into region files of 32x32 chunks each, called `r.{x}.{z}.{extension}`.
+//!
+//! The region files are the same for every format, only how a chunk is written into them differs.
+//! Chunk columns are stored by their x and z only, since the chunk manager always keeps them at
+//! y 0. Region files stay open once they were used, and each is locked on its own, so threads
+//! working in different regions don't wait for each other.
+
+use crate::chunking::chunk_storage::ChunkStorage;
+use crate::chunking::region_file::{RegionFile, REGION_WIDTH};
+use kittymc_lib::error::KittyMCError;
+use kittymc_lib::packets::client::play::chunk_data_20::Chunk;
+use kittymc_lib::subtypes::ChunkPosition;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// What the chunks are stored as. None of the Anvil compression types fit kittymc's own format,
+/// so it gets a number of its own that Anvil doesn't use.
+const KITTY_CHUNK_COMPRESSION: u8 = 0x7F;
+
+type RegionPosition = (isize, isize);
+
+/// How chunks are written into region files
+pub trait RegionFormat: Debug + Send + Sync {
+    /// What the names of the region files end in
+    fn extension(&self) -> &'static str;
+
+    /// Turns what's stored for the chunk at `pos` back into a chunk
+    fn decode(
+        &self,
+        pos: &ChunkPosition,
+        compression: u8,
+        data: &[u8],
+    ) -> Result<Box<Chunk>, KittyMCError>;
+
+    /// Returns the compression type and the data to store for the chunk at `pos`
+    fn encode(&self, pos: &ChunkPosition, chunk: &Chunk) -> Result<(u8, Vec<u8>), KittyMCError>;
+}
+
+/// kittymc's own format, which stores chunks in the savefile format
+#[derive(Debug, Default)]
+pub struct KittyRegionFormat;
+
+impl RegionFormat for KittyRegionFormat {
+    fn extension(&self) -> &'static str {
+        "kitty"
+    }
+
+    fn decode(
+        &self,
+        _pos: &ChunkPosition,
+        compression: u8,
+        data: &[u8],
+    ) -> Result<Box<Chunk>, KittyMCError> {
+        match compression {
+            KITTY_CHUNK_COMPRESSION => Chunk::from_bytes(data),
+            _ => Err(KittyMCError::InvalidEnumValue(
+                "region chunk compression",
+                compression as i64,
+            )),
+        }
+    }
+
+    fn encode(&self, _pos: &ChunkPosition, chunk: &Chunk) -> Result<(u8, Vec<u8>), KittyMCError> {
+        Ok((KITTY_CHUNK_COMPRESSION, chunk.to_bytes()?))
+    }
+}
+
+#[derive(Debug)]
+pub struct RegionChunkStorage<F = KittyRegionFormat> {
+    dir: PathBuf,
+    format: F,
+    regions: Mutex<HashMap<RegionPosition, Arc<Mutex<RegionFile>>>>,
+}
+
+impl RegionChunkStorage {
+    pub fn new(dir: impl Into<PathBuf>) -> RegionChunkStorage {
+        RegionChunkStorage::with_format(dir, KittyRegionFormat)
+    }
+}
+
+impl<F: RegionFormat> RegionChunkStorage<F> {
+    pub fn with_format(dir: impl Into<PathBuf>, format: F) -> RegionChunkStorage<F> {
+        RegionChunkStorage {
+            dir: dir.into(),
+            format,
+            regions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The region the chunk is in and where it is within it
+    fn locate(pos: &ChunkPosition) -> (RegionPosition, (usize, usize)) {
+        let width = REGION_WIDTH as isize;
+        let region = (pos.chunk_x().div_euclid(width), pos.chunk_z().div_euclid(width));
+        let local = (
+            pos.chunk_x().rem_euclid(width) as usize,
+            pos.chunk_z().rem_euclid(width) as usize,
+        );
+
+        (region, local)
+    }
+
+    fn region_path(&self, (x, z): RegionPosition) -> PathBuf {
+        self.dir.join(format!("r.{x}.{z}.{}", self.format.extension()))
+    }
+
+    fn parse_file_name(&self, name: &str) -> Option<RegionPosition> {
+        let (x, z) = name
+            .strip_prefix("r.")?
+            .strip_suffix(self.format.extension())?
+            .strip_suffix('.')?
+            .split_once('.')?;
+        Some((x.parse().ok()?, z.parse().ok()?))
+    }
+
+    /// The region file, which is created if there's none yet
+    fn open_region(&self, region: RegionPosition) -> Result<Arc<Mutex<RegionFile>>, KittyMCError> {
+        let mut regions = self.regions.lock().map_err(|_| KittyMCError::LockPoisonError)?;
+        if let Some(file) = regions.get(&region) {
+            return Ok(file.clone());
+        }
+
+        fs::create_dir_all(&self.dir)?;
+        let file = Arc::new(Mutex::new(RegionFile::open(&self.region_path(region))?));
+        regions.insert(region, file.clone());
+
+        Ok(file)
+    }
+
+    /// The region file, or `None` if nothing in the region was saved yet
+    fn existing_region(
+        &self,
+        region: RegionPosition,
+    ) -> Result<Option<Arc<Mutex<RegionFile>>>, KittyMCError> {
+        if !self.region_path(region).exists() {
+            return Ok(None);
+        }
+
+        self.open_region(region).map(Some)
+    }
+}
+
+impl<F: RegionFormat> ChunkStorage for RegionChunkStorage<F> {
+    fn load(&self, pos: &ChunkPosition) -> Result<Option<Box<Chunk>>, KittyMCError> {
+        let (region, (x, z)) = Self::locate(pos);
+        let Some(region) = self.existing_region(region)? else {
+            return Ok(None);
+        };
+
+        let data = region
+            .lock()
+            .map_err(|_| KittyMCError::LockPoisonError)?
+            .read(x, z)?;
+
+        data.map(|(compression, data)| self.format.decode(pos, compression, &data))
+            .transpose()
+    }
+
+    fn save(&self, pos: &ChunkPosition, chunk: &Chunk) -> Result<(), KittyMCError> {
+        let (compression, data) = self.format.encode(pos, chunk)?;
+        let (region, (x, z)) = Self::locate(pos);
+        self.open_region(region)?
+            .lock()
+            .map_err(|_| KittyMCError::LockPoisonError)?
+            .write(x, z, compression, &data)
+    }
+
+    fn remove(&self, pos: &ChunkPosition) -> Result<(), KittyMCError> {
+        let (region, (x, z)) = Self::locate(pos);
+        let Some(region) = self.existing_region(region)? else {
+            return Ok(());
+        };
+
+        let mut region = region.lock().map_err(|_| KittyMCError::LockPoisonError)?;
+        region.remove(x, z)
+    }
+
+    fn positions(&self) -> Result<Vec<ChunkPosition>, KittyMCError> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut positions = vec![];
+        for entry in entries {
+            let name = entry?.file_name();
+            let region = name.to_str().and_then(|name| self.parse_file_name(name));
+            let Some((region_x, region_z)) = region else {
+                continue;
+            };
+            let Some(region) = self.existing_region((region_x, region_z))? else {
+                continue;
+            };
+
+            let region = region.lock().map_err(|_| KittyMCError::LockPoisonError)?;
+            let width = REGION_WIDTH as isize;
+            positions.extend(region.chunks().map(|(x, z)| {
+                ChunkPosition::new(region_x * width + x as isize, 0, region_z * width + z as isize)
+            }));
+        }
+
+        Ok(positions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunks_are_located_in_their_region() {
+        let locate =
+            |x, z| RegionChunkStorage::<KittyRegionFormat>::locate(&ChunkPosition::new(x, 0, z));
+        let storage = RegionChunkStorage::new("world/region");
+
+        assert_eq!(locate(0, 0), ((0, 0), (0, 0)));
+        assert_eq!(locate(31, 32), ((0, 1), (31, 0)));
+        assert_eq!(locate(-1, -32), ((-1, -1), (31, 0)));
+        assert_eq!(locate(-33, 70), ((-2, 2), (31, 6)));
+        assert_eq!(storage.parse_file_name("r.-2.2.kitty"), Some((-2, 2)));
+        assert_eq!(storage.parse_file_name("r.0.0.mca"), None);
+        assert_eq!(storage.parse_file_name("r.0.0kitty"), None);
+    }
+}
@@ -0,0 +1,70 @@
+//! What chunks look like before anyone changed them.
+//!
+//! Each world remembers the generator it was created with in its level.dat, by the same names
+//! vanilla uses where there's an equivalent, so it keeps looking the same when the server restarts.
+
+use crate::chunking::flat_generator::FlatGenerator;
+use crate::chunking::noise_generator::NoiseGenerator;
+use crate::level::LevelInfo;
+use kittymc_lib::error::KittyMCError;
+use kittymc_lib::packets::client::play::chunk_data_20::{
+    Chunk, DEFAULT_FLAT_CHUNK, DEFAULT_FLAT_CHUNK_2,
+};
+use kittymc_lib::packets::client::play::LevelType;
+use kittymc_lib::subtypes::ChunkPosition;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+/// The flat chunks with wool stripes kittymc always generated
+pub const STRIPED_GENERATOR: &str = "kittymc:striped";
+/// Hills and oceans, which vanilla continues with its own overworld generator
+pub const NOISE_GENERATOR: &str = "default";
+/// Layers from a superflat preset, which is kept in the generator options of the world
+pub const FLAT_GENERATOR: &str = "flat";
+
+pub type SharedWorldGenerator = Arc<dyn WorldGenerator>;
+
+/// Generates the chunks of a world. The same position always has to give the same chunk, so
+/// generators are shared by the generator threads and may not keep any state between chunks.
+pub trait WorldGenerator: Debug + Send + Sync {
+    fn generate(&self, pos: &ChunkPosition) -> Box<Chunk>;
+
+    /// What the clients are told the world is, which changes where they draw the horizon
+    fn level_type(&self) -> LevelType {
+        LevelType::Default
+    }
+}
+
+/// The generator the world in `level` was created with
+pub fn create_generator(level: &LevelInfo) -> Result<SharedWorldGenerator, KittyMCError> {
+    match level.generator_name.as_str() {
+        STRIPED_GENERATOR => Ok(Arc::new(StripedFlatGenerator)),
+        NOISE_GENERATOR => Ok(Arc::new(NoiseGenerator::new(level.seed))),
+        FLAT_GENERATOR => Ok(Arc::new(FlatGenerator::from_options(&level.generator_options)?)),
+        name => Err(KittyMCError::UnknownGenerator(name.to_string())),
+    }
+}
+
+/// Where players appear in a new world, on top of the highest block at 0, 0
+pub fn find_spawn(generator: &dyn WorldGenerator) -> (i32, i32, i32) {
+    let chunk = generator.generate(&ChunkPosition::new(0, 0, 0));
+    let y = (0..256)
+        .rev()
+        .find(|y| chunk.get_block(0, *y, 0).is_some_and(|state| state != 0))
+        .map_or(0, |y| y + 1);
+
+    (0, y as i32, 0)
+}
+
+#[derive(Debug, Default)]
+pub struct StripedFlatGenerator;
+
+impl WorldGenerator for StripedFlatGenerator {
+    fn generate(&self, pos: &ChunkPosition) -> Box<Chunk> {
+        if pos.chunk_z().abs() % 2 == 1 {
+            DEFAULT_FLAT_CHUNK.clone()
+        } else {
+            DEFAULT_FLAT_CHUNK_2.clone()
+        }
+    }
+}
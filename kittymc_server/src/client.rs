@@ -3,27 +3,35 @@ use log::error;
 use std::collections::HashSet;
 use std::fmt::Debug;
 use std::io;
-use std::io::{ErrorKind, Read, Write};
+use std::io::ErrorKind;
 use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc::Receiver;
 use std::time::{Duration, Instant};
+use tokio::runtime::Handle;
 use uuid::Uuid;
 
-use tracing::{debug, info, instrument, trace, warn};
+use tracing::{debug, info, instrument, warn};
 
 use crate::player::Player;
 use kittymc_lib::error::KittyMCError;
 use kittymc_lib::packets::client::play::keep_alive_1f::ServerKeepAlivePacket;
-use kittymc_lib::packets::client::play::player_list_item_2e::PlayerListItemAction;
+use kittymc_lib::packets::client::play::player_list_item_2e::{
+    PlayerListItemAction, PlayerListItemProperties,
+};
+use kittymc_lib::packets::client::play::chunk_data_20::Chunk;
 use kittymc_lib::packets::client::play::{
-    ChunkDataPacket, GameMode, PlayerListItemPacket, SpawnPlayerPacket, UnloadChunkPacket,
+    ChunkDataPacket, PlayerListItemPacket, SpawnPlayerPacket, UnloadChunkPacket,
 };
-use kittymc_lib::packets::packet_serialization::compress_packet;
-use kittymc_lib::packets::{packet_serialization::SerializablePacket, CompressionInfo, Packet};
+use kittymc_lib::packets::codec::PacketCodec;
+use kittymc_lib::packets::protocol::ProtocolVersion;
+use kittymc_lib::packets::{packet_serialization::SerializablePacket, Packet};
+use kittymc_lib::session::GameProfile;
 use kittymc_lib::subtypes::metadata::EntityMetadata;
 use kittymc_lib::subtypes::state::State;
 use kittymc_lib::subtypes::{ChunkPosition, Location};
 use kittymc_lib::utils::rainbowize_cool_people_textcomp;
 use crate::chunking::chunk_manager::ChunkManager;
+use crate::connection::Connection;
 
 const DEFAULT_CHUNK_LOAD_RADIUS: u32 = 4;
 
@@ -31,33 +39,48 @@ const DEFAULT_CHUNK_LOAD_RADIUS: u32 = 4;
 pub struct ClientInfo {
     pub uuid: Uuid,
     pub username: String,
+    /// The skin and cape as signed by the session server, which offline players don't have
+    pub properties: Vec<PlayerListItemProperties>,
+}
+
+/// How far an online mode login has come
+#[derive(Debug)]
+pub enum LoginStage {
+    /// The Encryption Request went out, waiting for the response
+    AwaitingEncryption {
+        username: String,
+        verify_token: [u8; 4],
+    },
+    /// The session server is being asked on another thread
+    Verifying {
+        username: String,
+        profile: Receiver<Result<GameProfile, KittyMCError>>,
+    },
 }
 
 #[derive(Debug)]
 #[allow(dead_code)]
 pub struct Client {
     connected_at: Instant,
-    socket: TcpStream,
+    codec: PacketCodec<Connection>,
     addr: SocketAddr,
-    current_state: State,
     uuid: Uuid,
     last_heartbeat: Instant,
     last_heartbeat_id: u64,
     last_backbeat: Instant,
-    buffer: Vec<u8>,
-    buffer_size: usize,
-    fragmented: bool,
-    compression: CompressionInfo,
     brand: Option<String>,
     loaded_chunks: HashSet<ChunkPosition>,
     view_distance: u32,
-    pub(crate) load_initial_chunks: bool,
+    login_stage: Option<LoginStage>,
+    /// The chunk all chunks in view distance were last loaded around
+    chunk_center: Option<ChunkPosition>,
 }
 
 #[allow(dead_code)]
 impl Client {
-    #[instrument(skip(server))]
-    pub fn accept(server: &TcpListener) -> Result<Option<Client>, KittyMCError> {
+    /// Accepts the next connection, if there is one. Its packets are sent on `runtime`.
+    #[instrument(skip(server, runtime))]
+    pub fn accept(server: &TcpListener, runtime: &Handle) -> Result<Option<Client>, KittyMCError> {
         let (socket, addr) = match server.accept() {
             Ok(socket) => socket,
             Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(None),
@@ -68,51 +91,60 @@ impl Client {
             .set_nonblocking(true)
             .expect("Couldn't set socket to nonblocking");
 
-        Client::new(socket, addr).map(Some)
+        Client::new(socket, addr, runtime).map(Some)
     }
 
-    #[instrument(skip(socket, addr))]
-    pub fn new(socket: TcpStream, addr: SocketAddr) -> Result<Client, KittyMCError> {
+    #[instrument(skip(socket, addr, runtime))]
+    pub fn new(
+        socket: TcpStream,
+        addr: SocketAddr,
+        runtime: &Handle,
+    ) -> Result<Client, KittyMCError> {
         info!("[{}] Client connected", addr);
 
         Ok(Client {
             connected_at: Instant::now(),
-            socket,
+            codec: PacketCodec::new(Connection::new(socket, runtime)?),
             addr,
-            current_state: State::Handshake,
             uuid: Uuid::default(),
             last_heartbeat: Instant::now(),
             last_heartbeat_id: 0,
             last_backbeat: Instant::now(),
-            buffer: vec![0; 2048],
-            buffer_size: 0,
-            fragmented: false,
-            compression: CompressionInfo::default(),
             brand: None,
             loaded_chunks: HashSet::new(),
             view_distance: DEFAULT_CHUNK_LOAD_RADIUS,
-            load_initial_chunks: true,
+            login_stage: None,
+            chunk_center: None,
         })
     }
 
     pub fn try_clone(&self) -> io::Result<Client> {
+        // Both clones would need the same cipher state, which can't be shared
+        if self.codec.is_encrypted() {
+            return Err(io::Error::new(
+                ErrorKind::Unsupported,
+                "can't clone an encrypted connection",
+            ));
+        }
+
+        let mut codec = PacketCodec::new(self.codec.get_ref().try_clone()?);
+        codec.set_state(self.codec.state());
+        codec.set_compression(self.codec.compression().clone());
+        codec.set_protocol(self.codec.protocol());
+
         Ok(Client {
             connected_at: self.connected_at,
-            socket: self.socket.try_clone()?,
+            codec,
             addr: self.addr,
-            current_state: self.current_state,
             uuid: Uuid::default(),
             last_heartbeat: self.last_heartbeat,
             last_heartbeat_id: self.last_heartbeat_id,
             last_backbeat: self.last_backbeat,
-            buffer: vec![],
-            buffer_size: 0,
-            fragmented: false,
-            compression: self.compression.clone(),
             brand: self.brand.clone(),
             loaded_chunks: self.loaded_chunks.clone(),
             view_distance: self.view_distance,
-            load_initial_chunks: self.load_initial_chunks,
+            login_stage: None,
+            chunk_center: self.chunk_center.clone(),
         })
     }
 
@@ -120,43 +152,52 @@ impl Client {
         &self.addr
     }
 
-    pub fn set_state(&mut self, state: State) {
-        if state == State::Play {
-            self.last_heartbeat = Instant::now();
-            self.last_backbeat = Instant::now();
-        }
+    /// Whether the client didn't keep up with what was sent to it and can't be sent more
+    pub fn is_overflowed(&self) -> bool {
+        self.codec.get_ref().outbound().is_overflowed()
+    }
 
-        self.current_state = state;
+    /// How many bytes are still waiting to be sent
+    pub fn queued_bytes(&self) -> usize {
+        self.codec.get_ref().outbound().queued_bytes()
     }
 
-    pub fn set_uuid(&mut self, uuid: Uuid) {
-        self.uuid = uuid;
+    pub fn state(&self) -> State {
+        self.codec.state()
     }
 
-    pub fn set_compression(&mut self, compress: bool, threshold: u32) {
-        self.compression.enabled = compress;
-        self.compression.compression_threshold = threshold;
+    pub fn protocol(&self) -> ProtocolVersion {
+        self.codec.protocol()
+    }
+
+    pub fn set_uuid(&mut self, uuid: Uuid) {
+        self.uuid = uuid;
     }
 
     pub fn set_brand(&mut self, brand: String) {
         self.brand = Some(brand);
     }
 
-    // TODO: Something is broken with compression
-    #[instrument(skip(self, b_packet))]
-    pub fn send_packet_raw(&mut self, b_packet: &[u8]) -> Result<(), KittyMCError> {
-        if self.compression.enabled {
-            let compressed = compress_packet(b_packet, self.compression.compression_threshold)?;
-            self.socket.write_all(&compressed)?;
-        } else {
-            self.socket.write_all(b_packet)?;
-        }
-
-        Ok(())
+    /// In chunks
+    pub fn view_distance(&self) -> u32 {
+        self.view_distance
     }
 
     pub fn set_view_distance(&mut self, view_distance: u32) {
         self.view_distance = view_distance;
+        self.chunk_center = None;
+    }
+
+    pub fn set_login_stage(&mut self, stage: LoginStage) {
+        self.login_stage = Some(stage);
+    }
+
+    pub fn take_login_stage(&mut self) -> Option<LoginStage> {
+        self.login_stage.take()
+    }
+
+    pub fn enable_encryption(&mut self, shared_secret: &[u8]) -> Result<(), KittyMCError> {
+        self.codec.enable_encryption(shared_secret)
     }
 
     #[instrument(skip(self, packet))]
@@ -173,8 +214,20 @@ impl Client {
                 P::id()
             );
         }
-        self.send_packet_raw(&packet.serialize())?;
-        Ok(())
+        self.codec.write_packet(packet)
+    }
+
+    /// Sends the chunk data packet the chunk has cached, so it's only built and compressed once
+    /// for everyone who loads it. Older protocol versions need it translated, so they don't share.
+    fn send_chunk(&mut self, chunk: &Chunk, pos: &ChunkPosition) -> Result<(), KittyMCError> {
+        let (x, z) = (pos.chunk_x() as i32, pos.chunk_z() as i32);
+
+        if self.protocol() != ProtocolVersion::LATEST {
+            return self.send_packet(&ChunkDataPacket::new(chunk, x, z));
+        }
+
+        let frame = chunk.encoded_frame(x, z, self.codec.compression())?;
+        self.codec.write_frame(&frame)
     }
 
     pub fn add_player_to_player_list(&mut self, player: &Player) -> Result<(), KittyMCError> {
@@ -184,8 +237,8 @@ impl Client {
                 *player.uuid(),
                 PlayerListItemAction::AddPlayer {
                     name: player.name().to_string(),
-                    properties: vec![],
-                    game_mode: GameMode::Survival,
+                    properties: player.properties().to_vec(),
+                    game_mode: player.game_mode(),
                     ping: 0, // TODO: fix ping
                     display_name,
                 },
@@ -207,7 +260,10 @@ impl Client {
     }
 
     pub fn do_heartbeat(&mut self) -> Result<bool, KittyMCError> {
-        if self.current_state != State::Play {
+        if self.state() != State::Play {
+            // Heartbeats only start once the client is playing
+            self.last_heartbeat = Instant::now();
+            self.last_backbeat = Instant::now();
             return Ok(true);
         }
 
@@ -229,80 +285,35 @@ impl Client {
 
     #[instrument(skip(self))]
     pub fn fetch_packet(&mut self) -> Result<Option<Packet>, KittyMCError> {
-        let mut n = self.buffer_size;
-        if n == self.buffer.len() {
-            // buffer has not enough space to fit packet. so extend it.
-            self.buffer.resize(n + 2048, 0);
-            trace!("[{}] Increased buffer size to fit bigger packet", self.addr);
-        }
-        let max_len = self.buffer.len();
-        match self.socket.read(&mut self.buffer[n..max_len]) {
-            Ok(0) => {
-                // The other side closed the connection
-                return Err(KittyMCError::Disconnected);
-            }
-            Ok(new_n) => {
-                self.fragmented = false;
-                n += new_n;
-            }
-            Err(e) if e.kind() == ErrorKind::WouldBlock => {
-                if n == 0 || self.fragmented {
-                    return Ok(None);
-                }
-            }
-            Err(e) => return Err(e.into()),
-        }
-        self.buffer_size = n;
-
-        trace!(
-            "[{}] Complete Received Data : {:?}",
-            self.addr,
-            &self.buffer[..n]
-        );
+        loop {
+            let Some(frame) = self.codec.read_frame()? else {
+                return Ok(None);
+            };
 
-        let (packet_len, packet) =
-            match Packet::deserialize(self.current_state, &self.buffer[..n], &self.compression) {
+            match frame.packet {
                 Ok(packet) => {
                     debug!(
                         "[{}] IN <<< {}(0x{:x?})({})",
                         self.addr,
-                        packet.1.name(),
-                        packet.1.id(),
-                        packet.1.id()
+                        packet.name(),
+                        packet.id(),
+                        packet.id()
                     );
-                    (packet.0, Some(packet.1))
-                }
-                Err(KittyMCError::NotEnoughData(_, _)) => {
-                    trace!("[{}] Not enough data. Waiting for more", self.addr);
-                    self.fragmented = true;
-                    return Ok(None);
+                    return Ok(Some(packet));
                 }
                 Err(KittyMCError::NotImplemented(packet_id, packet_len)) => {
                     warn!(
                         "[{}] IN UNIMPLEMENTED <<< UNKNOWN(0x{:x?})({}) (len: {})",
                         self.addr, packet_id, packet_id, packet_len
                     );
-                    (packet_len, None)
                 }
                 Err(e) => {
                     warn!("[{}] Error when deserializing packet: {}", self.addr, e);
-                    warn!(
-                        "[{}] Packet started with : {:?}",
-                        self.addr,
-                        &self.buffer[..n]
-                    );
+                    warn!("[{}] Packet started with : {:?}", self.addr, frame.data);
                     return Err(e);
                 }
-            };
-
-        self.buffer_size = n - packet_len;
-        self.buffer.drain(0..packet_len);
-
-        if self.buffer_size < 2048 {
-            self.buffer.resize(2048, 0); // shouldn't be able to become smaller than 2048
+            }
         }
-
-        Ok(packet)
     }
 
     /// Returns true if all of its chunks could be loaded
@@ -327,12 +338,7 @@ impl Client {
 
             {
                 let chunk = chunk.read().unwrap();
-                let packet = ChunkDataPacket::new(
-                    chunk.as_ref(),
-                    pos.chunk_x() as i32,
-                    pos.chunk_z() as i32,
-                );
-                self.send_packet(&packet)?;
+                self.send_chunk(&chunk, pos)?;
             }
             self.loaded_chunks.insert(pos.clone());
         }
@@ -376,4 +382,74 @@ impl Client {
 
         Ok(all_loaded)
     }
+
+    /// Updates the loaded chunks once the client crossed into another chunk, or if some of them
+    /// weren't ready the last time. The chunks it has are kept from being unloaded either way.
+    pub fn tick_chunks(
+        &mut self,
+        pos: &Location,
+        chunk_manager: &mut ChunkManager,
+    ) -> Result<(), KittyMCError> {
+        for chunk_pos in &self.loaded_chunks {
+            chunk_manager.tap_chunk(chunk_pos);
+        }
+
+        let center = ChunkPosition::from(pos);
+        if let Some(last) = &self.chunk_center {
+            if last.chunk_x() == center.chunk_x() && last.chunk_z() == center.chunk_z() {
+                return Ok(());
+            }
+        }
+
+        self.chunk_center = match self.update_chunks(pos, chunk_manager)? {
+            true => Some(center),
+            false => None,
+        };
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunking::chunk_storage::MemoryChunkStorage;
+    use crate::chunking::chunk_unloader::CHUNK_REMOVE_TIME_S;
+    use crate::chunking::flat_generator::FlatGenerator;
+    use std::sync::Arc;
+    use std::thread::sleep;
+    use tokio::runtime::Builder;
+
+    #[test]
+    fn test_standing_still_keeps_chunks_loaded() {
+        let runtime = Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_io()
+            .build()
+            .unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let _peer = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (socket, addr) = listener.accept().unwrap();
+        socket.set_nonblocking(true).unwrap();
+        let mut client = Client::new(socket, addr, runtime.handle()).unwrap();
+        client.set_view_distance(1);
+
+        let mut chunk_manager = ChunkManager::new(
+            Arc::new(MemoryChunkStorage::new()),
+            Arc::new(FlatGenerator::from_options("").unwrap()),
+        );
+        let pos = Location::new(8., 4., 8.);
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while client.chunk_center.is_none() {
+            assert!(Instant::now() < deadline, "chunks never got generated");
+            client.tick_chunks(&pos, &mut chunk_manager).unwrap();
+            sleep(Duration::from_millis(10));
+        }
+
+        chunk_manager.age_accesses(Duration::from_secs(CHUNK_REMOVE_TIME_S + 1));
+        client.tick_chunks(&pos, &mut chunk_manager).unwrap();
+        chunk_manager.unload_old();
+
+        chunk_manager.set_block(&pos, 1 << 4).unwrap();
+    }
 }
@@ -0,0 +1,310 @@
+//! The types of arguments commands take, how they are parsed and what they complete to.
+
+use crate::chunking::blocks::{self, BLOCK_NAMES, NAMESPACE};
+use crate::command::{CommandError, CommandRegistry, CommandSender};
+use crate::player::Player;
+use kittymc_lib::packets::client::play::chunk_data_20::BlockStateId;
+use kittymc_lib::packets::client::play::GameMode;
+use kittymc_lib::subtypes::Location2;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+const RELATIVE_PREFIX: char = '~';
+
+const GAME_MODES: [(&str, &str, GameMode); 4] = [
+    ("survival", "s", GameMode::Survival),
+    ("creative", "c", GameMode::Creative),
+    ("adventure", "a", GameMode::Adventure),
+    ("spectator", "sp", GameMode::Spectator),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgumentType {
+    /// The name of a player that is online
+    Player,
+    Int {
+        min: i32,
+        max: i32,
+    },
+    /// Three coordinates that are each either absolute or relative (`~`)
+    Coordinates,
+    /// A block name like `stone` or `minecraft:wool:14`, or the numeric id and data (`35:14`)
+    Block,
+    GameMode,
+    /// The name of a command
+    Command,
+    /// The rest of the line
+    Text,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coordinate {
+    pub value: f64,
+    pub relative: bool,
+}
+
+impl Coordinate {
+    fn resolve(&self, origin: f64) -> f64 {
+        match self.relative {
+            true => origin + self.value,
+            false => self.value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coordinates {
+    pub x: Coordinate,
+    pub y: Coordinate,
+    pub z: Coordinate,
+}
+
+impl Coordinates {
+    pub fn is_relative(&self) -> bool {
+        self.x.relative || self.y.relative || self.z.relative
+    }
+
+    pub fn resolve(&self, origin: &Location2) -> Location2 {
+        Location2::new(
+            self.x.resolve(origin.x),
+            self.y.resolve(origin.y),
+            self.z.resolve(origin.z),
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Argument {
+    Player(Uuid),
+    Int(i32),
+    Coordinates(Coordinates),
+    Block(BlockStateId),
+    GameMode(GameMode),
+    Command(String),
+    Text(String),
+}
+
+/// What parsing and completing arguments can look at
+pub struct ArgumentContext<'a> {
+    pub sender: CommandSender,
+    pub players: &'a HashMap<Uuid, Player>,
+    pub commands: &'a CommandRegistry,
+}
+
+impl ArgumentType {
+    /// How many words the argument takes up, `None` if it takes everything that's left
+    pub fn width(&self) -> Option<usize> {
+        match self {
+            ArgumentType::Coordinates => Some(3),
+            ArgumentType::Text => None,
+            _ => Some(1),
+        }
+    }
+
+    /// Parses the words taken up by the argument, which are exactly [`Self::width`] many
+    pub fn parse(&self, words: &[&str], ctx: &ArgumentContext) -> Result<Argument, CommandError> {
+        let word = words.first().copied().unwrap_or_default();
+
+        Ok(match self {
+            ArgumentType::Player => Argument::Player(parse_player(word, ctx)?),
+            ArgumentType::Int { min, max } => Argument::Int(parse_int(word, *min, *max)?),
+            ArgumentType::Coordinates => Argument::Coordinates(Coordinates {
+                x: parse_coordinate(words[0])?,
+                y: parse_coordinate(words[1])?,
+                z: parse_coordinate(words[2])?,
+            }),
+            ArgumentType::Block => Argument::Block(parse_block(word)?),
+            ArgumentType::GameMode => Argument::GameMode(parse_game_mode(word)?),
+            ArgumentType::Command => match ctx.commands.get(word) {
+                Some(command) => Argument::Command(command.name.to_string()),
+                None => return Err(CommandError::UnknownCommand),
+            },
+            ArgumentType::Text => Argument::Text(words.join(" ")),
+        })
+    }
+
+    /// Everything the word that's being typed could be completed to
+    pub fn complete(&self, word: &str, ctx: &ArgumentContext) -> Vec<String> {
+        match self {
+            // Like in vanilla, text (e.g. in /say) completes to player names
+            ArgumentType::Player | ArgumentType::Text => complete_player(word, ctx.players),
+            ArgumentType::Coordinates if word.is_empty() => vec![RELATIVE_PREFIX.to_string()],
+            ArgumentType::Block => {
+                let namespaced = word.starts_with(NAMESPACE);
+                BLOCK_NAMES
+                    .iter()
+                    .filter(|name| !name.is_empty())
+                    .map(|name| match namespaced {
+                        true => format!("{NAMESPACE}{name}"),
+                        false => name.to_string(),
+                    })
+                    .filter(|name| starts_with_ignore_case(name, word))
+                    .collect()
+            }
+            ArgumentType::GameMode => GAME_MODES
+                .iter()
+                .map(|(name, _, _)| name.to_string())
+                .filter(|name| starts_with_ignore_case(name, word))
+                .collect(),
+            ArgumentType::Command => ctx.commands.complete_name(word, ctx),
+            ArgumentType::Int { .. } | ArgumentType::Coordinates => vec![],
+        }
+    }
+}
+
+pub fn starts_with_ignore_case(text: &str, prefix: &str) -> bool {
+    text.len() >= prefix.len()
+        && text.is_char_boundary(prefix.len())
+        && text[..prefix.len()].eq_ignore_ascii_case(prefix)
+}
+
+pub fn complete_player(word: &str, players: &HashMap<Uuid, Player>) -> Vec<String> {
+    players
+        .values()
+        .map(|player| player.name().to_string())
+        .filter(|name| starts_with_ignore_case(name, word))
+        .collect()
+}
+
+pub fn game_mode_name(game_mode: GameMode) -> &'static str {
+    GAME_MODES
+        .iter()
+        .find(|(_, _, mode)| *mode as u8 == game_mode as u8 & 0b111)
+        .map_or("unknown", |(name, _, _)| name)
+}
+
+fn parse_player(word: &str, ctx: &ArgumentContext) -> Result<Uuid, CommandError> {
+    ctx.players
+        .values()
+        .find(|player| player.name().eq_ignore_ascii_case(word))
+        .map(|player| *player.uuid())
+        .ok_or_else(|| CommandError::InvalidArgument(format!("Player {word} is not online")))
+}
+
+fn parse_int(word: &str, min: i32, max: i32) -> Result<i32, CommandError> {
+    let value: i32 = word
+        .parse()
+        .map_err(|_| CommandError::InvalidArgument(format!("{word} is not a valid number")))?;
+
+    if value < min {
+        return Err(CommandError::InvalidArgument(format!(
+            "The number you have entered ({value}) is too small, it must be at least {min}"
+        )));
+    }
+    if value > max {
+        return Err(CommandError::InvalidArgument(format!(
+            "The number you have entered ({value}) is too big, it must be at most {max}"
+        )));
+    }
+
+    Ok(value)
+}
+
+fn parse_coordinate(word: &str) -> Result<Coordinate, CommandError> {
+    let (relative, number) = match word.strip_prefix(RELATIVE_PREFIX) {
+        Some(offset) => (true, offset),
+        None => (false, word),
+    };
+
+    if relative && number.is_empty() {
+        return Ok(Coordinate {
+            value: 0.,
+            relative,
+        });
+    }
+
+    match number.parse::<f64>() {
+        Ok(value) if value.is_finite() => Ok(Coordinate { value, relative }),
+        _ => Err(CommandError::InvalidArgument(format!(
+            "{word} is not a valid coordinate"
+        ))),
+    }
+}
+
+fn parse_block(word: &str) -> Result<BlockStateId, CommandError> {
+    blocks::parse_block(word)
+        .ok_or_else(|| CommandError::InvalidArgument(format!("There is no such block: {word}")))
+}
+
+fn parse_game_mode(word: &str) -> Result<GameMode, CommandError> {
+    GAME_MODES
+        .iter()
+        .find(|(name, short, mode)| {
+            name.eq_ignore_ascii_case(word)
+                || short.eq_ignore_ascii_case(word)
+                || word.parse::<u8>() == Ok(*mode as u8)
+        })
+        .map(|(_, _, mode)| *mode)
+        .ok_or_else(|| CommandError::InvalidArgument(format!("There is no such game mode: {word}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_coordinates() {
+        assert_eq!(
+            parse_coordinate("~").unwrap(),
+            Coordinate {
+                value: 0.,
+                relative: true
+            }
+        );
+        assert_eq!(
+            parse_coordinate("~-2.5").unwrap(),
+            Coordinate {
+                value: -2.5,
+                relative: true
+            }
+        );
+        assert_eq!(
+            parse_coordinate("64").unwrap(),
+            Coordinate {
+                value: 64.,
+                relative: false
+            }
+        );
+        assert!(parse_coordinate("~~").is_err());
+        assert!(parse_coordinate("NaN").is_err());
+
+        let coordinates = Coordinates {
+            x: parse_coordinate("~1").unwrap(),
+            y: parse_coordinate("70").unwrap(),
+            z: parse_coordinate("~").unwrap(),
+        };
+        assert!(coordinates.is_relative());
+        assert_eq!(
+            coordinates.resolve(&Location2::new(10., 5., -3.)),
+            Location2::new(11., 70., -3.)
+        );
+    }
+
+    #[test]
+    fn test_parse_block() {
+        assert_eq!(parse_block("stone").unwrap(), 1 << 4);
+        assert_eq!(parse_block("minecraft:wool:14").unwrap(), (35 << 4) | 14);
+        assert_eq!(parse_block("35:14").unwrap(), (35 << 4) | 14);
+        assert_eq!(parse_block("STRUCTURE_BLOCK").unwrap(), 255 << 4);
+        assert!(parse_block("253").is_err());
+        assert!(parse_block("wool:16").is_err());
+        assert!(parse_block("kitty").is_err());
+    }
+
+    #[test]
+    fn test_parse_game_mode() {
+        assert_eq!(parse_game_mode("creative").unwrap(), GameMode::Creative);
+        assert_eq!(parse_game_mode("SP").unwrap(), GameMode::Spectator);
+        assert_eq!(parse_game_mode("2").unwrap(), GameMode::Adventure);
+        assert!(parse_game_mode("8").is_err());
+        assert_eq!(game_mode_name(GameMode::CreativeH), "creative");
+    }
+
+    #[test]
+    fn test_parse_int_bounds() {
+        assert_eq!(parse_int("15", 0, 15).unwrap(), 15);
+        assert!(parse_int("16", 0, 15).is_err());
+        assert!(parse_int("-1", 0, 15).is_err());
+        assert!(parse_int("one", 0, 15).is_err());
+    }
+}
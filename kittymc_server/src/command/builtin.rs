@@ -0,0 +1,468 @@
+use crate::command::args::{game_mode_name, ArgumentType};
+use crate::chunking::anvil_export::export_dir;
+use crate::command::{Arguments, Command, CommandContext, CommandError, Parameter, Permission};
+use crate::server::KittyMCServer;
+use kittymc_lib::error::KittyMCError;
+use kittymc_lib::packets::client::play::chat_message_0f::ChatPosition;
+use kittymc_lib::packets::client::play::chunk_data_20::BlockStateId;
+use kittymc_lib::packets::client::play::player_list_item_2e::PlayerListItemAction;
+use kittymc_lib::packets::client::play::{
+    BlockChangePacket, ChangeGameStatePacket, ClientChatMessagePacket, PlayerListItemPacket,
+    ServerPlayerPositionAndLookPacket,
+};
+use kittymc_lib::subtypes::components::{Component, TextComponent, TranslationComponent};
+use kittymc_lib::subtypes::{Location, Location2};
+use rand::random;
+use tracing::info;
+use uuid::Uuid;
+
+const ANNOUNCEMENT_TRANSLATION_TAG: &str = "chat.type.announcement";
+const CONSOLE_NAME: &str = "Server";
+const DEFAULT_KICK_REASON: &str = "Kicked by an operator";
+
+pub const BUILTIN_COMMANDS: &[Command] = &[
+    Command {
+        name: "help",
+        aliases: &["?"],
+        description: "Lists the commands or shows how to use one",
+        permission: Permission::Everyone,
+        signatures: &[&[Parameter::optional("command", ArgumentType::Command)]],
+        executor: help,
+    },
+    Command {
+        name: "list",
+        aliases: &[],
+        description: "Lists the players that are online",
+        permission: Permission::Everyone,
+        signatures: &[&[]],
+        executor: list,
+    },
+    Command {
+        name: "kick",
+        aliases: &[],
+        description: "Disconnects a player",
+        permission: Permission::Operator,
+        signatures: &[&[
+            Parameter::required("player", ArgumentType::Player),
+            Parameter::optional("reason", ArgumentType::Text),
+        ]],
+        executor: kick,
+    },
+    Command {
+        name: "op",
+        aliases: &[],
+        description: "Lets a player run operator commands",
+        permission: Permission::Operator,
+        signatures: &[&[Parameter::required("player", ArgumentType::Player)]],
+        executor: op,
+    },
+    Command {
+        name: "deop",
+        aliases: &[],
+        description: "Stops a player from running operator commands",
+        permission: Permission::Operator,
+        signatures: &[&[Parameter::required("player", ArgumentType::Player)]],
+        executor: deop,
+    },
+    Command {
+        name: "say",
+        aliases: &[],
+        description: "Announces a message to everyone",
+        permission: Permission::Operator,
+        signatures: &[&[Parameter::required("message", ArgumentType::Text)]],
+        executor: say,
+    },
+    Command {
+        name: "gamemode",
+        aliases: &["gm"],
+        description: "Changes the game mode of a player",
+        permission: Permission::Operator,
+        signatures: &[&[
+            Parameter::required("mode", ArgumentType::GameMode),
+            Parameter::optional("player", ArgumentType::Player),
+        ]],
+        executor: gamemode,
+    },
+    Command {
+        name: "tp",
+        aliases: &["teleport"],
+        description: "Teleports a player to another player or to a position",
+        permission: Permission::Operator,
+        signatures: &[
+            &[Parameter::required("destination", ArgumentType::Player)],
+            &[Parameter::required("position", ArgumentType::Coordinates)],
+            &[
+                Parameter::required("target", ArgumentType::Player),
+                Parameter::required("destination", ArgumentType::Player),
+            ],
+            &[
+                Parameter::required("target", ArgumentType::Player),
+                Parameter::required("position", ArgumentType::Coordinates),
+            ],
+        ],
+        executor: teleport,
+    },
+    Command {
+        name: "setblock",
+        aliases: &[],
+        description: "Places a block",
+        permission: Permission::Operator,
+        signatures: &[&[
+            Parameter::required("position", ArgumentType::Coordinates),
+            Parameter::required("block", ArgumentType::Block),
+            Parameter::optional("data", ArgumentType::Int { min: 0, max: 15 }),
+        ]],
+        executor: set_block,
+    },
+    Command {
+        name: "save",
+        aliases: &["save-all"],
+        description: "Writes the loaded world and the online players to disk",
+        permission: Permission::Operator,
+        signatures: &[&[]],
+        executor: save,
+    },
+    Command {
+        name: "export",
+        aliases: &[],
+        description: "Saves the world and copies it to the exports directory as a vanilla world",
+        permission: Permission::Operator,
+        signatures: &[&[Parameter::required("name", ArgumentType::Text)]],
+        executor: export,
+    },
+    Command {
+        name: "tps",
+        aliases: &["mspt"],
+        description: "Shows how well the server keeps up with the game ticks",
+        permission: Permission::Everyone,
+        signatures: &[&[]],
+        executor: tps,
+    },
+    Command {
+        name: "stop",
+        aliases: &[],
+        description: "Shuts the server down",
+        permission: Permission::Operator,
+        signatures: &[&[]],
+        executor: stop,
+    },
+];
+
+fn player_name(server: &KittyMCServer, uuid: &Uuid) -> Result<String, CommandError> {
+    let player = server.player(uuid).ok_or(KittyMCError::PlayerNotFound)?;
+    Ok(player.name().to_string())
+}
+
+fn help(
+    server: &mut KittyMCServer,
+    ctx: &mut CommandContext,
+    args: &Arguments,
+) -> Result<(), CommandError> {
+    let commands = server.commands();
+
+    if let Ok(name) = args.command("command") {
+        let Some(command) = commands.get(&name) else {
+            return Err(CommandError::UnknownCommand);
+        };
+        ctx.reply_text(format!("{} - {}", command.usage(), command.description))?;
+        return Ok(());
+    }
+
+    let lines: Vec<String> = commands
+        .commands()
+        .filter(|command| commands.can_use(command, &ctx.sender, server.players()))
+        .map(|command| format!("{} - {}", command.usage(), command.description))
+        .collect();
+    for line in lines {
+        ctx.reply_text(line)?;
+    }
+
+    Ok(())
+}
+
+fn list(
+    server: &mut KittyMCServer,
+    ctx: &mut CommandContext,
+    _args: &Arguments,
+) -> Result<(), CommandError> {
+    let mut names: Vec<&str> = server.players().values().map(|p| p.name()).collect();
+    names.sort_unstable();
+
+    match names.is_empty() {
+        true => ctx.reply_text("There are no players online")?,
+        false => ctx.reply_text(format!(
+            "There are {} players online: {}",
+            names.len(),
+            names.join(", ")
+        ))?,
+    }
+
+    Ok(())
+}
+
+fn kick(
+    server: &mut KittyMCServer,
+    ctx: &mut CommandContext,
+    args: &Arguments,
+) -> Result<(), CommandError> {
+    let target = args.player("player")?;
+    let reason = args
+        .text("reason")
+        .unwrap_or_else(|_| DEFAULT_KICK_REASON.to_string());
+    let name = player_name(server, &target)?;
+
+    server.kick(&target, &reason);
+    ctx.reply_text(format!("Kicked {name}: {reason}"))?;
+
+    Ok(())
+}
+
+fn op(
+    server: &mut KittyMCServer,
+    ctx: &mut CommandContext,
+    args: &Arguments,
+) -> Result<(), CommandError> {
+    let target = args.player("player")?;
+    let name = player_name(server, &target)?;
+
+    match server.set_operator(&target, true)? {
+        true => ctx.reply_text(format!("Made {name} a server operator"))?,
+        false => ctx.reply_error(format!("{name} already is a server operator"))?,
+    }
+
+    Ok(())
+}
+
+fn deop(
+    server: &mut KittyMCServer,
+    ctx: &mut CommandContext,
+    args: &Arguments,
+) -> Result<(), CommandError> {
+    let target = args.player("player")?;
+    let name = player_name(server, &target)?;
+
+    match server.set_operator(&target, false)? {
+        true => ctx.reply_text(format!("Made {name} no longer a server operator"))?,
+        false => ctx.reply_error(format!("{name} isn't a server operator"))?,
+    }
+
+    Ok(())
+}
+
+fn say(
+    server: &mut KittyMCServer,
+    ctx: &mut CommandContext,
+    args: &Arguments,
+) -> Result<(), CommandError> {
+    let message = args.text("message")?;
+    let name = match ctx.player() {
+        Ok(uuid) => player_name(server, &uuid)?,
+        Err(_) => CONSOLE_NAME.to_string(),
+    };
+
+    info!("[{name}] {message}");
+    let announcement = Component::Translation(
+        TranslationComponent::builder()
+            .translate(ANNOUNCEMENT_TRANSLATION_TAG.to_string())
+            .with(vec![
+                Component::Text(TextComponent::builder().text(name).build()),
+                Component::Text(TextComponent::builder().text(message).build()),
+            ])
+            .build(),
+    );
+    server.send_to_all(
+        ctx.client.as_deref_mut(),
+        &ClientChatMessagePacket {
+            text: announcement,
+            position: ChatPosition::Chat,
+        },
+    )?;
+
+    Ok(())
+}
+
+fn gamemode(
+    server: &mut KittyMCServer,
+    ctx: &mut CommandContext,
+    args: &Arguments,
+) -> Result<(), CommandError> {
+    let game_mode = args.game_mode("mode")?;
+    let target = match args.player("player") {
+        Ok(target) => target,
+        Err(_) => ctx.player()?,
+    };
+
+    let name = {
+        let player = server
+            .player_mut(&target)
+            .ok_or(KittyMCError::PlayerNotFound)?;
+        player.set_game_mode(game_mode);
+        player.name().to_string()
+    };
+
+    ctx.send_to(
+        server,
+        &target,
+        &ChangeGameStatePacket::change_game_mode(game_mode),
+    )?;
+    server.send_to_all(
+        ctx.client.as_deref_mut(),
+        &PlayerListItemPacket {
+            actions: vec![(target, PlayerListItemAction::UpdateGameMode(game_mode))],
+        },
+    )?;
+
+    ctx.reply_text(format!(
+        "Set {name}'s game mode to {}",
+        game_mode_name(game_mode)
+    ))?;
+
+    Ok(())
+}
+
+fn teleport(
+    server: &mut KittyMCServer,
+    ctx: &mut CommandContext,
+    args: &Arguments,
+) -> Result<(), CommandError> {
+    let target = match args.player("target") {
+        Ok(target) => target,
+        Err(_) => ctx.player()?,
+    };
+
+    let (destination, direction) = {
+        let player = server.player(&target).ok_or(KittyMCError::PlayerNotFound)?;
+        let destination = match args.player("destination") {
+            Ok(destination) => *server
+                .player(&destination)
+                .ok_or(KittyMCError::PlayerNotFound)?
+                .position(),
+            // Relative coordinates are relative to the player being teleported
+            Err(_) => args.coordinates("position")?.resolve(player.position()),
+        };
+        (destination, *player.direction())
+    };
+
+    let name = {
+        let player = server
+            .player_mut(&target)
+            .ok_or(KittyMCError::PlayerNotFound)?;
+        player.set_position(&destination);
+        player.name().to_string()
+    };
+
+    ctx.send_to(
+        server,
+        &target,
+        &ServerPlayerPositionAndLookPacket {
+            location: destination,
+            direction,
+            relative_flags: 0,
+            teleport_id: random(),
+        },
+    )?;
+
+    ctx.reply_text(format!(
+        "Teleported {name} to {:.2}, {:.2}, {:.2}",
+        destination.x, destination.y, destination.z
+    ))?;
+
+    Ok(())
+}
+
+fn set_block(
+    server: &mut KittyMCServer,
+    ctx: &mut CommandContext,
+    args: &Arguments,
+) -> Result<(), CommandError> {
+    let coordinates = args.coordinates("position")?;
+    let mut block = args.block("block")?;
+    if let Ok(data) = args.int("data") {
+        block = (block & !0b1111) | data as BlockStateId;
+    }
+
+    let origin = match ctx.player() {
+        Ok(uuid) => *server
+            .player(&uuid)
+            .ok_or(KittyMCError::PlayerNotFound)?
+            .position(),
+        Err(e) if coordinates.is_relative() => return Err(e),
+        Err(_) => Location2::zeros(),
+    };
+    let position = coordinates.resolve(&origin);
+    let location = Location::new(
+        position.x.floor() as f32,
+        position.y.floor() as f32,
+        position.z.floor() as f32,
+    );
+
+    server.set_block(&location, block)?;
+    server.send_to_all(
+        ctx.client.as_deref_mut(),
+        &BlockChangePacket::new(location, block),
+    )?;
+
+    ctx.reply_text("Block placed")?;
+
+    Ok(())
+}
+
+fn save(
+    server: &mut KittyMCServer,
+    ctx: &mut CommandContext,
+    _args: &Arguments,
+) -> Result<(), CommandError> {
+    ctx.reply_text("Saving the world...")?;
+    let saved = server.save_world()?;
+    let players = server.save_players();
+    ctx.reply_text(format!("Saved {saved} chunks and {players} players"))?;
+
+    Ok(())
+}
+
+fn export(
+    server: &mut KittyMCServer,
+    ctx: &mut CommandContext,
+    args: &Arguments,
+) -> Result<(), CommandError> {
+    let name = args.text("name")?;
+    let dir = export_dir(&name).ok_or_else(|| {
+        CommandError::InvalidArgument(format!("{name:?} isn't a plain directory name"))
+    })?;
+    if server.is_exporting() {
+        ctx.reply_error("The world is already being exported")?;
+        return Ok(());
+    }
+
+    ctx.reply_text(format!("Exporting the world to {}...", dir.display()))?;
+    server.start_export(dir, ctx.sender)?;
+
+    Ok(())
+}
+
+fn tps(
+    server: &mut KittyMCServer,
+    ctx: &mut CommandContext,
+    _args: &Arguments,
+) -> Result<(), CommandError> {
+    let stats = server.tick_stats();
+    ctx.reply_text(format!(
+        "TPS: {:.1}, MSPT: {:.2} (over the last {:.1}s)",
+        stats.tps(),
+        stats.mspt(),
+        stats.window().as_secs_f64()
+    ))?;
+
+    Ok(())
+}
+
+fn stop(
+    server: &mut KittyMCServer,
+    ctx: &mut CommandContext,
+    _args: &Arguments,
+) -> Result<(), CommandError> {
+    ctx.reply_text("Stopping the server")?;
+    server.request_shutdown();
+
+    Ok(())
+}
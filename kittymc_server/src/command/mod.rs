@@ -0,0 +1,689 @@
+//! Commands players run by chatting a message that starts with `/`.
+//!
+//! Every [`Command`] lists the ways it can be called as signatures of typed [`Parameter`]s. The
+//! [`CommandRegistry`] parses a line against them, checks the permission of whoever runs it and
+//! hands the parsed [`Arguments`] to the command's executor. The same signatures drive the tab
+//! completion of command names and arguments.
+
+pub mod args;
+mod builtin;
+
+use crate::client::Client;
+use crate::player::Player;
+use crate::server::KittyMCServer;
+use args::Coordinates;
+use args::{complete_player, starts_with_ignore_case, Argument, ArgumentContext, ArgumentType};
+use kittymc_lib::error::KittyMCError;
+use kittymc_lib::packets::client::play::chat_message_0f::ChatPosition;
+use kittymc_lib::packets::client::play::chunk_data_20::BlockStateId;
+use kittymc_lib::packets::client::play::{ClientChatMessagePacket, GameMode};
+use kittymc_lib::packets::packet_serialization::{NamedPacket, SerializablePacket};
+use kittymc_lib::packets::server::play::ServerTabCompletePacket;
+use kittymc_lib::subtypes::components::{BaseComponent, Component, TextComponent};
+use kittymc_lib::subtypes::Color;
+use std::collections::HashMap;
+use std::fmt::{Debug, Display, Formatter};
+use tracing::info;
+use uuid::Uuid;
+
+const COMMAND_PREFIX: char = '/';
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandSender {
+    Player(Uuid),
+    Console,
+}
+
+/// Who is allowed to run a command. What that means for a player is up to the registry's
+/// [`PermissionHook`], the console may run everything.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    Everyone,
+    Operator,
+}
+
+/// Decides if `player` has `permission`
+pub type PermissionHook = fn(player: &Player, permission: Permission) -> bool;
+
+pub fn default_permission_hook(player: &Player, permission: Permission) -> bool {
+    match permission {
+        Permission::Everyone => true,
+        Permission::Operator => player.is_operator(),
+    }
+}
+
+#[derive(Debug)]
+pub enum CommandError {
+    UnknownCommand,
+    NoPermission,
+    /// The words didn't fit any signature of the command, holds its usage
+    Usage(String),
+    InvalidArgument(String),
+    /// A command asked for an argument it doesn't declare
+    MissingArgument(&'static str),
+    /// The command needs a player to run it
+    PlayerOnly,
+    Failed(KittyMCError),
+}
+
+impl Display for CommandError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommandError::UnknownCommand => {
+                write!(f, "Unknown command. Try /help for a list of commands")
+            }
+            CommandError::NoPermission => {
+                write!(f, "You do not have permission to use this command")
+            }
+            CommandError::Usage(usage) => write!(f, "Usage: {usage}"),
+            CommandError::InvalidArgument(reason) => write!(f, "{reason}"),
+            CommandError::MissingArgument(name) => write!(f, "Missing argument <{name}>"),
+            CommandError::PlayerOnly => write!(f, "This command can only be run by a player"),
+            CommandError::Failed(e) => {
+                write!(f, "An error occurred while running the command: {e}")
+            }
+        }
+    }
+}
+
+impl From<KittyMCError> for CommandError {
+    fn from(e: KittyMCError) -> Self {
+        CommandError::Failed(e)
+    }
+}
+
+/// Who runs a command and where their feedback goes
+pub struct CommandContext<'a> {
+    pub sender: CommandSender,
+    /// The connection of the player running the command. It isn't in the server's client list
+    /// while the player's packets are handled, so everything sent to them goes through here.
+    pub client: Option<&'a mut Client>,
+}
+
+impl<'a> CommandContext<'a> {
+    pub fn new(sender: CommandSender, client: Option<&'a mut Client>) -> Self {
+        CommandContext { sender, client }
+    }
+
+    /// The player running the command
+    pub fn player(&self) -> Result<Uuid, CommandError> {
+        match self.sender {
+            CommandSender::Player(uuid) => Ok(uuid),
+            CommandSender::Console => Err(CommandError::PlayerOnly),
+        }
+    }
+
+    /// Sends a system message to whoever runs the command, or logs it for the console
+    pub fn reply(&mut self, text: Component) -> Result<(), KittyMCError> {
+        match &mut self.client {
+            Some(client) => client.send_packet(&ClientChatMessagePacket {
+                text,
+                position: ChatPosition::System,
+            }),
+            None => {
+                info!("{}", text.to_plain_text());
+                Ok(())
+            }
+        }
+    }
+
+    pub fn reply_text(&mut self, text: impl Into<String>) -> Result<(), KittyMCError> {
+        self.reply(Component::Text(TextComponent::builder().text(text).build()))
+    }
+
+    pub fn reply_error(&mut self, text: impl Into<String>) -> Result<(), KittyMCError> {
+        self.reply(Component::Text(
+            TextComponent::builder()
+                .text(text)
+                .options(BaseComponent::builder().color(Color::Red).build())
+                .build(),
+        ))
+    }
+
+    /// Sends `packet` to the player with `uuid`, who may be the one running the command
+    pub fn send_to<P: SerializablePacket + Debug + NamedPacket>(
+        &mut self,
+        server: &KittyMCServer,
+        uuid: &Uuid,
+        packet: &P,
+    ) -> Result<(), KittyMCError> {
+        match &mut self.client {
+            Some(client) if self.sender == CommandSender::Player(*uuid) => {
+                client.send_packet(packet)
+            }
+            _ => server.send_to(uuid, packet),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Parameter {
+    pub name: &'static str,
+    pub kind: ArgumentType,
+    /// Optional parameters can only come last
+    pub optional: bool,
+}
+
+impl Parameter {
+    pub const fn required(name: &'static str, kind: ArgumentType) -> Self {
+        Parameter {
+            name,
+            kind,
+            optional: false,
+        }
+    }
+
+    pub const fn optional(name: &'static str, kind: ArgumentType) -> Self {
+        Parameter {
+            name,
+            kind,
+            optional: true,
+        }
+    }
+
+    fn usage(&self) -> String {
+        let name = match self.kind {
+            ArgumentType::Coordinates => "x> <y> <z",
+            _ => self.name,
+        };
+        match self.optional {
+            true => format!("[{name}]"),
+            false => format!("<{name}>"),
+        }
+    }
+}
+
+/// The arguments of a command, by the name of their parameter
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Arguments {
+    values: Vec<(&'static str, Argument)>,
+}
+
+macro_rules! argument_getter {
+    ($fn_name:ident, $variant:ident, $ty:ty) => {
+        pub fn $fn_name(&self, name: &'static str) -> Result<$ty, CommandError> {
+            match self.get(name) {
+                Some(Argument::$variant(value)) => Ok(value.clone()),
+                _ => Err(CommandError::MissingArgument(name)),
+            }
+        }
+    };
+}
+
+impl Arguments {
+    pub fn get(&self, name: &str) -> Option<&Argument> {
+        self.values
+            .iter()
+            .find(|(param, _)| *param == name)
+            .map(|(_, value)| value)
+    }
+
+    argument_getter!(player, Player, Uuid);
+    argument_getter!(int, Int, i32);
+    argument_getter!(coordinates, Coordinates, Coordinates);
+    argument_getter!(block, Block, BlockStateId);
+    argument_getter!(game_mode, GameMode, GameMode);
+    argument_getter!(command, Command, String);
+    argument_getter!(text, Text, String);
+}
+
+pub type CommandExecutor =
+    fn(&mut KittyMCServer, &mut CommandContext, &Arguments) -> Result<(), CommandError>;
+
+#[derive(Debug, Clone)]
+pub struct Command {
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    pub description: &'static str,
+    pub permission: Permission,
+    /// The ways the command can be called, tried in order
+    pub signatures: &'static [&'static [Parameter]],
+    pub executor: CommandExecutor,
+}
+
+impl Command {
+    pub fn usage(&self) -> String {
+        self.signatures
+            .iter()
+            .map(|params| {
+                let mut usage = format!("{COMMAND_PREFIX}{}", self.name);
+                for param in params.iter() {
+                    usage.push(' ');
+                    usage.push_str(&param.usage());
+                }
+                usage
+            })
+            .collect::<Vec<_>>()
+            .join(" OR ")
+    }
+
+    fn is_called(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name)
+            || self
+                .aliases
+                .iter()
+                .any(|alias| alias.eq_ignore_ascii_case(name))
+    }
+
+    fn parse(&self, words: &[&str], ctx: &ArgumentContext) -> Result<Arguments, CommandError> {
+        // The error of the signature that got the furthest is the most helpful one
+        let mut best_error: Option<(usize, Option<CommandError>)> = None;
+
+        for params in self.signatures {
+            match parse_signature(params, words, ctx) {
+                Ok(arguments) => return Ok(arguments),
+                Err((pos, e)) => {
+                    if best_error.as_ref().is_none_or(|(best, _)| pos > *best) {
+                        best_error = Some((pos, e));
+                    }
+                }
+            }
+        }
+
+        Err(best_error
+            .and_then(|(_, e)| e)
+            .unwrap_or_else(|| CommandError::Usage(self.usage())))
+    }
+
+    /// Completes the last of `words`, which is the one being typed
+    fn complete(&self, words: &[&str], ctx: &ArgumentContext) -> Vec<String> {
+        let Some((word, typed)) = words.split_last() else {
+            return vec![];
+        };
+
+        let mut matches = vec![];
+        'signatures: for params in self.signatures {
+            let mut pos = 0;
+            for param in params.iter() {
+                let width = param.kind.width().unwrap_or(usize::MAX);
+                if typed.len() - pos < width {
+                    matches.extend(param.kind.complete(word, ctx));
+                    break;
+                }
+
+                // Arguments in front of the word have to be valid for the signature to apply
+                if param.kind.parse(&typed[pos..pos + width], ctx).is_err() {
+                    continue 'signatures;
+                }
+                pos += width;
+            }
+        }
+
+        matches
+    }
+}
+
+/// Returns how many words were parsed along with the error. The error is `None` if the number
+/// of words didn't fit.
+fn parse_signature(
+    params: &[Parameter],
+    words: &[&str],
+    ctx: &ArgumentContext,
+) -> Result<Arguments, (usize, Option<CommandError>)> {
+    let mut arguments = Arguments::default();
+    let mut pos = 0;
+
+    for param in params {
+        if pos == words.len() && param.optional {
+            break;
+        }
+
+        let width = param.kind.width().unwrap_or(words.len() - pos).max(1);
+        let Some(taken) = words.get(pos..pos + width) else {
+            return Err((pos, None));
+        };
+        let value = param.kind.parse(taken, ctx).map_err(|e| (pos, Some(e)))?;
+        arguments.values.push((param.name, value));
+        pos += width;
+    }
+
+    if pos != words.len() {
+        return Err((pos, None));
+    }
+
+    Ok(arguments)
+}
+
+/// A command that's ready to run
+#[derive(Debug)]
+pub struct ParsedCommand {
+    pub executor: CommandExecutor,
+    pub arguments: Arguments,
+}
+
+#[derive(Debug)]
+pub struct CommandRegistry {
+    commands: Vec<Command>,
+    permission_hook: PermissionHook,
+}
+
+#[allow(dead_code)]
+impl CommandRegistry {
+    pub fn new() -> Self {
+        CommandRegistry {
+            commands: vec![],
+            permission_hook: default_permission_hook,
+        }
+    }
+
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        for command in builtin::BUILTIN_COMMANDS {
+            registry.register(command.clone());
+        }
+        registry
+    }
+
+    /// Adds `command`, replacing a command of the same name
+    pub fn register(&mut self, command: Command) {
+        self.commands.retain(|c| c.name != command.name);
+        self.commands.push(command);
+    }
+
+    pub fn set_permission_hook(&mut self, hook: PermissionHook) {
+        self.permission_hook = hook;
+    }
+
+    /// Finds a command by its name or one of its aliases
+    pub fn get(&self, name: &str) -> Option<&Command> {
+        self.commands.iter().find(|command| command.is_called(name))
+    }
+
+    pub fn commands(&self) -> impl Iterator<Item = &Command> {
+        self.commands.iter()
+    }
+
+    pub fn can_use(
+        &self,
+        command: &Command,
+        sender: &CommandSender,
+        players: &HashMap<Uuid, Player>,
+    ) -> bool {
+        match sender {
+            CommandSender::Console => true,
+            CommandSender::Player(uuid) => players
+                .get(uuid)
+                .is_some_and(|player| (self.permission_hook)(player, command.permission)),
+        }
+    }
+
+    /// Parses a command line without the leading `/`
+    pub fn parse(
+        &self,
+        line: &str,
+        sender: CommandSender,
+        players: &HashMap<Uuid, Player>,
+    ) -> Result<ParsedCommand, CommandError> {
+        let mut words = line.split_whitespace();
+        let name = words.next().unwrap_or_default();
+        let command = self.get(name).ok_or(CommandError::UnknownCommand)?;
+
+        if !self.can_use(command, &sender, players) {
+            return Err(CommandError::NoPermission);
+        }
+
+        let ctx = ArgumentContext {
+            sender,
+            players,
+            commands: self,
+        };
+        let words: Vec<&str> = words.collect();
+
+        Ok(ParsedCommand {
+            executor: command.executor,
+            arguments: command.parse(&words, &ctx)?,
+        })
+    }
+
+    /// Completes the last word of a command line without the leading `/`
+    pub fn complete(
+        &self,
+        line: &str,
+        sender: CommandSender,
+        players: &HashMap<Uuid, Player>,
+    ) -> Vec<String> {
+        let ctx = ArgumentContext {
+            sender,
+            players,
+            commands: self,
+        };
+
+        let mut matches = match line.split_once(' ') {
+            None => self.complete_name(line, &ctx),
+            Some((name, rest)) => match self.get(name) {
+                Some(command) if self.can_use(command, &sender, players) => {
+                    let words: Vec<&str> = rest.split(' ').collect();
+                    command.complete(&words, &ctx)
+                }
+                _ => vec![],
+            },
+        };
+
+        matches.sort();
+        matches.dedup();
+        matches
+    }
+
+    /// Answers a tab completion request the way vanilla does. Chat messages only complete to
+    /// player names, and command names get their `/` back unless a block is targeted.
+    pub fn tab_complete(
+        &self,
+        request: &ServerTabCompletePacket,
+        sender: CommandSender,
+        players: &HashMap<Uuid, Player>,
+    ) -> Vec<String> {
+        let line = match request.text.strip_prefix(COMMAND_PREFIX) {
+            Some(line) => line,
+            None if request.assume_command => &request.text,
+            None => {
+                let word = request.text.rsplit(' ').next().unwrap_or_default();
+                let mut matches = complete_player(word, players);
+                matches.sort();
+                return matches;
+            }
+        };
+
+        let matches = self.complete(line, sender, players);
+        if line.contains(' ') || request.looked_at_block.is_some() {
+            return matches;
+        }
+
+        matches
+            .into_iter()
+            .map(|name| format!("{COMMAND_PREFIX}{name}"))
+            .collect()
+    }
+
+    fn complete_name(&self, word: &str, ctx: &ArgumentContext) -> Vec<String> {
+        self.commands
+            .iter()
+            .filter(|command| self.can_use(command, &ctx.sender, ctx.players))
+            .flat_map(|command| std::iter::once(&command.name).chain(command.aliases))
+            .filter(|name| starts_with_ignore_case(name, word))
+            .map(|name| name.to_string())
+            .collect()
+    }
+}
+
+impl Default for CommandRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kittymc_lib::subtypes::{Direction, Location, Location2};
+
+    fn player(name: &str) -> Player {
+        Player::new(
+            Uuid::new_v3(&Uuid::NAMESPACE_OID, name.as_bytes()),
+            name.to_string(),
+            0,
+            &Location2::new(0., 5., 0.),
+            &Direction::zeros(),
+            GameMode::Creative,
+        )
+    }
+
+    fn players(names: &[&str]) -> HashMap<Uuid, Player> {
+        names
+            .iter()
+            .map(|name| player(name))
+            .map(|player| (*player.uuid(), player))
+            .collect()
+    }
+
+    fn tab_complete(
+        text: &str,
+        registry: &CommandRegistry,
+        players: &HashMap<Uuid, Player>,
+    ) -> Vec<String> {
+        let request = ServerTabCompletePacket {
+            text: text.to_string(),
+            assume_command: false,
+            looked_at_block: None,
+        };
+        registry.tab_complete(&request, CommandSender::Console, players)
+    }
+
+    #[test]
+    fn test_parse_picks_matching_signature() {
+        let registry = CommandRegistry::with_builtins();
+        let players = players(&["MrMeow", "Purr"]);
+        let meow = *players
+            .values()
+            .find(|p| p.name() == "MrMeow")
+            .unwrap()
+            .uuid();
+
+        let parsed = registry
+            .parse("tp mrmeow 1 ~2 3", CommandSender::Console, &players)
+            .unwrap();
+        assert_eq!(parsed.arguments.player("target").unwrap(), meow);
+        assert!(parsed
+            .arguments
+            .coordinates("position")
+            .unwrap()
+            .is_relative());
+        assert!(parsed.arguments.player("destination").is_err());
+
+        let parsed = registry
+            .parse("TP purr mrmeow", CommandSender::Console, &players)
+            .unwrap();
+        assert_eq!(parsed.arguments.player("destination").unwrap(), meow);
+    }
+
+    #[test]
+    fn test_parse_optional_text() {
+        let registry = CommandRegistry::with_builtins();
+        let players = players(&["MrMeow"]);
+
+        let parsed = registry
+            .parse("kick MrMeow  being too  cute", CommandSender::Console, &players)
+            .unwrap();
+        assert_eq!(parsed.arguments.text("reason").unwrap(), "being too cute");
+
+        let parsed = registry
+            .parse("kick MrMeow", CommandSender::Console, &players)
+            .unwrap();
+        assert!(parsed.arguments.text("reason").is_err());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let registry = CommandRegistry::with_builtins();
+        let players = players(&["MrMeow"]);
+
+        assert!(matches!(
+            registry.parse("meow", CommandSender::Console, &players),
+            Err(CommandError::UnknownCommand)
+        ));
+        assert!(matches!(
+            registry.parse("gamemode", CommandSender::Console, &players),
+            Err(CommandError::Usage(usage)) if usage == "/gamemode <mode> [player]"
+        ));
+        assert!(matches!(
+            registry.parse("gamemode creative Nobody", CommandSender::Console, &players),
+            Err(CommandError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            registry.parse("setblock 1 2 3 stone 4 5", CommandSender::Console, &players),
+            Err(CommandError::Usage(_))
+        ));
+        assert!(matches!(
+            registry.parse("setblock 1 2 3 stone 16", CommandSender::Console, &players),
+            Err(CommandError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn test_permission_hook() {
+        let mut registry = CommandRegistry::with_builtins();
+        let mut players = players(&["Purr"]);
+        let purr_uuid = *players.keys().next().unwrap();
+        let purr = CommandSender::Player(purr_uuid);
+
+        assert!(matches!(
+            registry.parse("gamemode creative", purr, &players),
+            Err(CommandError::NoPermission)
+        ));
+        assert!(registry.parse("list", purr, &players).is_ok());
+        assert!(!tab_complete("/", &registry, &players).is_empty());
+        assert!(registry
+            .tab_complete(
+                &ServerTabCompletePacket {
+                    text: "/game".to_string(),
+                    assume_command: false,
+                    looked_at_block: None,
+                },
+                purr,
+                &players
+            )
+            .is_empty());
+
+        players.get_mut(&purr_uuid).unwrap().set_operator(true);
+        assert!(registry.parse("gamemode creative", purr, &players).is_ok());
+
+        players.get_mut(&purr_uuid).unwrap().set_operator(false);
+        registry.set_permission_hook(|_, _| true);
+        assert!(registry.parse("gamemode creative", purr, &players).is_ok());
+    }
+
+    #[test]
+    fn test_tab_complete() {
+        let registry = CommandRegistry::with_builtins();
+        let players = players(&["MrMeow", "MrPurr", "Kitty"]);
+
+        assert_eq!(tab_complete("/gam", &registry, &players), vec!["/gamemode"]);
+        assert_eq!(
+            tab_complete("/gamemode s", &registry, &players),
+            vec!["spectator", "survival"]
+        );
+        assert_eq!(
+            tab_complete("/gamemode creative mr", &registry, &players),
+            vec!["MrMeow", "MrPurr"]
+        );
+        assert_eq!(
+            tab_complete("/tp MrMeow ", &registry, &players),
+            vec!["Kitty", "MrMeow", "MrPurr", "~"]
+        );
+        assert_eq!(tab_complete("/tp 1 ", &registry, &players), vec!["~"]);
+        assert_eq!(
+            tab_complete("/setblock ~ ~ ~ minecraft:gold_", &registry, &players),
+            vec!["minecraft:gold_block", "minecraft:gold_ore"]
+        );
+        assert_eq!(tab_complete("hello k", &registry, &players), vec!["Kitty"]);
+
+        let request = ServerTabCompletePacket {
+            text: "gam".to_string(),
+            assume_command: true,
+            looked_at_block: Some(Location::new(0., 0., 0.)),
+        };
+        assert_eq!(
+            registry.tab_complete(&request, CommandSender::Console, &players),
+            vec!["gamemode"]
+        );
+    }
+}
@@ -0,0 +1,225 @@
+//! The socket of a client.
+//!
+//! Reading stays non-blocking on the main loop, but writes only put the bytes into a queue per
+//! connection. A task on the I/O runtime writes them to the socket whenever it can take more, so a
+//! slow client never holds up the game. How much may wait in a queue is bounded. A client that
+//! doesn't read fast enough overflows it and is given up on instead of being buffered for forever.
+
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::TcpStream;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::runtime::Handle;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+/// How many bytes may wait to be sent to one client. Joining with a large view distance queues a
+/// few megabytes of chunks at once, which has to fit.
+pub const MAX_QUEUED_BYTES: usize = 16 * 1024 * 1024;
+
+#[derive(Debug, Default)]
+struct QueueState {
+    queued_bytes: AtomicUsize,
+    overflowed: AtomicBool,
+    closed: AtomicBool,
+}
+
+/// Bytes on their way to a socket
+#[derive(Debug)]
+pub struct OutboundQueue {
+    sender: UnboundedSender<Vec<u8>>,
+    state: Arc<QueueState>,
+    limit: usize,
+}
+
+impl OutboundQueue {
+    /// Starts writing to `socket` on `runtime`. The socket has to be non-blocking.
+    pub fn start(socket: TcpStream, runtime: &Handle, limit: usize) -> io::Result<OutboundQueue> {
+        let socket = {
+            let _guard = runtime.enter();
+            tokio::net::TcpStream::from_std(socket)?
+        };
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let state = Arc::new(QueueState::default());
+
+        runtime.spawn(write_queued(socket, receiver, state.clone()));
+
+        Ok(OutboundQueue {
+            sender,
+            state,
+            limit,
+        })
+    }
+
+    /// How many bytes haven't made it to the socket yet
+    pub fn queued_bytes(&self) -> usize {
+        self.state.queued_bytes.load(Ordering::Acquire)
+    }
+
+    /// Whether more was queued than the limit allows. Nothing is sent anymore after that.
+    pub fn is_overflowed(&self) -> bool {
+        self.state.overflowed.load(Ordering::Acquire)
+    }
+}
+
+impl Write for OutboundQueue {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.is_overflowed() {
+            return Err(io::Error::other("outbound queue overflowed"));
+        }
+        if self.state.closed.load(Ordering::Acquire) {
+            return Err(ErrorKind::BrokenPipe.into());
+        }
+
+        let queued = self.state.queued_bytes.fetch_add(buf.len(), Ordering::AcqRel) + buf.len();
+        if queued > self.limit {
+            self.state.overflowed.store(true, Ordering::Release);
+            return Err(io::Error::other("outbound queue overflowed"));
+        }
+
+        self.sender
+            .send(buf.to_vec())
+            .map_err(|_| io::Error::from(ErrorKind::BrokenPipe))?;
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Writes everything that's queued until the queue is dropped, then closes the socket
+async fn write_queued(
+    mut socket: tokio::net::TcpStream,
+    mut receiver: UnboundedReceiver<Vec<u8>>,
+    state: Arc<QueueState>,
+) {
+    while let Some(data) = receiver.recv().await {
+        if state.overflowed.load(Ordering::Acquire) {
+            break;
+        }
+        if socket.write_all(&data).await.is_err() {
+            state.closed.store(true, Ordering::Release);
+            return;
+        }
+        state.queued_bytes.fetch_sub(data.len(), Ordering::AcqRel);
+    }
+
+    let _ = socket.shutdown().await;
+}
+
+/// Reads from a non-blocking socket and writes through an [`OutboundQueue`]
+#[derive(Debug)]
+pub struct Connection {
+    socket: TcpStream,
+    outbound: OutboundQueue,
+    runtime: Handle,
+}
+
+impl Connection {
+    pub fn new(socket: TcpStream, runtime: &Handle) -> io::Result<Connection> {
+        let outbound = OutboundQueue::start(socket.try_clone()?, runtime, MAX_QUEUED_BYTES)?;
+
+        Ok(Connection {
+            socket,
+            outbound,
+            runtime: runtime.clone(),
+        })
+    }
+
+    /// Another connection on the same socket, with its own queue
+    pub fn try_clone(&self) -> io::Result<Connection> {
+        Connection::new(self.socket.try_clone()?, &self.runtime)
+    }
+
+    pub fn outbound(&self) -> &OutboundQueue {
+        &self.outbound
+    }
+}
+
+impl Read for Connection {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.socket.read(buf)
+    }
+}
+
+impl Write for Connection {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.outbound.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.outbound.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::time::{Duration, Instant};
+    use tokio::runtime::{Builder, Runtime};
+
+    fn runtime() -> Runtime {
+        Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_io()
+            .build()
+            .unwrap()
+    }
+
+    fn socket_pair() -> (TcpStream, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = listener.accept().unwrap();
+        server.set_nonblocking(true).unwrap();
+        (server, client)
+    }
+
+    #[test]
+    fn test_queued_bytes_arrive_in_order() {
+        let runtime = runtime();
+        let (server, mut client) = socket_pair();
+        let mut queue = OutboundQueue::start(server, runtime.handle(), MAX_QUEUED_BYTES).unwrap();
+
+        queue.write_all(b"meow ").unwrap();
+        queue.write_all(b"mrrp").unwrap();
+        drop(queue);
+
+        // The socket is closed once everything was written
+        let mut received = vec![];
+        client.read_to_end(&mut received).unwrap();
+        assert_eq!(received, b"meow mrrp");
+    }
+
+    #[test]
+    fn test_queue_drains() {
+        let runtime = runtime();
+        let (server, mut client) = socket_pair();
+        let mut queue = OutboundQueue::start(server, runtime.handle(), MAX_QUEUED_BYTES).unwrap();
+
+        queue.write_all(&[7; 1000]).unwrap();
+        let mut received = [0; 1000];
+        client.read_exact(&mut received).unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while queue.queued_bytes() != 0 && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(queue.queued_bytes(), 0);
+    }
+
+    #[test]
+    fn test_overflow_stops_the_queue() {
+        let runtime = runtime();
+        let (server, _client) = socket_pair();
+        let mut queue = OutboundQueue::start(server, runtime.handle(), 16).unwrap();
+
+        assert!(queue.write_all(&[0; 8]).is_ok());
+        assert!(!queue.is_overflowed());
+        assert!(queue.write_all(&[0; 64]).is_err());
+        assert!(queue.is_overflowed());
+        assert!(queue.write_all(&[0; 1]).is_err());
+    }
+}
@@ -0,0 +1,143 @@
+//! The operator console on stdin.
+//!
+//! Lines are read on a thread of their own, with line editing and history, and handed to the main
+//! loop which runs them as commands. While the prompt is shown, log output is printed above it
+//! instead of into the line that's being typed.
+
+use kittymc_lib::error::KittyMCError;
+use rustyline::error::ReadlineError;
+use rustyline::{DefaultEditor, ExternalPrinter};
+use std::io::{self, Write};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Mutex;
+use std::thread;
+use tracing::{debug, error};
+use tracing_subscriber::fmt::MakeWriter;
+
+const PROMPT: &str = "> ";
+/// What Ctrl-C in the console runs
+const INTERRUPT_COMMAND: &str = "stop";
+
+/// Where log lines go while the console is running
+static LOG_PRINTER: Mutex<Option<Box<dyn ExternalPrinter + Send>>> = Mutex::new(None);
+
+/// Makes the log print above the console prompt. Without a console it prints to stdout.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConsoleLogWriter;
+
+impl<'a> MakeWriter<'a> for ConsoleLogWriter {
+    type Writer = LogLine;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        LogLine { buffer: vec![] }
+    }
+}
+
+/// Collects one log event and prints it in one go once it's dropped
+pub struct LogLine {
+    buffer: Vec<u8>,
+}
+
+impl Write for LogLine {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for LogLine {
+    fn drop(&mut self) {
+        if self.buffer.is_empty() {
+            return;
+        }
+
+        let mut printer = LOG_PRINTER.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(printer) = printer.as_mut() {
+            let line = String::from_utf8_lossy(&self.buffer).into_owned();
+            if printer.print(line).is_ok() {
+                return;
+            }
+        }
+        drop(printer);
+
+        let _ = io::stdout().write_all(&self.buffer);
+    }
+}
+
+#[derive(Debug)]
+pub struct Console {
+    lines: Receiver<String>,
+    #[cfg(unix)]
+    terminal_mode: Option<nix::sys::termios::Termios>,
+}
+
+impl Console {
+    /// Starts reading stdin. Log output only moves above the prompt if stdin and stdout are
+    /// terminals.
+    pub fn start() -> Result<Console, KittyMCError> {
+        #[cfg(unix)]
+        let terminal_mode = nix::sys::termios::tcgetattr(io::stdin()).ok();
+
+        let mut editor = DefaultEditor::new().map_err(io::Error::other)?;
+        match editor.create_external_printer() {
+            Ok(printer) => {
+                *LOG_PRINTER.lock().unwrap_or_else(|e| e.into_inner()) = Some(Box::new(printer))
+            }
+            Err(e) => debug!("Console output can't be kept apart from the prompt: {e}"),
+        }
+
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || loop {
+            let line = match editor.readline(PROMPT) {
+                Ok(line) => line.trim().to_string(),
+                Err(ReadlineError::Interrupted) => INTERRUPT_COMMAND.to_string(),
+                Err(ReadlineError::Eof) => break,
+                Err(e) => {
+                    error!("Console stopped reading input: {e}");
+                    break;
+                }
+            };
+            if line.is_empty() {
+                continue;
+            }
+
+            let _ = editor.add_history_entry(line.as_str());
+            if sender.send(line).is_err() {
+                break;
+            }
+        });
+
+        Ok(Console {
+            lines,
+            #[cfg(unix)]
+            terminal_mode,
+        })
+    }
+
+    /// The next line that was entered, if there is one
+    pub fn next_line(&self) -> Option<String> {
+        self.lines.try_recv().ok()
+    }
+}
+
+impl Drop for Console {
+    fn drop(&mut self) {
+        *LOG_PRINTER.lock().unwrap_or_else(|e| e.into_inner()) = None;
+
+        // The reading thread is most likely still waiting for input with the terminal in raw
+        // mode, which would outlive the server otherwise
+        #[cfg(unix)]
+        if let Some(mode) = &self.terminal_mode {
+            let _ = nix::sys::termios::tcsetattr(
+                io::stdin(),
+                nix::sys::termios::SetArg::TCSANOW,
+                mode,
+            );
+            println!();
+        }
+    }
+}
@@ -1,10 +1,53 @@
+use kittymc_lib::packets::client::play::window_items_14::SlotData;
+use kittymc_lib::packets::client::play::WindowItemsPacket;
+use kittymc_lib::subtypes::item_nbt::ItemNbt;
+use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+/// Crafting grid, armor, main inventory, hotbar and off hand
+pub const PLAYER_INVENTORY_SIZE: i16 = 46;
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct ItemStack {
     pub item_id: u16,
     pub damage: u16,
     pub count: u8,
+    pub nbt: Option<ItemNbt>,
+}
+
+#[allow(dead_code)]
+impl ItemStack {
+    pub fn new(item_id: u16, count: u8) -> Self {
+        ItemStack {
+            item_id,
+            damage: 0,
+            count,
+            nbt: None,
+        }
+    }
+
+    /// Returns `None` for an empty slot
+    pub fn from_slot(slot: &SlotData) -> Option<Self> {
+        if slot.id == u16::MAX {
+            return None;
+        }
+
+        Some(ItemStack {
+            item_id: slot.id,
+            damage: slot.item_damage,
+            count: slot.item_count,
+            nbt: slot.nbt.clone(),
+        })
+    }
+
+    pub fn to_slot(&self) -> SlotData {
+        SlotData {
+            id: self.item_id,
+            item_count: self.count,
+            item_damage: self.damage,
+            nbt: self.nbt.clone(),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -40,9 +83,9 @@ impl Inventory {
 
         // Second pass: fill empty slots
         for slot_num in 0..=35 {
-            if !self.slots.contains_key(&slot_num) {
+            if let Entry::Vacant(e) = self.slots.entry(slot_num) {
                 let add = count.min(64);
-                self.slots.insert(slot_num, ItemStack { item_id, count: add, damage: 0 });
+                e.insert(ItemStack::new(item_id, add));
                 count -= add;
                 if count == 0 {
                     return 0;
@@ -80,14 +123,9 @@ impl Inventory {
 
     pub fn get_item_count(&self, item_id: u16) -> u32 {
         self.slots
-            .iter()
-            .filter_map(|(_, slot)| {
-                if slot.item_id == item_id {
-                    Some(slot.count as u32)
-                } else {
-                    None
-                }
-            })
+            .values()
+            .filter(|slot| slot.item_id == item_id)
+            .map(|slot| slot.count as u32)
             .sum()
     }
 
@@ -107,6 +145,23 @@ impl Inventory {
         };
     }
 
+    pub fn slots(&self) -> impl Iterator<Item = (i16, &ItemStack)> {
+        self.slots.iter().map(|(index, item)| (*index, item))
+    }
+
+    /// The whole player inventory window as the client expects it
+    pub fn to_window_items(&self) -> WindowItemsPacket {
+        WindowItemsPacket {
+            window_id: 0,
+            slot_data: (0..PLAYER_INVENTORY_SIZE)
+                .map(|index| match self.slots.get(&index) {
+                    Some(item) => item.to_slot(),
+                    None => SlotData::default(),
+                })
+                .collect(),
+        }
+    }
+
     pub fn find_item_slot(&self, item_id: u16) -> Option<i16> {
         self.slots
             .iter()
@@ -160,16 +215,16 @@ mod tests {
     #[test]
     fn slot_management() {
         let mut inv = Inventory::new();
-        inv.set_slot(0, Some(ItemStack { item_id: 1, damage: 0, count: 10 }));
-        inv.set_slot(255, Some(ItemStack { item_id: 2, damage: 0, count: 20 }));
+        inv.set_slot(0, Some(ItemStack::new(1, 10)));
+        inv.set_slot(255, Some(ItemStack::new(2, 20)));
 
         assert_eq!(
             inv.get_slot(0),
-            Some(ItemStack { item_id: 1, damage: 0, count: 10 })
+            Some(ItemStack::new(1, 10))
         );
         assert_eq!(
             inv.get_slot(255),
-            Some(ItemStack { item_id: 2, damage: 0, count: 20 })
+            Some(ItemStack::new(2, 20))
         );
     }
 
@@ -177,9 +232,27 @@ mod tests {
     fn inventory_capacity() {
         let mut inv = Inventory::new();
         for i in 0..=35 {
-            inv.set_slot(i, Some(ItemStack { item_id: 1, damage: 0, count: 64 }));
+            inv.set_slot(i, Some(ItemStack::new(1, 64)));
         }
         assert!(inv.is_full());
         assert_eq!(inv.add_item(1, 1), 1);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn item_nbt_survives_slot_round_trip() {
+        let mut nbt = ItemNbt::new();
+        nbt.set_display_name(Some("Kitty Pick"));
+        nbt.set_unbreakable(true);
+
+        let slot = SlotData {
+            id: 278,
+            item_count: 1,
+            item_damage: 0,
+            nbt: Some(nbt),
+        };
+
+        let item = ItemStack::from_slot(&slot).unwrap();
+        assert_eq!(item.to_slot(), slot);
+        assert_eq!(ItemStack::from_slot(&SlotData::default()), None);
+    }
+}
@@ -0,0 +1,154 @@
+//! What a world is besides its chunks, kept in a gzipped level.dat the way vanilla does it.
+//!
+//! Only the fields kittymc uses are read back, but the file is complete enough for vanilla to open
+//! the world, which is how exported worlds get theirs.
+
+use crate::chunking::anvil::{compress_gzip, decompress_gzip, DATA_VERSION};
+use fastnbt::nbt;
+use kittymc_lib::error::KittyMCError;
+use kittymc_lib::packets::client::play::GameMode;
+use kittymc_lib::utils::write_file_atomically;
+use serde::Deserialize;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The level.dat format version Anvil worlds have
+const ANVIL_LEVEL_VERSION: i32 = 19133;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct LevelInfo {
+    #[serde(rename = "LevelName")]
+    pub name: String,
+    #[serde(rename = "RandomSeed")]
+    pub seed: i64,
+    /// Which generator the world is continued with, like `default` or `flat`
+    #[serde(rename = "generatorName")]
+    pub generator_name: String,
+    #[serde(rename = "generatorOptions", default)]
+    pub generator_options: String,
+    #[serde(rename = "SpawnX")]
+    pub spawn_x: i32,
+    #[serde(rename = "SpawnY")]
+    pub spawn_y: i32,
+    #[serde(rename = "SpawnZ")]
+    pub spawn_z: i32,
+    #[serde(rename = "GameType")]
+    pub game_type: i32,
+    /// The age of the world in ticks
+    #[serde(rename = "Time")]
+    pub time: i64,
+    #[serde(rename = "DayTime")]
+    pub day_time: i64,
+}
+
+#[derive(Deserialize)]
+struct LevelDat {
+    #[serde(rename = "Data")]
+    data: LevelInfo,
+}
+
+impl LevelInfo {
+    /// A world that wasn't played on yet. The spawn is left at 0, 0, 0 until the generator is
+    /// asked where the ground is.
+    pub fn new(name: impl Into<String>, generator_name: impl Into<String>, seed: i64) -> LevelInfo {
+        LevelInfo {
+            name: name.into(),
+            seed,
+            generator_name: generator_name.into(),
+            generator_options: String::new(),
+            spawn_x: 0,
+            spawn_y: 0,
+            spawn_z: 0,
+            game_type: GameMode::Creative as i32,
+            time: 0,
+            day_time: 0,
+        }
+    }
+
+    pub fn file_path(world_dir: &Path) -> PathBuf {
+        world_dir.join("level.dat")
+    }
+
+    /// Returns `None` if the world has no level.dat yet
+    pub fn load(world_dir: &Path) -> Result<Option<LevelInfo>, KittyMCError> {
+        let data = match fs::read(Self::file_path(world_dir)) {
+            Ok(data) => data,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let level: LevelDat = fastnbt::from_bytes(&decompress_gzip(&data)?)?;
+        Ok(Some(level.data))
+    }
+
+    pub fn save(&self, world_dir: &Path) -> Result<(), KittyMCError> {
+        let last_played = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_millis() as i64);
+
+        let nbt = fastnbt::to_bytes(&nbt!({
+            "Data": {
+                "version": ANVIL_LEVEL_VERSION,
+                "DataVersion": DATA_VERSION,
+                "Version": {
+                    "Id": DATA_VERSION,
+                    "Name": "1.12.2",
+                    "Snapshot": 0i8,
+                },
+                "initialized": 1i8,
+                "LevelName": self.name.as_str(),
+                "RandomSeed": self.seed,
+                "generatorName": self.generator_name.as_str(),
+                "generatorVersion": 1,
+                "generatorOptions": self.generator_options.as_str(),
+                "MapFeatures": 1i8,
+                "GameType": self.game_type,
+                "hardcore": 0i8,
+                "allowCommands": 1i8,
+                "SpawnX": self.spawn_x,
+                "SpawnY": self.spawn_y,
+                "SpawnZ": self.spawn_z,
+                "Time": self.time,
+                "DayTime": self.day_time,
+                "LastPlayed": last_played,
+                "raining": 0i8,
+                "thundering": 0i8,
+            },
+        }))?;
+
+        fs::create_dir_all(world_dir)?;
+        write_file_atomically(&Self::file_path(world_dir), &compress_gzip(&nbt))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kittymc_lib::utils::TempDir;
+
+    #[test]
+    fn test_level_survives_saving() {
+        let world = TempDir::new("kittymc-level").unwrap();
+        let world = world.path();
+        assert_eq!(LevelInfo::load(world).unwrap(), None);
+
+        let level = LevelInfo {
+            name: "meow".to_string(),
+            seed: -1234,
+            generator_name: "default".to_string(),
+            generator_options: String::new(),
+            spawn_x: 3,
+            spawn_y: 70,
+            spawn_z: -8,
+            game_type: 1,
+            time: 500,
+            day_time: 300,
+        };
+        level.save(world).unwrap();
+        assert_eq!(LevelInfo::load(world).unwrap(), Some(level));
+    }
+}
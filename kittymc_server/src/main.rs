@@ -1,11 +1,27 @@
 mod client;
 mod player;
+mod player_data;
 mod server;
 mod inventory;
+mod level;
 mod chunking;
+mod command;
+mod connection;
+mod console;
+mod tick;
+mod tracker;
+mod operators;
 
 use log::error;
-use crate::server::KittyMCServer;
+use crate::chunking::world_generator::STRIPED_GENERATOR;
+use crate::console::{Console, ConsoleLogWriter};
+use crate::level::LevelInfo;
+use crate::server::{KittyMCServer, WORLD_DIR};
+use kittymc_lib::session::HttpSessionVerifier;
+use rand::random;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
 use tracing::metadata::LevelFilter;
 use tracing_subscriber::EnvFilter;
 
@@ -21,9 +37,28 @@ fn main() {
         .with_target(false)
         .with_line_number(false)
         .with_file(false)
+        .with_writer(ConsoleLogWriter)
         .init();
 
-    let mut server = match KittyMCServer::new(25565) {
+    let args: Vec<String> = std::env::args().collect();
+    // Only used if there's no world yet, an existing world keeps its generator and seed
+    let generator = arg_value(&args, "--generator").unwrap_or(STRIPED_GENERATOR);
+    let seed = match arg_value(&args, "--seed").map(str::parse).transpose() {
+        Ok(seed) => seed.unwrap_or_else(random),
+        Err(e) => {
+            error!("Invalid seed, expected a number: {e}");
+            return;
+        }
+    };
+
+    let new_level = LevelInfo {
+        generator_options: arg_value(&args, "--generator-options")
+            .unwrap_or_default()
+            .to_string(),
+        ..LevelInfo::new(WORLD_DIR, generator, seed)
+    };
+
+    let mut server = match KittyMCServer::new(25565, new_level) {
         Ok(server) => server,
         Err(e) => {
             error!("Error while trying to start the server: {e}");
@@ -31,7 +66,43 @@ fn main() {
         }
     };
 
+    if std::env::args().any(|arg| arg == "--online-mode") {
+        if let Err(e) = server.enable_online_mode(Arc::new(HttpSessionVerifier::mojang())) {
+            error!("Couldn't enable online mode: {e}");
+            return;
+        }
+    }
+
+    if let Some(seconds) = arg_value(&args, "--autosave-interval") {
+        match seconds.parse() {
+            Ok(seconds) => server.set_autosave_interval(Duration::from_secs(seconds)),
+            Err(e) => {
+                error!("Invalid autosave interval {seconds:?}, expected seconds: {e}");
+                return;
+            }
+        }
+    }
+
+    if let Some(world_dir) = arg_value(&args, "--anvil-world") {
+        if let Err(e) = server.use_anvil_world(Path::new(world_dir)) {
+            error!("Couldn't use the Anvil world: {e}");
+            return;
+        }
+    }
+
+    match Console::start() {
+        Ok(console) => server.attach_console(console),
+        Err(e) => error!("Couldn't start the console: {e}"),
+    }
+
     if let Err(e) = server.run() {
         error!("Error occurred while server was running: {e}");
     };
 } 
+
+/// What comes after `name` in the arguments, or an empty string if `name` is the last one
+fn arg_value<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == name)
+        .map(|i| args.get(i + 1).map(String::as_str).unwrap_or_default())
+}
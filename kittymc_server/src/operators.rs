@@ -0,0 +1,134 @@
+//! The players that may run operator commands, kept in the world's ops.json.
+//!
+//! The file has the same layout as vanilla's, so it can be edited by hand or copied from a vanilla
+//! server. Players are matched by their UUID, the name is only there to make the file readable.
+
+use kittymc_lib::error::KittyMCError;
+use kittymc_lib::utils::write_file_atomically;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+pub const OPERATORS_FILE: &str = "world/ops.json";
+/// Vanilla's highest permission level, which is the only one kittymc knows
+const OPERATOR_LEVEL: i32 = 4;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Operator {
+    pub uuid: Uuid,
+    pub name: String,
+    #[serde(default = "default_level")]
+    pub level: i32,
+}
+
+fn default_level() -> i32 {
+    OPERATOR_LEVEL
+}
+
+#[derive(Debug)]
+pub struct OperatorList {
+    path: PathBuf,
+    operators: Vec<Operator>,
+}
+
+#[allow(dead_code)]
+impl OperatorList {
+    /// Reads the list at `path`. Nobody is an operator if there's no file yet.
+    pub fn load(path: impl Into<PathBuf>) -> Result<OperatorList, KittyMCError> {
+        let path = path.into();
+        let operators = match fs::read(&path) {
+            Ok(data) => serde_json::from_slice(&data)?,
+            Err(e) if e.kind() == ErrorKind::NotFound => vec![],
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(OperatorList { path, operators })
+    }
+
+    pub fn save(&self) -> Result<(), KittyMCError> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_vec_pretty(&self.operators)?;
+        write_file_atomically(&self.path, &json)?;
+
+        Ok(())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn is_operator(&self, uuid: &Uuid) -> bool {
+        self.operators.iter().any(|operator| operator.uuid == *uuid)
+    }
+
+    pub fn operators(&self) -> &[Operator] {
+        &self.operators
+    }
+
+    /// Returns false if the player already was an operator
+    pub fn add(&mut self, uuid: Uuid, name: &str) -> bool {
+        if self.is_operator(&uuid) {
+            return false;
+        }
+
+        self.operators.push(Operator {
+            uuid,
+            name: name.to_string(),
+            level: OPERATOR_LEVEL,
+        });
+        true
+    }
+
+    /// Returns false if the player wasn't an operator
+    pub fn remove(&mut self, uuid: &Uuid) -> bool {
+        let count = self.operators.len();
+        self.operators.retain(|operator| operator.uuid != *uuid);
+        self.operators.len() != count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kittymc_lib::utils::TempDir;
+
+    #[test]
+    fn test_operators_survive_saving() {
+        let dir = TempDir::new("kittymc-ops").unwrap();
+        let path = dir.join("ops.json");
+        let (meow, purr) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let mut operators = OperatorList::load(&path).unwrap();
+        assert!(!operators.is_operator(&meow));
+        assert!(operators.add(meow, "MrMeow"));
+        assert!(!operators.add(meow, "MrMeow"));
+        assert!(operators.add(purr, "MrPurr"));
+        assert!(operators.remove(&purr));
+        assert!(!operators.remove(&purr));
+        operators.save().unwrap();
+
+        let loaded = OperatorList::load(&path).unwrap();
+        assert!(loaded.is_operator(&meow));
+        assert!(!loaded.is_operator(&purr));
+        assert_eq!(loaded.operators(), operators.operators());
+    }
+
+    #[test]
+    fn test_vanilla_ops_file() {
+        let dir = TempDir::new("kittymc-ops").unwrap();
+        let path = dir.join("ops.json");
+        fs::write(
+            &path,
+            r#"[{"uuid": "069a79f4-44e9-4726-a5be-fca90e38aaf5", "name": "Notch", "level": 4,
+                 "bypassesPlayerLimit": false}]"#,
+        )
+        .unwrap();
+
+        let notch = Uuid::parse_str("069a79f4-44e9-4726-a5be-fca90e38aaf5").unwrap();
+        assert!(OperatorList::load(&path).unwrap().is_operator(&notch));
+    }
+}
@@ -1,4 +1,5 @@
 use crate::client::ClientInfo;
+use kittymc_lib::packets::client::play::player_list_item_2e::PlayerListItemProperties;
 use kittymc_lib::packets::client::play::GameMode;
 use kittymc_lib::subtypes::{Direction, Location2};
 use uuid::Uuid;
@@ -6,6 +7,9 @@ use kittymc_lib::subtypes::metadata::{EntityMetaState, PlayerMetadata};
 use kittymc_lib::utils::is_cool;
 use crate::inventory::Inventory;
 
+pub const HOTBAR_SIZE: i16 = 9;
+pub const MAX_HEALTH: f32 = 20.;
+
 #[derive(Debug)]
 pub struct Player {
     uuid: Uuid,
@@ -13,12 +17,16 @@ pub struct Player {
     entity_id: i32,
     position: Location2,
     direction: Direction,
+    /// Where the other players last saw this one
     last_position: Location2,
     last_direction: Direction,
     game_mode: GameMode,
     pub inventory: Inventory,
     current_slot: i16,
     state: PlayerMetadata,
+    properties: Vec<PlayerListItemProperties>,
+    /// If the player is on the server's operator list
+    operator: bool,
 }
 
 #[allow(dead_code)]
@@ -30,14 +38,16 @@ impl Player {
         direction: &Direction,
         game_mode: GameMode,
     ) -> Self {
-        Self::new(
+        let mut player = Self::new(
             client_info.uuid,
             client_info.username,
             id,
             position,
             direction,
             game_mode,
-        )
+        );
+        player.properties = client_info.properties;
+        player
     }
 
     pub fn new(
@@ -48,6 +58,9 @@ impl Player {
         direction: &Direction,
         game_mode: GameMode,
     ) -> Self {
+        let mut state = PlayerMetadata::default();
+        state.living.health = MAX_HEALTH;
+
         Self {
             uuid,
             username,
@@ -59,7 +72,9 @@ impl Player {
             game_mode,
             inventory: Inventory::new(),
             current_slot: 0,
-            state: Default::default(),
+            state,
+            properties: vec![],
+            operator: false,
         }
     }
 
@@ -71,6 +86,11 @@ impl Player {
         &self.username
     }
 
+    /// The skin and cape other players are told about
+    pub fn properties(&self) -> &[PlayerListItemProperties] {
+        &self.properties
+    }
+
     pub fn id(&self) -> i32 {
         self.entity_id
     }
@@ -80,7 +100,6 @@ impl Player {
     }
 
     pub fn set_position(&mut self, position: &Location2) {
-        self.last_position = self.position;
         self.position = *position;
     }
 
@@ -89,7 +108,6 @@ impl Player {
     }
 
     pub fn set_direction(&mut self, direction: &Direction) {
-        self.last_direction = self.direction;
         self.direction = *direction;
     }
 
@@ -101,6 +119,24 @@ impl Player {
         &self.last_direction
     }
 
+    /// Whether the player moved since the others were last told where it is
+    pub fn has_moved(&self) -> bool {
+        self.position != self.last_position
+    }
+
+    pub fn has_rotated(&self) -> bool {
+        self.direction != self.last_direction
+    }
+
+    /// Remembers the current position as the one the other players know about
+    pub fn sync_position(&mut self) {
+        self.last_position = self.position;
+    }
+
+    pub fn sync_direction(&mut self) {
+        self.last_direction = self.direction;
+    }
+
     pub fn game_mode(&self) -> GameMode {
         self.game_mode
     }
@@ -125,10 +161,26 @@ impl Player {
         is_cool(&self.username)
     }
 
+    pub fn is_operator(&self) -> bool {
+        self.operator
+    }
+
+    pub fn set_operator(&mut self, operator: bool) {
+        self.operator = operator;
+    }
+
     pub fn get_state(&self) -> &PlayerMetadata {
         &self.state
     }
 
+    pub fn health(&self) -> f32 {
+        self.state.living.health
+    }
+
+    pub fn set_health(&mut self, health: f32) {
+        self.state.living.health = health;
+    }
+
     pub fn set_crouching(&mut self, is_crouching: bool) {
         self.state.living.entity.meta_state.set(EntityMetaState::crouched, is_crouching);
     }
@@ -0,0 +1,278 @@
+//! What's kept about a player between sessions.
+//!
+//! Every player gets an uncompressed NBT file named after their UUID. The files carry a format
+//! version, so older ones can be upgraded when the format changes, while files from a newer server
+//! are refused instead of being misread.
+
+use crate::inventory::{ItemStack, PLAYER_INVENTORY_SIZE};
+use crate::player::{Player, HOTBAR_SIZE, MAX_HEALTH};
+use fastnbt::Value;
+use kittymc_lib::error::KittyMCError;
+use kittymc_lib::packets::client::play::GameMode;
+use kittymc_lib::subtypes::item_nbt::ItemNbt;
+use kittymc_lib::subtypes::{Direction, Location2};
+use kittymc_lib::utils::write_file_atomically;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+pub const PLAYER_DATA_DIR: &str = "world/playerdata";
+pub const PLAYER_DATA_VERSION: i32 = 1;
+const PLAYER_DATA_FORMAT: &str = "player data";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SavedItem {
+    pub slot: i16,
+    pub id: i16,
+    pub damage: i16,
+    pub count: i8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct PlayerData {
+    pub version: i32,
+    pub position: [f64; 3],
+    pub rotation: [f32; 2],
+    pub game_mode: i8,
+    pub selected_slot: i16,
+    pub health: f32,
+    pub inventory: Vec<SavedItem>,
+}
+
+/// Just enough of any version to tell which one it is
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct VersionHeader {
+    version: i32,
+}
+
+impl PlayerData {
+    pub fn from_player(player: &Player) -> PlayerData {
+        let position = player.position();
+        let direction = player.direction();
+
+        let mut inventory: Vec<SavedItem> = player
+            .inventory
+            .slots()
+            .map(|(slot, item)| SavedItem {
+                slot,
+                id: item.item_id as i16,
+                damage: item.damage as i16,
+                count: item.count as i8,
+                tag: item.nbt.as_ref().map(ItemNbt::to_value),
+            })
+            .collect();
+        inventory.sort_by_key(|item| item.slot);
+
+        PlayerData {
+            version: PLAYER_DATA_VERSION,
+            position: [position.x, position.y, position.z],
+            rotation: [direction.x, direction.y],
+            game_mode: player.game_mode() as i8,
+            selected_slot: player.current_slot(),
+            health: player.health(),
+            inventory,
+        }
+    }
+
+    /// Files can be edited by hand, so slots the client doesn't have are left out and the health
+    /// is kept in range
+    pub fn apply_to(&self, player: &mut Player) -> Result<(), KittyMCError> {
+        let [x, y, z] = self.position;
+        let [yaw, pitch] = self.rotation;
+        let position = Location2::new(x, y, z);
+        let direction = Direction::new(yaw, pitch);
+
+        player.set_position(&position);
+        player.sync_position();
+        player.set_direction(&direction);
+        player.sync_direction();
+        player.set_game_mode(GameMode::try_from(self.game_mode as u8)?);
+        if (0..HOTBAR_SIZE).contains(&self.selected_slot) {
+            player.set_current_slot(self.selected_slot);
+        } else {
+            warn!("Ignoring the selected slot {} of {}", self.selected_slot, player.name());
+        }
+        player.set_health(match self.health.is_nan() {
+            true => MAX_HEALTH,
+            false => self.health.clamp(0., MAX_HEALTH),
+        });
+
+        for item in &self.inventory {
+            if !(0..PLAYER_INVENTORY_SIZE).contains(&item.slot) {
+                warn!("Ignoring an item in slot {} of {}", item.slot, player.name());
+                continue;
+            }
+
+            let nbt = item.tag.clone().map(ItemNbt::from_value).transpose()?;
+            player.inventory.set_slot(
+                item.slot,
+                Some(ItemStack {
+                    item_id: item.id as u16,
+                    damage: item.damage as u16,
+                    count: item.count as u8,
+                    nbt,
+                }),
+            );
+        }
+
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, KittyMCError> {
+        Ok(fastnbt::to_bytes(self)?)
+    }
+
+    /// Reads any version up to the current one
+    pub fn from_bytes(data: &[u8]) -> Result<PlayerData, KittyMCError> {
+        let header: VersionHeader = fastnbt::from_bytes(data)?;
+
+        match header.version {
+            PLAYER_DATA_VERSION => Ok(fastnbt::from_bytes(data)?),
+            version => Err(KittyMCError::UnsupportedVersion(
+                PLAYER_DATA_FORMAT,
+                version,
+            )),
+        }
+    }
+
+    pub fn file_path(dir: &Path, uuid: &Uuid) -> PathBuf {
+        dir.join(format!("{uuid}.dat"))
+    }
+
+    /// Returns `None` if the player never played here
+    pub fn load(dir: &Path, uuid: &Uuid) -> Result<Option<PlayerData>, KittyMCError> {
+        match fs::read(Self::file_path(dir, uuid)) {
+            Ok(data) => Self::from_bytes(&data).map(Some),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Writes to a temporary file first, so a crash can't leave a half written file behind
+    pub fn save(&self, dir: &Path, uuid: &Uuid) -> Result<(), KittyMCError> {
+        fs::create_dir_all(dir)?;
+
+        write_file_atomically(&Self::file_path(dir, uuid), &self.to_bytes()?)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kittymc_lib::utils::{temp_file_path, TempDir};
+
+    fn test_player() -> Player {
+        Player::new(
+            Uuid::new_v4(),
+            "MrMeow".to_string(),
+            0,
+            &Location2::new(0., 5., 0.),
+            &Direction::zeros(),
+            GameMode::Creative,
+        )
+    }
+
+    #[test]
+    fn test_player_data_round_trip() {
+        let mut player = test_player();
+        player.set_position(&Location2::new(12.5, 70., -3.25));
+        player.set_direction(&Direction::new(90., -10.));
+        player.set_game_mode(GameMode::Survival);
+        player.set_current_slot(4);
+        player.set_health(13.5);
+
+        let mut nbt = ItemNbt::new();
+        nbt.set_display_name(Some("Whiskers"));
+        player.inventory.set_slot(
+            36,
+            Some(ItemStack {
+                item_id: 276,
+                damage: 12,
+                count: 1,
+                nbt: Some(nbt),
+            }),
+        );
+        player.inventory.set_slot(9, Some(ItemStack::new(1, 64)));
+
+        let data = PlayerData::from_player(&player);
+        let loaded = PlayerData::from_bytes(&data.to_bytes().unwrap()).unwrap();
+        assert_eq!(loaded, data);
+
+        let mut restored = test_player();
+        loaded.apply_to(&mut restored).unwrap();
+        assert_eq!(restored.position(), player.position());
+        assert!(!restored.has_moved());
+        assert_eq!(restored.direction(), player.direction());
+        assert_eq!(restored.game_mode(), GameMode::Survival);
+        assert_eq!(restored.current_slot(), 4);
+        assert_eq!(restored.health(), 13.5);
+        assert_eq!(restored.inventory.get_slot(36), player.inventory.get_slot(36));
+        assert_eq!(restored.inventory.get_slot(9), player.inventory.get_slot(9));
+        assert_eq!(
+            restored.inventory.get_slot(36).unwrap().nbt.unwrap().display_name(),
+            Some("Whiskers")
+        );
+    }
+
+    #[test]
+    fn test_invalid_values_are_left_out() {
+        let mut data = PlayerData::from_player(&test_player());
+        data.selected_slot = 9;
+        data.health = f32::NAN;
+        for slot in [-1, 5, PLAYER_INVENTORY_SIZE] {
+            data.inventory.push(SavedItem {
+                slot,
+                id: 1,
+                damage: 0,
+                count: 64,
+                tag: None,
+            });
+        }
+
+        let mut player = test_player();
+        player.set_current_slot(3);
+        data.apply_to(&mut player).unwrap();
+        assert_eq!(player.current_slot(), 3);
+        assert_eq!(player.health(), MAX_HEALTH);
+        assert_eq!(player.inventory.slots().map(|(slot, _)| slot).collect::<Vec<_>>(), vec![5]);
+
+        data.health = 1000.;
+        data.apply_to(&mut player).unwrap();
+        assert_eq!(player.health(), MAX_HEALTH);
+    }
+
+    #[test]
+    fn test_newer_versions_are_refused() {
+        let mut data = PlayerData::from_player(&test_player());
+        data.version = PLAYER_DATA_VERSION + 1;
+
+        assert!(matches!(
+            PlayerData::from_bytes(&data.to_bytes().unwrap()),
+            Err(KittyMCError::UnsupportedVersion(_, version)) if version == PLAYER_DATA_VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = TempDir::new("kittymc-player-data").unwrap();
+        let dir = dir.path();
+        let player = test_player();
+
+        assert_eq!(PlayerData::load(dir, player.uuid()).unwrap(), None);
+
+        let data = PlayerData::from_player(&player);
+        data.save(dir, player.uuid()).unwrap();
+        assert_eq!(PlayerData::load(dir, player.uuid()).unwrap(), Some(data));
+        assert!(!temp_file_path(&PlayerData::file_path(dir, player.uuid())).exists());
+    }
+}
@@ -1,76 +1,324 @@
-use crate::client::{Client, ClientInfo};
+use crate::client::{Client, ClientInfo, LoginStage};
 use crate::player::Player;
+use kittymc_lib::crypto::{server_id_hash, ServerKeyPair};
 use kittymc_lib::error::KittyMCError;
 use kittymc_lib::packets::client::login::*;
+use kittymc_lib::packets::client::play::chat_message_0f::ChatPosition;
 use kittymc_lib::packets::client::play::animation_06::{AnimationType, ServerAnimationPacket};
 use kittymc_lib::packets::client::play::disconnect_1a::DisconnectPlayPacket;
-use kittymc_lib::packets::client::play::entity_look_28::EntityLookPacket;
-use kittymc_lib::packets::client::play::entity_relative_move_26::EntityRelativeMovePacket;
+use kittymc_lib::packets::client::play::entity_relative_move_26::relative_move_delta;
 use kittymc_lib::packets::client::play::player_list_item_2e::PlayerListItemAction;
 use kittymc_lib::packets::client::play::*;
 use kittymc_lib::packets::client::status::*;
 use kittymc_lib::packets::packet_serialization::NamedPacket;
 use kittymc_lib::packets::packet_serialization::SerializablePacket;
-use kittymc_lib::packets::server::login::LoginStartPacket;
+use kittymc_lib::packets::server::login::EncryptionResponsePacket;
 use kittymc_lib::packets::server::play::client_settings_04::Hand;
 use kittymc_lib::packets::server::play::player_digging_14::PlayerDiggingStatus;
+use kittymc_lib::packets::protocol::ProtocolVersion;
 use kittymc_lib::packets::Packet;
-use kittymc_lib::subtypes::metadata::EntityMetadata;
+use kittymc_lib::session::SessionVerifier;
+use kittymc_lib::subtypes::components::{BaseComponent, Component, TextComponent};
 use kittymc_lib::subtypes::state::State;
-use kittymc_lib::subtypes::{Direction, Location, Location2};
+use kittymc_lib::subtypes::{Color, Direction, Location, Location2, CHUNK_WIDTH};
 use log::{debug, error};
 use rand::random;
 use std::collections::{HashMap, VecDeque};
 use std::fmt::Debug;
-use std::io::ErrorKind;
+use std::io::{self, ErrorKind};
 use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, TryRecvError};
 use std::sync::{Arc, Mutex, RwLock};
-use std::thread::sleep;
-use std::time::Duration;
+use std::thread::{self, sleep, JoinHandle};
+use std::time::{Duration, Instant};
+use tokio::runtime::{Builder, Runtime};
 use tracing::{info, instrument, warn};
 use uuid::Uuid;
 use kittymc_lib::packets::client::play::chunk_data_20::BlockStateId;
 use kittymc_lib::packets::server::play::entity_action_15::EntityAction;
+use crate::chunking::anvil::{is_anvil_world, AnvilChunkStorage};
+use crate::chunking::anvil_export::export_world;
+use crate::chunking::world_generator::{create_generator, find_spawn, SharedWorldGenerator};
+use crate::level::LevelInfo;
 use crate::chunking::chunk_manager::ChunkManager;
+use crate::chunking::chunk_storage::{
+    has_chunk_files, migrate_chunks, ChunkStorage, FileChunkStorage,
+};
+use crate::chunking::region_storage::RegionChunkStorage;
+use crate::command::{CommandContext, CommandError, CommandRegistry, CommandSender};
+use crate::console::Console;
 use crate::inventory::ItemStack;
+use crate::operators::{OperatorList, OPERATORS_FILE};
+use crate::player_data::{PlayerData, PLAYER_DATA_DIR};
+use crate::tick::{TickClock, TickStats, TICKS_PER_SECOND, TICK_DURATION};
+use crate::tracker::EntityTracker;
+
+/// How long the main loop waits between checking the network, if no tick is due before that
+const NETWORK_POLL_INTERVAL: Duration = Duration::from_millis(1);
+const DAY_LENGTH: u64 = 24000;
+const TIMEOUT_REASON: &str = "Timed out";
+/// Threads writing the queued packets to the sockets
+const IO_THREADS: usize = 2;
+/// How long the packets that are still queued get to reach the clients when shutting down
+const SHUTDOWN_FLUSH_TIMEOUT: Duration = Duration::from_secs(2);
+pub const WORLD_DIR: &str = "world";
+const REGION_DIR: &str = "world/region";
+/// How often the world and the players are saved unless configured otherwise
+const DEFAULT_AUTOSAVE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Clients can send any float as their yaw and pitch, but other clients only understand numbers
+fn is_valid_direction(direction: &Direction) -> bool {
+    direction.x.is_finite() && direction.y.is_finite()
+}
+
+/// What's needed to authenticate players instead of trusting the name they log in with
+#[derive(Debug)]
+pub struct OnlineMode {
+    key_pair: ServerKeyPair,
+    verifier: Arc<dyn SessionVerifier>,
+}
+
+/// An export that runs on its own thread and who to tell about it
+#[derive(Debug)]
+struct PendingExport {
+    world_dir: PathBuf,
+    sender: CommandSender,
+    handle: JoinHandle<Result<usize, KittyMCError>>,
+}
 
 #[derive(Debug)]
 pub struct KittyMCServer {
     server: TcpListener,
+    io_runtime: Runtime,
     players: HashMap<Uuid, Player>,
     clients: RwLock<HashMap<Uuid, Client>>,
     registering_clients: VecDeque<Client>,
     chunk_manager: RwLock<ChunkManager>,
     next_entity_id: i32,
     shutdown_signal: Arc<Mutex<bool>>,
+    online_mode: Option<OnlineMode>,
+    commands: CommandRegistry,
+    operators: OperatorList,
+    export: Option<PendingExport>,
+    console: Option<Console>,
+    /// Players that get disconnected the next time their packets are handled, with the reason
+    kicked_players: HashMap<Uuid, String>,
+    tick_clock: TickClock,
+    tick_stats: TickStats,
+    tracker: EntityTracker,
+    world_age: u64,
+    time_of_day: u64,
+    /// The world as it was loaded. The time in it is only updated when it's saved.
+    level: LevelInfo,
+    level_type: LevelType,
+    /// How many ticks pass between autosaves, or 0 if the server doesn't save by itself
+    autosave_interval: u64,
 }
 
 #[allow(dead_code)]
 impl KittyMCServer {
-    #[instrument(skip(port))]
-    pub fn new(port: u16) -> Result<KittyMCServer, KittyMCError> {
+    #[instrument(skip(port, new_level))]
+    /// Starts the server on the world in the world directory. If there's none yet, `new_level` is
+    /// used to create one.
+    pub fn new(port: u16, new_level: LevelInfo) -> Result<KittyMCServer, KittyMCError> {
         let server = TcpListener::bind(("0.0.0.0", port))?;
 
         server.set_nonblocking(true)?;
 
+        let io_runtime = Builder::new_multi_thread()
+            .worker_threads(IO_THREADS)
+            .thread_name("kittymc-io")
+            .enable_io()
+            .build()?;
+
         info!("Starting server on port {port}");
 
+        let storage = Arc::new(RegionChunkStorage::new(REGION_DIR));
+        Self::migrate_chunk_files(&*storage)?;
+        let (level, world) = Self::open_level(new_level)?;
+        let level_type = world.level_type();
+        let operators = OperatorList::load(OPERATORS_FILE)?;
+        if operators.operators().is_empty() {
+            info!("There are no operators yet, add them with the op command in the console");
+        }
+
         Ok(KittyMCServer {
             server,
+            io_runtime,
             players: HashMap::new(),
             clients: RwLock::new(HashMap::new()),
             registering_clients: VecDeque::new(),
-            chunk_manager: RwLock::new(ChunkManager::new()),
+            chunk_manager: RwLock::new(ChunkManager::new(storage, world)),
             next_entity_id: 0,
             shutdown_signal: Arc::new(Mutex::new(false)),
+            online_mode: None,
+            commands: CommandRegistry::with_builtins(),
+            operators,
+            export: None,
+            console: None,
+            kicked_players: HashMap::new(),
+            tick_clock: TickClock::new(Instant::now()),
+            tick_stats: TickStats::new(),
+            tracker: EntityTracker::new(),
+            world_age: level.time as u64,
+            time_of_day: level.day_time as u64,
+            level,
+            level_type,
+            autosave_interval: Self::interval_to_ticks(DEFAULT_AUTOSAVE_INTERVAL),
         })
     }
 
+    /// Moves the chunks of worlds from before region files into `storage`
+    fn migrate_chunk_files(storage: &dyn ChunkStorage) -> Result<(), KittyMCError> {
+        if !has_chunk_files(Path::new(WORLD_DIR)) {
+            return Ok(());
+        }
+
+        info!("Moving the chunks of the world into region files...");
+        let moved = migrate_chunks(&FileChunkStorage::new(WORLD_DIR), storage)?;
+        info!("Moved {moved} chunks");
+
+        Ok(())
+    }
+
+    /// Takes the chunks that were never saved from the vanilla world in `world_dir`, so an
+    /// existing world can be played on. Chunks it doesn't have are still generated.
+    pub fn use_anvil_world(&self, world_dir: &Path) -> Result<(), KittyMCError> {
+        if !is_anvil_world(world_dir) {
+            return Err(io::Error::new(
+                ErrorKind::NotFound,
+                format!("{} has no Anvil region files", world_dir.display()),
+            )
+            .into());
+        }
+
+        info!("Loading missing chunks from the Anvil world in {}", world_dir.display());
+        self.chunk_manager
+            .read()
+            .map_err(|_| KittyMCError::LockPoisonError)?
+            .set_source(Some(Arc::new(AnvilChunkStorage::open_world(world_dir))));
+
+        Ok(())
+    }
+
+    /// Reads the level.dat of the world, or creates one for a new world
+    fn open_level(new_level: LevelInfo) -> Result<(LevelInfo, SharedWorldGenerator), KittyMCError> {
+        let world_dir = Path::new(WORLD_DIR);
+        if let Some(level) = LevelInfo::load(world_dir)? {
+            let world = create_generator(&level)?;
+            info!("Generating the world with the {} generator", level.generator_name);
+            return Ok((level, world));
+        }
+
+        let mut level = new_level;
+        let world = create_generator(&level)?;
+        (level.spawn_x, level.spawn_y, level.spawn_z) = find_spawn(&*world);
+        level.save(world_dir)?;
+        info!(
+            "Created a new world with the {} generator and seed {}",
+            level.generator_name, level.seed
+        );
+
+        Ok((level, world))
+    }
+
+    fn interval_to_ticks(interval: Duration) -> u64 {
+        (interval.as_millis() / TICK_DURATION.as_millis()) as u64
+    }
+
+    /// Changes how often changed chunks and the online players are saved. Saving is only done
+    /// when asked for and on shutdown if the interval is zero.
+    pub fn set_autosave_interval(&mut self, interval: Duration) {
+        self.autosave_interval = Self::interval_to_ticks(interval);
+        match self.autosave_interval {
+            0 => info!("Autosaving is turned off"),
+            _ => info!("Autosaving every {}s", interval.as_secs_f64()),
+        }
+    }
+
+    /// Encrypts all connections and checks every login with `verifier`
+    pub fn enable_online_mode(
+        &mut self,
+        verifier: Arc<dyn SessionVerifier>,
+    ) -> Result<(), KittyMCError> {
+        info!("Generating server key pair for online mode");
+
+        self.online_mode = Some(OnlineMode {
+            key_pair: ServerKeyPair::generate()?,
+            verifier,
+        });
+
+        Ok(())
+    }
+
+    /// Runs the lines entered into `console` as commands
+    pub fn attach_console(&mut self, console: Console) {
+        self.console = Some(console);
+    }
+
     fn get_name_from_uuid(&self, uuid: &Uuid) -> Option<&str> {
         self.players.get(uuid).map(|p| p.name())
     }
 
-    fn send_to_all<P: SerializablePacket + Debug + NamedPacket>(
+    pub fn players(&self) -> &HashMap<Uuid, Player> {
+        &self.players
+    }
+
+    pub fn player(&self, uuid: &Uuid) -> Option<&Player> {
+        self.players.get(uuid)
+    }
+
+    pub fn player_mut(&mut self, uuid: &Uuid) -> Option<&mut Player> {
+        self.players.get_mut(uuid)
+    }
+
+    /// Adds the player to the operator list or takes them off it and saves the list. Returns false
+    /// if nothing changed.
+    pub fn set_operator(&mut self, uuid: &Uuid, operator: bool) -> Result<bool, KittyMCError> {
+        let player = self.players.get_mut(uuid).ok_or(KittyMCError::PlayerNotFound)?;
+        let changed = match operator {
+            true => self.operators.add(*uuid, player.name()),
+            false => self.operators.remove(uuid),
+        };
+        player.set_operator(operator);
+
+        if changed {
+            self.operators.save()?;
+        }
+        Ok(changed)
+    }
+
+    pub fn commands(&self) -> &CommandRegistry {
+        &self.commands
+    }
+
+    pub fn commands_mut(&mut self) -> &mut CommandRegistry {
+        &mut self.commands
+    }
+
+    pub fn tick_stats(&self) -> &TickStats {
+        &self.tick_stats
+    }
+
+    pub fn current_tick(&self) -> u64 {
+        self.tick_clock.current_tick()
+    }
+
+    /// Sends `packet` to one player. Players whose packets are being handled right now aren't
+    /// reachable through this.
+    pub fn send_to<P: SerializablePacket + Debug + NamedPacket>(
+        &self,
+        uuid: &Uuid,
+        packet: &P,
+    ) -> Result<(), KittyMCError> {
+        match self.clients.write().unwrap().get_mut(uuid) {
+            Some(client) => client.send_packet(packet),
+            None => Err(KittyMCError::PlayerNotFound),
+        }
+    }
+
+    pub fn send_to_all<P: SerializablePacket + Debug + NamedPacket>(
         &mut self,
         sender: Option<&mut Client>,
         packet: &P,
@@ -92,26 +340,42 @@ impl KittyMCServer {
         error
     }
 
-    fn send_initial_chunks(&mut self, client: &mut Client) -> Result<(), KittyMCError> {
-        if client.load_initial_chunks {
-            let mut chunk_manager = self.chunk_manager.write().unwrap();
-            if client.update_chunks(&Location::new(0., 5., 0.), &mut chunk_manager)? {
-                client.load_initial_chunks = false;
+    /// Sends `packet` to every client that can see the entity
+    pub fn send_to_observers<P: SerializablePacket + Debug + NamedPacket>(
+        &self,
+        entity_id: i32,
+        packet: &P,
+    ) -> Result<(), KittyMCError> {
+        let mut error = Ok(());
+        let mut clients = self.clients.write().unwrap();
+
+        for observer in self.tracker.observers_of(entity_id) {
+            let Some(client) = clients.get_mut(observer) else {
+                continue;
+            };
+            if let Err(e) = client.send_packet(packet) {
+                error = Err(e);
             }
         }
-        Ok(())
+
+        error
     }
 
     fn handle_client(&mut self, uuid: &Uuid, client: &mut Client) -> Result<bool, KittyMCError> {
-        if !client.do_heartbeat()? {
-            debug!(
-                "[{}] Client didn't respond to heartbeats for too long",
+        if client.is_overflowed() {
+            warn!(
+                "[{}] Kicked for not receiving the packets sent to it fast enough",
                 client.addr()
             );
             return Ok(false);
         }
 
-        self.send_initial_chunks(client)?;
+        if let Some(reason) = self.kicked_players.remove(uuid) {
+            client.send_packet(&DisconnectPlayPacket {
+                reason: Component::Text(TextComponent::builder().text(reason).build()),
+            })?;
+            return Ok(false);
+        }
 
         loop {
             let Some(packet) = client.fetch_packet()? else {
@@ -130,45 +394,38 @@ impl KittyMCServer {
                 Packet::ClientSettings(settings) => {
                     client.set_view_distance(settings.view_distance as u32);
                 }
+                // Movement is broadcast and chunks are sent on the next tick
                 Packet::PlayerPositionAndLook(packet) => {
-                    let location = Location::new(
-                        packet.location.x as f32,
-                        packet.location.y as f32,
-                        packet.location.z as f32,
-                    );
-                    {
-                        let player = self.players.get_mut(&uuid).unwrap();
-                        player.set_position(&packet.location);
+                    let player = self.players.get_mut(uuid).unwrap();
+                    player.set_position(&packet.location);
+                    if is_valid_direction(&packet.direction) {
                         player.set_direction(&packet.direction);
                     }
-                    self.update_global_position(uuid)?;
-                    self.update_global_rotation(uuid)?;
-                    let mut chunk_manager = self.chunk_manager.write().unwrap();
-                    client.update_chunks(&location, &mut chunk_manager)?;
                 }
                 Packet::PlayerPosition(packet) => {
-                    let location = Location::new(
-                        packet.location.x as f32,
-                        packet.location.y as f32,
-                        packet.location.z as f32,
-                    );
-                    {
-                        let player = self.players.get_mut(&uuid).unwrap();
-                        player.set_position(&packet.location);
-                    }
-                    self.update_global_position(uuid)?;
-                    let mut chunk_manager = self.chunk_manager.write().unwrap();
-                    client.update_chunks(&location, &mut chunk_manager)?;
+                    let player = self.players.get_mut(uuid).unwrap();
+                    player.set_position(&packet.location);
                 }
-                Packet::PlayerLook(packet) => {
-                    {
-                        let player = self.players.get_mut(&uuid).unwrap();
-                        player.set_direction(&packet.direction);
-                    }
-                    self.update_global_rotation(uuid)?;
+                Packet::PlayerLook(packet) if is_valid_direction(&packet.direction) => {
+                    let player = self.players.get_mut(uuid).unwrap();
+                    player.set_direction(&packet.direction);
+                }
+                Packet::ChatMessage(chat) if chat.message.starts_with('/') => {
+                    let name = self.get_name_from_uuid(uuid).unwrap_or("UNNAMED");
+                    info!("{} issued server command: {}", name, chat.message);
+                    let line = &chat.message[1..];
+                    self.run_command(CommandSender::Player(*uuid), Some(client), line)?;
+                }
+                Packet::TabComplete(request) => {
+                    let matches = self.commands.tab_complete(
+                        request,
+                        CommandSender::Player(*uuid),
+                        &self.players,
+                    );
+                    client.send_packet(&ClientTabCompletePacket::new(matches))?;
                 }
                 Packet::ChatMessage(chat) => {
-                    let name = self.get_name_from_uuid(uuid).unwrap_or_else(|| "UNNAMED");
+                    let name = self.get_name_from_uuid(uuid).unwrap_or("UNNAMED");
                     let broadcast = ClientChatMessagePacket::new_chat_message(name, &chat.message);
                     info!("<{}> {}", name, chat.message);
                     self.send_to_all(Some(client), &broadcast)?;
@@ -176,8 +433,8 @@ impl KittyMCServer {
                 Packet::ClientAnimation(animation) => {
                     let entity_id = self.players.get(uuid).unwrap().id();
                     match animation.hand {
-                        Hand::Left => self.send_to_all(
-                            None,
+                        Hand::Left => self.send_to_observers(
+                            entity_id,
                             &ServerAnimationPacket {
                                 entity_id,
                                 animation: AnimationType::SwingMainArm,
@@ -200,18 +457,16 @@ impl KittyMCServer {
 
                     // TODO: Range Check
 
-                    if (digging.status == PlayerDiggingStatus::StartedDigging
-                        && game_mode == GameMode::Creative
-                        || (digging.status == PlayerDiggingStatus::StartedDigging
-                            && game_mode != GameMode::Adventure))
+                    if digging.status == PlayerDiggingStatus::StartedDigging
+                        && game_mode != GameMode::Adventure
                         && is_cool
                     {
-                        let loc = digging.location.clone();
+                        let loc = digging.location;
                         self.set_block(&loc, 0)?;
 
                         self.send_to_all(
                             None,
-                            &BlockChangePacket::new_empty(loc.clone()),
+                            &BlockChangePacket::new_empty(loc),
                         )?;
                         self.send_to_all(
                             None,
@@ -223,15 +478,7 @@ impl KittyMCServer {
                     let player = self.players.get_mut(uuid)
                         .ok_or(KittyMCError::PlayerNotFound)?;
 
-                    let item = if action.clicked_item.id == u16::MAX {
-                        None
-                    } else {
-                        Some(ItemStack {
-                            item_id: action.clicked_item.id,
-                            damage: action.clicked_item.item_damage,
-                            count: action.clicked_item.item_count,
-                        })
-                    };
+                    let item = ItemStack::from_slot(&action.clicked_item);
 
                     player.inventory.set_slot(action.slot, item);
                 }
@@ -272,7 +519,7 @@ impl KittyMCServer {
                     let state;
 
                     {
-                        let player = self.players.get_mut(&uuid).unwrap();
+                        let player = self.players.get_mut(uuid).unwrap();
                         match action.action {
                             EntityAction::StartSprinting => player.set_sprinting(true),
                             EntityAction::StopSprinting => player.set_sprinting(false),
@@ -284,13 +531,156 @@ impl KittyMCServer {
                         state = player.get_state().living.entity.clone();
                     }
 
-                    self.send_to_all(Some(client), &EntityMetadataPacket::new(player_eid as i32, state))?;
+                    let metadata = EntityMetadataPacket::new(player_eid, state);
+                    client.send_packet(&metadata)?;
+                    self.send_to_observers(player_eid, &metadata)?;
                 }
                 _ => (),
             }
         }
     }
 
+    /// Runs a command line without the leading `/`. Whatever goes wrong is told to the sender.
+    pub fn run_command(
+        &mut self,
+        sender: CommandSender,
+        client: Option<&mut Client>,
+        line: &str,
+    ) -> Result<(), KittyMCError> {
+        let mut ctx = CommandContext::new(sender, client);
+
+        let result = self
+            .commands
+            .parse(line, sender, &self.players)
+            .and_then(|command| (command.executor)(self, &mut ctx, &command.arguments));
+
+        match result {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                if let CommandError::Failed(e) = &e {
+                    warn!("Command \"{line}\" failed: {e}");
+                }
+                ctx.reply_error(e.to_string())
+            }
+        }
+    }
+
+    fn handle_console(&mut self) -> Result<(), KittyMCError> {
+        while let Some(line) = self.console.as_ref().and_then(|console| console.next_line()) {
+            let line = line.strip_prefix('/').unwrap_or(&line);
+            self.run_command(CommandSender::Console, None, line)?;
+        }
+        Ok(())
+    }
+
+    /// Disconnects the player the next time their packets are handled
+    pub fn kick(&mut self, uuid: &Uuid, reason: &str) {
+        self.kicked_players.insert(*uuid, reason.to_string());
+    }
+
+    /// Makes the main loop shut the server down
+    pub fn request_shutdown(&self) {
+        *self.shutdown_signal.lock().unwrap() = true;
+    }
+
+    /// Writes every loaded chunk that changed and the level.dat to disk and returns how many
+    /// chunks there were
+    pub fn save_world(&self) -> Result<usize, KittyMCError> {
+        let saved = self
+            .chunk_manager
+            .read()
+            .map_err(|_| KittyMCError::LockPoisonError)?
+            .save_dirty();
+        self.level_info().save(Path::new(WORLD_DIR))?;
+
+        saved
+    }
+
+    pub fn is_exporting(&self) -> bool {
+        self.export.is_some()
+    }
+
+    /// Saves the world and starts writing a copy of it that vanilla can open to `world_dir` on
+    /// another thread. `sender` is told how it went once it's done.
+    pub fn start_export(
+        &mut self,
+        world_dir: PathBuf,
+        sender: CommandSender,
+    ) -> Result<(), KittyMCError> {
+        self.save_world()?;
+
+        let storages = self
+            .chunk_manager
+            .read()
+            .map_err(|_| KittyMCError::LockPoisonError)?
+            .storages();
+        let level = self.level_info();
+        let dir = world_dir.clone();
+        // Lighting and encoding every chunk takes far longer than a tick
+        let handle = thread::Builder::new()
+            .name("kittymc-export".to_string())
+            .spawn(move || {
+                let storages: Vec<&dyn ChunkStorage> =
+                    storages.iter().map(|storage| &**storage).collect();
+                export_world(&storages, &dir, &level)
+            })?;
+
+        self.export = Some(PendingExport {
+            world_dir,
+            sender,
+            handle,
+        });
+        Ok(())
+    }
+
+    /// Tells whoever started the export how it went once it's done
+    fn tick_export(&mut self) {
+        if !self.export.as_ref().is_some_and(|export| export.handle.is_finished()) {
+            return;
+        }
+        let export = self.export.take().unwrap();
+        let dir = export.world_dir.display();
+
+        let (message, color) = match export.handle.join() {
+            Ok(Ok(exported)) => (format!("Exported {exported} chunks to {dir}"), Color::White),
+            Ok(Err(e)) => {
+                warn!("Exporting the world to {dir} failed: {e}");
+                (format!("Couldn't export the world to {dir}: {e}"), Color::Red)
+            }
+            Err(_) => {
+                error!("The export to {dir} crashed");
+                (format!("Couldn't export the world to {dir}"), Color::Red)
+            }
+        };
+
+        let text = Component::Text(
+            TextComponent::builder()
+                .text(message)
+                .options(BaseComponent::builder().color(color).build())
+                .build(),
+        );
+        match export.sender {
+            CommandSender::Player(uuid) => {
+                let packet = ClientChatMessagePacket {
+                    text,
+                    position: ChatPosition::System,
+                };
+                // They might have left in the meantime
+                let _ = self.send_to(&uuid, &packet);
+            }
+            CommandSender::Console => info!("{}", text.to_plain_text()),
+        }
+    }
+
+    /// The level with the current time
+    pub fn level_info(&self) -> LevelInfo {
+        LevelInfo {
+            time: self.world_age as i64,
+            day_time: self.time_of_day as i64,
+            ..self.level.clone()
+        }
+    }
+
     pub fn set_block(&mut self, location: &Location, block_state: BlockStateId) -> Result<(), KittyMCError> {
         let mut chunk_manager = self.chunk_manager
             .write()
@@ -298,81 +688,89 @@ impl KittyMCServer {
         chunk_manager.set_block(location, block_state)
     }
 
-    pub fn update_global_position(&mut self, uuid: &Uuid) -> Result<(), KittyMCError> {
-        let current_pos;
-        let last_pos;
+    /// Tells everyone who can see the player how it moved and turned since they were last told,
+    /// with the smallest packet that can express it
+    pub fn update_global_movement(&mut self, uuid: &Uuid) -> Result<(), KittyMCError> {
         let entity_id;
+        let location;
+        let delta;
+        let direction;
+        let rotated;
+        let head_turned;
         {
-            let Some(player) = self.players.get(uuid) else {
+            let Some(player) = self.players.get_mut(uuid) else {
                 return Ok(());
             };
 
-            current_pos = player.position();
-            last_pos = player.last_position();
             entity_id = player.id();
-        };
-        let relative = current_pos - last_pos;
-
-        if relative.magnitude() > 4.0 {
-            // self.send_to_all(
-            //     None,
-            //     &EntityTeleportPacket {
-            //         entity_id,
-            //         location: current_pos,
-            //         on_ground: false,
-            //     },
-            // )
-            Ok(())
-        } else {
-            let delta_x = ((current_pos.x * 32. - last_pos.x * 32.) * 128.0).round() as i16;
-            let delta_y = ((current_pos.y * 32. - last_pos.y * 32.) * 128.0).round() as i16;
-            let delta_z = ((current_pos.z * 32. - last_pos.z * 32.) * 128.0).round() as i16;
-
-            self.send_to_all(
-                None,
+            location = *player.position();
+            direction = *player.direction();
+            delta = player
+                .has_moved()
+                .then(|| relative_move_delta(player.last_position(), &location));
+            rotated = player.has_rotated();
+            head_turned = (direction.x - player.last_direction().x).abs() > f32::EPSILON;
+
+            player.sync_position();
+            player.sync_direction();
+        }
+        let on_ground = false;
+
+        let result = match (delta, rotated) {
+            (None, false) => Ok(()),
+            (None, true) => self.send_to_observers(
+                entity_id,
+                &EntityLookPacket {
+                    entity_id,
+                    direction,
+                    on_ground,
+                },
+            ),
+            (Some(Some([delta_x, delta_y, delta_z])), false) => self.send_to_observers(
+                entity_id,
                 &EntityRelativeMovePacket {
                     entity_id,
                     delta_x,
                     delta_y,
                     delta_z,
-                    on_ground: false,
+                    on_ground,
                 },
-            )
-        }
-    }
-
-    pub fn update_global_rotation(&mut self, uuid: &Uuid) -> Result<(), KittyMCError> {
-        let current_rot;
-        let last_rot;
-        let entity_id;
-        {
-            let Some(player) = self.players.get(uuid) else {
-                return Ok(());
-            };
-
-            current_rot = player.direction().clone();
-            last_rot = player.last_direction().clone();
-            entity_id = player.id();
+            ),
+            (Some(Some([delta_x, delta_y, delta_z])), true) => self.send_to_observers(
+                entity_id,
+                &EntityLookAndRelativeMovePacket {
+                    entity_id,
+                    delta_x,
+                    delta_y,
+                    delta_z,
+                    direction,
+                    on_ground,
+                },
+            ),
+            // Too far for a relative move
+            (Some(None), _) => self.send_to_observers(
+                entity_id,
+                &EntityTeleportPacket {
+                    entity_id,
+                    location,
+                    direction,
+                    on_ground,
+                },
+            ),
         };
 
-        if (current_rot.x - last_rot.x).abs() > f32::EPSILON {
-            let _ = self.send_to_all(
-                None,
+        // The head is turned separately from the body
+        if head_turned {
+            self.send_to_observers(
+                entity_id,
                 &EntityHeadLookPacket {
                     entity_id,
-                    yaw: current_rot.x,
+                    yaw: direction.x,
                 },
-            );
+            )?;
         }
 
-        self.send_to_all(
-            None,
-            &EntityLookPacket {
-                entity_id,
-                direction: current_rot,
-                on_ground: false,
-            },
-        )
+        result
     }
 
     fn add_player_to_all_player_lists(
@@ -402,20 +800,7 @@ impl KittyMCServer {
         self.send_to_all(
             Some(client),
             &PlayerListItemPacket {
-                actions: vec![(player.uuid().clone(), PlayerListItemAction::RemovePlayer)],
-            },
-        )
-    }
-
-    fn spawn_player_to_all(&mut self, player: &Player) -> Result<(), KittyMCError> {
-        self.send_to_all(
-            None,
-            &SpawnPlayerPacket {
-                entity_id: player.id(),
-                player_uuid: player.uuid().clone(),
-                location: *player.position(),
-                direction: *player.direction(),
-                metadata: EntityMetadata::default(),
+                actions: vec![(*player.uuid(), PlayerListItemAction::RemovePlayer)],
             },
         )
     }
@@ -423,85 +808,231 @@ impl KittyMCServer {
     fn login_player(
         &mut self,
         client: &mut Client,
-        login: &LoginStartPacket,
+        client_info: ClientInfo,
     ) -> Result<Uuid, KittyMCError> {
-        let success = LoginSuccessPacket::from_name_cracked(&login.name)?;
-        let client_info = ClientInfo {
-            username: success.username.clone(),
-            uuid: success.uuid.clone(),
+        let success = LoginSuccessPacket {
+            uuid: client_info.uuid,
+            username: client_info.username.clone(),
         };
 
-        let player = Player::from_client_info(
+        let mut player = Player::from_client_info(
             client_info,
             self.get_next_entity_id(),
-            &Location2::new(0., 5., 0.),
+            &Location2::new(
+                self.level.spawn_x as f64,
+                self.level.spawn_y as f64,
+                self.level.spawn_z as f64,
+            ),
             &Direction::zeros(),
             GameMode::Creative,
         );
-        let uuid = player.uuid().clone();
+        let uuid = *player.uuid();
+        player.set_operator(self.operators.is_operator(&uuid));
+        Self::load_player_data(&mut player);
+        let held_item = ServerHeldItemChangePacket {
+            slot: player.current_slot() as u8,
+        };
+        let health = UpdateHealthPacket::with_full_food(player.health());
+        let position = ServerPlayerPositionAndLookPacket {
+            location: *player.position(),
+            direction: *player.direction(),
+            ..Default::default()
+        };
+        let inventory = player.inventory.to_window_items();
 
-        client.set_uuid(uuid.clone());
+        client.set_uuid(uuid);
 
         let compression = SetCompressionPacket::default();
         client.send_packet(&compression)?;
-        client.set_compression(true, compression.threshold);
-
         client.send_packet(&success)?;
-        client.set_state(State::Play);
 
-        client.send_packet(&JoinGamePacket::new(player.id()))?;
+        client.send_packet(&JoinGamePacket {
+            gamemode: player.game_mode(),
+            level_type: self.level_type,
+            ..JoinGamePacket::new(player.id())
+        })?;
         let _ = self.add_player_to_all_player_lists(client, &player);
-        self.spawn_player_to_all(&player)?;
-        self.players.insert(uuid.clone(), player);
+        self.players.insert(uuid, player);
 
         client.send_packet(&ServerPluginMessagePacket::default_brand())?;
         client.send_packet(&ServerDifficultyPacket::default())?;
         client.send_packet(&PlayerAbilitiesPacket::default())?;
-        client.send_packet(&ServerHeldItemChangePacket::default())?;
+        client.send_packet(&held_item)?;
         client.send_packet(&EntityStatusPacket::default())?;
         client.send_packet(&UnlockRecipesPacket::default())?;
+        // Everyone is spawned by the entity tracker once they're in range
         for player in &self.players {
             client.add_player_to_player_list(player.1)?; // TODO: Add all players in one packet
-            client.spawn_player(player.1)?;
         }
 
         // Another Player List Item
-        client.send_packet(&ServerPlayerPositionAndLookPacket::default())?;
+        client.send_packet(&position)?;
+        client.send_packet(&inventory)?;
+        client.send_packet(&health)?;
         // World Border
-        client.send_packet(&TimeUpdatePacket::default())?;
-        client.send_packet(&SpawnPositionPacket::default())?;
+        client.send_packet(&TimeUpdatePacket {
+            world_age: self.world_age,
+            time_of_day: self.time_of_day,
+        })?;
+        client.send_packet(&SpawnPositionPacket {
+            position: Location::new(
+                self.level.spawn_x as f32,
+                self.level.spawn_y as f32,
+                self.level.spawn_z as f32,
+            ),
+        })?;
         // Player Digging ???
         // Steer Vehicle ???
 
         Ok(uuid)
     }
 
+    /// Restores what the player had when they last left, or keeps the defaults for new players
+    fn load_player_data(player: &mut Player) {
+        let result = PlayerData::load(Path::new(PLAYER_DATA_DIR), player.uuid())
+            .and_then(|data| data.map_or(Ok(()), |data| data.apply_to(player)));
+
+        if let Err(e) = result {
+            warn!("Couldn't load the player data of {}: {e}", player.name());
+        }
+    }
+
+    fn save_player_data(player: &Player) -> bool {
+        let result =
+            PlayerData::from_player(player).save(Path::new(PLAYER_DATA_DIR), player.uuid());
+
+        if let Err(e) = &result {
+            warn!("Couldn't save the player data of {}: {e}", player.name());
+        }
+        result.is_ok()
+    }
+
+    /// Writes the data of every online player to disk and returns how many were saved
+    pub fn save_players(&self) -> usize {
+        self.players
+            .values()
+            .filter(|player| Self::save_player_data(player))
+            .count()
+    }
+
+    /// Decrypts the response to our Encryption Request, turns on encryption and starts asking the
+    /// session server about the client in the background.
+    fn begin_session_verification(
+        &self,
+        client: &mut Client,
+        response: &EncryptionResponsePacket,
+    ) -> Result<(), KittyMCError> {
+        let (Some(online_mode), Some(LoginStage::AwaitingEncryption { username, verify_token })) =
+            (&self.online_mode, client.take_login_stage())
+        else {
+            return Err(KittyMCError::UnexpectedPacket("EncryptionResponsePacket"));
+        };
+
+        if online_mode.key_pair.decrypt(&response.verify_token)? != verify_token {
+            return Err(KittyMCError::VerifyTokenMismatch);
+        }
+
+        let shared_secret = online_mode.key_pair.decrypt(&response.shared_secret)?;
+        client.enable_encryption(&shared_secret)?;
+
+        let server_hash =
+            server_id_hash("", &shared_secret, online_mode.key_pair.public_key_der());
+        let verifier = online_mode.verifier.clone();
+        let (sender, profile) = mpsc::channel();
+        let name = username.clone();
+        // The session server can take a while to answer, which would stall everyone else
+        thread::spawn(move || {
+            let _ = sender.send(verifier.has_joined(&name, &server_hash));
+        });
+
+        client.set_login_stage(LoginStage::Verifying { username, profile });
+
+        Ok(())
+    }
+
+    /// Finishes the login once the session server answered
+    fn poll_session_verification(
+        &mut self,
+        client: &mut Client,
+    ) -> Result<Option<Uuid>, KittyMCError> {
+        let Some(LoginStage::Verifying { username, profile }) = client.take_login_stage() else {
+            return Ok(None);
+        };
+
+        match profile.try_recv() {
+            Ok(Ok(profile)) => {
+                debug!("[{}] Authenticated {} as {}", client.addr(), username, profile.id);
+                let client_info = ClientInfo {
+                    uuid: profile.id,
+                    username: profile.name,
+                    properties: profile.properties,
+                };
+                Ok(Some(self.login_player(client, client_info)?))
+            }
+            Ok(Err(e)) => {
+                client.send_packet(&DisconnectLoginPacket::not_authenticated())?;
+                Err(e)
+            }
+            Err(TryRecvError::Empty) => {
+                client.set_login_stage(LoginStage::Verifying { username, profile });
+                Ok(None)
+            }
+            Err(TryRecvError::Disconnected) => Err(KittyMCError::NotAuthenticated(username)),
+        }
+    }
+
     fn handle_client_pre_play(
         &mut self,
         client: &mut Client,
     ) -> Result<Option<Uuid>, KittyMCError> {
+        if let Some(uuid) = self.poll_session_verification(client)? {
+            return Ok(Some(uuid));
+        }
+
         loop {
             let Some(packet) = client.fetch_packet()? else {
                 return Ok(None);
             };
 
             match &packet {
-                Packet::Handshake(handshake) => {
-                    if handshake.protocol_version != 340 && handshake.next_state != State::Status {
-                        info!("[{}] Client tried to connect with protocol version {} != 340. Disconnecting.", client.addr(), handshake.protocol_version);
-                        client.send_packet(&DisconnectLoginPacket::wrong_version())?;
-                        return Err(KittyMCError::VersionMissmatch);
-                    }
-                    client.set_state(handshake.next_state);
+                Packet::Handshake(handshake)
+                    if ProtocolVersion::from_id(handshake.protocol_version).is_none()
+                        && handshake.next_state != State::Status =>
+                {
+                    info!("[{}] Client tried to connect with unsupported protocol version {}. Disconnecting.", client.addr(), handshake.protocol_version);
+                    client.send_packet(&DisconnectLoginPacket::wrong_version())?;
+                    return Err(KittyMCError::VersionMissmatch);
                 }
                 Packet::StatusRequest(_) => {
-                    client.send_packet(&StatusResponsePacket::default())?;
+                    client.send_packet(&StatusResponsePacket::for_protocol(client.protocol()))?;
                 }
                 Packet::StatusPing(ping) => {
                     client.send_packet(ping)?;
                 }
-                Packet::LoginStart(login) => {
-                    return Ok(Some(self.login_player(client, login)?));
+                Packet::LoginStart(login) => match &self.online_mode {
+                    Some(online_mode) => {
+                        let verify_token: [u8; 4] = random();
+                        client.send_packet(&EncryptionRequestPacket::new(
+                            online_mode.key_pair.public_key_der(),
+                            &verify_token,
+                        ))?;
+                        client.set_login_stage(LoginStage::AwaitingEncryption {
+                            username: login.name.clone(),
+                            verify_token,
+                        });
+                    }
+                    None => {
+                        let success = LoginSuccessPacket::from_name_cracked(&login.name)?;
+                        let client_info = ClientInfo {
+                            uuid: success.uuid,
+                            username: success.username,
+                            properties: vec![],
+                        };
+                        return Ok(Some(self.login_player(client, client_info)?));
+                    }
+                },
+                Packet::EncryptionResponse(response) => {
+                    self.begin_session_verification(client, response)?;
                 }
                 _ => {}
             }
@@ -509,7 +1040,7 @@ impl KittyMCServer {
     }
 
     fn handle_clients(&mut self) -> Result<(), KittyMCError> {
-        let new_client = Client::accept(&self.server)?;
+        let new_client = Client::accept(&self.server, self.io_runtime.handle())?;
 
         if let Some(new_client) = new_client {
             self.registering_clients.push_back(new_client);
@@ -534,7 +1065,7 @@ impl KittyMCServer {
                             &ClientChatMessagePacket::new_join_message(name),
                         )?;
 
-                        self.clients.write().unwrap().insert(uuid.clone(), client);
+                        self.clients.write().unwrap().insert(uuid, client);
                     }
                     None => self.registering_clients.push_back(client),
                 },
@@ -547,7 +1078,7 @@ impl KittyMCServer {
                 .write()
                 .unwrap()
                 .iter()
-                .map(|c| c.0.clone())
+                .map(|c| *c.0)
                 .collect();
             for uuid in uuids {
                 let Some(mut client) = self.clients.write().unwrap().remove(&uuid) else {
@@ -568,7 +1099,7 @@ impl KittyMCServer {
                         info!("[{}] Client disconnected", client.addr());
                     }
                     Err(e) => {
-                        client.send_packet(&DisconnectPlayPacket::default_error(&e))?;
+                        let _ = client.send_packet(&DisconnectPlayPacket::default_error(&e));
                         warn!("[{}] Disconnected client due to error: {e}", client.addr());
                     }
                 };
@@ -577,9 +1108,11 @@ impl KittyMCServer {
                     continue;
                 };
                 info!("{} left the game", player.name());
+                Self::save_player_data(&player);
 
                 let _ = self.remove_player_from_all_player_lists(&mut client, &player);
-                let _ = self.despawn_entity(None, player.id());
+                self.tracker.remove_observer(&uuid);
+                let _ = self.despawn_entity(player.id());
                 let _ = self.send_to_all(
                     Some(&mut client),
                     &ClientChatMessagePacket::new_quit_message(player.name()),
@@ -604,16 +1137,208 @@ impl KittyMCServer {
 
     #[instrument(skip(self))]
     pub fn shutdown(&mut self) {
-        for (_, client) in self.clients.write().unwrap().iter_mut() {
-            client
-                .send_packet(&DisconnectPlayPacket::default_restart())
-                .unwrap()
+        if let Some(export) = self.export.take() {
+            info!("Waiting for the export to {} to finish...", export.world_dir.display());
+            let _ = export.handle.join();
+        }
+        self.save_players();
+        match self.save_world() {
+            Ok(saved) => info!("Saved {saved} chunks"),
+            Err(e) => error!("Couldn't save the whole world: {e}"),
+        }
+
+        let mut clients = self.clients.write().unwrap();
+        for (_, client) in clients.iter_mut() {
+            if let Err(e) = client.send_packet(&DisconnectPlayPacket::default_restart()) {
+                debug!("[{}] Couldn't send disconnect: {e}", client.addr());
+            }
+        }
+
+        // The I/O runtime stops with the server, so whatever is still queued has to get out first
+        let deadline = Instant::now() + SHUTDOWN_FLUSH_TIMEOUT;
+        while Instant::now() < deadline
+            && clients
+                .values()
+                .any(|client| !client.is_overflowed() && client.queued_bytes() != 0)
+        {
+            sleep(NETWORK_POLL_INTERVAL);
+        }
+    }
+
+    /// Advances the world time and tells everyone about it once a second
+    fn tick_time(&mut self) -> Result<(), KittyMCError> {
+        self.world_age += 1;
+        self.time_of_day = (self.time_of_day + 1) % DAY_LENGTH;
+
+        if !self.world_age.is_multiple_of(TICKS_PER_SECOND as u64) {
+            return Ok(());
+        }
+
+        self.send_to_all(
+            None,
+            &TimeUpdatePacket {
+                world_age: self.world_age,
+                time_of_day: self.time_of_day,
+            },
+        )
+    }
+
+    /// Sends keep alives and kicks the players that stopped answering them
+    fn tick_keep_alives(&mut self) {
+        let mut timed_out = vec![];
+
+        for (uuid, client) in self.clients.write().unwrap().iter_mut() {
+            match client.do_heartbeat() {
+                Ok(true) => {}
+                Ok(false) => {
+                    debug!(
+                        "[{}] Client didn't respond to heartbeats for too long",
+                        client.addr()
+                    );
+                    timed_out.push(*uuid);
+                }
+                Err(e) => {
+                    debug!("[{}] Couldn't send keep alive: {e}", client.addr());
+                    timed_out.push(*uuid);
+                }
+            }
+        }
+
+        for uuid in timed_out {
+            self.kick(&uuid, TIMEOUT_REASON);
+        }
+    }
+
+    /// Tells everyone about the players that moved or turned since the last tick
+    fn tick_movement(&mut self) -> Result<(), KittyMCError> {
+        let changed: Vec<_> = self
+            .players
+            .iter()
+            .filter(|(_, player)| player.has_moved() || player.has_rotated())
+            .map(|(uuid, _)| *uuid)
+            .collect();
+
+        let mut error = Ok(());
+        for uuid in changed {
+            if let Err(e) = self.update_global_movement(&uuid) {
+                error = Err(e);
+            }
+        }
+
+        error
+    }
+
+    /// Sends the chunks that came into view and unloads the ones that left it
+    fn tick_chunks(&mut self) {
+        let mut chunk_manager = self.chunk_manager.write().unwrap();
+
+        for (uuid, client) in self.clients.write().unwrap().iter_mut() {
+            let Some(player) = self.players.get(uuid) else {
+                continue;
+            };
+            let position = player.position();
+            let location = Location::new(position.x as f32, position.y as f32, position.z as f32);
+
+            if let Err(e) = client.tick_chunks(&location, &mut chunk_manager) {
+                warn!("[{}] Couldn't update chunks: {e}", client.addr());
+            }
+        }
+    }
+
+    /// Spawns the entities that came into range of each client and destroys the ones that left it
+    fn tick_tracking(&mut self) {
+        let entities: Vec<(i32, Location2)> = self
+            .players
+            .values()
+            .map(|player| (player.id(), *player.position()))
+            .collect();
+
+        for (uuid, client) in self.clients.write().unwrap().iter_mut() {
+            let Some(observer) = self.players.get(uuid) else {
+                continue;
+            };
+            let range = (client.view_distance() as isize * CHUNK_WIDTH) as f64;
+            let update = self.tracker.update_observer(
+                *uuid,
+                observer.id(),
+                observer.position(),
+                range,
+                entities.iter().map(|(id, position)| (*id, position)),
+            );
+
+            for id in update.spawned {
+                let Some(player) = self.players.values().find(|player| player.id() == id) else {
+                    continue;
+                };
+                if let Err(e) = client.spawn_player(player) {
+                    debug!("[{}] Couldn't spawn {}: {e}", client.addr(), player.name());
+                }
+            }
+            if !update.destroyed.is_empty() {
+                if let Err(e) = client.send_packet(&DestroyEntitiesPacket::new(update.destroyed)) {
+                    debug!("[{}] Couldn't destroy entities: {e}", client.addr());
+                }
+            }
+        }
+    }
+
+    /// Saves the changed chunks and everyone's player data every now and then, so a crash
+    /// doesn't lose too much
+    fn tick_autosave(&mut self) {
+        if self.autosave_interval == 0 || !self.world_age.is_multiple_of(self.autosave_interval) {
+            return;
+        }
+
+        let players = self.save_players();
+        match self.save_world() {
+            Ok(chunks) => debug!("Autosaved {chunks} chunks and {players} players"),
+            Err(e) => error!("Couldn't autosave the whole world: {e}"),
+        }
+    }
+
+    /// Runs one tick of the game. The packets that came in since the last one are already handled.
+    fn tick(&mut self) {
+        if let Err(e) = self.tick_time() {
+            debug!("Couldn't send the time to everyone: {e}");
+        }
+        self.tick_keep_alives();
+        if let Err(e) = self.tick_movement() {
+            debug!("Couldn't send movement to everyone: {e}");
+        }
+        // After the movement, so newly spawned entities don't get moved from where they spawned
+        self.tick_tracking();
+        self.tick_chunks();
+        self.tick_autosave();
+        self.tick_export();
+    }
+
+    /// Runs a tick if one is due and keeps track of how long it took
+    fn run_due_tick(&mut self) {
+        let start = Instant::now();
+        if !self.tick_clock.is_due(start) {
+            return;
+        }
+
+        self.tick();
+
+        let end = Instant::now();
+        self.tick_stats.record(start, end - start);
+        if let Some(behind) = self.tick_clock.advance(end) {
+            warn!(
+                "Can't keep up! Is the server overloaded? Running {}ms or {} ticks behind \
+                 (TPS: {:.1}, MSPT: {:.2})",
+                behind.as_millis(),
+                behind.as_millis() / TICK_DURATION.as_millis(),
+                self.tick_stats.tps(),
+                self.tick_stats.mspt()
+            );
         }
     }
 
     #[instrument(skip(self))]
     pub fn run(&mut self) -> Result<(), KittyMCError> {
         self.setup_shutdown_signal_handler();
+        self.tick_clock = TickClock::new(Instant::now());
         loop {
             if *self.shutdown_signal.lock().unwrap() {
                 info!("Acknowledged shutdown signal. Initiating shut down...");
@@ -621,13 +1346,19 @@ impl KittyMCServer {
                 self.shutdown();
 
                 info!("Shut down complete. Bye bye!");
+                self.console = None;
                 return Ok(());
             }
-            // TODO: Monitor if this runs fine
-            sleep(Duration::from_millis(1));
+            if let Err(e) = self.handle_console() {
+                error!("Console command failed with error: {e}");
+            }
             if let Err(e) = self.handle_clients() {
                 error!("Client Loop exited early with error: {e}");
             }
+            self.run_due_tick();
+
+            let idle = self.tick_clock.until_next_tick(Instant::now());
+            sleep(idle.min(NETWORK_POLL_INTERVAL));
         }
     }
 
@@ -637,7 +1368,21 @@ impl KittyMCServer {
         id
     }
 
-    fn despawn_entity(&mut self, client: Option<&mut Client>, entity_id: i32) -> Result<(), KittyMCError> {
-        self.send_to_all(client, &DestroyEntitiesPacket::new(vec![entity_id]))
+    /// Destroys the entity for everyone who could see it
+    fn despawn_entity(&mut self, entity_id: i32) -> Result<(), KittyMCError> {
+        let packet = DestroyEntitiesPacket::new(vec![entity_id]);
+        let mut error = Ok(());
+        let mut clients = self.clients.write().unwrap();
+
+        for observer in self.tracker.remove_entity(entity_id) {
+            let Some(client) = clients.get_mut(&observer) else {
+                continue;
+            };
+            if let Err(e) = client.send_packet(&packet) {
+                error = Err(e);
+            }
+        }
+
+        error
     }
 }
@@ -0,0 +1,203 @@
+//! The fixed rate game tick and how well the server keeps up with it.
+//!
+//! Network I/O is polled as often as possible, while everything that simulates the world runs
+//! once per tick. When a tick takes too long the following ones start late, and once the server is
+//! too far behind it gives up on catching up instead of running ticks back to back.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+pub const TICKS_PER_SECOND: u32 = 20;
+pub const TICK_DURATION: Duration = Duration::from_millis(1000 / TICKS_PER_SECOND as u64);
+
+/// How many ticks the TPS and MSPT averages span
+const STATS_WINDOW: usize = 100;
+/// How far behind the server may fall before the missed ticks are dropped
+const MAX_TICK_BACKLOG: Duration = Duration::from_secs(2);
+/// Falling behind is only logged this often, so an overloaded server doesn't flood the log
+const LAG_WARNING_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Decides when the next tick is due
+#[derive(Debug)]
+pub struct TickClock {
+    next_tick: Instant,
+    current_tick: u64,
+    last_lag_warning: Option<Instant>,
+}
+
+impl TickClock {
+    /// A clock whose first tick is due at `now`
+    pub fn new(now: Instant) -> TickClock {
+        TickClock {
+            next_tick: now,
+            current_tick: 0,
+            last_lag_warning: None,
+        }
+    }
+
+    /// How many ticks have run so far
+    pub fn current_tick(&self) -> u64 {
+        self.current_tick
+    }
+
+    pub fn is_due(&self, now: Instant) -> bool {
+        now >= self.next_tick
+    }
+
+    pub fn until_next_tick(&self, now: Instant) -> Duration {
+        self.next_tick.saturating_duration_since(now)
+    }
+
+    /// Schedules the next tick after one finished at `now`. If the server fell too far behind,
+    /// the missed ticks are dropped and how far behind it was is returned, unless that was
+    /// already reported recently.
+    pub fn advance(&mut self, now: Instant) -> Option<Duration> {
+        self.current_tick += 1;
+        self.next_tick += TICK_DURATION;
+
+        let behind = now.saturating_duration_since(self.next_tick);
+        if behind <= MAX_TICK_BACKLOG {
+            return None;
+        }
+
+        self.next_tick = now;
+        let report = self
+            .last_lag_warning
+            .is_none_or(|last| now.duration_since(last) >= LAG_WARNING_INTERVAL);
+        if !report {
+            return None;
+        }
+
+        self.last_lag_warning = Some(now);
+        Some(behind)
+    }
+}
+
+/// Rolling averages over the last ticks
+#[derive(Debug, Default)]
+pub struct TickStats {
+    /// When each tick started and how long it took
+    ticks: VecDeque<(Instant, Duration)>,
+}
+
+impl TickStats {
+    pub fn new() -> TickStats {
+        TickStats::default()
+    }
+
+    pub fn record(&mut self, start: Instant, duration: Duration) {
+        if self.ticks.len() == STATS_WINDOW {
+            self.ticks.pop_front();
+        }
+        self.ticks.push_back((start, duration));
+    }
+
+    /// Ticks per second, which never exceeds [`TICKS_PER_SECOND`]
+    pub fn tps(&self) -> f64 {
+        let (Some((first, _)), Some((last, _))) = (self.ticks.front(), self.ticks.back()) else {
+            return TICKS_PER_SECOND as f64;
+        };
+
+        let elapsed = last.duration_since(*first).as_secs_f64();
+        if elapsed <= 0. {
+            return TICKS_PER_SECOND as f64;
+        }
+
+        ((self.ticks.len() - 1) as f64 / elapsed).min(TICKS_PER_SECOND as f64)
+    }
+
+    /// Milliseconds the average tick took
+    pub fn mspt(&self) -> f64 {
+        if self.ticks.is_empty() {
+            return 0.;
+        }
+
+        let total: Duration = self.ticks.iter().map(|(_, duration)| *duration).sum();
+        total.as_secs_f64() * 1000. / self.ticks.len() as f64
+    }
+
+    /// The time span the averages cover
+    pub fn window(&self) -> Duration {
+        match (self.ticks.front(), self.ticks.back()) {
+            (Some((first, _)), Some((last, duration))) => last.duration_since(*first) + *duration,
+            _ => Duration::ZERO,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock_runs_at_fixed_rate() {
+        let start = Instant::now();
+        let mut clock = TickClock::new(start);
+
+        assert!(clock.is_due(start));
+        assert_eq!(clock.advance(start + Duration::from_millis(5)), None);
+        assert_eq!(clock.current_tick(), 1);
+
+        let now = start + Duration::from_millis(20);
+        assert!(!clock.is_due(now));
+        assert_eq!(clock.until_next_tick(now), Duration::from_millis(30));
+        assert!(clock.is_due(start + TICK_DURATION));
+    }
+
+    #[test]
+    fn test_clock_drops_ticks_when_too_far_behind() {
+        let start = Instant::now();
+        let mut clock = TickClock::new(start);
+
+        // Slightly late ticks are caught up with
+        let late = start + Duration::from_millis(500);
+        assert_eq!(clock.advance(late), None);
+        assert!(clock.is_due(late));
+
+        let stalled = start + Duration::from_secs(5);
+        let behind = clock.advance(stalled).unwrap();
+        assert_eq!(behind, Duration::from_millis(4900));
+        // The next tick starts right away, but the ones after it don't rush
+        assert!(clock.is_due(stalled));
+        assert_eq!(clock.advance(stalled), None);
+        assert_eq!(clock.until_next_tick(stalled), TICK_DURATION);
+
+        // Only reported again once the warning interval passed
+        let stalled_again = stalled + Duration::from_secs(5);
+        assert_eq!(clock.advance(stalled_again), None);
+        let much_later = stalled_again + Duration::from_secs(15);
+        assert!(clock.advance(much_later).is_some());
+    }
+
+    #[test]
+    fn test_stats_averages() {
+        let start = Instant::now();
+        let mut stats = TickStats::new();
+        assert_eq!(stats.tps(), 20.);
+        assert_eq!(stats.mspt(), 0.);
+
+        // Every tick took 100ms, so only half of them could run
+        for i in 0..10 {
+            stats.record(start + Duration::from_millis(100) * i, Duration::from_millis(100));
+        }
+        assert!((stats.tps() - 10.).abs() < 1e-9);
+        assert!((stats.mspt() - 100.).abs() < 1e-9);
+        assert_eq!(stats.window(), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_stats_only_keep_the_window() {
+        let start = Instant::now();
+        let mut stats = TickStats::new();
+
+        for i in 0..STATS_WINDOW as u32 {
+            stats.record(start + TICK_DURATION * i, Duration::from_millis(40));
+        }
+        for i in STATS_WINDOW as u32..STATS_WINDOW as u32 * 2 {
+            stats.record(start + TICK_DURATION * i, Duration::from_millis(10));
+        }
+
+        assert!((stats.mspt() - 10.).abs() < 1e-9);
+        assert!((stats.tps() - 20.).abs() < 1e-9);
+    }
+}
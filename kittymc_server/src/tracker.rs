@@ -0,0 +1,193 @@
+//! Which entities each client knows about.
+//!
+//! A client only sees the entities within its tracking range, which follows its view distance.
+//! Entities are spawned for it when they come into that range and destroyed when they leave it,
+//! and everything that happens to an entity is only sent to the clients tracking it.
+
+use kittymc_lib::subtypes::Location2;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// What a client has to be told after its tracked entities were updated
+#[derive(Debug, Default, PartialEq)]
+pub struct TrackingUpdate {
+    pub spawned: Vec<i32>,
+    pub destroyed: Vec<i32>,
+}
+
+#[derive(Debug, Default)]
+pub struct EntityTracker {
+    /// The entities each client is tracking
+    tracked: HashMap<Uuid, HashSet<i32>>,
+    /// The clients tracking each entity
+    observers: HashMap<i32, HashSet<Uuid>>,
+}
+
+#[allow(dead_code)]
+impl EntityTracker {
+    pub fn new() -> EntityTracker {
+        EntityTracker::default()
+    }
+
+    /// Whether an entity at `entity` is within `range` blocks of `observer` on both horizontal
+    /// axes
+    pub fn in_range(observer: &Location2, entity: &Location2, range: f64) -> bool {
+        (observer.x - entity.x).abs() <= range && (observer.z - entity.z).abs() <= range
+    }
+
+    /// Starts tracking the `entities` that came into `range` of the observer standing at
+    /// `position` and stops tracking the ones that left it or don't exist anymore. The observer's
+    /// own entity is never tracked.
+    pub fn update_observer<'a, I>(
+        &mut self,
+        observer: Uuid,
+        observer_entity: i32,
+        position: &Location2,
+        range: f64,
+        entities: I,
+    ) -> TrackingUpdate
+    where
+        I: IntoIterator<Item = (i32, &'a Location2)>,
+    {
+        let visible: HashSet<i32> = entities
+            .into_iter()
+            .filter(|(id, _)| *id != observer_entity)
+            .filter(|(_, entity)| Self::in_range(position, entity, range))
+            .map(|(id, _)| id)
+            .collect();
+
+        let tracked = self.tracked.entry(observer).or_default();
+        let mut update = TrackingUpdate {
+            spawned: visible.difference(tracked).copied().collect(),
+            destroyed: tracked.difference(&visible).copied().collect(),
+        };
+        update.spawned.sort_unstable();
+        update.destroyed.sort_unstable();
+
+        for id in &update.spawned {
+            tracked.insert(*id);
+            self.observers.entry(*id).or_default().insert(observer);
+        }
+        for id in &update.destroyed {
+            tracked.remove(id);
+            if let Some(observers) = self.observers.get_mut(id) {
+                observers.remove(&observer);
+                if observers.is_empty() {
+                    self.observers.remove(id);
+                }
+            }
+        }
+
+        update
+    }
+
+    /// The clients that can see the entity
+    pub fn observers_of(&self, entity_id: i32) -> impl Iterator<Item = &Uuid> {
+        self.observers.get(&entity_id).into_iter().flatten()
+    }
+
+    pub fn is_tracking(&self, observer: &Uuid, entity_id: i32) -> bool {
+        self.tracked
+            .get(observer)
+            .is_some_and(|tracked| tracked.contains(&entity_id))
+    }
+
+    /// Forgets a client that left
+    pub fn remove_observer(&mut self, observer: &Uuid) {
+        let Some(tracked) = self.tracked.remove(observer) else {
+            return;
+        };
+
+        for id in tracked {
+            if let Some(observers) = self.observers.get_mut(&id) {
+                observers.remove(observer);
+                if observers.is_empty() {
+                    self.observers.remove(&id);
+                }
+            }
+        }
+    }
+
+    /// Forgets an entity that's gone and returns who still has to be told to destroy it
+    pub fn remove_entity(&mut self, entity_id: i32) -> Vec<Uuid> {
+        let observers: Vec<Uuid> = self
+            .observers
+            .remove(&entity_id)
+            .into_iter()
+            .flatten()
+            .collect();
+
+        for observer in &observers {
+            if let Some(tracked) = self.tracked.get_mut(observer) {
+                tracked.remove(&entity_id);
+            }
+        }
+
+        observers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RANGE: f64 = 64.;
+
+    #[test]
+    fn test_entities_are_spawned_and_destroyed_by_range() {
+        let mut tracker = EntityTracker::new();
+        let observer = Uuid::new_v4();
+        let origin = Location2::new(0., 64., 0.);
+        let near = Location2::new(10., 64., -60.);
+        let far = Location2::new(1000., 64., 0.);
+
+        let update = tracker.update_observer(
+            observer,
+            0,
+            &origin,
+            RANGE,
+            [(0, &origin), (1, &near), (2, &far)],
+        );
+        assert_eq!(update.spawned, vec![1]);
+        assert!(update.destroyed.is_empty());
+        assert!(tracker.is_tracking(&observer, 1));
+        assert!(!tracker.is_tracking(&observer, 0));
+        assert_eq!(tracker.observers_of(1).collect::<Vec<_>>(), vec![&observer]);
+        assert_eq!(tracker.observers_of(2).count(), 0);
+
+        // Nothing changes while everyone stays where they are
+        let update =
+            tracker.update_observer(observer, 0, &origin, RANGE, [(1, &near), (2, &far)]);
+        assert_eq!(update, TrackingUpdate::default());
+
+        let update = tracker.update_observer(observer, 0, &origin, RANGE, [(1, &far), (2, &near)]);
+        assert_eq!(update.spawned, vec![2]);
+        assert_eq!(update.destroyed, vec![1]);
+        assert_eq!(tracker.observers_of(1).count(), 0);
+    }
+
+    #[test]
+    fn test_removed_entities_are_destroyed_for_their_observers() {
+        let mut tracker = EntityTracker::new();
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let origin = Location2::new(0., 64., 0.);
+        let entities = [(0, &origin), (1, &origin), (2, &origin)];
+
+        tracker.update_observer(a, 0, &origin, RANGE, entities);
+        tracker.update_observer(b, 1, &origin, RANGE, entities);
+
+        let mut observers = tracker.remove_entity(2);
+        observers.sort();
+        let mut expected = vec![a, b];
+        expected.sort();
+        assert_eq!(observers, expected);
+        assert!(!tracker.is_tracking(&a, 2));
+
+        // An entity that vanished without being removed is destroyed on the next update
+        let update = tracker.update_observer(a, 0, &origin, RANGE, [(0, &origin)]);
+        assert_eq!(update.destroyed, vec![1]);
+
+        tracker.remove_observer(&b);
+        assert_eq!(tracker.observers_of(0).count(), 0);
+    }
+}